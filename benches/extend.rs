@@ -0,0 +1,76 @@
+use {
+    criterion::{BatchSize, Criterion, criterion_group, criterion_main},
+    growlock::GrowLock,
+    std::hint::black_box,
+};
+
+const LEN: usize = 100_000;
+
+/// Cheap element which is [`Clone`] but not [`Copy`].
+#[derive(Clone)]
+struct Elem {
+    _value: u64,
+}
+
+/// `push` in a loop, which is what `extend` did for every iterator.
+fn push_each<T, I: Iterator<Item = T>>(lock: &GrowLock<T>, iter: I) {
+    let mut guard = lock.write().unwrap();
+    for elem in iter {
+        guard.push(elem);
+    }
+}
+
+fn extend(crit: &mut Criterion) {
+    let src: Vec<u64> = (0..LEN as u64).collect();
+
+    let mut group = crit.benchmark_group("extend_vec_into_iter");
+    group.bench_function("push_each", |bencher| {
+        bencher.iter_batched(
+            || src.clone(),
+            |vec| {
+                let lock = GrowLock::with_capacity(LEN);
+                push_each(&lock, vec.into_iter());
+                lock
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.bench_function("extend", |bencher| {
+        bencher.iter_batched(
+            || src.clone(),
+            |vec| {
+                let lock = GrowLock::with_capacity(LEN);
+                lock.write().unwrap().extend(vec);
+                lock
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+
+    let src: Vec<Elem> = src
+        .into_iter()
+        .map(|value| Elem { _value: value })
+        .collect();
+    let mut group = crit.benchmark_group("extend_slice_cloned");
+    group.bench_function("push_each", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            push_each(&lock, black_box(&src[..]).iter().cloned());
+            lock
+        });
+    });
+    group.bench_function("extend", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            lock.write()
+                .unwrap()
+                .extend(black_box(&src[..]).iter().cloned());
+            lock
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, extend);
+criterion_main!(benches);
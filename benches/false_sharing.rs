@@ -0,0 +1,47 @@
+//! Compare the results with and without the `cache-padded` feature.
+
+use {
+    criterion::{Criterion, criterion_group, criterion_main},
+    growlock::GrowLock,
+    std::{
+        hint::black_box,
+        sync::atomic::{AtomicBool, Ordering},
+        thread,
+        time::Instant,
+    },
+};
+
+const READERS: usize = 7;
+
+/// One writer pushing (taking the write lock for every push), while the
+/// readers spin on the length.
+fn false_sharing(crit: &mut Criterion) {
+    let mut group = crit.benchmark_group("false_sharing");
+    group.bench_function("1_writer_7_readers", |bencher| {
+        bencher.iter_custom(|iters| {
+            let lock =
+                GrowLock::with_capacity(usize::try_from(iters).unwrap());
+            let running = AtomicBool::new(true);
+            thread::scope(|s| {
+                for _ in 0..READERS {
+                    s.spawn(|| {
+                        while running.load(Ordering::Relaxed) {
+                            black_box(lock.len());
+                        }
+                    });
+                }
+                let start = Instant::now();
+                for i in 0..iters {
+                    lock.write().unwrap().push(black_box(i));
+                }
+                let elapsed = start.elapsed();
+                running.store(false, Ordering::Relaxed);
+                elapsed
+            })
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, false_sharing);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+use {
+    criterion::{Criterion, criterion_group, criterion_main},
+    growlock::GrowLock,
+    rayon::prelude::*,
+    std::hint::black_box,
+};
+
+const LEN: usize = 10_000_000;
+
+fn par_extend(crit: &mut Criterion) {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(8)
+        .build()
+        .unwrap();
+    let mut group = crit.benchmark_group("fill_10M");
+    group.sample_size(10);
+    group.bench_function("extend", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            lock.write()
+                .unwrap()
+                .extend((0..LEN).map(|i| black_box(i).wrapping_mul(31)));
+            lock
+        });
+    });
+    group.bench_function("par_extend_8_threads", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            pool.install(|| {
+                lock.write().unwrap().par_extend(
+                    (0..LEN)
+                        .into_par_iter()
+                        .map(|i| black_box(i).wrapping_mul(31)),
+                );
+            });
+            lock
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, par_extend);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+use {
+    criterion::{Criterion, criterion_group, criterion_main},
+    growlock::GrowLock,
+    std::{hint::black_box, thread},
+};
+
+const LEN: usize = 10_000;
+
+/// Single pushes, which wake up the waiting readers after every store of
+/// the length.
+fn push(crit: &mut Criterion) {
+    let mut group = crit.benchmark_group("push");
+    group.bench_function("no_waiters", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            let mut guard = lock.write().unwrap();
+            for i in 0..LEN {
+                guard.push(black_box(i));
+            }
+            drop(guard);
+            lock
+        });
+    });
+    // the length is stored (and the readers woken up) once every 64
+    // pushes.
+    group.bench_function("deferred", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            let mut guard = lock.write().unwrap();
+            for i in 0..LEN {
+                guard.push_deferred(black_box(i)).unwrap();
+                if i % 64 == 63 {
+                    guard.publish();
+                }
+            }
+            drop(guard);
+            lock
+        });
+    });
+    // the write lock is taken for every push, so the readers are also
+    // woken up when it's released.
+    group.bench_function("relock_each", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            for i in 0..LEN {
+                lock.write().unwrap().push(black_box(i));
+            }
+            lock
+        });
+    });
+    // the first push opens the lock-free path, then no lock is taken.
+    group.bench_function("lockfree", |bencher| {
+        bencher.iter(|| {
+            let lock = GrowLock::with_capacity(LEN);
+            for i in 0..LEN {
+                lock.push_lockfree(black_box(i)).unwrap();
+            }
+            lock
+        });
+    });
+    group.bench_function("vec", |bencher| {
+        bencher.iter(|| {
+            let mut vec = Vec::with_capacity(LEN);
+            for i in 0..LEN {
+                vec.push(black_box(i));
+            }
+            vec
+        });
+    });
+    group.finish();
+}
+
+/// Lock-free pushes from several threads, which publish their lengths in
+/// order without taking any lock.
+fn push_lockfree_threads(crit: &mut Criterion) {
+    let mut group = crit.benchmark_group("push_lockfree");
+    for threads in [1, 2, 4, 8] {
+        group.bench_function(format!("threads_{threads}"), |bencher| {
+            bencher.iter(|| {
+                let lock = GrowLock::with_capacity(LEN);
+                thread::scope(|s| {
+                    for _ in 0..threads {
+                        s.spawn(|| {
+                            for i in 0..LEN / threads {
+                                lock.push_lockfree(black_box(i)).unwrap();
+                            }
+                        });
+                    }
+                });
+                lock
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push, push_lockfree_threads);
+criterion_main!(benches);
@@ -0,0 +1,42 @@
+//! A minimal `no_std` crate using `growlock` without its `std` feature.
+//!
+//! Build it with `cargo build --manifest-path examples/no_std/Cargo.toml`.
+
+#![no_std]
+
+extern crate alloc;
+
+use {
+    alloc::boxed::Box,
+    growlock::{GrowLock, array::ArrayGrowLock, grow_lock},
+};
+
+/// A statically allocated log, without an allocator.
+static EVENTS: ArrayGrowLock<u32, 16> = ArrayGrowLock::new();
+
+/// Records an event, or returns `false` if the log is full.
+pub fn record(event: u32) -> bool {
+    EVENTS
+        .write()
+        .is_ok_and(|mut guard| guard.try_push(event).is_ok())
+}
+
+/// The events recorded until now.
+pub fn events() -> &'static [u32] {
+    &EVENTS
+}
+
+/// Pushes to a heap-allocated [`GrowLock`], while reading its elements.
+pub fn sum_while_pushing() -> u32 {
+    let lock: GrowLock<u32> = grow_lock!(8, [1, 2, 3]);
+    let first = &lock[0];
+    let mut guard = lock.write().unwrap_or_else(|e| e.into_inner());
+    guard.extend([4, 5]);
+    drop(guard);
+    *first + lock.iter().sum::<u32>()
+}
+
+/// Leaks a [`GrowLock`] to share it for the rest of the program.
+pub fn leaked(capacity: usize) -> &'static GrowLock<u8> {
+    Box::leak(Box::new(GrowLock::with_capacity(capacity)))
+}
@@ -19,7 +19,7 @@ fn main() {
 
         // we simulate a very slow write from another thread
         guard.push(4);
-        thread::sleep(Duration::from_millis(1000));
+        thread::sleep(Duration::from_secs(1));
         guard.push(5);
     });
 
@@ -0,0 +1,31 @@
+//! [`arbitrary`](::arbitrary) support.
+
+use {
+    crate::GrowLock,
+    ::arbitrary::{Arbitrary, Result, Unstructured, size_hint},
+};
+
+/// The maximum spare capacity of an arbitrary [`GrowLock`].
+const MAX_SPARE: usize = 64;
+
+/// Generates the elements like a [`Vec`], and then a spare capacity of
+/// up to 64 elements.
+impl<'a, T: Arbitrary<'a>> Arbitrary<'a> for GrowLock<T> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let elems: Vec<T> = u.arbitrary()?;
+        let spare = u.int_in_range(0..=MAX_SPARE)?;
+        Ok(Self::from_iter_with_capacity(elems.len() + spare, elems))
+    }
+    fn arbitrary_take_rest(mut u: Unstructured<'a>) -> Result<Self> {
+        let spare = u.int_in_range(0..=MAX_SPARE)?;
+        let elems = Vec::<T>::arbitrary_take_rest(u)?;
+        Ok(Self::from_iter_with_capacity(elems.len() + spare, elems))
+    }
+    #[inline]
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        size_hint::and(
+            <Vec<T> as Arbitrary>::size_hint(depth),
+            <usize as Arbitrary>::size_hint(depth),
+        )
+    }
+}
@@ -0,0 +1,158 @@
+//! Arena-style allocation: pushing returns a reference to the new element,
+//! which lives as long as the [`GrowLock`] is borrowed.
+
+use {
+    crate::{
+        GrowLock,
+        compat::Allocator,
+        error::{GrowLockError, LengthError, PushError},
+        sync::PoisonError,
+    },
+    core::{ptr, str},
+};
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Pushes `value`, and returns a reference to it.
+    ///
+    /// The reference borrows `self`, not the write guard (which is
+    /// released before returning): the elements are never moved nor
+    /// removed while the [`GrowLock`] is shared, so it stays valid
+    /// while other threads keep allocating.
+    ///
+    /// The lock is poisoned only if a writer panicked, which can't leave
+    /// an element half-written, so the poisoning is ignored.
+    ///
+    /// # Errors
+    /// Returns [`GrowLockError::Full`], which gives back the value, if the
+    /// [`GrowLock`] is full.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, error::GrowLockError};
+    ///
+    /// let arena = GrowLock::with_capacity(2);
+    /// let a = arena.alloc(String::from("a")).unwrap();
+    /// let b = arena.alloc(String::from("b")).unwrap();
+    /// assert_eq!([a.as_str(), b.as_str()], ["a", "b"]);
+    /// let err = arena.alloc(String::from("c")).unwrap_err();
+    /// assert!(matches!(err, GrowLockError::Full { len: 2, .. }));
+    /// assert_eq!(err.into_value().as_deref(), Some("c"));
+    /// ```
+    pub fn alloc(&self, value: T) -> Result<&T, GrowLockError<T>> {
+        let guard = self.write().unwrap_or_else(PoisonError::into_inner);
+        let index = guard.len();
+        // SAFETY: the write guard is held.
+        unsafe { self.guarded_try_push(value) }
+            .map_err(|value| PushError::new(value, self.capacity()))?;
+        drop(guard);
+        Ok(&self.as_slice()[index])
+    }
+    /// Clones the elements of `src` to the end of the [`GrowLock`], and
+    /// returns the slice spanning exactly them.
+    ///
+    /// The elements are appended contiguously, under one acquisition of
+    /// the write lock, so concurrent calls return disjoint slices. Like
+    /// [`alloc`](GrowLock::alloc), the poisoning is ignored.
+    ///
+    /// For [`Copy`] types,
+    /// [`alloc_slice_copy`](GrowLock::alloc_slice_copy) copies all the
+    /// elements at once.
+    ///
+    /// # Errors
+    /// Returns an error if the elements don't fit in the spare capacity
+    /// (before cloning any element).
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let arena = GrowLock::with_capacity(5);
+    /// let args = arena.alloc_slice(&[String::from("-v"), String::from("-q")]);
+    /// assert_eq!(args.unwrap(), ["-v", "-q"]);
+    /// assert!(arena.alloc_slice(&vec![String::new(); 4]).is_err());
+    /// ```
+    pub fn alloc_slice(&self, src: &[T]) -> Result<&[T], LengthError>
+    where
+        T: Clone,
+    {
+        let guard = self.write().unwrap_or_else(PoisonError::into_inner);
+        let start = guard.len();
+        if src.len() > self.capacity() - start {
+            return Err(LengthError::new(start, self.capacity()));
+        }
+        // SAFETY: the write guard is held, and the elements fit.
+        unsafe { self.extend_from_slice_unchecked(src) };
+        drop(guard);
+        Ok(&self.as_slice()[start..start + src.len()])
+    }
+    /// Same as [`alloc_slice`](GrowLock::alloc_slice), but copies all the
+    /// elements at once.
+    ///
+    /// # Errors
+    /// Returns an error if the elements don't fit in the spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let arena = GrowLock::with_capacity(5);
+    /// let a = arena.alloc_slice_copy(&[1, 2]).unwrap();
+    /// let b = arena.alloc_slice_copy(&[3, 4, 5]).unwrap();
+    /// assert_eq!((a, b), (&[1, 2][..], &[3, 4, 5][..]));
+    /// ```
+    pub fn alloc_slice_copy(&self, src: &[T]) -> Result<&[T], LengthError>
+    where
+        T: Copy,
+    {
+        let guard = self.write().unwrap_or_else(PoisonError::into_inner);
+        let start = guard.len();
+        if src.len() > self.capacity() - start {
+            return Err(LengthError::new(start, self.capacity()));
+        }
+        // SAFETY:
+        // * `start + src.len() <= capacity`.
+        // * `src` can't overlap the spare capacity, which is not reachable
+        //   through a shared reference.
+        // * the new elements are initialized, and the write guard is held.
+        unsafe {
+            let dst = self.as_non_null_shared().add(start);
+            ptr::copy_nonoverlapping(
+                src.as_ptr(),
+                dst.as_ptr(),
+                src.len(),
+            );
+            self.publish_len(start + src.len());
+        }
+        drop(guard);
+        Ok(&self.as_slice()[start..start + src.len()])
+    }
+}
+
+impl<A: Allocator> GrowLock<u8, A> {
+    /// Appends the bytes of `s`, and returns them as a [`str`] (e.g. to
+    /// intern strings).
+    ///
+    /// See [`alloc_slice_copy`](GrowLock::alloc_slice_copy). A leaked
+    /// [`GrowLock`] returns `&'static str`s.
+    ///
+    /// # Errors
+    /// Returns an error if the bytes don't fit in the spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let interner: &'static GrowLock<u8> =
+    ///     Box::leak(Box::new(GrowLock::with_capacity(1024)));
+    /// let ident: &'static str = interner.alloc_str("foo").unwrap();
+    /// assert_eq!(ident, "foo");
+    /// assert_eq!(interner.alloc_str("bar").unwrap(), "bar");
+    /// assert_eq!(interner.as_slice(), b"foobar");
+    /// ```
+    pub fn alloc_str(&self, s: &str) -> Result<&str, LengthError> {
+        let bytes = self.alloc_slice_copy(s.as_bytes())?;
+        // SAFETY: the bytes are a copy of `s`, and they're never mutated
+        // while `self` is borrowed.
+        Ok(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+}
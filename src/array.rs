@@ -0,0 +1,49 @@
+//! A [`GrowLock`](crate::GrowLock) which never allocates, for the targets
+//! without an allocator.
+
+use crate::fixed::{ArraySlots, Fixed, fixed_lock};
+
+/// A [`GrowLock`](crate::GrowLock) backed by an inline array of `N`
+/// elements, which never allocates.
+///
+/// It has the same write-lock API as a [`GrowLock`](crate::GrowLock):
+/// the elements are pushed through the [`ArrayGrowGuard`] returned by
+/// [`write`](ArrayGrowLock::write), and the readers see them (through
+/// [`Deref`](std::ops::Deref)) once the new length is published.
+///
+/// [`new`](ArrayGrowLock::new) is `const`, so it can be used in a
+/// `static`.
+///
+/// The elements are dropped in reverse order, like the ones of a
+/// [`GrowLock`](crate::GrowLock#drop-order).
+///
+/// # Examples
+/// ```
+/// use growlock::array::ArrayGrowLock;
+/// use std::thread;
+///
+/// static EVENTS: ArrayGrowLock<u32, 16> = ArrayGrowLock::new();
+///
+/// thread::spawn(|| EVENTS.write().unwrap().extend([1, 2]))
+///     .join()
+///     .unwrap();
+/// assert_eq!(*EVENTS, [1, 2]);
+/// assert_eq!(EVENTS.capacity(), 16);
+/// ```
+pub struct ArrayGrowLock<T, const N: usize> {
+    inner: Fixed<T, ArraySlots<T, N>>,
+}
+
+fixed_lock!(ArrayGrowLock, ArrayGrowGuard);
+
+impl<T, const N: usize> ArrayGrowLock<T, N> {
+    /// Creates an empty [`ArrayGrowLock`], with a capacity of `N` (or
+    /// [`usize::MAX`] if `T` is zero-sized).
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inner: Fixed::new(ArraySlots::new()),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! In-place updates of the elements of a [`GrowLock`] of atomics,
+//! without the write lock.
+
+use {
+    crate::{GrowLock, compat::Allocator},
+    core::sync::atomic::Ordering,
+};
+
+/// An atomic type, which can be stored in a [`GrowLock`] to update its
+/// elements through a shared reference.
+///
+/// The elements of a [`GrowLock<u64>`] can't be updated atomically in
+/// place: the readers hold plain `&u64`s to them, so writing them while
+/// they're shared would be a data race. Instead, the [`GrowLock`] is
+/// converted to a [`GrowLock<AtomicU64>`] with
+/// [`into_atomic`](GrowLock::into_atomic) (reusing the allocation when
+/// the two types have the same size and alignment), and converted back
+/// with [`into_values`](GrowLock::into_values).
+pub trait AtomicElement: Sized {
+    /// The non-atomic type.
+    type Value: Copy;
+    fn new(value: Self::Value) -> Self;
+    fn into_inner(self) -> Self::Value;
+    /// See [`AtomicU64::try_update`].
+    ///
+    /// # Errors
+    /// Returns the previous value if `f` returned [`None`].
+    fn fetch_update<F>(
+        &self,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Result<Self::Value, Self::Value>
+    where
+        F: FnMut(Self::Value) -> Option<Self::Value>;
+}
+
+macro_rules! impl_atomic_element {
+    ($($width:literal: $atomic:ident => $value:ty),* $(,)?) => {$(
+        #[cfg(target_has_atomic = $width)]
+        impl AtomicElement for core::sync::atomic::$atomic {
+            type Value = $value;
+            #[inline]
+            fn new(value: $value) -> Self {
+                Self::new(value)
+            }
+            #[inline]
+            fn into_inner(self) -> $value {
+                Self::into_inner(self)
+            }
+            #[inline]
+            fn fetch_update<F>(
+                &self,
+                set_order: Ordering,
+                fetch_order: Ordering,
+                f: F,
+            ) -> Result<$value, $value>
+            where
+                F: FnMut($value) -> Option<$value>,
+            {
+                Self::try_update(self, set_order, fetch_order, f)
+            }
+        }
+    )*};
+}
+
+impl_atomic_element! {
+    "8": AtomicBool => bool,
+    "8": AtomicI8 => i8,
+    "16": AtomicI16 => i16,
+    "32": AtomicI32 => i32,
+    "64": AtomicI64 => i64,
+    "ptr": AtomicIsize => isize,
+    "8": AtomicU8 => u8,
+    "16": AtomicU16 => u16,
+    "32": AtomicU32 => u32,
+    "64": AtomicU64 => u64,
+    "ptr": AtomicUsize => usize,
+}
+
+impl<T: Copy, A: Allocator> GrowLock<T, A> {
+    /// Converts the elements to their atomic type, so they can be updated
+    /// in place through a shared reference.
+    ///
+    /// The allocation is reused if the two types have the same size and
+    /// alignment (see [`map`](GrowLock::map)), which is the case of every
+    /// atomic type except, on some targets, the 64-bit ones.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    ///
+    /// let buckets = grow_lock!(4, [0_u64; 4]).into_atomic::<AtomicU64>();
+    /// buckets[2].fetch_add(1, Ordering::Relaxed);
+    /// assert_eq!(buckets.into_values(), [0, 0, 1, 0]);
+    /// ```
+    #[inline]
+    pub fn into_atomic<U>(self) -> GrowLock<U, A>
+    where
+        U: AtomicElement<Value = T>,
+    {
+        self.map(U::new)
+    }
+}
+
+impl<T: AtomicElement, A: Allocator> GrowLock<T, A> {
+    /// Updates the element at `index` with `f`, like
+    /// [`AtomicU64::try_update`], without taking the write lock.
+    ///
+    /// Returns [`None`] if `index` is out of bounds (the elements which
+    /// aren't published yet can't be updated), and the result of
+    /// the update otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    /// use std::{
+    ///     sync::atomic::{AtomicU64, Ordering},
+    ///     thread,
+    /// };
+    ///
+    /// let buckets = grow_lock!(4, [0_u64; 4]).into_atomic::<AtomicU64>();
+    /// thread::scope(|s| {
+    ///     for i in 0..8 {
+    ///         let buckets = &buckets;
+    ///         s.spawn(move || {
+    ///             let add = |x| Some(x + 1);
+    ///             let (set, fetch) = (Ordering::AcqRel, Ordering::Acquire);
+    ///             buckets.fetch_update_at(i % 4, set, fetch, add);
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(buckets.into_values(), [2; 4]);
+    /// assert!(
+    ///     grow_lock!(4, [0_u64; 2])
+    ///         .into_atomic::<AtomicU64>()
+    ///         .fetch_update_at(2, Ordering::Relaxed, Ordering::Relaxed, Some)
+    ///         .is_none()
+    /// );
+    /// ```
+    #[inline]
+    pub fn fetch_update_at<F>(
+        &self,
+        index: usize,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        f: F,
+    ) -> Option<Result<T::Value, T::Value>>
+    where
+        F: FnMut(T::Value) -> Option<T::Value>,
+    {
+        let elem = self.get(index)?;
+        Some(elem.fetch_update(set_order, fetch_order, f))
+    }
+    /// Converts the elements back to their non-atomic type (see
+    /// [`into_atomic`](GrowLock::into_atomic)).
+    #[inline]
+    pub fn into_values(self) -> GrowLock<T::Value, A> {
+        self.map(T::into_inner)
+    }
+}
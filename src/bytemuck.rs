@@ -0,0 +1,229 @@
+//! [`bytemuck`](::bytemuck) helpers, to view plain data as bytes and back.
+
+use {
+    crate::{
+        GrowLock,
+        compat::Allocator,
+        error::{ExtendFromBytesError, LengthError},
+        guard::GrowGuard,
+        sync::atomic::Ordering,
+    },
+    ::bytemuck::{AnyBitPattern, NoUninit, PodCastError},
+    std::{io, ptr, slice},
+};
+
+/// Magic bytes at the start of a snapshot.
+pub(crate) const SNAPSHOT_MAGIC: [u8; 8] = *b"GROWLOCK";
+/// Size of the header of a snapshot: the magic bytes, the size of an
+/// element and the number of elements.
+pub(crate) const SNAPSHOT_HEADER_LEN: usize = 24;
+
+impl<T: NoUninit, A: Allocator> GrowLock<T, A> {
+    /// Returns the bytes of the elements pushed so far, without copying.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(10, [1u16, 2]);
+    /// assert_eq!(lock.as_bytes(), [1u16.to_ne_bytes(), 2u16.to_ne_bytes()].concat());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        ::bytemuck::cast_slice(self.as_slice())
+    }
+}
+
+impl<T: NoUninit, A: Allocator> GrowLock<T, A> {
+    /// Writes a snapshot of the elements pushed so far to `w`, which can
+    /// be read back with [`read_snapshot`](GrowLock::read_snapshot).
+    ///
+    /// The snapshot is a header (with the size of an element and the
+    /// number of elements) followed by the raw bytes of the elements, in
+    /// native endianness.
+    ///
+    /// # Errors
+    /// Returns any error of `w`.
+    pub fn write_snapshot<W: io::Write>(
+        &self,
+        mut w: W,
+    ) -> io::Result<()> {
+        // the length is loaded once, by `as_slice`.
+        let elems = self.as_slice();
+        let mut header = [0; SNAPSHOT_HEADER_LEN];
+        header[..8].copy_from_slice(&SNAPSHOT_MAGIC);
+        header[8..16]
+            .copy_from_slice(&(size_of::<T>() as u64).to_le_bytes());
+        header[16..].copy_from_slice(&(elems.len() as u64).to_le_bytes());
+        w.write_all(&header)?;
+        w.write_all(::bytemuck::cast_slice(elems))
+    }
+}
+
+impl<T: AnyBitPattern> GrowLock<T> {
+    /// Reads a snapshot written by
+    /// [`write_snapshot`](GrowLock::write_snapshot), into a new
+    /// [`GrowLock`] with `extra_capacity` spare capacity.
+    ///
+    /// The buffer is allocated after reading the header, and the elements
+    /// are read directly into it.
+    ///
+    /// # Errors
+    /// * [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData) if the
+    ///   header is invalid, or if it was written for a different element
+    ///   size.
+    /// * [`ErrorKind::OutOfMemory`](io::ErrorKind::OutOfMemory) if the
+    ///   allocation fails.
+    /// * [`ErrorKind::UnexpectedEof`](io::ErrorKind::UnexpectedEof) if the
+    ///   snapshot is truncated.
+    /// * Any other error of `r`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, grow_lock};
+    /// use std::io::Cursor;
+    ///
+    /// let lock = grow_lock!(10, [1.5f32, 2.5]);
+    /// let mut file = Cursor::new(Vec::new());
+    /// lock.write_snapshot(&mut file).unwrap();
+    ///
+    /// file.set_position(0);
+    /// let read = GrowLock::<f32>::read_snapshot(&mut file, 5).unwrap();
+    /// assert_eq!(read, [1.5, 2.5]);
+    /// assert_eq!(read.capacity(), 7);
+    /// ```
+    pub fn read_snapshot<R: io::Read>(
+        mut r: R,
+        extra_capacity: usize,
+    ) -> io::Result<Self> {
+        fn invalid(msg: &'static str) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, msg)
+        }
+
+        let mut magic = [0; 8];
+        let mut size = [0; 8];
+        let mut len = [0; 8];
+        r.read_exact(&mut magic)?;
+        r.read_exact(&mut size)?;
+        r.read_exact(&mut len)?;
+        if magic != SNAPSHOT_MAGIC {
+            return Err(invalid("invalid snapshot header"));
+        }
+        if u64::from_le_bytes(size) != size_of::<T>() as u64 {
+            return Err(invalid("snapshot of a different element size"));
+        }
+        let len = usize::try_from(u64::from_le_bytes(len))
+            .map_err(|_| invalid("snapshot length overflow"))?;
+        let capacity = len
+            .checked_add(extra_capacity)
+            .ok_or_else(|| invalid("snapshot length overflow"))?;
+
+        let lock = Self::try_with_capacity(capacity)?;
+        // `len <= capacity`, and the layout of `[T; capacity]` was
+        // allocated, so this can't overflow.
+        let bytes = len * size_of::<T>();
+        // SAFETY:
+        // * `lock` is not shared yet.
+        // * the first `bytes` bytes are inside the allocation, and they
+        //   are zeroed before being borrowed as initialized.
+        let spare = unsafe {
+            let ptr = lock.as_non_null_shared().cast::<u8>().as_ptr();
+            ptr::write_bytes(ptr, 0, bytes);
+            slice::from_raw_parts_mut(ptr, bytes)
+        };
+        // on error, `lock` is dropped with a length of 0.
+        r.read_exact(spare)?;
+        // the elements are initialized, as `T` is valid for any bit
+        // pattern.
+        crate::macros::debug_invariant!(len <= lock.capacity());
+        lock.len.store(len, Ordering::Release);
+        Ok(lock)
+    }
+}
+
+impl<A: Allocator> GrowLock<u8, A> {
+    /// Reinterprets the bytes pushed so far as a slice of `T`, without
+    /// copying.
+    ///
+    /// # Errors
+    /// Returns an error if the bytes are not aligned for `T`, or if their
+    /// number is not a multiple of `size_of::<T>()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::<u8>::from_iter_with_capacity(16, [0; 8]);
+    /// assert_eq!(lock.cast_slice::<u16>(), Ok(&[0; 4][..]));
+    /// assert!(lock.cast_slice::<[u8; 3]>().is_err());
+    /// ```
+    #[inline]
+    pub fn cast_slice<T: AnyBitPattern>(
+        &self,
+    ) -> Result<&[T], PodCastError> {
+        ::bytemuck::try_cast_slice(self.as_slice())
+    }
+}
+
+impl<T: AnyBitPattern, A: Allocator> GrowGuard<'_, T, A> {
+    /// Reinterprets `bytes` as elements, and appends them with a single
+    /// copy.
+    ///
+    /// `bytes` doesn't need to be aligned for `T`. Either all the elements
+    /// are pushed, or none of them.
+    ///
+    /// # Errors
+    /// * [`ExtendFromBytesError::Cast`] if `bytes.len()` is not a multiple
+    ///   of `size_of::<T>()`.
+    /// * [`ExtendFromBytesError::Length`] if the elements don't fit in the
+    ///   spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::<u32>::with_capacity(2);
+    /// let mut guard = lock.write().unwrap();
+    /// guard.extend_from_bytes(&7u32.to_ne_bytes()).unwrap();
+    /// assert!(guard.extend_from_bytes(&[0; 3]).is_err());
+    /// assert_eq!(lock, [7]);
+    /// ```
+    pub fn extend_from_bytes(
+        &mut self,
+        bytes: &[u8],
+    ) -> Result<(), ExtendFromBytesError> {
+        let size = size_of::<T>();
+        if size == 0 {
+            return if bytes.is_empty() {
+                Ok(())
+            } else {
+                Err(PodCastError::SizeMismatch.into())
+            };
+        }
+        if !bytes.len().is_multiple_of(size) {
+            return Err(PodCastError::OutputSliceWouldHaveSlop.into());
+        }
+        let count = bytes.len() / size;
+        let len = self.len();
+        if count > self.capacity() - len {
+            return Err(LengthError::new(len, self.capacity()).into());
+        }
+        // SAFETY:
+        // * `len + count <= capacity`, so there is space for the bytes.
+        // * `T` is valid for any bit pattern.
+        // * `bytes` can't overlap the spare capacity.
+        unsafe {
+            let dst = self.lock.as_non_null_shared().add(len);
+            ptr::copy_nonoverlapping(
+                bytes.as_ptr(),
+                dst.cast::<u8>().as_ptr(),
+                bytes.len(),
+            );
+        }
+        // SAFETY: the new elements are initialized, and the write guard is
+        // held.
+        unsafe { self.lock.publish_len(len + count) };
+        Ok(())
+    }
+}
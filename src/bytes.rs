@@ -0,0 +1,407 @@
+//! Methods and conversions specific to [`GrowLock<u8>`](crate::GrowLock),
+//! useful to use it as an append-only byte log.
+
+#[cfg(feature = "std")]
+use std::io::{self, IoSlice};
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        error::{FromUtf8Error, LengthError},
+        guard::GrowGuard,
+    },
+    alloc::{string::String, vec::Vec},
+    core::{
+        fmt, ptr,
+        str::{self, Utf8Error},
+    },
+};
+#[cfg(all(feature = "std", feature = "nightly"))]
+use {
+    core::{mem::MaybeUninit, slice},
+    std::io::BorrowedBuf,
+};
+
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+use crate::guard::OwnedGrowGuard;
+
+impl GrowLock<u8> {
+    /// Creates a new [`GrowLock<u8>`] with the given capacity, and copies
+    /// the bytes of `s` into it.
+    ///
+    /// # Panics
+    /// Panics if `capacity < s.len()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from_str_with_capacity("hello", 10);
+    /// assert_eq!(lock, *b"hello");
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn from_str_with_capacity(s: &str, capacity: usize) -> Self {
+        Self::from_slice_in(s.as_bytes(), capacity, Global)
+    }
+    /// Converts the bytes pushed so far to a string slice, without
+    /// copying.
+    ///
+    /// Note that a writer could be in the middle of pushing a multi-byte
+    /// character, so an error doesn't necessarily mean that the data is
+    /// invalid.
+    ///
+    /// # Errors
+    /// Returns an error if the bytes are not valid UTF-8.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from("hello");
+    /// assert_eq!(lock.as_str(), Ok("hello"));
+    /// ```
+    #[inline]
+    pub fn as_str(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(self)
+    }
+}
+
+impl<A: Allocator> GrowLock<u8, A> {
+    /// Copies as many bytes of `bytes` as fit after the first `len` ones,
+    /// and publishes the new length. Returns the number of bytes copied.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock, and the first `len` bytes
+    /// must be initialized (`len` can include the deferred ones).
+    unsafe fn guarded_push_bytes_truncated(
+        &self,
+        len: usize,
+        bytes: &[u8],
+    ) -> usize {
+        let count = bytes.len().min(self.capacity() - len);
+        // SAFETY:
+        // * `len + count <= capacity`.
+        // * `bytes` can't overlap the spare capacity, which is not
+        //   reachable through a shared reference.
+        unsafe {
+            let dst = self.as_non_null_shared().add(len);
+            ptr::copy_nonoverlapping(bytes.as_ptr(), dst.as_ptr(), count);
+        }
+        // SAFETY: the new elements are initialized, and the caller holds
+        // the write lock.
+        unsafe { self.publish_len(len + count) };
+        count
+    }
+}
+
+impl<A: Allocator> GrowGuard<'_, u8, A> {
+    /// Copies as many bytes of `bytes` as fit in the spare capacity, and
+    /// publishes the new length. Returns the number of bytes copied.
+    pub(crate) fn push_bytes_truncated(&mut self, bytes: &[u8]) -> usize {
+        // SAFETY: the write guard is held, and the first `self.len()`
+        // bytes are initialized.
+        unsafe {
+            self.lock.guarded_push_bytes_truncated(self.len(), bytes)
+        }
+    }
+    /// Appends the bytes of `s`, only if all of them fit in the spare
+    /// capacity (so a multi-byte character is never split).
+    ///
+    /// # Errors
+    /// Returns an error, without pushing anything, if `s.len()` is
+    /// greater than the spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::<u8>::with_capacity(4);
+    /// let mut guard = lock.write().unwrap();
+    /// guard.push_str("ab").unwrap();
+    /// assert!(guard.push_str("cde").is_err());
+    /// assert_eq!(lock.as_str(), Ok("ab"));
+    /// ```
+    pub fn push_str(&mut self, s: &str) -> Result<(), LengthError> {
+        if s.len() > self.capacity() - self.len() {
+            return Err(LengthError::new(self.len(), self.capacity()));
+        }
+        self.push_bytes_truncated(s.as_bytes());
+        Ok(())
+    }
+    /// Returns the spare capacity, which is not reachable by readers.
+    #[cfg(all(feature = "std", feature = "nightly"))]
+    fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<u8>] {
+        let len = self.len();
+        // SAFETY:
+        // * `len <= capacity`.
+        // * the write guard is held, and readers only see the first `len`
+        //   bytes, so the spare capacity is borrowed exclusively.
+        unsafe {
+            let ptr = self.lock.as_non_null_shared().add(len);
+            slice::from_raw_parts_mut(
+                ptr.cast().as_ptr(),
+                self.capacity() - len,
+            )
+        }
+    }
+    /// Reads from `reader` directly into the spare capacity, with a single
+    /// [`read`](io::Read::read) (retried if
+    /// [`Interrupted`](io::ErrorKind::Interrupted)), and publishes the new
+    /// length.
+    ///
+    /// Returns the number of bytes read, which is `0` at EOF or if the
+    /// [`GrowLock`] is full.
+    ///
+    /// # Errors
+    /// Returns any error (other than
+    /// [`Interrupted`](io::ErrorKind::Interrupted)) of `reader`.
+    ///
+    /// This needs the `nightly` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::<u8>::with_capacity(4);
+    /// let mut guard = lock.write().unwrap();
+    /// let mut reader: &[u8] = b"hello";
+    /// assert_eq!(guard.extend_from_reader(&mut reader).unwrap(), 4);
+    /// assert_eq!(lock, *b"hell");
+    /// assert_eq!(guard.extend_from_reader(&mut reader).unwrap(), 0);
+    /// ```
+    #[cfg(all(feature = "std", feature = "nightly"))]
+    pub fn extend_from_reader<R>(
+        &mut self,
+        reader: &mut R,
+    ) -> io::Result<usize>
+    where
+        R: io::Read + ?Sized,
+    {
+        let len = self.len();
+        let mut buf = BorrowedBuf::from(self.spare_capacity_mut());
+        loop {
+            match reader.read_buf(buf.unfilled()) {
+                Ok(()) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let count = buf.len();
+        // SAFETY: the new elements are initialized, and the write guard is
+        // held.
+        unsafe { self.lock.publish_len(len + count) };
+        Ok(count)
+    }
+    /// Reads exactly `count` bytes from `reader` directly into the spare
+    /// capacity, and publishes the new length.
+    ///
+    /// The length is published only once all the bytes are read: on
+    /// error, the [`GrowLock`] is left unchanged (but the bytes read from
+    /// `reader` are lost).
+    ///
+    /// # Errors
+    /// * [`ErrorKind::Other`](io::ErrorKind::Other), wrapping a
+    ///   [`LengthError`], if `count` is greater than the spare capacity.
+    /// * [`ErrorKind::UnexpectedEof`](io::ErrorKind::UnexpectedEof) if
+    ///   `reader` reaches EOF before `count` bytes.
+    /// * Any other error of `reader`.
+    ///
+    /// This needs the `nightly` feature.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::<u8>::with_capacity(10);
+    /// let mut guard = lock.write().unwrap();
+    /// let mut reader: &[u8] = b"hello";
+    /// guard.extend_from_reader_exact(&mut reader, 4).unwrap();
+    /// assert_eq!(lock, *b"hell");
+    /// assert!(guard.extend_from_reader_exact(&mut reader, 2).is_err());
+    /// assert_eq!(lock, *b"hell");
+    /// ```
+    #[cfg(all(feature = "std", feature = "nightly"))]
+    pub fn extend_from_reader_exact<R>(
+        &mut self,
+        reader: &mut R,
+        count: usize,
+    ) -> io::Result<()>
+    where
+        R: io::Read + ?Sized,
+    {
+        let len = self.len();
+        let spare = self.spare_capacity_mut();
+        if count > spare.len() {
+            return Err(io::Error::other(LengthError::new(
+                len,
+                len + spare.len(),
+            )));
+        }
+        let mut buf = BorrowedBuf::from(&mut spare[..count]);
+        reader.read_buf_exact(buf.unfilled())?;
+        // SAFETY: the new elements are initialized, and the write guard is
+        // held.
+        unsafe { self.lock.publish_len(len + count) };
+        Ok(())
+    }
+}
+
+/// Appends the bytes to the [`GrowLock`], up to its capacity.
+///
+/// The length is published after each call, so readers can follow the
+/// bytes as they are written. Once the [`GrowLock`] is full, [`write`]
+/// returns `Ok(0)`, so [`write_all`] fails with
+/// [`ErrorKind::WriteZero`](io::ErrorKind::WriteZero).
+///
+/// [`write`]: io::Write::write
+/// [`write_all`]: io::Write::write_all
+///
+/// # Examples
+/// ```
+/// use growlock::GrowLock;
+/// use std::io::Write;
+///
+/// let lock = GrowLock::<u8>::with_capacity(8);
+/// let mut guard = lock.write().unwrap();
+/// let (a, b) = (12, 34);
+/// write!(guard, "{a}-{b}").unwrap();
+/// assert_eq!(lock, *b"12-34");
+/// assert!(guard.write_all(b"5678").is_err());
+/// assert_eq!(lock, *b"12-34567");
+/// ```
+#[cfg(feature = "std")]
+impl<A: Allocator> io::Write for GrowGuard<'_, u8, A> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.push_bytes_truncated(buf))
+    }
+    fn write_vectored(
+        &mut self,
+        bufs: &[IoSlice<'_>],
+    ) -> io::Result<usize> {
+        let mut written = 0;
+        for buf in bufs {
+            let count = self.push_bytes_truncated(buf);
+            written += count;
+            if count < buf.len() {
+                break;
+            }
+        }
+        Ok(written)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Appends the formatted strings with [`push_str`](GrowGuard::push_str),
+/// so a string which doesn't fit is not pushed at all (and a
+/// [`fmt::Error`] is returned).
+///
+/// Note that with [`write!`], the pieces before the one which didn't fit
+/// are still pushed.
+///
+/// # Examples
+/// ```
+/// use growlock::GrowLock;
+/// use std::fmt::Write;
+///
+/// let lock = GrowLock::<u8>::with_capacity(16);
+/// let mut guard = lock.write().unwrap();
+/// let (key, value) = ("id", 42);
+/// write!(guard, "{key}: {value}").unwrap();
+/// assert_eq!(lock.as_str(), Ok("id: 42"));
+/// ```
+impl<A: Allocator> fmt::Write for GrowGuard<'_, u8, A> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+}
+
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<A: Allocator> OwnedGrowGuard<u8, A> {
+    /// Same as [`GrowGuard::push_bytes_truncated`].
+    fn push_bytes_truncated(&mut self, bytes: &[u8]) -> usize {
+        let lock = Self::lock(self);
+        // SAFETY: the write lock is held, and there are no deferred bytes.
+        unsafe { lock.guarded_push_bytes_truncated(self.len(), bytes) }
+    }
+    /// See [`GrowGuard::push_str`].
+    ///
+    /// # Errors
+    /// Returns an error, without pushing anything, if `s.len()` is
+    /// greater than the spare capacity.
+    #[inline]
+    pub fn push_str(&mut self, s: &str) -> Result<(), LengthError> {
+        if s.len() > self.capacity() - self.len() {
+            return Err(LengthError::new(self.len(), self.capacity()));
+        }
+        self.push_bytes_truncated(s.as_bytes());
+        Ok(())
+    }
+}
+/// See the implementation for [`GrowGuard`].
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<A: Allocator> io::Write for OwnedGrowGuard<u8, A> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(self.push_bytes_truncated(buf))
+    }
+    #[inline]
+    fn write_vectored(
+        &mut self,
+        bufs: &[IoSlice<'_>],
+    ) -> io::Result<usize> {
+        let mut written = 0;
+        for buf in bufs {
+            let count = self.push_bytes_truncated(buf);
+            written += count;
+            if count < buf.len() {
+                break;
+            }
+        }
+        Ok(written)
+    }
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl From<String> for GrowLock<u8> {
+    /// Takes over the allocation of the [`String`], without copying.
+    #[inline]
+    fn from(value: String) -> Self {
+        value.into_bytes().into()
+    }
+}
+impl From<&str> for GrowLock<u8> {
+    /// Copies the bytes of the string in a full [`GrowLock`].
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self::from_str_with_capacity(value, value.len())
+    }
+}
+impl TryFrom<GrowLock<u8>> for String {
+    type Error = FromUtf8Error;
+    /// Converts the [`GrowLock`] to a [`String`], reusing its allocation.
+    ///
+    /// # Errors
+    /// Returns an error (containing the [`GrowLock`]) if the bytes are
+    /// not valid UTF-8.
+    fn try_from(value: GrowLock<u8>) -> Result<Self, Self::Error> {
+        match str::from_utf8(&value) {
+            Ok(_) => {
+                let vec = Vec::from(value);
+                // SAFETY: we just checked that the bytes are valid UTF-8.
+                Ok(unsafe { Self::from_utf8_unchecked(vec) })
+            }
+            Err(error) => Err(FromUtf8Error { lock: value, error }),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Alignment of the fields written by the writers to their own cache
+//! lines.
+
+use core::ops;
+
+/// Aligns `T` to a cache line (and pads it to a whole line) with the
+/// `cache-padded` feature, so that it doesn't share it with other fields.
+/// Without the feature, it's just `T`.
+///
+/// The lines are 128 bytes on the architectures which prefetch them in
+/// pairs (or have bigger ones), and 64 bytes elsewhere.
+#[cfg_attr(
+    all(
+        feature = "cache-padded",
+        any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "powerpc64",
+        ),
+    ),
+    repr(align(128))
+)]
+#[cfg_attr(
+    all(
+        feature = "cache-padded",
+        not(any(
+            target_arch = "x86_64",
+            target_arch = "aarch64",
+            target_arch = "powerpc64",
+        )),
+    ),
+    repr(align(64))
+)]
+pub(crate) struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    #[inline]
+    pub(crate) const fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> ops::Deref for CachePadded<T> {
+    type Target = T;
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> ops::DerefMut for CachePadded<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
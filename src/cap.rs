@@ -1,7 +1,5 @@
 //! Capacity abstraction to permit its invariants.
 
-use std::mem::SizedTypeProperties as _;
-
 /// Representation of the `capacity`.
 ///
 /// # Invariants
@@ -17,10 +15,18 @@ impl Cap {
     /// [`isize::MAX`]. The result is undefined if it is not.
     ///
     /// # Safety
-    /// `cap` must be <= [`isize::MAX`]
+    /// `cap` must be <= [`isize::MAX`] (unless `T` is a ZST)
     #[inline]
     pub(crate) const unsafe fn new_unchecked<T>(cap: usize) -> Self {
-        if T::IS_ZST { Self::ZERO } else { Self(cap) }
+        crate::macros::debug_invariant!(
+            size_of::<T>() == 0 || cap <= isize::MAX as usize,
+            "the capacity overflows `isize::MAX`",
+        );
+        if size_of::<T>() == 0 {
+            Self::ZERO
+        } else {
+            Self(cap)
+        }
     }
 
     /// Creates a new `capacity` if it is <= [`isize::MAX`]
@@ -30,7 +36,7 @@ impl Cap {
     pub(crate) const fn new<T>(cap: usize) -> Option<Self> {
         const I_MAX: usize = isize::MAX as usize;
         match cap {
-            _ if T::IS_ZST => Some(Cap::ZERO),
+            _ if size_of::<T>() == 0 => Some(Cap::ZERO),
             // SAFETY: `cap` is in the correct range of values.
             0..I_MAX => Some(unsafe { Self::new_unchecked::<T>(cap) }),
             _ => None,
@@ -0,0 +1,165 @@
+//! The allocator API used by the crate.
+//!
+//! * With the `allocator-api2` feature, these are the ones of
+//!   [`allocator_api2`], which is implemented by most of the allocator
+//!   crates (like `bumpalo`), also on stable. With the `nightly` feature
+//!   too, they are re-exported by [`allocator_api2`] from
+//!   [`alloc::alloc`].
+//! * With only the `nightly` feature, these are [`Allocator`] and
+//!   [`Global`] of [`alloc::alloc`].
+//! * Without them (on stable), [`Allocator`] is a minimal copy of the
+//!   unstable trait which can't be named (nor implemented) outside of the
+//!   crate, so the only allocators are [`Global`] and (with the `std`
+//!   feature) [`System`](std::alloc::System).
+
+#[cfg(all(
+    any(test, feature = "test-util", feature = "mmap", feature = "mlock"),
+    feature = "nightly",
+    not(feature = "allocator-api2"),
+))]
+pub(crate) use alloc::alloc::AllocError;
+#[cfg(all(feature = "nightly", not(feature = "allocator-api2")))]
+pub(crate) use alloc::alloc::{Allocator, Global};
+
+#[cfg(all(
+    any(test, feature = "test-util", feature = "mmap", feature = "mlock"),
+    feature = "allocator-api2"
+))]
+pub(crate) use allocator_api2::alloc::AllocError;
+#[cfg(feature = "allocator-api2")]
+pub(crate) use allocator_api2::alloc::{Allocator, Global};
+#[cfg(all(
+    any(test, feature = "test-util", feature = "mmap", feature = "mlock"),
+    not(any(feature = "nightly", feature = "allocator-api2")),
+))]
+pub(crate) use stable::AllocError;
+#[cfg(not(any(feature = "nightly", feature = "allocator-api2")))]
+pub(crate) use stable::{Allocator, Global};
+
+#[cfg(not(any(feature = "nightly", feature = "allocator-api2")))]
+mod stable {
+    use {
+        alloc::alloc,
+        core::{
+            alloc::Layout,
+            ptr::{self, NonNull},
+        },
+    };
+
+    /// The error returned by [`Allocator::allocate`].
+    #[derive(Debug)]
+    pub struct AllocError;
+
+    /// See `alloc::alloc::Allocator`.
+    ///
+    /// # Safety
+    /// The blocks returned by [`allocate`](Allocator::allocate) must stay
+    /// valid until they are deallocated, or until the allocator is
+    /// dropped.
+    pub unsafe trait Allocator {
+        /// Allocates a block of memory which fits `layout`.
+        ///
+        /// # Errors
+        /// Returns an error if memory is exhausted.
+        fn allocate(
+            &self,
+            layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError>;
+        /// Deallocates a block of memory returned by
+        /// [`allocate`](Allocator::allocate).
+        ///
+        /// # Safety
+        /// `ptr` must have been allocated by `self`, and `layout` must fit
+        /// the block: its size can be any between the requested one and
+        /// the returned one.
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+    }
+
+    /// Allocates a block with `alloc`, which is only called if the size
+    /// of `layout` is not zero.
+    fn allocate_with(
+        layout: Layout,
+        alloc: impl FnOnce(Layout) -> *mut u8,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            let ptr = ptr::without_provenance_mut(layout.align());
+            // SAFETY: the alignment is never zero.
+            let ptr = unsafe { NonNull::new_unchecked(ptr) };
+            return Ok(NonNull::slice_from_raw_parts(ptr, 0));
+        }
+        NonNull::new(alloc(layout))
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+            .ok_or(AllocError)
+    }
+
+    /// The global memory allocator.
+    #[derive(Clone, Copy, Default, Debug)]
+    pub struct Global;
+
+    // SAFETY: the blocks are allocated with the global allocator.
+    unsafe impl Allocator for Global {
+        #[inline]
+        fn allocate(
+            &self,
+            layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            allocate_with(layout, |layout| {
+                // SAFETY: the size of `layout` is not zero.
+                unsafe { alloc::alloc(layout) }
+            })
+        }
+        #[inline]
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            if layout.size() != 0 {
+                // SAFETY: the safety contract is transferred to the
+                // caller.
+                unsafe { alloc::dealloc(ptr.as_ptr(), layout) }
+            }
+        }
+    }
+
+    // SAFETY: the blocks are allocated with the system allocator.
+    #[cfg(feature = "std")]
+    unsafe impl Allocator for std::alloc::System {
+        #[inline]
+        fn allocate(
+            &self,
+            layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            allocate_with(layout, |layout| {
+                // SAFETY: the size of `layout` is not zero.
+                unsafe { std::alloc::GlobalAlloc::alloc(self, layout) }
+            })
+        }
+        #[inline]
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            if layout.size() != 0 {
+                // SAFETY: the safety contract is transferred to the
+                // caller.
+                unsafe {
+                    std::alloc::GlobalAlloc::dealloc(
+                        self,
+                        ptr.as_ptr(),
+                        layout,
+                    );
+                }
+            }
+        }
+    }
+
+    // SAFETY: the blocks are allocated by `A`.
+    unsafe impl<A: Allocator + ?Sized> Allocator for &A {
+        #[inline]
+        fn allocate(
+            &self,
+            layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            (**self).allocate(layout)
+        }
+        #[inline]
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            // SAFETY: the safety contract is transferred to the caller.
+            unsafe { (**self).deallocate(ptr, layout) }
+        }
+    }
+}
@@ -0,0 +1,337 @@
+//! Cursors which consume the elements of a [`GrowLock`] as they are
+//! pushed.
+
+#[cfg(feature = "std")]
+use {
+    crate::compat::Global,
+    core::{fmt, time::Duration},
+};
+
+use crate::{GrowLock, compat::Allocator, error::StaleCursor};
+
+/// A position in a [`GrowLock`], which returns the elements pushed since
+/// the last call of [`poll`](Cursor::poll).
+///
+/// The cursor doesn't borrow the [`GrowLock`], so it can be stored next to
+/// it. It records the [`generation`](GrowLock::generation) of the
+/// [`GrowLock`] at the first poll, and refuses to read it again if the
+/// elements were removed or moved since then.
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, cursor::Cursor};
+///
+/// let lock = GrowLock::with_capacity(5);
+/// let mut cursor = Cursor::new();
+/// lock.write().unwrap().extend([1, 2]);
+/// assert_eq!(cursor.poll(&lock), [1, 2]);
+/// assert!(cursor.poll(&lock).is_empty());
+///
+/// lock.write().unwrap().push(3);
+/// assert_eq!(cursor.poll(&lock), [3]);
+/// assert_eq!(cursor.position(), 3);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    pos: usize,
+    /// The generation of the `GrowLock`, once it's polled.
+    generation: Option<u64>,
+}
+
+impl Cursor {
+    /// Creates a new [`Cursor`] at the start of the [`GrowLock`].
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::at(0)
+    }
+    /// Creates a new [`Cursor`] at the index `pos`.
+    #[inline]
+    #[must_use]
+    pub const fn at(pos: usize) -> Self {
+        Self {
+            pos,
+            generation: None,
+        }
+    }
+    /// Returns the index of the next element to be returned.
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+    /// Returns the elements pushed since the last call (or since the
+    /// position of the cursor), and moves the cursor after them.
+    ///
+    /// The length is loaded once. If the cursor is past the end of `lock`,
+    /// the returned slice is empty and the cursor doesn't move.
+    ///
+    /// # Panics
+    /// Panics if the cursor is stale (see
+    /// [`try_poll`](Cursor::try_poll)).
+    #[inline]
+    pub fn poll<'a, T, A: Allocator>(
+        &mut self,
+        lock: &'a GrowLock<T, A>,
+    ) -> &'a [T] {
+        match self.try_poll(lock) {
+            Ok(batch) => batch,
+            Err(e) => panic!("{e}"),
+        }
+    }
+    /// Same as [`poll`](Cursor::poll), but returns an error instead of
+    /// panicking if the cursor is stale.
+    ///
+    /// # Errors
+    /// Returns [`StaleCursor`] if the [`generation`](GrowLock::generation)
+    /// of `lock` changed since the first poll. The cursor doesn't move.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, cursor::Cursor, error::StaleCursor};
+    ///
+    /// let mut lock = GrowLock::from_iter_with_capacity(5, [1, 2, 3]);
+    /// let mut cursor = Cursor::new();
+    /// assert_eq!(cursor.try_poll(&lock), Ok(&[1, 2, 3][..]));
+    ///
+    /// lock.clone_from(&GrowLock::from_iter_with_capacity(5, [4]));
+    /// assert_eq!(cursor.try_poll(&lock), Err(StaleCursor));
+    /// ```
+    pub fn try_poll<'a, T, A: Allocator>(
+        &mut self,
+        lock: &'a GrowLock<T, A>,
+    ) -> Result<&'a [T], StaleCursor> {
+        let batch = self.chunk(lock, usize::MAX)?;
+        self.pos += batch.len();
+        Ok(batch)
+    }
+    /// Returns up to `max` elements from the position of the cursor,
+    /// without moving it.
+    fn chunk<'a, T, A: Allocator>(
+        &mut self,
+        lock: &'a GrowLock<T, A>,
+        max: usize,
+    ) -> Result<&'a [T], StaleCursor> {
+        let generation = lock.generation();
+        if *self.generation.get_or_insert(generation) != generation {
+            return Err(StaleCursor);
+        }
+        Ok(lock.chunk_from(self.pos, max))
+    }
+}
+
+/// A [`Cursor`] which returns the new elements of a [`GrowLock`] in
+/// chunks of at most `max` elements.
+///
+/// Like [`Cursor`], it doesn't borrow the [`GrowLock`], and refuses to
+/// read it again if the elements were removed or moved since the first
+/// call.
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, cursor::ChunkCursor};
+///
+/// let lock = GrowLock::from_iter_with_capacity(10, 0..5);
+/// let mut cursor = ChunkCursor::new(2);
+/// assert_eq!(cursor.next_chunk(&lock), Some(&[0, 1][..]));
+/// assert_eq!(cursor.next_chunk(&lock), Some(&[2, 3][..]));
+/// assert_eq!(cursor.next_chunk(&lock), Some(&[4][..]));
+/// assert_eq!(cursor.next_chunk(&lock), None);
+///
+/// lock.write().unwrap().push(5);
+/// assert_eq!(cursor.next_chunk(&lock), Some(&[5][..]));
+/// assert_eq!(cursor.position(), 6);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChunkCursor {
+    cursor: Cursor,
+    max: usize,
+}
+
+impl ChunkCursor {
+    /// Creates a new [`ChunkCursor`] at the start of the [`GrowLock`],
+    /// returning at most `max` elements at a time.
+    ///
+    /// # Panics
+    /// Panics if `max` is `0`.
+    #[inline]
+    #[must_use]
+    pub const fn new(max: usize) -> Self {
+        Self::at(0, max)
+    }
+    /// Creates a new [`ChunkCursor`] at the index `pos`, returning at most
+    /// `max` elements at a time.
+    ///
+    /// # Panics
+    /// Panics if `max` is `0`.
+    #[inline]
+    #[must_use]
+    pub const fn at(pos: usize, max: usize) -> Self {
+        assert!(max != 0, "the chunks of a `ChunkCursor` can't be empty");
+        Self {
+            cursor: Cursor::at(pos),
+            max,
+        }
+    }
+    /// Returns the index of the next element to be returned.
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.cursor.position()
+    }
+    /// Returns the maximum length of the chunks.
+    #[inline]
+    #[must_use]
+    pub const fn max(&self) -> usize {
+        self.max
+    }
+    /// Returns up to [`max`](ChunkCursor::max) of the elements pushed
+    /// since the last chunk (or since the position of the cursor), and
+    /// moves the cursor after them.
+    ///
+    /// The length is loaded once. Returns [`None`] if there are no new
+    /// elements, or if the cursor is past the end of `lock` (then the
+    /// cursor doesn't move).
+    ///
+    /// # Panics
+    /// Panics if the cursor is stale (see
+    /// [`try_next_chunk`](ChunkCursor::try_next_chunk)).
+    #[inline]
+    pub fn next_chunk<'a, T, A: Allocator>(
+        &mut self,
+        lock: &'a GrowLock<T, A>,
+    ) -> Option<&'a [T]> {
+        match self.try_next_chunk(lock) {
+            Ok(chunk) => chunk,
+            Err(e) => panic!("{e}"),
+        }
+    }
+    /// Same as [`next_chunk`](ChunkCursor::next_chunk), but returns an
+    /// error instead of panicking if the cursor is stale.
+    ///
+    /// # Errors
+    /// Returns [`StaleCursor`] if the [`generation`](GrowLock::generation)
+    /// of `lock` changed since the first call. The cursor doesn't move.
+    pub fn try_next_chunk<'a, T, A: Allocator>(
+        &mut self,
+        lock: &'a GrowLock<T, A>,
+    ) -> Result<Option<&'a [T]>, StaleCursor> {
+        let chunk = self.cursor.chunk(lock, self.max)?;
+        if chunk.is_empty() {
+            return Ok(None);
+        }
+        self.cursor.pos += chunk.len();
+        Ok(Some(chunk))
+    }
+}
+
+/// A [`Cursor`] bound to a [`GrowLock`], which blocks until new elements
+/// are pushed.
+///
+/// This structure is created by the [`tail`](GrowLock::tail) method on
+/// [`GrowLock`]. Waiting doesn't spin: the writers wake up the waiting
+/// cursors after publishing new elements.
+#[cfg(feature = "std")]
+pub struct TailCursor<'a, T, A: Allocator = Global> {
+    lock: &'a GrowLock<T, A>,
+    cursor: Cursor,
+}
+
+#[cfg(feature = "std")]
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Returns a [`TailCursor`] starting from the first element.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::thread;
+    ///
+    /// let lock = GrowLock::with_capacity(10);
+    /// thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         for i in 0..10 {
+    ///             lock.write().unwrap().push(i);
+    ///         }
+    ///     });
+    ///
+    ///     let mut tail = lock.tail();
+    ///     let mut sum = 0;
+    ///     // an empty batch means the lock is full, and everything was
+    ///     // read.
+    ///     loop {
+    ///         let batch = tail.next_batch();
+    ///         if batch.is_empty() {
+    ///             break;
+    ///         }
+    ///         sum += batch.iter().sum::<i32>();
+    ///     }
+    ///     assert_eq!(sum, 45);
+    /// });
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn tail(&self) -> TailCursor<'_, T, A> {
+        TailCursor {
+            lock: self,
+            cursor: Cursor::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, T, A: Allocator> TailCursor<'a, T, A> {
+    /// Returns the index of the next element to be returned.
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.cursor.position()
+    }
+    /// Returns the elements pushed since the last call, without blocking.
+    ///
+    /// See [`Cursor::poll`]. The cursor is never stale, because it
+    /// borrows the [`GrowLock`].
+    #[inline]
+    pub fn poll(&mut self) -> &'a [T] {
+        self.cursor.poll(self.lock)
+    }
+    /// Blocks until new elements are pushed, and returns them.
+    ///
+    /// The returned slice is empty only if the [`GrowLock`] is full, and
+    /// every element was already returned.
+    pub fn next_batch(&mut self) -> &'a [T] {
+        self.wait(None);
+        self.poll()
+    }
+    /// Same as [`next_batch`](TailCursor::next_batch), but gives up after
+    /// `timeout`, returning an empty slice.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::time::Duration;
+    ///
+    /// let lock = GrowLock::<u8>::with_capacity(10);
+    /// let mut tail = lock.tail();
+    /// assert!(tail.next_batch_timeout(Duration::from_millis(10)).is_empty());
+    /// ```
+    pub fn next_batch_timeout(&mut self, timeout: Duration) -> &'a [T] {
+        self.wait(Some(timeout));
+        self.poll()
+    }
+    /// Blocks until there are elements to return, or until the
+    /// [`GrowLock`] is full.
+    fn wait(&self, timeout: Option<Duration>) {
+        self.lock
+            .wait_for_len(self.position().saturating_add(1), timeout);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for TailCursor<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TailCursor")
+            .field("lock", self.lock)
+            .field("pos", &self.position())
+            .finish()
+    }
+}
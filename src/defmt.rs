@@ -0,0 +1,86 @@
+//! [`defmt`](::defmt) support, to log the errors and byte
+//! [`GrowLock`]s on embedded targets.
+
+use {
+    crate::{
+        GrowLock,
+        compat::Allocator,
+        error::{FromUtf8Error, TryReserveError},
+    },
+    ::defmt::{Format, Formatter, write},
+};
+
+#[cfg(feature = "bytemuck")]
+use crate::error::ExtendFromBytesError;
+
+/// Maximum number of bytes printed by the [`Format`] implementation of
+/// [`GrowLock<u8>`].
+const PREFIX_LEN: usize = 16;
+
+impl Format for TryReserveError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::CapacityOverflow => write!(
+                f,
+                "memory allocation failed because capacity exceeded maximum"
+            ),
+            Self::AllocError(layout) => write!(
+                f,
+                "memory allocation failed because allocator returned an \
+                 error (size: {=usize}, align: {=usize})",
+                layout.size(),
+                layout.align(),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+impl Format for ExtendFromBytesError {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            Self::Cast(e) => write!(f, "{}", ::defmt::Debug2Format(e)),
+            Self::Length(e) => e.format(f),
+        }
+    }
+}
+
+impl Format for FromUtf8Error {
+    fn format(&self, f: Formatter<'_>) {
+        write!(
+            f,
+            "invalid utf-8 sequence after {=usize} valid bytes in {}",
+            self.error.valid_up_to(),
+            self.lock,
+        );
+    }
+}
+
+/// Prints the length, the capacity, and the first bytes (in hex) of the
+/// [`GrowLock`].
+impl<A: Allocator> Format for GrowLock<u8, A> {
+    fn format(&self, f: Formatter<'_>) {
+        let bytes = self.as_slice();
+        if let Some(prefix) = bytes.get(..PREFIX_LEN)
+            && bytes.len() > PREFIX_LEN
+        {
+            write!(
+                f,
+                "GrowLock {{ len: {=usize}, capacity: {=usize}, bytes: \
+                 {=[u8]:02x}.. }}",
+                bytes.len(),
+                self.capacity(),
+                prefix,
+            );
+        } else {
+            write!(
+                f,
+                "GrowLock {{ len: {=usize}, capacity: {=usize}, bytes: \
+                 {=[u8]:02x} }}",
+                bytes.len(),
+                self.capacity(),
+                bytes,
+            );
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Helper to drop the elements of a lock in reverse order, newest first.
+
+use core::{mem, ptr};
+
+/// Drops the `len` elements starting from `ptr`, from the last one to the
+/// first one.
+///
+/// If a `drop` panics, the remaining elements are still dropped (in
+/// reverse order) while unwinding, like [`ptr::drop_in_place`] does for a
+/// slice.
+///
+/// # Safety
+/// The `len` elements starting from `ptr` must be initialized, properly
+/// aligned, and never used again.
+pub(crate) unsafe fn drop_in_place_rev<T>(ptr: *mut T, len: usize) {
+    /// The elements not dropped yet: `drop` only runs while unwinding,
+    /// as `self` is forgotten after the loop.
+    struct Rest<T> {
+        ptr: *mut T,
+        len: usize,
+    }
+    impl<T> Drop for Rest<T> {
+        fn drop(&mut self) {
+            // SAFETY: the first `len` elements are not dropped yet.
+            unsafe { drop_in_place_rev(self.ptr, self.len) };
+        }
+    }
+
+    if !mem::needs_drop::<T>() {
+        return;
+    }
+    let mut rest = Rest { ptr, len };
+    while rest.len > 0 {
+        rest.len -= 1;
+        // SAFETY: the element at `len` is initialized, and it's no longer
+        // reachable through `rest`.
+        unsafe { ptr::drop_in_place(rest.ptr.add(rest.len)) };
+    }
+    mem::forget(rest);
+}
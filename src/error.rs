@@ -1,4 +1,16 @@
-use {std::alloc::Layout, thiserror::Error};
+#[cfg(feature = "nightly")]
+use alloc::collections::TryReserveErrorKind;
+#[cfg(feature = "std")]
+use std::io;
+
+use {
+    crate::{
+        GrowLock,
+        sync::{PoisonError, TryLockError},
+    },
+    core::{alloc::Layout, str::Utf8Error},
+    thiserror::Error,
+};
 
 /// Error type for `try_with_capacity` methods.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
@@ -10,16 +22,370 @@ pub enum TryReserveError {
     )]
     AllocError(Layout),
 }
+impl TryReserveError {
+    /// Returns the layout of the allocation which failed, or [`None`] if
+    /// the capacity overflowed.
+    #[inline]
+    #[must_use]
+    pub const fn layout(&self) -> Option<Layout> {
+        match self {
+            Self::CapacityOverflow => None,
+            Self::AllocError(layout) => Some(*layout),
+        }
+    }
+}
 impl From<Layout> for TryReserveError {
     #[inline]
     fn from(e: Layout) -> Self {
         Self::AllocError(e)
     }
 }
+#[cfg(feature = "nightly")]
+impl From<TryReserveError> for alloc::collections::TryReserveError {
+    #[inline]
+    fn from(e: TryReserveError) -> Self {
+        match e {
+            TryReserveError::CapacityOverflow => {
+                TryReserveErrorKind::CapacityOverflow.into()
+            }
+            TryReserveError::AllocError(layout) => {
+                TryReserveErrorKind::AllocError {
+                    layout,
+                    non_exhaustive: (),
+                }
+                .into()
+            }
+        }
+    }
+}
+#[cfg(feature = "nightly")]
+impl From<alloc::collections::TryReserveError> for TryReserveError {
+    #[inline]
+    fn from(e: alloc::collections::TryReserveError) -> Self {
+        match e.kind() {
+            TryReserveErrorKind::CapacityOverflow => {
+                Self::CapacityOverflow
+            }
+            TryReserveErrorKind::AllocError { layout, .. } => {
+                Self::AllocError(layout)
+            }
+        }
+    }
+}
+/// The error of a failed allocation is an
+/// [`ErrorKind::OutOfMemory`](io::ErrorKind::OutOfMemory).
+#[cfg(feature = "std")]
+impl From<TryReserveError> for io::Error {
+    #[inline]
+    fn from(e: TryReserveError) -> Self {
+        Self::new(io::ErrorKind::OutOfMemory, e)
+    }
+}
+
+/// Error type for
+/// [`try_from_vec_with_capacity`](GrowLock::try_from_vec_with_capacity).
+///
+/// # Examples
+/// ```
+/// use growlock::{
+///     GrowLock,
+///     error::{FromVecError, LengthError},
+/// };
+///
+/// let err = GrowLock::try_from_vec_with_capacity(vec![1, 2, 3], 2);
+/// assert_eq!(err, Err(FromVecError::Length(LengthError::new(3, 2))));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum FromVecError {
+    /// The capacity is smaller than the length of the [`Vec`].
+    #[error(transparent)]
+    Length(#[from] LengthError),
+    /// The [`Vec`] couldn't be reallocated to the capacity.
+    #[error(transparent)]
+    Alloc(#[from] TryReserveError),
+}
 
-/// Error type for `try_push` method.
+/// Error type for the methods which push more elements than the spare
+/// capacity of a [`GrowLock`].
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, error::LengthError};
+///
+/// let lock = GrowLock::<u8>::with_capacity(4);
+/// let mut guard = lock.write().unwrap();
+/// guard.push_str("abc").unwrap();
+/// let err = guard.push_str("de").unwrap_err();
+/// assert_eq!(err, LengthError::new(3, 4));
+/// assert_eq!((err.len, err.capacity), (3, 4));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[error(
+    "tried to push past the capacity of the `GrowLock` (length: {len}, \
+     capacity: {capacity})"
+)]
+#[non_exhaustive]
+pub struct LengthError {
+    /// The length of the [`GrowLock`] when the elements were pushed.
+    pub len: usize,
+    /// The capacity of the [`GrowLock`].
+    pub capacity: usize,
+}
+impl LengthError {
+    /// Creates a new [`LengthError`], for a [`GrowLock`] with the given
+    /// length and capacity.
+    #[inline]
+    #[must_use]
+    pub const fn new(len: usize, capacity: usize) -> Self {
+        Self { len, capacity }
+    }
+}
+impl<T> From<PushError<T>> for LengthError {
+    #[inline]
+    fn from(e: PushError<T>) -> Self {
+        Self::new(e.capacity, e.capacity)
+    }
+}
+
+/// Error type for the `try_push` methods of the write guards: the
+/// [`GrowLock`] is full, and the value is given back.
+///
+/// It can be converted to a [`LengthError`] (dropping the value), to
+/// migrate the code written for the older `try_push`.
+///
+/// # Examples
+/// ```
+/// use growlock::{
+///     GrowLock,
+///     error::{LengthError, PushError},
+/// };
+///
+/// let lock = GrowLock::with_capacity(1);
+/// let mut guard = lock.write().unwrap();
+/// guard.push(String::from("a"));
+/// let err = guard.try_push(String::from("b")).unwrap_err();
+/// assert_eq!(err, PushError::new(String::from("b"), 1));
+/// assert_eq!(LengthError::from(err), LengthError::new(1, 1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[error(
+    "tried to push to the `GrowLock`, but the `GrowLock` is already full \
+     (capacity: {capacity})"
+)]
+#[non_exhaustive]
+pub struct PushError<T> {
+    /// The value that couldn't be pushed.
+    pub value: T,
+    /// The capacity of the [`GrowLock`], which is also its length.
+    pub capacity: usize,
+}
+impl<T> PushError<T> {
+    /// Creates a new [`PushError`], which gives back `value`, for a full
+    /// [`GrowLock`] with the given capacity.
+    #[inline]
+    pub const fn new(value: T, capacity: usize) -> Self {
+        Self { value, capacity }
+    }
+    /// Returns the value that couldn't be pushed.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+/// Error type for [`Cursor::try_poll`](crate::cursor::Cursor::try_poll).
+///
+/// The elements of the [`GrowLock`] were removed, moved or overwritten
+/// since the cursor last read them (see
+/// [`generation`](GrowLock::generation)), so its position is meaningless.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[error(
-    "tried to push to the `GrowLock`, but the `GrowLock` is already full"
+    "the elements of the `GrowLock` changed since the cursor read them"
 )]
-pub struct LengthError;
+pub struct StaleCursor;
+
+/// Error type for methods that allocate a [`GrowLock`](crate::GrowLock)
+/// and then fill it, like
+/// [`try_from_iter_with_capacity`](crate::GrowLock::try_from_iter_with_capacity)
+/// and [`try_grow_lock!`](crate::try_grow_lock).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TryFillError {
+    /// The allocation failed.
+    #[error(transparent)]
+    Reserve(#[from] TryReserveError),
+    /// There were more elements than the capacity.
+    #[error(transparent)]
+    Length(#[from] LengthError),
+}
+impl<T> From<PushError<T>> for TryFillError {
+    #[inline]
+    fn from(e: PushError<T>) -> Self {
+        Self::Length(e.into())
+    }
+}
+
+/// Error type covering every way a fallible operation on a [`GrowLock`]
+/// can fail, to match on a single type.
+///
+/// It's returned by [`GrowLock::try_push`],
+/// [`GrowLock::push_nonblocking`] and [`GrowLock::alloc`], and the errors
+/// of the other fallible methods convert to it (with `?`). `T` is the
+/// type of the value given back when it couldn't be pushed, `()` for the
+/// methods which push nothing.
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, error::GrowLockError};
+///
+/// fn log(lock: &GrowLock<u8>, line: &str) -> Result<(), GrowLockError> {
+///     let mut guard = lock.try_write()?;
+///     guard.push_str(line)?;
+///     Ok(())
+/// }
+///
+/// let lock = GrowLock::with_capacity(4);
+/// log(&lock, "ab").unwrap();
+/// let err = log(&lock, "cde").unwrap_err();
+/// assert_eq!(
+///     err,
+///     GrowLockError::Full { value: (), len: 2, capacity: 4 },
+/// );
+/// let guard = lock.write().unwrap();
+/// assert_eq!(log(&lock, "c"), Err(GrowLockError::WouldBlock(())));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum GrowLockError<T = ()> {
+    /// The elements don't fit in the spare capacity. `value` is given
+    /// back.
+    #[error(
+        "the elements don't fit in the `GrowLock` (length: {len}, \
+         capacity: {capacity})"
+    )]
+    Full {
+        value: T,
+        len: usize,
+        capacity: usize,
+    },
+    /// The write lock is held by another writer. The value is given
+    /// back.
+    #[error("the write lock of the `GrowLock` is held")]
+    WouldBlock(T),
+    /// The write lock couldn't be acquired in time. The value is given
+    /// back.
+    #[error("timed out waiting for the write lock of the `GrowLock`")]
+    TimedOut(T),
+    /// The write lock is poisoned (another writer panicked while holding
+    /// it). Like [`GrowLock::push`], the value is pushed anyway.
+    #[error("the write lock of the `GrowLock` is poisoned")]
+    Poisoned,
+    /// The allocation failed.
+    #[error(transparent)]
+    Alloc(#[from] TryReserveError),
+}
+impl<T> GrowLockError<T> {
+    /// Returns the value that couldn't be pushed, if any.
+    #[inline]
+    pub fn into_value(self) -> Option<T> {
+        match self {
+            Self::Full { value, .. }
+            | Self::WouldBlock(value)
+            | Self::TimedOut(value) => Some(value),
+            Self::Poisoned | Self::Alloc(_) => None,
+        }
+    }
+}
+impl<T> From<PushError<T>> for GrowLockError<T> {
+    #[inline]
+    fn from(e: PushError<T>) -> Self {
+        Self::Full {
+            value: e.value,
+            len: e.capacity,
+            capacity: e.capacity,
+        }
+    }
+}
+impl From<LengthError> for GrowLockError {
+    #[inline]
+    fn from(e: LengthError) -> Self {
+        Self::Full {
+            value: (),
+            len: e.len,
+            capacity: e.capacity,
+        }
+    }
+}
+impl From<TryFillError> for GrowLockError {
+    #[inline]
+    fn from(e: TryFillError) -> Self {
+        match e {
+            TryFillError::Reserve(e) => e.into(),
+            TryFillError::Length(e) => e.into(),
+        }
+    }
+}
+/// The guard is dropped: the write lock is released.
+impl<T, G> From<PoisonError<G>> for GrowLockError<T> {
+    #[inline]
+    fn from(_: PoisonError<G>) -> Self {
+        Self::Poisoned
+    }
+}
+/// The guard is dropped: the write lock is released.
+impl<G> From<TryLockError<G>> for GrowLockError {
+    #[inline]
+    fn from(e: TryLockError<G>) -> Self {
+        match e {
+            TryLockError::Poisoned(_) => Self::Poisoned,
+            TryLockError::WouldBlock => Self::WouldBlock(()),
+        }
+    }
+}
+
+/// Error type for
+/// [`extend_from_bytes`](crate::guard::GrowGuard::extend_from_bytes).
+#[cfg(feature = "bytemuck")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum ExtendFromBytesError {
+    /// The bytes can't be cast to whole elements.
+    #[error(transparent)]
+    Cast(#[from] bytemuck::PodCastError),
+    /// There were more elements than the spare capacity.
+    #[error(transparent)]
+    Length(#[from] LengthError),
+}
+
+/// Error type for the conversion from [`GrowLock<u8>`] to [`String`].
+///
+/// The [`GrowLock`] which failed the conversion can be recovered with
+/// [`into_lock`](FromUtf8Error::into_lock).
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{error}")]
+pub struct FromUtf8Error {
+    pub(crate) lock: GrowLock<u8>,
+    pub(crate) error: Utf8Error,
+}
+impl FromUtf8Error {
+    /// Returns the bytes that were attempted to convert.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.lock
+    }
+    /// Returns the [`GrowLock`] that was attempted to convert.
+    #[inline]
+    #[must_use]
+    pub fn into_lock(self) -> GrowLock<u8> {
+        self.lock
+    }
+    /// Returns the underlying [`Utf8Error`], with details about the
+    /// conversion failure.
+    #[inline]
+    #[must_use]
+    pub const fn utf8_error(&self) -> Utf8Error {
+        self.error
+    }
+}
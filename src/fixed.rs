@@ -0,0 +1,431 @@
+//! The fixed-capacity locks: the length and the write lock, shared by
+//! the locks which store their elements inline.
+
+// not the ones of `loom`, which can't be created in a `const`.
+#[cfg(feature = "std")]
+use std::sync::{Mutex, MutexGuard};
+
+use {
+    crate::{
+        drop_order::drop_in_place_rev,
+        sync::{LockResult, TryLockResult},
+    },
+    core::{
+        cell::UnsafeCell,
+        marker::PhantomData,
+        mem::{self, MaybeUninit},
+        ptr, slice,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+};
+
+#[cfg(not(feature = "std"))]
+use crate::sync::{Mutex, MutexGuard};
+
+/// The guard of the write lock of a [`Fixed`] lock.
+pub(crate) type FixedGuard<'a> = MutexGuard<'a, ()>;
+
+/// The buffer of a [`Fixed`] lock.
+///
+/// # Safety
+/// [`as_ptr`](Slots::as_ptr) must return a pointer aligned for `T`,
+/// valid for reads and writes of [`capacity`](Slots::capacity) elements
+/// through a shared reference, which doesn't change while `self` is not
+/// moved.
+pub(crate) unsafe trait Slots<T> {
+    fn as_ptr(&self) -> *mut T;
+    fn capacity(&self) -> usize;
+}
+
+/// The length and the write lock of a fixed-capacity buffer: everything
+/// the fixed-capacity locks share, except where their elements are
+/// stored.
+///
+/// The elements are dropped with the [`Fixed`], and the [`Slots`] only
+/// free the memory.
+pub(crate) struct Fixed<T, S: Slots<T>> {
+    pub(crate) slots: S,
+    len: AtomicUsize,
+    mutex: Mutex<()>,
+    /// Elements can be pushed through a shared reference (see
+    /// [`GrowLock`](crate::GrowLock)).
+    _invariant: PhantomData<fn(T) -> T>,
+}
+
+impl<T, S: Slots<T>> Fixed<T, S> {
+    pub(crate) const fn new(slots: S) -> Self {
+        Self {
+            slots,
+            len: AtomicUsize::new(0),
+            mutex: Mutex::new(()),
+            _invariant: PhantomData,
+        }
+    }
+    #[inline]
+    pub(crate) fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized, and they are
+        // never written again while `self` is shared.
+        unsafe {
+            slice::from_raw_parts(
+                self.slots.as_ptr(),
+                self.len.load(Ordering::Acquire),
+            )
+        }
+    }
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+    pub(crate) fn write(&self) -> LockResult<FixedGuard<'_>> {
+        self.mutex.lock()
+    }
+    pub(crate) fn try_write(&self) -> TryLockResult<FixedGuard<'_>> {
+        self.mutex.try_lock()
+    }
+    /// Appends `value`, or gives it back if the buffer is full.
+    ///
+    /// # Safety
+    /// The caller must hold the write lock.
+    pub(crate) unsafe fn guarded_try_push(
+        &self,
+        value: T,
+    ) -> Result<(), T> {
+        // the caller guarantees that writes cannot happen.
+        let len = self.len.load(Ordering::Relaxed);
+        if len >= self.capacity() {
+            return Err(value);
+        }
+        // SAFETY: `len < capacity`, and the caller guarantees we have
+        // write access.
+        unsafe { self.slots.as_ptr().add(len).write(value) };
+        crate::macros::debug_invariant!(len < self.capacity());
+        self.len.store(len + 1, Ordering::Release);
+        Ok(())
+    }
+    /// Appends every element of `iter`, like
+    /// [`GrowLock::guarded_extend`](crate::GrowLock::guarded_extend):
+    /// they're published all at once, or not at all.
+    ///
+    /// # Panics
+    /// Panics if the iterator has more elements than the spare capacity
+    /// (before consuming any element, if its lower size bound already
+    /// doesn't fit).
+    ///
+    /// # Safety
+    /// The caller must hold the write lock.
+    pub(crate) unsafe fn guarded_extend(
+        &self,
+        iter: impl IntoIterator<Item = T>,
+    ) {
+        /// The elements written (but not published) by `guarded_extend`,
+        /// which are dropped if the batch is abandoned.
+        struct Batch<T> {
+            ptr: *mut T,
+            written: usize,
+        }
+        impl<T> Drop for Batch<T> {
+            fn drop(&mut self) {
+                // SAFETY: the first `written` elements are initialized,
+                // and they were never published.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.ptr,
+                        self.written,
+                    ));
+                }
+            }
+        }
+
+        let iter = iter.into_iter();
+        let len = self.len.load(Ordering::Relaxed);
+        let spare = self.capacity() - len;
+        assert!(iter.size_hint().0 <= spare, "length overflow");
+        let mut batch = Batch {
+            // SAFETY: `len <= capacity`, so the ptr is still in the
+            // buffer.
+            ptr: unsafe { self.slots.as_ptr().add(len) },
+            written: 0,
+        };
+        for elem in iter {
+            // `elem` and the batch are dropped while unwinding.
+            assert!(batch.written < spare, "length overflow");
+            // SAFETY: `len + written < capacity`, and the caller holds the
+            // write lock.
+            unsafe { batch.ptr.add(batch.written).write(elem) };
+            batch.written += 1;
+        }
+        let written = batch.written;
+        mem::forget(batch);
+        crate::macros::debug_invariant!(written <= spare);
+        self.len.store(len + written, Ordering::Release);
+    }
+}
+
+impl<T, S: Slots<T>> Drop for Fixed<T, S> {
+    fn drop(&mut self) {
+        // SAFETY: the first `len` elements are initialized, and they are
+        // never used again.
+        unsafe {
+            drop_in_place_rev(self.slots.as_ptr(), *self.len.get_mut());
+        }
+    }
+}
+
+/// `N` slots, stored inline.
+pub(crate) struct ArraySlots<T, const N: usize>(
+    UnsafeCell<[MaybeUninit<T>; N]>,
+);
+
+impl<T, const N: usize> ArraySlots<T, N> {
+    pub(crate) const fn new() -> Self {
+        Self(UnsafeCell::new([const { MaybeUninit::uninit() }; N]))
+    }
+}
+
+// SAFETY: the slots are inside an `UnsafeCell`, and they only move with
+// `self`.
+unsafe impl<T, const N: usize> Slots<T> for ArraySlots<T, N> {
+    #[inline]
+    fn as_ptr(&self) -> *mut T {
+        self.0.get().cast()
+    }
+    #[inline]
+    fn capacity(&self) -> usize {
+        // like in `GrowLock`, any number of ZSTs fit.
+        if size_of::<T>() == 0 { usize::MAX } else { N }
+    }
+}
+
+/// Implements the API shared by the fixed-capacity locks for
+/// `$lock<T, N>`, which must have an `inner: Fixed<T, _>` field, and
+/// declares its write guard `$guard<'lock, T, N>`.
+macro_rules! fixed_lock {
+    ($lock:ident, $guard:ident) => {
+        /// # Safety:
+        /// Same as [`GrowLock`](crate::GrowLock): the elements are owned
+        /// by the lock.
+        unsafe impl<T: Send, const N: usize> Send for $lock<T, N> {}
+        /// # Safety:
+        /// Same as [`GrowLock`](crate::GrowLock): the elements are only
+        /// written with the mutex locked, and only read once they are
+        /// published.
+        unsafe impl<T: Send + Sync, const N: usize> Sync for $lock<T, N> {}
+
+        impl<T, const N: usize> $lock<T, N> {
+            #[inline]
+            #[must_use]
+            pub fn as_slice(&self) -> &[T] {
+                self.inner.as_slice()
+            }
+            #[inline]
+            #[must_use]
+            pub fn len(&self) -> usize {
+                self.inner.len()
+            }
+            #[inline]
+            #[must_use]
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+            #[inline]
+            #[must_use]
+            pub fn capacity(&self) -> usize {
+                self.inner.capacity()
+            }
+            /// Locks this lock with exclusive write access, blocking the
+            /// current thread until it can be acquired.
+            ///
+            /// Readers are never blocked by the returned guard.
+            ///
+            /// # Errors
+            /// Returns an error if the lock is poisoned (another writer
+            /// panicked while holding the lock). The guard is still
+            /// returned inside the error.
+            #[inline]
+            pub fn write(
+                &self,
+            ) -> $crate::sync::LockResult<$guard<'_, T, N>> {
+                match self.inner.write() {
+                    Ok(guard) => Ok($guard::new(self, guard)),
+                    Err(e) => Err($crate::sync::PoisonError::new(
+                        $guard::new(self, e.into_inner()),
+                    )),
+                }
+            }
+            /// Attempts to lock this lock with exclusive write access.
+            ///
+            /// This function does not block.
+            ///
+            /// # Errors
+            /// * [`TryLockError::WouldBlock`] if the lock is already held.
+            /// * [`TryLockError::Poisoned`] if the lock is poisoned. The
+            ///   guard is still returned inside the error.
+            ///
+            /// [`TryLockError::WouldBlock`]: crate::sync::TryLockError::WouldBlock
+            /// [`TryLockError::Poisoned`]: crate::sync::TryLockError::Poisoned
+            #[inline]
+            pub fn try_write(
+                &self,
+            ) -> $crate::sync::TryLockResult<$guard<'_, T, N>> {
+                use $crate::sync::{PoisonError, TryLockError};
+                match self.inner.try_write() {
+                    Ok(guard) => Ok($guard::new(self, guard)),
+                    Err(TryLockError::Poisoned(e)) => {
+                        Err(TryLockError::Poisoned(PoisonError::new(
+                            $guard::new(self, e.into_inner()),
+                        )))
+                    }
+                    Err(TryLockError::WouldBlock) => {
+                        Err(TryLockError::WouldBlock)
+                    }
+                }
+            }
+        }
+
+        impl<T, const N: usize> Default for $lock<T, N> {
+            #[inline]
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T, const N: usize> ::core::ops::Deref for $lock<T, N> {
+            type Target = [T];
+            #[inline]
+            fn deref(&self) -> &[T] {
+                self.as_slice()
+            }
+        }
+
+        impl<T: ::core::fmt::Debug, const N: usize> ::core::fmt::Debug
+            for $lock<T, N>
+        {
+            #[inline]
+            fn fmt(
+                &self,
+                f: &mut ::core::fmt::Formatter<'_>,
+            ) -> ::core::fmt::Result {
+                ::core::fmt::Debug::fmt(&**self, f)
+            }
+        }
+
+        #[doc = concat!(
+            "RAII structure used to release the exclusive write access of ",
+            "an [`", stringify!($lock), "`] when dropped.\n\n",
+            "This structure is created by the [`write`](", stringify!($lock),
+            "::write) and [`try_write`](", stringify!($lock), "::try_write) ",
+            "methods on [`", stringify!($lock), "`]. It has the same API as a ",
+            "[`GrowGuard`](crate::guard::GrowGuard).",
+        )]
+        pub struct $guard<'lock, T, const N: usize> {
+            lock: &'lock $lock<T, N>,
+            _guard: $crate::fixed::FixedGuard<'lock>,
+        }
+
+        impl<'lock, T, const N: usize> $guard<'lock, T, N> {
+            const fn new(
+                lock: &'lock $lock<T, N>,
+                guard: $crate::fixed::FixedGuard<'lock>,
+            ) -> Self {
+                Self {
+                    lock,
+                    _guard: guard,
+                }
+            }
+            #[inline]
+            #[must_use]
+            pub fn as_slice(&self) -> &[T] {
+                self.lock.as_slice()
+            }
+            #[inline]
+            #[must_use]
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+            #[inline]
+            #[must_use]
+            pub fn is_full(&self) -> bool {
+                self.len() == self.capacity()
+            }
+            #[inline]
+            #[must_use]
+            pub fn capacity(&self) -> usize {
+                self.lock.capacity()
+            }
+            #[inline]
+            #[must_use]
+            pub fn len(&self) -> usize {
+                self.lock.len()
+            }
+            /// # Panics
+            /// Panics if `self.is_full()`.
+            #[inline]
+            pub fn push(&mut self, value: T) {
+                assert!(self.try_push(value).is_ok(), "length overflow");
+            }
+            /// # Errors
+            /// Returns an error if `self.is_full()`.
+            #[inline]
+            pub fn try_push(
+                &mut self,
+                value: T,
+            ) -> Result<(), $crate::error::PushError<T>> {
+                // SAFETY: the write guard is held.
+                unsafe { self.lock.inner.guarded_try_push(value) }
+                    .map_err(|value| {
+                        $crate::error::PushError::new(value, self.capacity())
+                    })
+            }
+        }
+
+        impl<T, const N: usize> ::core::ops::Deref for $guard<'_, T, N> {
+            type Target = [T];
+            #[inline]
+            fn deref(&self) -> &[T] {
+                self.as_slice()
+            }
+        }
+
+        impl<T, const N: usize> Extend<T> for $guard<'_, T, N> {
+            /// Extends the lock with the contents of an iterator,
+            /// publishing the elements all at once (like
+            /// [`GrowGuard`](crate::guard::GrowGuard)).
+            ///
+            /// # Panics
+            /// This panics if the iterator has more elements than
+            /// `self.capacity() - self.len()`, without publishing any of
+            /// them (and before consuming any element, if its lower size
+            /// bound already doesn't fit).
+            #[inline]
+            fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+                // SAFETY: the write guard is held.
+                unsafe { self.lock.inner.guarded_extend(iter) };
+            }
+        }
+
+        impl<T, const N: usize> $crate::guard::WriteGuard<T>
+            for $guard<'_, T, N>
+        {
+            #[inline]
+            fn capacity(&self) -> usize {
+                Self::capacity(self)
+            }
+            #[inline]
+            fn push(&mut self, value: T) {
+                Self::push(self, value);
+            }
+            #[inline]
+            fn try_push(
+                &mut self,
+                value: T,
+            ) -> Result<(), $crate::error::PushError<T>> {
+                Self::try_push(self, value)
+            }
+        }
+    };
+}
+pub(crate) use fixed_lock;
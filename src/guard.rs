@@ -1,14 +1,20 @@
-#[cfg(not(loom))]
-use std::sync::{MutexGuard, atomic::Ordering};
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+use alloc::sync::Arc;
 
-#[cfg(loom)]
-use loom::sync::{MutexGuard, atomic::Ordering};
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
 use {
-    crate::{GrowLock, error::LengthError},
-    std::{
-        alloc::{Allocator, Global},
-        ops,
+    crate::sync::{LockResult, PoisonError},
+    core::time::Duration,
+    std::sync::{Condvar, WaitTimeoutResult},
+};
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        error::PushError,
+        sync::{MutexGuard, atomic::Ordering},
     },
+    core::{cmp, mem::ManuallyDrop, ops, slice},
 };
 
 /// RAII structure used to release the exclusive write access of a lock
@@ -17,11 +23,35 @@ use {
 /// This structure is created by the [`write`][write] and
 /// [`try_write`][try_write] method on [`GrowLock`]
 ///
+/// The elements pushed with [`push_deferred`](GrowGuard::push_deferred)
+/// are only published by [`publish`](GrowGuard::publish), or when the
+/// guard is dropped.
+///
 /// [write]: GrowLock::write
 /// [try_write]: GrowLock::try_write
 pub struct GrowGuard<'lock, T, A: Allocator = Global> {
-    lock: &'lock GrowLock<T, A>,
-    _guard: MutexGuard<'lock, ()>,
+    pub(crate) lock: &'lock GrowLock<T, A>,
+    /// The length including the deferred elements, if there are any.
+    written: usize,
+    /// Dropped by hand, to wake up the async writers after the unlock.
+    guard: ManuallyDrop<MutexGuard<'lock, ()>>,
+}
+
+impl<T, A: Allocator> Drop for GrowGuard<'_, T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        // also when unwinding: the deferred elements are initialized.
+        self.publish();
+        // wakes up the readers which could have been missed by the
+        // pushes.
+        self.lock.notifier.notify_unlock(self.len());
+        self.lock.open_lockfree();
+        self.lock.owner.clear();
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+        #[cfg(feature = "tokio")]
+        self.lock.notifier.wake_async_writers();
+    }
 }
 
 impl<T, A: Allocator> ops::Deref for GrowGuard<'_, T, A> {
@@ -34,19 +64,52 @@ impl<T, A: Allocator> ops::Deref for GrowGuard<'_, T, A> {
 impl<'lock, T, A: Allocator> GrowGuard<'lock, T, A> {
     #[inline]
     #[must_use]
-    pub(super) const fn new(
+    pub(super) fn new(
+        lock: &'lock GrowLock<T, A>,
+        guard: MutexGuard<'lock, ()>,
+    ) -> Self {
+        lock.close_lockfree();
+        Self::locked(lock, guard)
+    }
+    /// Same as [`new`](GrowGuard::new), but unlocks `guard` and returns
+    /// [`None`] if the lock-free writers in progress don't finish within
+    /// `spins` iterations of [`spin_loop`](core::hint::spin_loop).
+    #[inline]
+    pub(super) fn try_new(
+        lock: &'lock GrowLock<T, A>,
+        guard: MutexGuard<'lock, ()>,
+        spins: &mut usize,
+    ) -> Option<Self> {
+        if lock.try_close_lockfree(spins) {
+            return Some(Self::locked(lock, guard));
+        }
+        drop(guard);
+        #[cfg(feature = "tokio")]
+        lock.notifier.wake_async_writers();
+        None
+    }
+    /// Called once the lock-free writers are closed.
+    #[inline]
+    fn locked(
         lock: &'lock GrowLock<T, A>,
         guard: MutexGuard<'lock, ()>,
     ) -> Self {
+        #[cfg(feature = "tokio")]
+        lock.notifier.wait_parked();
+        lock.owner.set();
         Self {
             lock,
-            _guard: guard,
+            written: 0,
+            guard: ManuallyDrop::new(guard),
         }
     }
+    /// Returns the elements, including the deferred ones.
     #[inline]
     #[must_use]
     pub fn as_slice(&self) -> &[T] {
-        self.lock.as_slice()
+        // SAFETY: the first `self.len()` elements are initialized, and
+        // the deferred ones are only written through `&mut self`.
+        unsafe { slice::from_raw_parts(self.lock.as_ptr(), self.len()) }
     }
     #[inline]
     #[must_use]
@@ -63,62 +126,647 @@ impl<'lock, T, A: Allocator> GrowGuard<'lock, T, A> {
     pub const fn capacity(&self) -> usize {
         self.lock.capacity()
     }
+    /// Returns the number of elements, including the deferred ones.
     #[inline]
     #[must_use]
     pub fn len(&self) -> usize {
         // We locked the mutex so writes cannot happen.
-        self.lock.len.load(Ordering::Relaxed)
+        let published = self.lock.len.load(Ordering::Relaxed);
+        // the other writes publish the deferred elements too.
+        cmp::max(self.written, published)
     }
     /// # Panics
     /// Panics if `self.is_full()`.
+    #[inline]
     pub fn push(&mut self, value: T) {
+        self.publish();
+        // SAFETY: the write guard is held.
+        unsafe { self.lock.guarded_push(value) };
+    }
+    /// # Errors
+    /// Returns an error, which gives back `value`, if `self.is_full()`.
+    #[inline]
+    pub fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        self.publish();
+        // SAFETY: the write guard is held.
+        unsafe { self.lock.guarded_try_push(value) }
+            .map_err(|value| PushError::new(value, self.lock.capacity()))
+    }
+    /// Appends `value` without publishing it: the readers see it only
+    /// after the next [`publish`](GrowGuard::publish) (or any other write
+    /// through this guard), or when the guard is dropped.
+    ///
+    /// This skips the `Release` store of the length (and the wake-up of
+    /// the waiting readers) done by [`push`](GrowGuard::push), so the
+    /// readers see the elements in batches. If the thread panics, the
+    /// elements pushed until then are published when the guard is
+    /// dropped.
+    ///
+    /// # Errors
+    /// Returns an error if `self.is_full()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::with_capacity(4);
+    /// let mut guard = lock.write().unwrap();
+    /// guard.push_deferred(1).unwrap();
+    /// guard.push_deferred(2).unwrap();
+    /// assert_eq!(*guard, [1, 2]);
+    /// assert!(lock.is_empty());
+    /// guard.publish();
+    /// assert_eq!(*lock, [1, 2]);
+    /// ```
+    #[inline]
+    pub fn push_deferred(&mut self, value: T) -> Result<(), PushError<T>> {
         let len = self.len();
-        let cap = self.capacity();
+        if len >= self.capacity() {
+            return Err(PushError::new(value, self.capacity()));
+        }
+        // SAFETY: `len < capacity`, and the write guard is held.
+        unsafe { self.lock.as_non_null_shared().add(len).write(value) };
+        self.written = len + 1;
+        Ok(())
+    }
+    /// Publishes the elements pushed with
+    /// [`push_deferred`](GrowGuard::push_deferred), with a single store of
+    /// the length.
+    #[inline]
+    pub fn publish(&mut self) {
+        if self.written > self.lock.len.load(Ordering::Relaxed) {
+            // SAFETY: the write guard is held, and the deferred elements
+            // are initialized.
+            unsafe { self.lock.publish_len(self.written) };
+        }
+    }
+}
+
+/// The condition of [`GrowGuard::wait_while`], if any.
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
+type Condition<'a, T> = Option<&'a mut dyn FnMut(&[T]) -> bool>;
 
-        assert!(len < cap, "length overflow");
+/// Returns the guard of `result`, recording if it's poisoned.
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
+fn unpoison<G>(result: LockResult<G>, poisoned: &mut bool) -> G {
+    result.unwrap_or_else(|e| {
+        *poisoned = true;
+        e.into_inner()
+    })
+}
 
-        // SAFETY: the ptr is still in the allocated block, even after
-        // add(len)
-        unsafe {
-            let dst = self.lock.as_non_null_ref().add(len);
-            dst.write(value);
-            self.lock.len.store(len + 1, Ordering::Release);
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
+impl<T, A: Allocator> GrowGuard<'_, T, A> {
+    /// Blocks the current thread until `cv` is notified, releasing the
+    /// write lock while waiting, like [`Condvar::wait`].
+    ///
+    /// The elements pushed with
+    /// [`push_deferred`](GrowGuard::push_deferred) are published
+    /// before waiting, and the waiting readers are woken up, as if the
+    /// guard was dropped. Like [`Condvar::wait`], this can wake
+    /// up spuriously: see [`wait_while`](GrowGuard::wait_while).
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned when it's reacquired. The
+    /// guard is still returned inside the error.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{sync::Condvar, thread};
+    ///
+    /// let lock = GrowLock::with_capacity(4);
+    /// let cv = Condvar::new();
+    /// thread::scope(|s| {
+    ///     let mut guard = lock.write().unwrap();
+    ///     s.spawn(|| {
+    ///         lock.write().unwrap().push(1);
+    ///         cv.notify_one();
+    ///     });
+    ///     while guard.is_empty() {
+    ///         guard = guard.wait(&cv).unwrap();
+    ///     }
+    ///     guard.push(2);
+    /// });
+    /// assert_eq!(*lock, [1, 2]);
+    /// ```
+    pub fn wait(self, cv: &Condvar) -> LockResult<Self> {
+        let (guard, _, poisoned) = self.park(cv, None, None);
+        if poisoned {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
         }
     }
+    /// Blocks the current thread while `condition` returns `true`, waiting
+    /// on `cv` like [`wait`](GrowGuard::wait), like
+    /// [`Condvar::wait_while`].
+    ///
+    /// `condition` is called with the elements of the [`GrowLock`] while
+    /// the write lock is held, before the first wait and after each wake
+    /// up.
+    ///
     /// # Errors
-    /// Returns an error if `self.is_full()`.
-    pub fn try_push(&mut self, value: T) -> Result<(), LengthError> {
-        // We locked the mutex so writes cannot happen.
-        let len = self.lock.len.load(Ordering::Relaxed);
-        let cap = self.lock.capacity();
-
-        if len >= cap {
-            return Err(LengthError);
+    /// Returns an error if the lock is poisoned when it's reacquired. The
+    /// guard is still returned inside the error.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{sync::Condvar, thread};
+    ///
+    /// let lock = GrowLock::with_capacity(4);
+    /// let cv = Condvar::new();
+    /// thread::scope(|s| {
+    ///     s.spawn(|| {
+    ///         for i in 0..3 {
+    ///             lock.write().unwrap().push(i);
+    ///             cv.notify_all();
+    ///         }
+    ///     });
+    ///     let guard = lock.write().unwrap();
+    ///     let guard = guard.wait_while(&cv, |elems| elems.len() < 3);
+    ///     assert_eq!(*guard.unwrap(), [0, 1, 2]);
+    /// });
+    /// ```
+    pub fn wait_while<F>(
+        self,
+        cv: &Condvar,
+        mut condition: F,
+    ) -> LockResult<Self>
+    where
+        F: FnMut(&[T]) -> bool,
+    {
+        let (guard, _, poisoned) =
+            self.park(cv, None, Some(&mut condition));
+        if poisoned {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
         }
-
-        // SAFETY: the ptr is still in the allocated block, even after
-        // add(len)
-        unsafe {
-            let dst = self.lock.as_non_null_ref().add(len);
-            dst.write(value);
+    }
+    /// Same as [`wait`](GrowGuard::wait), but gives up after `timeout`,
+    /// like [`Condvar::wait_timeout`].
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned when it's reacquired. The
+    /// guard is still returned inside the error.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{sync::Condvar, time::Duration};
+    ///
+    /// let lock = GrowLock::<u8>::with_capacity(4);
+    /// let cv = Condvar::new();
+    /// let guard = lock.write().unwrap();
+    /// let (guard, result) =
+    ///     guard.wait_timeout(&cv, Duration::from_millis(10)).unwrap();
+    /// assert!(result.timed_out() && guard.is_empty());
+    /// ```
+    pub fn wait_timeout(
+        self,
+        cv: &Condvar,
+        timeout: Duration,
+    ) -> LockResult<(Self, WaitTimeoutResult)> {
+        self.park_timeout(cv, timeout, None)
+    }
+    /// Same as [`wait_while`](GrowGuard::wait_while), but gives up after
+    /// `timeout`, like [`Condvar::wait_timeout_while`].
+    ///
+    /// The returned [`WaitTimeoutResult`] tells whether `timeout` elapsed
+    /// while `condition` still returned `true`.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned when it's reacquired. The
+    /// guard is still returned inside the error.
+    pub fn wait_timeout_while<F>(
+        self,
+        cv: &Condvar,
+        timeout: Duration,
+        mut condition: F,
+    ) -> LockResult<(Self, WaitTimeoutResult)>
+    where
+        F: FnMut(&[T]) -> bool,
+    {
+        self.park_timeout(cv, timeout, Some(&mut condition))
+    }
+    /// Same as [`park`](GrowGuard::park), with a timeout.
+    fn park_timeout(
+        self,
+        cv: &Condvar,
+        timeout: Duration,
+        condition: Condition<'_, T>,
+    ) -> LockResult<(Self, WaitTimeoutResult)> {
+        let (guard, result, poisoned) =
+            self.park(cv, Some(timeout), condition);
+        let result = result.expect("the timed waits return a result");
+        if poisoned {
+            Err(PoisonError::new((guard, result)))
+        } else {
+            Ok((guard, result))
         }
-        self.lock.len.store(len + 1, Ordering::Release);
+    }
+    /// Waits on `cv` (while `condition` returns `true`, if any), for at
+    /// most `timeout` (if any), returning whether the lock is poisoned.
+    ///
+    /// The lock is released like when the guard is dropped, and reacquired
+    /// like by [`GrowLock::write`].
+    fn park(
+        self,
+        cv: &Condvar,
+        timeout: Option<Duration>,
+        mut condition: Condition<'_, T>,
+    ) -> (Self, Option<WaitTimeoutResult>, bool) {
+        let lock = self.lock;
+        let mut this = ManuallyDrop::new(self);
+        this.publish();
+        lock.notifier.notify_unlock(this.len());
+        lock.open_lockfree();
+        lock.owner.clear();
+        // SAFETY: `this` is never used again, and never dropped.
+        let guard = unsafe { ManuallyDrop::take(&mut this.guard) };
 
-        Ok(())
+        let mut poisoned = false;
+        let mut wait = |guard, timeout: Option<Duration>| {
+            let poisoned = &mut poisoned;
+            match (timeout, condition.as_mut()) {
+                (None, None) => (unpoison(cv.wait(guard), poisoned), None),
+                (None, Some(f)) => {
+                    let result =
+                        cv.wait_while(guard, |()| f(lock.as_slice()));
+                    (unpoison(result, poisoned), None)
+                }
+                (Some(timeout), None) => {
+                    let result = cv.wait_timeout(guard, timeout);
+                    let (guard, result) = unpoison(result, poisoned);
+                    (guard, Some(result))
+                }
+                (Some(timeout), Some(f)) => {
+                    let result =
+                        cv.wait_timeout_while(guard, timeout, |()| {
+                            f(lock.as_slice())
+                        });
+                    let (guard, result) = unpoison(result, poisoned);
+                    (guard, Some(result))
+                }
+            }
+        };
+        #[cfg(not(feature = "tokio"))]
+        let (guard, result) = wait(guard, timeout);
+        // the condvar would unlock the mutex without waking up the async
+        // writers: it waits on the mutex of the parked guards instead, and
+        // the mutex is unlocked like when the guard is dropped.
+        #[cfg(feature = "tokio")]
+        let (guard, result) = {
+            let parked = lock.notifier.park();
+            drop(guard);
+            lock.notifier.wake_async_writers();
+            let (parked, result) = wait(parked, timeout);
+            drop(parked);
+            lock.notifier.unpark();
+            (unpoison(lock.mutex.lock(), &mut poisoned), result)
+        };
+        let guard =
+            unpoison(lock.notifier.wait_detached(guard), &mut poisoned);
+        (Self::new(lock, guard), result, poisoned)
     }
 }
 
 impl<T, A: Allocator> Extend<T> for GrowGuard<'_, T, A> {
     /// Extends the [`GrowLock<T>`] with the contents of an iterator.
     ///
+    /// The elements are published all at once, when the iterator is
+    /// exhausted: the readers never see a part of them. To publish them
+    /// while they're produced, push them one by one.
+    ///
     /// # Panics
     /// This panics if the iterator has more elements than
-    /// `self.capacity() - self.len()` (i.e. pushing all the
-    /// elements would overflow `self.capacity()`.
+    /// `self.capacity() - self.len()` (i.e. pushing all the elements
+    /// would overflow `self.capacity()`). If the lower bound of its
+    /// [`size_hint`](Iterator::size_hint) already doesn't fit, this panics
+    /// before consuming any element. Otherwise, or if the iterator
+    /// panics, the elements written until then are dropped, without
+    /// being published.
+    #[inline]
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        let iter = iter.into_iter();
-        for elem in iter {
-            self.push(elem);
-        }
+        self.publish();
+        // SAFETY: the write guard is held.
+        unsafe { self.lock.guarded_extend(iter) };
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T>
+    for GrowGuard<'_, T, A>
+{
+    /// Extends the [`GrowLock<T>`] with copies of the elements of an
+    /// iterator of references.
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`.
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+/// The write API shared by the guards of the locks of this crate, to
+/// write the code which works with any of them.
+///
+/// The elements are read through [`Deref`](ops::Deref).
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, guard::WriteGuard, local::LocalGrowLock};
+///
+/// fn fill(guard: &mut impl WriteGuard<u32>) {
+///     while !guard.is_full() {
+///         guard.push(guard.len() as u32);
+///     }
+/// }
+///
+/// let lock = GrowLock::with_capacity(3);
+/// fill(&mut lock.write().unwrap());
+/// let local = LocalGrowLock::with_capacity(3);
+/// fill(&mut local.write());
+/// assert_eq!(*lock, *local);
+/// ```
+pub trait WriteGuard<T>: ops::Deref<Target = [T]> + Extend<T> {
+    fn capacity(&self) -> usize;
+    /// # Panics
+    /// Panics if `self.is_full()`.
+    fn push(&mut self, value: T);
+    /// # Errors
+    /// Returns an error, which gives back `value`, if `self.is_full()`.
+    fn try_push(&mut self, value: T) -> Result<(), PushError<T>>;
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+}
+
+impl<T, A: Allocator> WriteGuard<T> for GrowGuard<'_, T, A> {
+    #[inline]
+    fn capacity(&self) -> usize {
+        Self::capacity(self)
+    }
+    #[inline]
+    fn push(&mut self, value: T) {
+        Self::push(self, value);
+    }
+    #[inline]
+    fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        Self::try_push(self, value)
+    }
+}
+
+/// Owned version of [`GrowGuard`], which keeps the [`GrowLock`] alive
+/// through an [`Arc`] instead of borrowing it.
+///
+/// This structure is created by the [`write_owned`](GrowLock::write_owned)
+/// method on [`GrowLock`].
+///
+/// Unlike [`GrowGuard`], it's [`Send`] (if the [`GrowLock`] is [`Sync`]):
+/// it doesn't hold the mutex of the [`GrowLock`], but the write lock of
+/// the async writers, so it can be dropped on another thread (or held
+/// across `.await`s on a multi-threaded executor). Like the async
+/// writers, it doesn't poison the lock if the thread panics.
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+pub struct OwnedGrowGuard<T, A: Allocator = Global> {
+    lock: Arc<GrowLock<T, A>>,
+}
+
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<T, A: Allocator> Drop for OwnedGrowGuard<T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        let lock = &*self.lock;
+        lock.notifier.notify_unlock(self.len());
+        lock.open_lockfree();
+        lock.notifier.unlock_detached(&lock.mutex);
+    }
+}
+
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<T, A: Allocator> OwnedGrowGuard<T, A> {
+    /// Takes over the write lock held by `guard`.
+    ///
+    /// # Safety
+    /// `guard` must borrow the [`GrowLock`] inside `lock`.
+    pub(crate) unsafe fn new(
+        lock: Arc<GrowLock<T, A>>,
+        guard: GrowGuard<'_, T, A>,
+    ) -> Self {
+        let mut guard = ManuallyDrop::new(guard);
+        lock.owner.clear();
+        // SAFETY: `guard` is never used again, and never dropped. The
+        // lock-free writers stay closed until `self` is dropped.
+        let guard = unsafe { ManuallyDrop::take(&mut guard.guard) };
+        lock.notifier.lock_detached(guard);
+        Self { lock }
+    }
+    /// Returns the [`GrowLock`] this guard locks.
+    #[inline]
+    #[must_use]
+    pub const fn lock(this: &Self) -> &Arc<GrowLock<T, A>> {
+        &this.lock
+    }
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.lock.as_slice()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.lock.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        // We hold the write lock so writes cannot happen.
+        self.lock.len.load(Ordering::Relaxed)
+    }
+    /// See [`GrowGuard::push`].
+    ///
+    /// # Panics
+    /// Panics if `self.is_full()`.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        // SAFETY: the write lock is held.
+        unsafe { self.lock.guarded_push(value) };
+    }
+    /// See [`GrowGuard::try_push`].
+    ///
+    /// # Errors
+    /// Returns an error if `self.is_full()`.
+    #[inline]
+    pub fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        // SAFETY: the write lock is held.
+        unsafe { self.lock.guarded_try_push(value) }
+            .map_err(|value| PushError::new(value, self.lock.capacity()))
+    }
+}
+
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<T, A: Allocator> ops::Deref for OwnedGrowGuard<T, A> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<T, A: Allocator> Extend<T> for OwnedGrowGuard<T, A> {
+    /// See [`GrowGuard::extend`](Extend::extend).
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`.
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        // SAFETY: the write lock is held.
+        unsafe { self.lock.guarded_extend(iter) };
+    }
+}
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T>
+    for OwnedGrowGuard<T, A>
+{
+    /// See [`GrowGuard::extend`](Extend::extend).
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`.
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a lock
+/// when dropped, which can be held across `.await`s.
+///
+/// This structure is created by the [`write_async`](GrowLock::write_async)
+/// method on [`GrowLock`]. Unlike [`GrowGuard`], it's [`Send`] (if the
+/// [`GrowLock`] is [`Sync`]).
+#[cfg(feature = "tokio")]
+pub struct AsyncGrowGuard<'lock, T, A: Allocator = Global> {
+    lock: &'lock GrowLock<T, A>,
+}
+
+#[cfg(feature = "tokio")]
+impl<T, A: Allocator> Drop for AsyncGrowGuard<'_, T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        let lock = self.lock;
+        lock.notifier.notify_unlock(self.len());
+        lock.open_lockfree();
+        lock.notifier.unlock_detached(&lock.mutex);
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, A: Allocator> ops::Deref for AsyncGrowGuard<'_, T, A> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_slice()
+    }
+}
+#[cfg(feature = "tokio")]
+impl<'lock, T, A: Allocator> AsyncGrowGuard<'lock, T, A> {
+    /// # Safety
+    /// The async write lock of `lock` must be held, and it's released
+    /// by the returned guard. The reserved slots must be resolved, and
+    /// the lock-free writers closed out.
+    #[inline]
+    #[must_use]
+    pub(crate) unsafe fn new(lock: &'lock GrowLock<T, A>) -> Self {
+        Self { lock }
+    }
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.lock.as_slice()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.lock.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        // We hold the write lock so writes cannot happen.
+        self.lock.len.load(Ordering::Relaxed)
+    }
+    /// See [`GrowGuard::push`].
+    ///
+    /// # Panics
+    /// Panics if `self.is_full()`.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        // SAFETY: the write lock is held.
+        unsafe { self.lock.guarded_push(value) };
+    }
+    /// See [`GrowGuard::try_push`].
+    ///
+    /// # Errors
+    /// Returns an error if `self.is_full()`.
+    #[inline]
+    pub fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        // SAFETY: the write lock is held.
+        unsafe { self.lock.guarded_try_push(value) }
+            .map_err(|value| PushError::new(value, self.lock.capacity()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T, A: Allocator> Extend<T> for AsyncGrowGuard<'_, T, A> {
+    /// See [`GrowGuard::extend`](Extend::extend).
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`.
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        // SAFETY: the write lock is held.
+        unsafe { self.lock.guarded_extend(iter) };
+    }
+}
+#[cfg(feature = "tokio")]
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T>
+    for AsyncGrowGuard<'_, T, A>
+{
+    /// See [`GrowGuard::extend`](Extend::extend).
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`.
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
     }
 }
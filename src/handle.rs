@@ -0,0 +1,155 @@
+//! Read-only handles to a shared [`GrowLock`].
+
+#[cfg(feature = "std")]
+use {crate::cursor::TailCursor, core::time::Duration};
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        snapshot::Snapshot,
+    },
+    alloc::sync::Arc,
+    core::{fmt, ops, slice},
+};
+
+/// A cheap, cloneable handle to a shared [`GrowLock`], which can only
+/// read it.
+///
+/// This structure is created by the [`read_handle`](GrowLock::read_handle)
+/// method on [`GrowLock`]. It keeps the [`GrowLock`] alive, but it never
+/// gives access to it, so it can't take the write lock:
+///
+/// ```compile_fail,E0599
+/// use growlock::GrowLock;
+/// use std::sync::Arc;
+///
+/// let lock = Arc::new(GrowLock::<u8>::with_capacity(10));
+/// let handle = lock.read_handle();
+/// handle.write();
+/// ```
+///
+/// Nor through the [`TailCursor`] returned by
+/// [`tail`](ReadHandle::tail), which doesn't give access to its
+/// [`GrowLock`] either:
+///
+/// ```compile_fail,E0599
+/// use growlock::GrowLock;
+/// use std::sync::Arc;
+///
+/// let lock = Arc::new(GrowLock::<u8>::with_capacity(10));
+/// let handle = lock.read_handle();
+/// handle.tail().get_ref().write();
+/// ```
+pub struct ReadHandle<T, A: Allocator = Global> {
+    lock: Arc<GrowLock<T, A>>,
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Returns a [`ReadHandle`] to this [`GrowLock`].
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{sync::Arc, thread};
+    ///
+    /// let lock = Arc::new(GrowLock::with_capacity(10));
+    /// let handle = lock.read_handle();
+    /// let consumer = thread::spawn(move || {
+    ///     assert!(handle.wait_for_len(3, None));
+    ///     handle.iter().sum::<i32>()
+    /// });
+    ///
+    /// lock.write().unwrap().extend([1, 2, 3]);
+    /// assert_eq!(consumer.join().unwrap(), 6);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn read_handle(self: &Arc<Self>) -> ReadHandle<T, A> {
+        ReadHandle {
+            lock: Arc::clone(self),
+        }
+    }
+}
+
+impl<T, A: Allocator> ReadHandle<T, A> {
+    /// See [`GrowLock::len`].
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock.len()
+    }
+    /// See [`GrowLock::is_empty`].
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock.is_empty()
+    }
+    /// See [`GrowLock::capacity`].
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.lock.capacity()
+    }
+    /// See [`GrowLock::as_slice`].
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.lock.as_slice()
+    }
+    /// See [`GrowLock::iter_from`].
+    #[inline]
+    pub fn iter_from(&self, start: usize) -> slice::Iter<'_, T> {
+        self.lock.iter_from(start)
+    }
+    /// See [`GrowLock::chunk_from`].
+    #[inline]
+    #[must_use]
+    pub fn chunk_from(&self, start: usize, max: usize) -> &[T] {
+        self.lock.chunk_from(start, max)
+    }
+    /// See [`GrowLock::snapshot`].
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<'_, T> {
+        self.lock.snapshot()
+    }
+    /// See [`GrowLock::tail`].
+    #[cfg(feature = "std")]
+    #[inline]
+    #[must_use]
+    pub fn tail(&self) -> TailCursor<'_, T, A> {
+        self.lock.tail()
+    }
+    /// See [`GrowLock::wait_for_len`].
+    #[cfg(feature = "std")]
+    #[inline]
+    #[must_use]
+    pub fn wait_for_len(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> bool {
+        self.lock.wait_for_len(n, timeout)
+    }
+}
+
+impl<T, A: Allocator> ops::Deref for ReadHandle<T, A> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+impl<T, A: Allocator> Clone for ReadHandle<T, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            lock: Arc::clone(&self.lock),
+        }
+    }
+}
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for ReadHandle<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReadHandle").field(&*self.lock).finish()
+    }
+}
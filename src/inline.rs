@@ -0,0 +1,124 @@
+//! A [`GrowLock`](crate::GrowLock) which stores a few elements inline,
+//! without allocating.
+
+use crate::{
+    cap::Cap,
+    compat::Global,
+    error::TryReserveError,
+    fixed::{ArraySlots, Fixed, Slots, fixed_lock},
+    raw::RawGrowLock,
+};
+
+/// The buffer of an [`InlineGrowLock`].
+enum InlineSlots<T, const N: usize> {
+    Inline(ArraySlots<T, N>),
+    Heap(RawGrowLock<T>),
+}
+
+// SAFETY: both kinds of slots uphold the contract.
+unsafe impl<T, const N: usize> Slots<T> for InlineSlots<T, N> {
+    #[inline]
+    fn as_ptr(&self) -> *mut T {
+        match self {
+            Self::Inline(slots) => slots.as_ptr(),
+            Self::Heap(buf) => buf.as_mut_ptr(),
+        }
+    }
+    #[inline]
+    fn capacity(&self) -> usize {
+        match self {
+            Self::Inline(slots) => slots.capacity(),
+            Self::Heap(buf) => buf.capacity(),
+        }
+    }
+}
+
+/// A [`GrowLock`](crate::GrowLock) which stores up to `N` elements
+/// inline, in the struct itself, instead of allocating them.
+///
+/// The storage is chosen when the lock is created: if the capacity is at
+/// most `N`, the elements are stored inline (and the capacity is `N`),
+/// otherwise they are allocated on the heap, like in a
+/// [`GrowLock`](crate::GrowLock). The elements are never moved to the
+/// heap later, so they keep their addresses while the lock is shared
+/// (the inline ones move with the lock, but it can't be moved while it's
+/// borrowed).
+///
+/// It has the same write-lock API as a [`GrowLock`](crate::GrowLock):
+/// the elements are pushed through the [`InlineGrowGuard`] returned by
+/// [`write`](InlineGrowLock::write), and the readers see them (through
+/// [`Deref`](std::ops::Deref)) once the new length is published.
+///
+/// The elements are dropped in reverse order, like the ones of a
+/// [`GrowLock`](crate::GrowLock#drop-order).
+///
+/// # Examples
+/// ```
+/// use growlock::inline::InlineGrowLock;
+///
+/// let lock = InlineGrowLock::<_, 4>::new();
+/// assert!(lock.is_inline());
+/// lock.write().unwrap().extend([1, 2, 3]);
+/// assert_eq!(*lock, [1, 2, 3]);
+///
+/// let big = InlineGrowLock::<u8, 4>::with_capacity(100);
+/// assert!(!big.is_inline());
+/// assert_eq!(big.capacity(), 100);
+/// ```
+pub struct InlineGrowLock<T, const N: usize> {
+    inner: Fixed<T, InlineSlots<T, N>>,
+}
+
+fixed_lock!(InlineGrowLock, InlineGrowGuard);
+
+impl<T, const N: usize> InlineGrowLock<T, N> {
+    /// Creates an empty [`InlineGrowLock`], which stores its elements
+    /// inline, with a capacity of `N`.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            inner: Fixed::new(InlineSlots::Inline(ArraySlots::new())),
+        }
+    }
+    /// Creates an empty [`InlineGrowLock`] with at least the specified
+    /// capacity, which stores its elements inline if it's at most `N`.
+    ///
+    /// # Panics
+    /// Panics if the capacity overflows `isize::MAX` bytes, or if the
+    /// allocation fails.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        match Self::try_with_capacity(capacity) {
+            Ok(lock) => lock,
+            Err(e) => panic!("{e}"),
+        }
+    }
+    /// Same as [`with_capacity`](InlineGrowLock::with_capacity), but
+    /// returns an error if the allocation fails.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// * `capacity * size_of::<T>` overflows `isize::MAX`
+    /// * memory is exhausted
+    pub fn try_with_capacity(
+        capacity: usize,
+    ) -> Result<Self, TryReserveError> {
+        if capacity <= N || size_of::<T>() == 0 {
+            return Ok(Self::new());
+        }
+        let cap = Cap::new::<T>(capacity)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        let buf = RawGrowLock::try_with_capacity_in(cap, Global)?;
+        Ok(Self {
+            inner: Fixed::new(InlineSlots::Heap(buf)),
+        })
+    }
+    /// Returns whether the elements are stored inline.
+    #[inline]
+    #[must_use]
+    pub const fn is_inline(&self) -> bool {
+        matches!(self.inner.slots, InlineSlots::Inline(_))
+    }
+}
@@ -0,0 +1,205 @@
+//! Iterators over a [`GrowLock`].
+
+use {
+    crate::{
+        GrowLock,
+        cap::Cap,
+        compat::{Allocator, Global},
+        raw::RawGrowLock,
+    },
+    core::{fmt, iter::FusedIterator, ptr, slice},
+};
+
+/// An iterator that moves out of a [`GrowLock`].
+///
+/// This structure is created by the [`into_iter`](IntoIterator::into_iter)
+/// method on [`GrowLock`] (provided by the [`IntoIterator`] trait).
+///
+/// When dropped, the remaining elements are dropped, and the buffer is
+/// deallocated.
+pub struct IntoIter<T, A: Allocator = Global> {
+    buf: RawGrowLock<T, A>,
+    /// The remaining elements are the ones in `start..end`.
+    start: usize,
+    end: usize,
+}
+
+impl<T, A: Allocator> IntoIter<T, A> {
+    /// Returns the remaining elements of this iterator as a slice.
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the elements in `start..end` are initialized, and they
+        // are only moved out through `&mut self`.
+        unsafe {
+            slice::from_raw_parts(
+                self.buf.as_ptr().add(self.start),
+                self.end - self.start,
+            )
+        }
+    }
+    /// Returns a reference to the underlying allocator.
+    #[inline]
+    #[must_use]
+    pub const fn allocator(&self) -> &A {
+        self.buf.allocator()
+    }
+    /// Drops the remaining elements. Only called when the iterator is
+    /// dropped.
+    fn drop_remaining(&mut self) {
+        // SAFETY: the elements in `start..end` are initialized, and they
+        // are never used again.
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.buf.as_mut_ptr().add(self.start),
+                self.end - self.start,
+            ));
+        }
+    }
+}
+
+// SAFETY: the elements are only dropped, like in `GrowLock`.
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, A: Allocator> Drop for IntoIter<T, A> {
+    fn drop(&mut self) {
+        self.drop_remaining();
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T, A: Allocator> Drop for IntoIter<T, A> {
+    fn drop(&mut self) {
+        self.drop_remaining();
+    }
+}
+
+impl<T, A: Allocator> Iterator for IntoIter<T, A> {
+    type Item = T;
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        // SAFETY: the element at `start` is initialized, and it's no
+        // longer reachable once `start` is incremented.
+        let value = unsafe { self.buf.as_ptr().add(self.start).read() };
+        self.start += 1;
+        Some(value)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
+    #[inline]
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            return None;
+        }
+        self.end -= 1;
+        // SAFETY: the element at `end` is initialized, and it's no longer
+        // reachable.
+        Some(unsafe { self.buf.as_ptr().add(self.end).read() })
+    }
+}
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
+impl<T, A: Allocator> FusedIterator for IntoIter<T, A> {}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for IntoIter<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("IntoIter").field(&self.as_slice()).finish()
+    }
+}
+
+impl<T, A: Allocator> IntoIterator for GrowLock<T, A> {
+    type Item = T;
+    type IntoIter = IntoIter<T, A>;
+    /// Creates a consuming iterator, that moves each element out of the
+    /// [`GrowLock`] (from start to end).
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        let (ptr, len, cap, alloc) = self.into_parts_with_alloc();
+        // SAFETY: these are the parts of a `GrowLock`, which owns the
+        // buffer.
+        let buf = unsafe {
+            RawGrowLock::from_nonnull_in(
+                ptr,
+                Cap::new_unchecked::<T>(cap),
+                alloc,
+            )
+        };
+        IntoIter {
+            buf,
+            start: 0,
+            end: len,
+        }
+    }
+}
+
+impl<'a, T, A: Allocator> IntoIterator for &'a GrowLock<T, A> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+    /// Creates an iterator over the elements pushed so far.
+    ///
+    /// The length is loaded once, when the iterator is created: elements
+    /// pushed after that are not visited.
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Returns an iterator over the elements pushed so far, starting from
+    /// the index `start`.
+    ///
+    /// The length is loaded once, when the iterator is created. If `start`
+    /// is out of range, the iterator is empty.
+    ///
+    /// See [`Cursor`](crate::cursor::Cursor) to keep track of the elements
+    /// already visited.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(5, [1, 2, 3]);
+    /// assert!(lock.iter_from(1).eq(&[2, 3]));
+    /// assert_eq!(lock.iter_from(10).next(), None);
+    /// ```
+    #[inline]
+    pub fn iter_from(&self, start: usize) -> slice::Iter<'_, T> {
+        let slice = self.as_slice();
+        slice[start.min(slice.len())..].iter()
+    }
+    /// Returns up to `max` elements pushed so far, starting from the index
+    /// `start`.
+    ///
+    /// This is `&self[start..len.min(start + max)]`, with the length
+    /// loaded once. If `start` is out of range, or `max` is `0`, the
+    /// slice is empty.
+    ///
+    /// See [`ChunkCursor`](crate::cursor::ChunkCursor) to keep track of
+    /// the elements already returned.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(5, [1, 2, 3]);
+    /// assert_eq!(lock.chunk_from(1, 1), [2]);
+    /// assert_eq!(lock.chunk_from(1, 10), [2, 3]);
+    /// assert_eq!(lock.chunk_from(1, 0), [0; 0]);
+    /// assert_eq!(lock.chunk_from(10, 1), [0; 0]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn chunk_from(&self, start: usize, max: usize) -> &[T] {
+        let slice = self.as_slice();
+        let start = start.min(slice.len());
+        let end = start.saturating_add(max).min(slice.len());
+        &slice[start..end]
+    }
+}
@@ -3,44 +3,140 @@
 //! ```
 #![doc = include_str!("../examples/basic_usage.rs")]
 //! ```
-#![feature(allocator_api, sized_type_properties)]
+#![cfg_attr(
+    feature = "nightly",
+    feature(
+        allocator_ext,
+        container_error_extra,
+        dropck_eyepatch,
+        try_reserve_kind
+    )
+)]
+// the `nightly` feature needs a nightly toolchain, not the MSRV.
+#![cfg_attr(feature = "nightly", allow(clippy::incompatible_msrv))]
+#![cfg_attr(
+    all(feature = "nightly", feature = "std"),
+    feature(core_io_borrowed_buf, read_buf)
+)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+mod arena;
+pub mod array;
+pub mod atomic;
+#[cfg(feature = "bytemuck")]
+mod bytemuck;
+mod bytes;
+mod cache_padded;
 mod cap;
+mod compat;
+pub mod cursor;
+#[cfg(feature = "defmt")]
+mod defmt;
+mod drop_order;
 pub mod error;
+mod fixed;
 pub mod guard;
+#[cfg(target_has_atomic = "ptr")]
+pub mod handle;
+pub mod inline;
+pub mod iter;
+pub mod local;
+mod lockfree;
 mod macros;
+mod map;
+#[cfg(feature = "mlock")]
+pub mod mlock;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+mod notify;
+mod owner;
+pub mod partition;
+#[cfg(feature = "proptest")]
+pub mod proptest;
+#[cfg(feature = "rand")]
+mod rand;
 mod raw;
-#[cfg(all(test, not(loom)))]
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "std")]
+pub mod reader;
+#[cfg(feature = "rkyv")]
+pub mod rkyv;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod set_len_on_drop;
+#[cfg(feature = "smallvec")]
+mod smallvec;
+pub mod snapshot;
+pub mod string;
+pub mod sync;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
+#[cfg(all(test, feature = "std", not(any(loom, shuttle))))]
 mod tests;
-#[cfg(all(test, loom))]
+#[cfg(all(test, feature = "std", loom))]
 mod tests_loom;
+#[cfg(all(test, feature = "std", shuttle))]
+mod tests_shuttle;
+pub mod ticket;
+#[cfg(feature = "tokio")]
+mod tokio;
+mod uninit;
+#[cfg(feature = "zeroize")]
+mod zeroize;
 
-#[cfg(not(loom))]
-use std::sync::{
-    LockResult, Mutex, TryLockResult,
-    atomic::{AtomicUsize, Ordering},
-};
+/// Used by the macros.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec;
+}
 
-#[cfg(loom)]
-use loom::sync::{
-    LockResult, Mutex, TryLockResult,
-    atomic::{AtomicUsize, Ordering},
+#[cfg(feature = "std")]
+use std::{
+    sync::mpsc::{self, Receiver},
+    time::Duration,
 };
+
+#[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+use {crate::guard::OwnedGrowGuard, alloc::sync::Arc};
 use {
     crate::{
-        cap::Cap, error::TryReserveError, guard::GrowGuard,
+        cache_padded::CachePadded,
+        cap::Cap,
+        compat::{Allocator, Global},
+        drop_order::drop_in_place_rev,
+        error::{
+            FromVecError, GrowLockError, LengthError, PushError,
+            TryFillError, TryReserveError,
+        },
+        guard::GrowGuard,
+        lockfree::Reservations,
+        notify::Notifier,
+        owner::Owner,
         raw::RawGrowLock,
+        set_len_on_drop::SetLenOnDrop,
+        sync::{
+            LockResult, Mutex, PoisonError, TryLockError, TryLockResult,
+            atomic::{AtomicU64, AtomicUsize, Ordering},
+            hint,
+        },
     },
-    std::{
-        alloc::{Allocator, Global},
+    alloc::{borrow::Cow, boxed::Box, collections::VecDeque, vec::Vec},
+    core::{
         borrow::Borrow,
-        fmt,
+        cmp, fmt,
         hash::{Hash, Hasher},
-        mem::ManuallyDrop,
+        marker::PhantomData,
+        mem::{self, ManuallyDrop},
         ops,
+        panic::{RefUnwindSafe, UnwindSafe},
+        pin::Pin,
         ptr::{self, NonNull},
         slice::{self, SliceIndex},
-        sync::{PoisonError, TryLockError},
     },
 };
 
@@ -54,8 +150,39 @@ use {
 /// ```
 pub struct GrowLock<T, A: Allocator = Global> {
     buf: RawGrowLock<T, A>,
-    len: AtomicUsize,
-    mutex: Mutex<()>,
+    /// Read by the readers, so it's kept away from the fields which are
+    /// only written by the writers (with the `cache-padded` feature).
+    len: CachePadded<AtomicUsize>,
+    /// Bumped by the methods which remove, move or overwrite elements
+    /// (see [`generation`](GrowLock::generation)).
+    generation: AtomicU64,
+    /// The slots reserved by [`push_lockfree`](GrowLock::push_lockfree)
+    /// and by the [`Ticket`](ticket::Ticket)s.
+    reservations: CachePadded<Reservations>,
+    mutex: CachePadded<Mutex<()>>,
+    /// The thread holding the write lock (with the `deadlock-detection`
+    /// feature).
+    owner: Owner,
+    notifier: Notifier,
+    /// Elements can be pushed through a shared reference, so `T` must be
+    /// invariant (like in [`Mutex<T>`]), otherwise a shorter-lived
+    /// element could be pushed into a longer-lived [`GrowLock`]:
+    ///
+    /// ```compile_fail,E0597
+    /// use growlock::GrowLock;
+    ///
+    /// fn push<'a>(lock: &GrowLock<&'a str>, s: &'a str) {
+    ///     lock.write().unwrap().push(s);
+    /// }
+    ///
+    /// let lock = GrowLock::<&'static str>::with_capacity(1);
+    /// {
+    ///     let s = String::from("hello");
+    ///     push(&lock, &s);
+    /// }
+    /// println!("{}", lock[0]);
+    /// ```
+    _invariant: PhantomData<fn(T) -> T>,
 }
 
 /// # Safety:
@@ -83,6 +210,27 @@ where
     A: Sync + Allocator,
 {
 }
+/// Like [`Vec`], an owned [`GrowLock`] is [`UnwindSafe`] if its elements
+/// are: a panic in a writer poisons the [`mutex`](Mutex), and the
+/// [`len`](AtomicUsize) is only ever published after the elements it
+/// covers are initialized, so no broken invariant can be observed after
+/// unwinding.
+impl<T, A> UnwindSafe for GrowLock<T, A>
+where
+    T: UnwindSafe,
+    A: UnwindSafe + Allocator,
+{
+}
+/// Unlike [`Mutex`], a shared [`GrowLock`] hands out `&T` without locking,
+/// so it's [`RefUnwindSafe`] only if `T` is. The buffer and the length
+/// are safe to observe after unwinding, for the same reasons as
+/// [`UnwindSafe`].
+impl<T, A> RefUnwindSafe for GrowLock<T, A>
+where
+    T: RefUnwindSafe,
+    A: RefUnwindSafe + Allocator,
+{
+}
 
 impl<T, A: Allocator> GrowLock<T, A> {
     #[inline]
@@ -105,6 +253,42 @@ impl<T, A: Allocator> GrowLock<T, A> {
     pub fn len(&self) -> usize {
         self.len.load(Ordering::Acquire)
     }
+    /// Returns the number of times the elements were removed, moved or
+    /// overwritten.
+    ///
+    /// Pushing never changes the generation: only the methods taking
+    /// `&mut self` (like [`shuffle`](GrowLock::shuffle) or
+    /// [`clone_from`](Clone::clone_from)) can, so it can't change while
+    /// the [`GrowLock`] is borrowed. It's checked by
+    /// [`Cursor`](cursor::Cursor), which doesn't borrow it.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let mut lock = GrowLock::from_iter_with_capacity(10, [1, 2, 3]);
+    /// let generation = lock.generation();
+    /// lock.write().unwrap().push(4);
+    /// assert_eq!(lock.generation(), generation);
+    ///
+    /// lock.clone_from(&GrowLock::from_iter_with_capacity(2, [5, 6]));
+    /// assert!(lock.generation() > generation);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        // only changed through `&mut self`.
+        self.generation.load(Ordering::Relaxed)
+    }
+    /// Bumps the [`generation`](GrowLock::generation), before removing,
+    /// moving or overwriting elements.
+    #[inline]
+    pub(crate) fn bump_generation(&mut self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        // the length may change too, so the lock-free pushers must not
+        // reserve slots from the old one.
+        self.reservations.close_mut();
+    }
     #[inline]
     #[must_use]
     pub const fn allocator(&self) -> &A {
@@ -116,6 +300,26 @@ impl<T, A: Allocator> GrowLock<T, A> {
     pub const fn as_ptr(&self) -> *const T {
         self.buf.as_ptr()
     }
+    /// Returns the range of raw pointers spanning the elements pushed so
+    /// far, like [`<[T]>::as_ptr_range`](slice::as_ptr_range).
+    ///
+    /// Since the buffer is never reallocated, `start` stays the same for
+    /// the whole life of the [`GrowLock`], and the range only grows.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from_iter_with_capacity(10, [1, 2, 3]);
+    /// let range = lock.as_ptr_range();
+    /// assert_eq!(range.start, lock.as_ptr());
+    /// assert!(range.contains(&&raw const lock[2]));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn as_ptr_range(&self) -> ops::Range<*const T> {
+        self.as_slice().as_ptr_range()
+    }
     #[inline]
     #[must_use]
     #[doc = include_str!("../docs/as_ptr/as_mut_ptr.md")]
@@ -128,15 +332,17 @@ impl<T, A: Allocator> GrowLock<T, A> {
     pub const fn as_non_null(&mut self) -> NonNull<T> {
         self.buf.as_non_null()
     }
-    /// Same as [`GrowLock::as_non_null`], but takes `self` by
-    /// immutable reference.
-    ///
-    /// # SAFETY:
-    /// calling this method is safe, but using the ptr is not. It's okay
-    /// because this is private and only used in the guard.
     #[inline]
     #[must_use]
-    pub(crate) const unsafe fn as_non_null_ref(&self) -> NonNull<T> {
+    #[doc = include_str!("../docs/as_ptr/as_mut_ptr_shared.md")]
+    pub const fn as_mut_ptr_shared(&self) -> *mut T {
+        self.buf.as_mut_ptr()
+    }
+    /// Same as [`as_mut_ptr_shared`](GrowLock::as_mut_ptr_shared), as a
+    /// [`NonNull`]: the writes through it follow the same contract.
+    #[inline]
+    #[must_use]
+    pub(crate) const fn as_non_null_shared(&self) -> NonNull<T> {
         self.buf.as_non_null()
     }
 
@@ -149,7 +355,8 @@ impl<T, A: Allocator> GrowLock<T, A> {
         // SAFETY:
         // * `self.as_ptr()` is never null, and valid for reads up to
         //   `self.len()` if we can have a reference to `self` (which we
-        //   do)
+        //   do). It's aligned for `T` even when nothing is allocated, as
+        //   the empty buffers are `NonNull::<T>::dangling()`.
         // * the entire block of memory is within a single allocation
         // * at least `self.len()` number of elements are correctly
         //   initialized.
@@ -157,10 +364,249 @@ impl<T, A: Allocator> GrowLock<T, A> {
         //   neither does `self.len() * size_of::<T>()`
         unsafe { slice::from_raw_parts(self.as_ptr(), self.len()) }
     }
+    /// Returns a pinned reference to the element at `index`, or [`None`]
+    /// if it's out of bounds.
+    ///
+    /// The elements of a pinned [`GrowLock`] are pinned too (see
+    /// [address stability](GrowLock#address-stability)): the [`GrowLock`]
+    /// can't be mutably borrowed anymore (unless `T` is [`Unpin`]), and
+    /// pushing never moves the elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{marker::PhantomPinned, sync::Arc};
+    ///
+    /// let lock = Arc::pin(GrowLock::with_capacity(10));
+    /// lock.write().unwrap().push((1, PhantomPinned));
+    /// let first = lock.as_ref().get_pinned(0).unwrap();
+    ///
+    /// lock.write().unwrap().extend((2..10).map(|i| (i, PhantomPinned)));
+    /// assert_eq!(first.0, 1);
+    /// assert!(std::ptr::eq(first.get_ref(), &lock[0]));
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn get_pinned(self: Pin<&Self>, index: usize) -> Option<Pin<&T>> {
+        // SAFETY: the elements are structurally pinned: they're never
+        // moved by the methods taking `&self`, the `&mut self` ones can't
+        // be called on a pinned `GrowLock` (which is `Unpin` only if `T`
+        // is), and they're dropped in place.
+        self.get_ref()
+            .get(index)
+            .map(|elem| unsafe { Pin::new_unchecked(elem) })
+    }
+    /// Returns the index of the element `elem` refers to, or [`None`] if
+    /// it doesn't refer to an element of this [`GrowLock`].
+    ///
+    /// This only compares addresses, never the values: a reference to an
+    /// equal value outside the buffer returns [`None`], as does a
+    /// reference which doesn't start at the beginning of an element.
+    ///
+    /// For zero-sized types, the references don't have a meaningful
+    /// address, so this always returns [`None`].
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from_iter_with_capacity(10, [1, 2, 3]);
+    /// let elem = lock.iter().find(|&&x| x == 2).unwrap();
+    /// assert_eq!(lock.index_of_ref(elem), Some(1));
+    /// assert_eq!(lock.index_of_ref(&2), None);
+    /// ```
+    #[must_use]
+    pub fn index_of_ref(&self, elem: &T) -> Option<usize> {
+        if size_of::<T>() == 0 {
+            return None;
+        }
+        let range = self.as_ptr_range();
+        let ptr = ptr::from_ref(elem);
+        if !range.contains(&ptr) {
+            return None;
+        }
+        let offset = ptr.addr() - range.start.addr();
+        let size = size_of::<T>();
+        offset.is_multiple_of(size).then_some(offset / size)
+    }
+    /// Extracts a mutable slice of the entire vector up to `self.len()`.
+    ///
+    /// Only reachable with exclusive access, so no reader can observe the
+    /// elements while they are mutated.
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        let len = self.len();
+        // SAFETY: same as `GrowLock::as_slice`, plus we have `&mut self`
+        // so nobody else can access the elements.
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), len) }
+    }
+    /// Publishes the new length, and wakes up the readers waiting for it.
+    ///
+    /// # Safety
+    /// * the caller must have exclusive write access to the buffer (either
+    ///   through `&mut self` or by holding the write lock).
+    /// * the first `len` elements must be initialized.
+    #[inline]
+    pub(crate) unsafe fn publish_len(&self, len: usize) {
+        crate::macros::debug_invariant!(
+            len <= self.capacity(),
+            "published length {len} > capacity {}",
+            self.capacity(),
+        );
+        self.len.store(len, Ordering::Release);
+        self.notifier.notify(len);
+    }
+    /// Appends `value`, or gives it back if the buffer is full.
+    ///
+    /// # Safety
+    /// The caller must have exclusive write access to the buffer (either
+    /// through `&mut self` or by holding the write lock).
+    pub(crate) unsafe fn guarded_try_push(
+        &self,
+        value: T,
+    ) -> Result<(), T> {
+        // the caller guarantees that writes cannot happen.
+        let len = self.len.load(Ordering::Relaxed);
+        if len >= self.capacity() {
+            return Err(value);
+        }
+        // SAFETY:
+        // * `len < capacity`, so the ptr is still in the allocated block.
+        // * the caller guarantees we have write access.
+        unsafe {
+            self.as_non_null_shared().add(len).write(value);
+            self.publish_len(len + 1);
+        }
+        Ok(())
+    }
+    /// Appends `value`.
+    ///
+    /// # Panics
+    /// Panics if the buffer is full.
+    ///
+    /// # Safety
+    /// The caller must have exclusive write access to the buffer (either
+    /// through `&mut self` or by holding the write lock).
+    #[inline]
+    pub(crate) unsafe fn guarded_push(&self, value: T) {
+        // SAFETY: the safety contract must be upheld by the caller
+        let result = unsafe { self.guarded_try_push(value) };
+        assert!(result.is_ok(), "length overflow");
+    }
+    /// Appends the elements of `iter`, like
+    /// [`GrowGuard::extend`](Extend::extend): they're published all at
+    /// once, or not at all.
+    ///
+    /// # Panics
+    /// Panics if the iterator has more elements than the spare capacity
+    /// (before consuming any element, if its lower size bound already
+    /// doesn't fit).
+    ///
+    /// # Safety
+    /// The caller must have exclusive write access to the buffer (either
+    /// through `&mut self` or by holding the write lock).
+    pub(crate) unsafe fn guarded_extend<I>(&self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        /// The elements written (but not published) by `guarded_extend`,
+        /// which are dropped if the batch is abandoned.
+        struct Batch<T> {
+            ptr: NonNull<T>,
+            written: usize,
+        }
+        impl<T> Drop for Batch<T> {
+            fn drop(&mut self) {
+                // SAFETY: the first `written` elements are initialized,
+                // and they were never published.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.ptr.as_ptr(),
+                        self.written,
+                    ));
+                }
+            }
+        }
+
+        let iter = iter.into_iter();
+        let len = self.len.load(Ordering::Relaxed);
+        let spare = self.capacity() - len;
+        assert!(iter.size_hint().0 <= spare, "length overflow");
+        let mut batch = Batch {
+            // SAFETY: `len <= capacity`, so the ptr is still in the
+            // allocated block.
+            ptr: unsafe { self.as_non_null_shared().add(len) },
+            written: 0,
+        };
+        for elem in iter {
+            // `elem` and the batch are dropped while unwinding.
+            assert!(batch.written < spare, "length overflow");
+            // SAFETY: `len + written < capacity`, and the caller
+            // guarantees we have write access.
+            unsafe { batch.ptr.add(batch.written).write(elem) };
+            batch.written += 1;
+        }
+        let written = batch.written;
+        mem::forget(batch);
+        // SAFETY: the first `len + written` elements are initialized.
+        unsafe { self.publish_len(len + written) };
+    }
+    /// Clones all the elements of `src` to the end of the buffer,
+    /// publishing the new length once at the end.
+    ///
+    /// If [`Clone::clone`] panics, the elements cloned until then are kept
+    /// (and so they will be dropped by the [`GrowLock`]).
+    ///
+    /// # Safety
+    /// * the caller must have exclusive write access to the buffer (either
+    ///   through `&mut self` or by holding the write lock).
+    /// * `self.len() + src.len()` must be <= `self.capacity()`
+    pub(crate) unsafe fn extend_from_slice_unchecked(&self, src: &[T])
+    where
+        T: Clone,
+    {
+        let mut len = SetLenOnDrop::new(&self.len, &self.notifier);
+        let ptr = self.as_non_null_shared();
+        for elem in src {
+            // SAFETY: the caller guarantees there is enough capacity, so
+            // the ptr is still in the allocated block.
+            unsafe { ptr.add(len.current_len()).write(elem.clone()) };
+            len.increment_len(1);
+        }
+    }
+    /// Moves elements from `iter` to the end of the buffer until either
+    /// the iterator or the spare capacity is exhausted, publishing the new
+    /// length once at the end.
+    ///
+    /// If the iterator panics, the elements written until then are kept.
+    ///
+    /// # Safety
+    /// The caller must have exclusive write access to the buffer (either
+    /// through `&mut self` or by holding the write lock).
+    pub(crate) unsafe fn extend_until_full<I>(&self, iter: &mut I)
+    where
+        I: Iterator<Item = T>,
+    {
+        let cap = self.capacity();
+        let mut len = SetLenOnDrop::new(&self.len, &self.notifier);
+        let ptr = self.as_non_null_shared();
+        while len.current_len() < cap {
+            let Some(elem) = iter.next() else {
+                break;
+            };
+            // SAFETY: `len < cap`, so the ptr is still in the allocated
+            // block.
+            unsafe { ptr.add(len.current_len()).write(elem) };
+            len.increment_len(1);
+        }
+    }
 
     /// Creates a new [`GrowLock<T>`] in the provided allocator,
     /// returning an error if the allocation fails
     ///
+    /// If the allocator returns a larger block than requested, the
+    /// capacity is rounded up to the elements which fit in it.
+    ///
     /// # Errors
     /// If any of these conditions happen, an error is returned:
     /// * `cap * size_of::<T>` overflows [`isize::MAX`]
@@ -168,12 +614,26 @@ impl<T, A: Allocator> GrowLock<T, A> {
     ///
     /// # Examples
     /// ```
-    /// #![feature(allocator_api)]
     /// use growlock::GrowLock;
     /// use std::alloc::System;
     ///
     /// let lock: GrowLock<u32, _> = GrowLock::try_with_capacity_in(10, System).unwrap();
     /// ```
+    ///
+    /// With the `test-util` feature, the failures of the allocator can be
+    /// injected with `test_util::FailingAllocator`:
+    /// ```
+    /// # #[cfg(feature = "test-util")] {
+    /// use growlock::{GrowLock, error::TryReserveError, test_util::FailingAllocator};
+    ///
+    /// let alloc = FailingAllocator::default();
+    /// alloc.fail_layouts_larger_than(16);
+    /// let lock = GrowLock::<u32, _>::try_with_capacity_in(4, &alloc);
+    /// assert!(lock.is_ok());
+    /// let lock = GrowLock::<u32, _>::try_with_capacity_in(5, &alloc);
+    /// assert!(matches!(lock, Err(TryReserveError::AllocError(_))));
+    /// # }
+    /// ```
     pub fn try_with_capacity_in(
         capacity: usize,
         alloc: A,
@@ -185,16 +645,23 @@ impl<T, A: Allocator> GrowLock<T, A> {
 
         Ok(Self {
             buf,
-            len: AtomicUsize::new(0),
-            mutex: Mutex::new(()),
+            len: CachePadded::new(AtomicUsize::new(0)),
+            generation: AtomicU64::new(0),
+            reservations: CachePadded::new(Reservations::new()),
+            mutex: CachePadded::new(Mutex::new(())),
+            owner: Owner::new(),
+            notifier: Notifier::new(),
+            _invariant: PhantomData,
         })
     }
 
     /// Creates a new [`GrowLock<T>`] in the provided allocator.
     ///
+    /// If the allocator returns a larger block than requested, the
+    /// capacity is rounded up to the elements which fit in it.
+    ///
     /// # Examples
     /// ```
-    /// #![feature(allocator_api)]
     /// use growlock::GrowLock;
     /// use std::alloc::System;
     ///
@@ -211,9 +678,203 @@ impl<T, A: Allocator> GrowLock<T, A> {
 
         Self {
             buf,
-            len: AtomicUsize::new(0),
-            mutex: Mutex::new(()),
+            len: CachePadded::new(AtomicUsize::new(0)),
+            generation: AtomicU64::new(0),
+            reservations: CachePadded::new(Reservations::new()),
+            mutex: CachePadded::new(Mutex::new(())),
+            owner: Owner::new(),
+            notifier: Notifier::new(),
+            _invariant: PhantomData,
+        }
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity in the
+    /// provided allocator, and fills it with the elements of `iter`,
+    /// returning an error if the allocation fails or if the iterator has
+    /// more than `capacity` elements.
+    ///
+    /// # Errors
+    /// * [`TryFillError::Reserve`] if the allocation fails (see
+    ///   [`try_with_capacity_in`](GrowLock::try_with_capacity_in)).
+    /// * [`TryFillError::Length`] if `iter` has more than `capacity`
+    ///   elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = GrowLock::try_from_iter_with_capacity_in(10, 0..5, System);
+    /// assert_eq!(lock.unwrap(), [0, 1, 2, 3, 4]);
+    ///
+    /// let lock = GrowLock::try_from_iter_with_capacity_in(2, 0..5, System);
+    /// assert!(lock.is_err());
+    /// ```
+    pub fn try_from_iter_with_capacity_in<I>(
+        capacity: usize,
+        iter: I,
+        alloc: A,
+    ) -> Result<Self, TryFillError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let lock = Self::try_with_capacity_in(capacity, alloc)?;
+        let mut iter = iter.into_iter();
+        // SAFETY: `lock` is not shared yet.
+        unsafe { lock.extend_until_full(&mut iter) };
+        match iter.next() {
+            None => Ok(lock),
+            Some(_) => {
+                Err(LengthError::new(lock.len(), lock.capacity()).into())
+            }
+        }
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity in the
+    /// provided allocator, and fills it with the elements of `iter`.
+    ///
+    /// # Panics
+    /// Panics if `iter` has more than `capacity` elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = GrowLock::from_iter_with_capacity_in(10, 0..5, System);
+    /// assert_eq!(lock, [0, 1, 2, 3, 4]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[must_use]
+    pub fn from_iter_with_capacity_in<I>(
+        capacity: usize,
+        iter: I,
+        alloc: A,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let lock = Self::with_capacity_in(capacity, alloc);
+        let mut iter = iter.into_iter();
+        // SAFETY: `lock` is not shared yet.
+        unsafe { lock.extend_until_full(&mut iter) };
+        assert!(iter.next().is_none(), "length overflow");
+        lock
+    }
+    /// Creates a new [`GrowLock<T>`] in the provided allocator, moving the
+    /// elements of `array` into it, with `extra_capacity` spare slots for
+    /// future pushes.
+    ///
+    /// # Panics
+    /// Panics if `N + extra_capacity` overflows the maximum capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = GrowLock::from_array_in([1, 2, 3], 7, System);
+    /// assert_eq!(lock, [1, 2, 3]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[must_use]
+    pub fn from_array_in<const N: usize>(
+        array: [T; N],
+        extra_capacity: usize,
+        alloc: A,
+    ) -> Self {
+        let Some(capacity) = N.checked_add(extra_capacity) else {
+            panic!("{}", TryReserveError::CapacityOverflow);
+        };
+        let mut lock = Self::with_capacity_in(capacity, alloc);
+        let array = ManuallyDrop::new(array);
+        // SAFETY:
+        // * `lock` has a capacity of at least `N`.
+        // * `array` is wrapped in `ManuallyDrop`, so its elements are
+        //   moved (and not dropped twice).
+        unsafe {
+            ptr::copy_nonoverlapping(array.as_ptr(), lock.as_mut_ptr(), N);
+        }
+        lock.len.store(N, Ordering::Release);
+        lock
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity in the
+    /// provided allocator, and clones the elements of `src` into it,
+    /// returning an error instead of panicking or aborting.
+    ///
+    /// If [`Clone::clone`] panics, the elements already cloned are
+    /// dropped, and the buffer is deallocated.
+    ///
+    /// # Errors
+    /// * [`TryReserveError::CapacityOverflow`] if `capacity < src.len()`,
+    ///   or if `capacity * size_of::<T>` overflows [`isize::MAX`].
+    /// * [`TryReserveError::AllocError`] if the allocator returns an
+    ///   error.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = GrowLock::try_from_slice_in(&[1, 2], 5, System).unwrap();
+    /// assert_eq!(lock, [1, 2]);
+    /// assert!(GrowLock::try_from_slice_in(&[1, 2], 1, System).is_err());
+    /// ```
+    ///
+    /// With the `test-util` feature, the failures of the allocator can be
+    /// injected with `test_util::FailingAllocator`:
+    /// ```
+    /// # #[cfg(feature = "test-util")] {
+    /// use growlock::{GrowLock, test_util::FailingAllocator};
+    ///
+    /// let alloc = FailingAllocator::default();
+    /// alloc.fail_after(0);
+    /// assert!(GrowLock::try_from_slice_in(&[1, 2], 5, &alloc).is_err());
+    /// assert_eq!(alloc.failures(), 1);
+    /// # }
+    /// ```
+    pub fn try_from_slice_in(
+        src: &[T],
+        capacity: usize,
+        alloc: A,
+    ) -> Result<Self, TryReserveError>
+    where
+        T: Clone,
+    {
+        if capacity < src.len() {
+            return Err(TryReserveError::CapacityOverflow);
         }
+        let lock = Self::try_with_capacity_in(capacity, alloc)?;
+        // SAFETY: `lock` is not shared yet, and it has enough capacity.
+        unsafe { lock.extend_from_slice_unchecked(src) };
+        Ok(lock)
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity in the
+    /// provided allocator, and clones the elements of `src` into it.
+    ///
+    /// If [`Clone::clone`] panics, the elements already cloned are
+    /// dropped, and the buffer is deallocated.
+    ///
+    /// # Panics
+    /// Panics if `capacity < src.len()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = GrowLock::from_slice_in(&[1, 2, 3], 10, System);
+    /// assert_eq!(lock, [1, 2, 3]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[must_use]
+    pub fn from_slice_in(src: &[T], capacity: usize, alloc: A) -> Self
+    where
+        T: Clone,
+    {
+        assert!(capacity >= src.len(), "length overflow");
+        let lock = Self::with_capacity_in(capacity, alloc);
+        // SAFETY: `lock` is not shared yet, and it has enough capacity.
+        unsafe { lock.extend_from_slice_unchecked(src) };
+        lock
     }
     /// Creates a new [`GrowLock<T>`] directly from a [`NonNull`]
     /// pointer, a capacity, and an allocator.
@@ -239,6 +900,10 @@ impl<T, A: Allocator> GrowLock<T, A> {
         capacity: usize,
         alloc: A,
     ) -> Self {
+        crate::macros::debug_invariant!(
+            size_of::<T>() == 0 || len <= capacity,
+            "`from_parts`: length {len} > capacity {capacity}",
+        );
         Self {
             // SAFETY: the safety contract must be upheld by the caller
             buf: unsafe {
@@ -248,8 +913,13 @@ impl<T, A: Allocator> GrowLock<T, A> {
                     alloc,
                 )
             },
-            len: AtomicUsize::new(len),
-            mutex: Mutex::new(()),
+            len: CachePadded::new(AtomicUsize::new(len)),
+            generation: AtomicU64::new(0),
+            reservations: CachePadded::new(Reservations::new()),
+            mutex: CachePadded::new(Mutex::new(())),
+            owner: Owner::new(),
+            notifier: Notifier::new(),
+            _invariant: PhantomData,
         }
     }
     /// Creates a new [`GrowLock<T>`] directly from a pointer,
@@ -271,10 +941,14 @@ impl<T, A: Allocator> GrowLock<T, A> {
     #[inline]
     pub unsafe fn from_raw_parts_in(
         ptr: *mut T,
-        len: AtomicUsize,
+        len: usize,
         capacity: usize,
         alloc: A,
     ) -> Self {
+        crate::macros::debug_invariant!(
+            size_of::<T>() == 0 || len <= capacity,
+            "`from_raw_parts`: length {len} > capacity {capacity}",
+        );
         Self {
             // SAFETY: the  safety contract must be upheld by the caller
             buf: unsafe {
@@ -284,15 +958,73 @@ impl<T, A: Allocator> GrowLock<T, A> {
                     alloc,
                 )
             },
-            len,
-            mutex: Mutex::new(()),
+            len: CachePadded::new(AtomicUsize::new(len)),
+            generation: AtomicU64::new(0),
+            reservations: CachePadded::new(Reservations::new()),
+            mutex: CachePadded::new(Mutex::new(())),
+            owner: Owner::new(),
+            notifier: Notifier::new(),
+            _invariant: PhantomData,
         }
     }
+    /// Same as [`from_raw_parts_in`](GrowLock::from_raw_parts_in), but
+    /// takes the length as an
+    /// [`AtomicUsize`](core::sync::atomic::AtomicUsize).
+    ///
+    /// # Safety
+    /// See [`from_raw_parts_in`](GrowLock::from_raw_parts_in).
+    #[inline]
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `from_raw_parts_in`, which takes the length as `usize`"
+    )]
+    pub unsafe fn from_atomic_raw_parts_in(
+        ptr: *mut T,
+        len: core::sync::atomic::AtomicUsize,
+        capacity: usize,
+        alloc: A,
+    ) -> Self {
+        let len = len.into_inner();
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { Self::from_raw_parts_in(ptr, len, capacity, alloc) }
+    }
 
+    /// Locks this [`GrowLock`] with exclusive write access, blocking the
+    /// current thread until it can be acquired.
+    ///
+    /// Readers are never blocked by the returned guard.
+    ///
+    /// Once the lock is acquired, this also waits for the
+    /// [`push_lockfree`](GrowLock::push_lockfree)s in progress and for
+    /// every reserved [`Ticket`](ticket::Ticket) to be committed or
+    /// dropped: if a ticket is held by this thread (or leaked), this never
+    /// returns. Use [`try_write`](GrowLock::try_write) to give up instead.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned (another writer panicked
+    /// while holding the lock). The guard is still returned inside the
+    /// error.
+    ///
+    /// # Panics
+    /// With the `deadlock-detection` feature, panics if the current
+    /// thread already holds the write lock (without it, this deadlocks).
     #[inline]
+    #[track_caller]
     #[doc(alias = "lock")]
     pub fn write(&self) -> LockResult<GrowGuard<'_, T, A>> {
-        match self.mutex.lock() {
+        self.owner.check();
+        let guard = self.mutex.lock();
+        #[cfg(all(feature = "std", not(loom)))]
+        let guard = match guard {
+            Ok(guard) => self.notifier.wait_detached(guard),
+            // stays poisoned, whatever happens while waiting.
+            Err(e) => Err(PoisonError::new(
+                self.notifier
+                    .wait_detached(e.into_inner())
+                    .unwrap_or_else(PoisonError::into_inner),
+            )),
+        };
+        match guard {
             Ok(guard) => Ok(GrowGuard::new(self, guard)),
             Err(e) => {
                 let guard = e.into_inner();
@@ -300,85 +1032,982 @@ impl<T, A: Allocator> GrowLock<T, A> {
             }
         }
     }
+    /// Attempts to lock this [`GrowLock`] with exclusive write access.
+    ///
+    /// This function does not block: it doesn't wait for the
+    /// [`push_lockfree`](GrowLock::push_lockfree)s in progress nor for the
+    /// reserved [`Ticket`](ticket::Ticket)s either, unlike
+    /// [`write`](GrowLock::write).
+    ///
+    /// # Errors
+    /// * [`TryLockError::WouldBlock`] if the lock is already held, or if
+    ///   some lock-free pushes or tickets are not finished yet.
+    /// * [`TryLockError::Poisoned`] if the lock is poisoned. The guard is
+    ///   still returned inside the error.
     #[inline]
     #[doc(alias = "try_lock")]
     pub fn try_write(&self) -> TryLockResult<GrowGuard<'_, T, A>> {
+        self.try_write_within(&mut 0)
+    }
+    /// Same as [`try_write`](GrowLock::try_write), but spins at most
+    /// `spins` times (subtracting them from it) while waiting for the
+    /// lock-free writers in progress.
+    fn try_write_within(
+        &self,
+        spins: &mut usize,
+    ) -> TryLockResult<GrowGuard<'_, T, A>> {
         match self.mutex.try_lock() {
-            Ok(guard) => Ok(GrowGuard::new(self, guard)),
+            #[cfg(all(feature = "std", not(loom)))]
+            Ok(_) if self.notifier.is_detached() => {
+                Err(TryLockError::WouldBlock)
+            }
+            #[cfg(all(feature = "std", not(loom)))]
+            Err(TryLockError::Poisoned(e))
+                if self.notifier.is_detached() =>
+            {
+                drop(e);
+                Err(TryLockError::WouldBlock)
+            }
+            Ok(guard) => GrowGuard::try_new(self, guard, spins)
+                .ok_or(TryLockError::WouldBlock),
             Err(TryLockError::Poisoned(e)) => {
-                let guard = e.into_inner();
-                Err(TryLockError::Poisoned(PoisonError::new(
-                    GrowGuard::new(self, guard),
-                )))
+                match GrowGuard::try_new(self, e.into_inner(), spins) {
+                    Some(guard) => Err(TryLockError::Poisoned(
+                        PoisonError::new(guard),
+                    )),
+                    None => Err(TryLockError::WouldBlock),
+                }
             }
 
             Err(TryLockError::WouldBlock) => Err(TryLockError::WouldBlock),
         }
     }
-    /// Decomposes a [`GrowLock<T>`] into its raw components:
-    /// ([`NonNull`] pointer, length, capacity, allocator).
-    ///
-    /// After calling this function, the caller is responsible for cleaning
-    /// up the [`GrowLock<T>`]. Most often, you can do this by calling
-    /// [`from_parts_in`](GrowLock::from_parts_in).
-    pub fn into_parts_with_alloc(self) -> (NonNull<T>, usize, usize, A) {
-        let mut this = ManuallyDrop::new(self);
-        let ptr = this.as_non_null();
-        let len = this.len();
-        let cap = this.capacity();
-        // SAFETY: `this.allocator()` is a reference
-        // so all precondition are satisfied.
-        let alloc = unsafe { ptr::read(this.allocator()) };
-        (ptr, len, cap, alloc)
-    }
-    /// Decomposes a [`GrowLock<T>`] into its raw components:
-    /// (pointer, length, capacity, allocator).
+    /// Attempts to lock this [`GrowLock`] with exclusive write access,
+    /// spinning while it's held by another writer, for at most
+    /// `max_spins` iterations of [`spin_loop`](core::hint::spin_loop).
     ///
-    /// After calling this function, the caller is responsible for cleaning
-    /// up the [`GrowLock<T>`]. Most often, you can do this by calling
-    /// [`from_raw_parts_in`](GrowLock::from_raw_parts_in).
-    #[inline]
-    pub fn into_raw_parts_with_alloc(self) -> (*mut T, usize, usize, A) {
-        let (ptr, len, cap, alloc) = self.into_parts_with_alloc();
-        let ptr = ptr.as_ptr();
-        (ptr, len, cap, alloc)
-    }
-}
-
-impl<T> GrowLock<T> {
-    /// Creates a new [`GrowLock<T>`],
-    /// returning an error if the allocation fails
+    /// The lock is retried with an exponential backoff (each wait spins
+    /// twice as long as the previous one, up to 64 iterations). Once the
+    /// lock is acquired, the same `max_spins` budget covers the wait for
+    /// the [`push_lockfree`](GrowLock::push_lockfree)s and the
+    /// [`Ticket`](ticket::Ticket)s in progress. The thread is never parked
+    /// nor yielded, so it can be used where blocking isn't allowed (like a
+    /// real-time thread). `try_write_spin(0)` is the same as
+    /// [`try_write`](GrowLock::try_write).
     ///
     /// # Errors
-    /// Returns an error if:
-    /// * `cap * size_of::<T>` overflows `isize::MAX`
-    /// * memory is exhausted
+    /// * [`TryLockError::WouldBlock`] if the lock is still held, or if
+    ///   some lock-free pushes or tickets are still not finished, after
+    ///   `max_spins` iterations.
+    /// * [`TryLockError::Poisoned`] if the lock is poisoned. The guard is
+    ///   still returned inside the error.
     ///
     /// # Examples
     /// ```
     /// use growlock::GrowLock;
+    /// use std::sync::TryLockError;
     ///
-    /// let lock: GrowLock<()> = GrowLock::try_with_capacity(10).unwrap();
+    /// let lock = GrowLock::with_capacity(2);
+    /// let guard = lock.try_write_spin(100).unwrap();
+    /// assert!(matches!(
+    ///     lock.try_write_spin(100),
+    ///     Err(TryLockError::WouldBlock),
+    /// ));
+    /// drop(guard);
+    /// lock.try_write_spin(100).unwrap().push(1);
+    /// assert_eq!(lock, [1]);
     /// ```
-    #[inline]
-    pub fn try_with_capacity(
-        capacity: usize,
-    ) -> Result<Self, TryReserveError> {
-        Self::try_with_capacity_in(capacity, Global)
+    pub fn try_write_spin(
+        &self,
+        max_spins: usize,
+    ) -> TryLockResult<GrowGuard<'_, T, A>> {
+        const MAX_BACKOFF: usize = 64;
+        let mut spins = max_spins;
+        let mut backoff = 1;
+        loop {
+            match self.try_write_within(&mut spins) {
+                Err(TryLockError::WouldBlock) if spins > 0 => {
+                    let wait = backoff.min(spins);
+                    for _ in 0..wait {
+                        hint::spin_loop();
+                    }
+                    spins -= wait;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+                result => return result,
+            }
+        }
     }
-
-    /// Creates a new [`GrowLock<T>`].
+    /// Same as [`write`](GrowLock::write), but the returned guard keeps
+    /// the [`GrowLock`] alive through the [`Arc`], instead of borrowing
+    /// it.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned (another writer panicked
+    /// while holding the lock). The guard is still returned inside the
+    /// error.
     ///
     /// # Examples
     /// ```
     /// use growlock::GrowLock;
+    /// use std::sync::Arc;
     ///
-    /// let lock: GrowLock<String> = GrowLock::with_capacity(10);
+    /// let lock = Arc::new(GrowLock::with_capacity(10));
+    /// let mut guard = lock.write_owned().unwrap();
+    /// guard.push(1);
+    /// assert_eq!(*lock, [1]);
+    /// ```
+    #[cfg(all(feature = "std", not(loom), target_has_atomic = "ptr"))]
+    #[inline]
+    pub fn write_owned(
+        self: &Arc<Self>,
+    ) -> LockResult<OwnedGrowGuard<T, A>> {
+        let lock = Arc::clone(self);
+        let (guard, poisoned) = match self.write() {
+            Ok(guard) => (guard, false),
+            Err(e) => (e.into_inner(), true),
+        };
+        // SAFETY: `guard` borrows the `GrowLock` inside `lock`.
+        let guard = unsafe { OwnedGrowGuard::new(lock, guard) };
+        if poisoned {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+    /// Locks this [`GrowLock`], pushes `value` and unlocks it.
+    ///
+    /// This is a shorthand for `lock.write().unwrap().push(value)`, which
+    /// doesn't hold the guard longer than needed. To push many elements,
+    /// pushing them with the same guard (or [`extend`](Extend::extend))
+    /// is faster, because the lock is taken only once.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned (another writer panicked
+    /// while holding the lock). The value is pushed anyway, like with the
+    /// guard returned inside the error of [`write`](GrowLock::write).
+    ///
+    /// # Panics
+    /// Panics if the [`GrowLock`] is full.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::thread;
+    ///
+    /// let lock = GrowLock::with_capacity(4);
+    /// thread::scope(|s| {
+    ///     for i in 0..4 {
+    ///         let lock = &lock;
+    ///         s.spawn(move || lock.push(i).unwrap());
+    ///     }
+    /// });
+    /// assert_eq!(lock.iter().sum::<i32>(), 6);
+    /// ```
+    #[inline]
+    pub fn push(&self, value: T) -> LockResult<()> {
+        match self.write() {
+            Ok(mut guard) => {
+                guard.push(value);
+                Ok(())
+            }
+            Err(e) => {
+                e.into_inner().push(value);
+                Err(PoisonError::new(()))
+            }
+        }
+    }
+    /// Same as [`push`](GrowLock::push), but gives back the value if the
+    /// [`GrowLock`] is full, instead of panicking.
+    ///
+    /// A poisoned lock is handled like by [`push`](GrowLock::push): the
+    /// value is pushed anyway, and the poisoning is reported.
+    ///
+    /// # Errors
+    /// * [`GrowLockError::Full`] if the [`GrowLock`] is full, also if the
+    ///   lock is poisoned. The value is given back.
+    /// * [`GrowLockError::Poisoned`] if the lock is poisoned (another
+    ///   writer panicked while holding the lock). The value is pushed
+    ///   anyway.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, error::GrowLockError};
+    ///
+    /// let lock = GrowLock::with_capacity(1);
+    /// assert_eq!(lock.try_push(1), Ok(()));
+    /// let err = lock.try_push(2).unwrap_err();
+    /// assert_eq!(
+    ///     err,
+    ///     GrowLockError::Full { value: 2, len: 1, capacity: 1 },
+    /// );
+    /// assert_eq!(err.into_value(), Some(2));
+    /// ```
+    #[inline]
+    pub fn try_push(&self, value: T) -> Result<(), GrowLockError<T>> {
+        let (guard, poisoned) = match self.write() {
+            Ok(guard) => (guard, false),
+            Err(e) => (e.into_inner(), true),
+        };
+        self.push_guarded(guard, value, poisoned)
+    }
+    /// Same as [`try_push`](GrowLock::try_push), but gives back the value
+    /// instead of waiting if the lock is held by another writer.
+    ///
+    /// This never blocks (it uses [`try_write`](GrowLock::try_write)), so
+    /// it can be called from threads which must not wait, dropping (or
+    /// retrying) the value on contention.
+    ///
+    /// # Errors
+    /// * [`GrowLockError::WouldBlock`] if the lock is held, or if some
+    ///   lock-free pushes or tickets are not finished yet. The value is
+    ///   given back.
+    /// * [`GrowLockError::Full`] if the [`GrowLock`] is full. The value is
+    ///   given back.
+    /// * [`GrowLockError::Poisoned`] if the lock is poisoned. Like
+    ///   [`try_push`](GrowLock::try_push), the value is pushed anyway.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, error::GrowLockError};
+    ///
+    /// let lock = GrowLock::with_capacity(10);
+    /// assert_eq!(lock.push_nonblocking(1), Ok(()));
+    ///
+    /// let guard = lock.write().unwrap();
+    /// assert_eq!(
+    ///     lock.push_nonblocking(2),
+    ///     Err(GrowLockError::WouldBlock(2)),
+    /// );
+    /// drop(guard);
+    /// assert_eq!(*lock, [1]);
+    /// ```
+    #[inline]
+    pub fn push_nonblocking(
+        &self,
+        value: T,
+    ) -> Result<(), GrowLockError<T>> {
+        let (guard, poisoned) = match self.try_write() {
+            Ok(guard) => (guard, false),
+            Err(TryLockError::Poisoned(e)) => (e.into_inner(), true),
+            Err(TryLockError::WouldBlock) => {
+                return Err(GrowLockError::WouldBlock(value));
+            }
+        };
+        self.push_guarded(guard, value, poisoned)
+    }
+    /// Pushes `value` while `guard` is held, for
+    /// [`try_push`](GrowLock::try_push) and
+    /// [`push_nonblocking`](GrowLock::push_nonblocking).
+    fn push_guarded(
+        &self,
+        guard: GrowGuard<'_, T, A>,
+        value: T,
+        poisoned: bool,
+    ) -> Result<(), GrowLockError<T>> {
+        // SAFETY: the write guard is held.
+        unsafe { self.guarded_try_push(value) }
+            .map_err(|value| PushError::new(value, self.capacity()))?;
+        drop(guard);
+        if poisoned {
+            Err(GrowLockError::Poisoned)
+        } else {
+            Ok(())
+        }
+    }
+    /// Locks this [`GrowLock`], appends the elements of `iter` until it's
+    /// full, and unlocks it, returning how many elements were appended.
+    ///
+    /// The elements which don't fit are left in the iterator: pass
+    /// [`by_ref`](Iterator::by_ref) to keep them. The new length is
+    /// published once at the end.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned (another writer panicked
+    /// while holding the lock). The elements are appended anyway, and
+    /// their count is returned inside the error.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::sync::mpsc;
+    ///
+    /// let lock = GrowLock::with_capacity(3);
+    /// let (tx, rx) = mpsc::channel();
+    /// for i in 0..5 {
+    ///     tx.send(i).unwrap();
+    /// }
+    /// let mut pending = rx.try_iter();
+    /// assert_eq!(lock.extend_locked(pending.by_ref()).unwrap(), 3);
+    /// assert_eq!(*lock, [0, 1, 2]);
+    /// assert_eq!(pending.next(), Some(3));
+    /// ```
+    pub fn extend_locked<I>(&self, iter: I) -> LockResult<usize>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let (guard, poisoned) = match self.write() {
+            Ok(guard) => (guard, false),
+            Err(e) => (e.into_inner(), true),
+        };
+        let len = guard.len();
+        // SAFETY: the write guard is held.
+        unsafe { self.extend_until_full(&mut iter.into_iter()) };
+        let count = guard.len() - len;
+        drop(guard);
+        if poisoned {
+            Err(PoisonError::new(count))
+        } else {
+            Ok(count)
+        }
+    }
+    /// Blocks until at least `n` elements are pushed, or until `timeout`
+    /// elapses (if any), returning whether `len() >= n`.
+    ///
+    /// If the condition is already met, this returns immediately, without
+    /// locking anything. If `n` is greater than the capacity, it returns
+    /// `false` immediately.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{thread, time::Duration};
+    ///
+    /// let results = GrowLock::with_capacity(4);
+    /// thread::scope(|s| {
+    ///     for i in 0..4 {
+    ///         let results = &results;
+    ///         s.spawn(move || results.write().unwrap().push(i * i));
+    ///     }
+    ///     assert!(results.wait_for_len(4, None));
+    /// });
+    /// assert!(!results.wait_for_len(5, Some(Duration::from_secs(1))));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn wait_for_len(
+        &self,
+        n: usize,
+        timeout: Option<Duration>,
+    ) -> bool {
+        if n > self.capacity() {
+            return false;
+        }
+        self.notifier.wait_until(|| self.len() >= n, timeout)
+    }
+    /// Returns a [`Receiver`] of the lengths published after every push
+    /// (or bulk write), starting with the current length.
+    ///
+    /// The lengths are sent through an unbounded channel, so a slow
+    /// subscriber never blocks the writers, but the lengths it doesn't
+    /// receive are buffered. The same length can be received more than
+    /// once, and a bulk write only sends its final length.
+    ///
+    /// When the [`Receiver`] is dropped, the subscription ends with the
+    /// next push.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::with_capacity(10);
+    /// let rx = lock.subscribe();
+    /// lock.write().unwrap().push(1);
+    /// lock.write().unwrap().extend([2, 3]);
+    ///
+    /// assert_eq!(rx.try_iter().collect::<Vec<_>>(), [0, 1, 3]);
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn subscribe(&self) -> Receiver<usize> {
+        let (tx, rx) = mpsc::channel();
+        self.notifier.subscribe(tx, || self.len());
+        rx
+    }
+    /// Clones the elements pushed so far into a new [`GrowLock`] with the
+    /// same capacity, allocated in the provided allocator, returning an
+    /// error if the allocation fails.
+    ///
+    /// `self` is only read, so other threads can keep reading (and
+    /// pushing) while the clone happens; elements pushed after the call
+    /// are not cloned.
+    ///
+    /// # Errors
+    /// If any of these conditions happen, an error is returned:
+    /// * `self.capacity() * size_of::<T>` overflows [`isize::MAX`] (only
+    ///   possible if `B` has different limits than `A`)
+    /// * memory is exhausted
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = grow_lock!(10, [1, 2, 3]);
+    /// let cloned = lock.try_clone_in(System).unwrap();
+    /// assert_eq!(cloned, [1, 2, 3]);
+    /// ```
+    ///
+    /// With the `test-util` feature, the failures of the allocator can be
+    /// injected with `test_util::FailingAllocator`:
+    /// ```
+    /// # #[cfg(feature = "test-util")] {
+    /// use growlock::{grow_lock, test_util::FailingAllocator};
+    ///
+    /// let lock = grow_lock!(10, [1, 2, 3]);
+    /// let alloc = FailingAllocator::default();
+    /// let cloned = lock.try_clone_in(&alloc).unwrap();
+    /// alloc.fail_after(0);
+    /// assert!(cloned.try_clone_in(&alloc).is_err());
+    /// drop(cloned);
+    /// assert_eq!(alloc.live(), 0);
+    /// # }
+    /// ```
+    pub fn try_clone_in<B: Allocator>(
+        &self,
+        alloc: B,
+    ) -> Result<GrowLock<T, B>, TryReserveError>
+    where
+        T: Clone,
+    {
+        let lock = GrowLock::try_with_capacity_in(self.capacity(), alloc)?;
+        // SAFETY: `lock` is not shared yet, and it has the same capacity
+        // as `self`, which is >= the length of the slice.
+        unsafe { lock.extend_from_slice_unchecked(self) };
+        Ok(lock)
+    }
+    /// Clones the elements pushed so far into a new [`GrowLock`] with the
+    /// same capacity, allocated in the provided allocator.
+    ///
+    /// See [`try_clone_in`](GrowLock::try_clone_in) for more details.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    /// use std::alloc::System;
+    ///
+    /// let lock = grow_lock!(10, [1, 2, 3]);
+    /// let cloned = lock.clone_in(System);
+    /// assert_eq!(cloned, [1, 2, 3]);
+    /// assert_eq!(cloned.capacity(), 10);
+    /// ```
+    #[must_use]
+    pub fn clone_in<B: Allocator>(&self, alloc: B) -> GrowLock<T, B>
+    where
+        T: Clone,
+    {
+        let lock = GrowLock::with_capacity_in(self.capacity(), alloc);
+        // SAFETY: `lock` is not shared yet, and it has the same capacity
+        // as `self`, which is >= the length of the slice.
+        unsafe { lock.extend_from_slice_unchecked(self) };
+        lock
+    }
+    /// Converts the [`GrowLock`] into a [`Box<[T]>`](Box), dropping the
+    /// spare capacity.
+    ///
+    /// This reallocates only if the [`GrowLock`] is not full.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(10, [1, 2, 3]);
+    /// let slice = lock.into_boxed_slice();
+    /// assert_eq!(slice.len(), 3);
+    /// ```
+    #[inline]
+    #[must_use]
+    #[cfg(feature = "nightly")]
+    pub fn into_boxed_slice(self) -> Box<[T], A> {
+        Vec::from(self).into_boxed_slice()
+    }
+    /// Converts `vec` into a [`GrowLock`] with (at least) the given
+    /// capacity, returning an error if the reallocation fails.
+    ///
+    /// The allocation of `vec` is taken over if its capacity is at least
+    /// `capacity`, otherwise it's reallocated to `capacity` elements
+    /// first.
+    ///
+    /// # Errors
+    /// * [`FromVecError::Length`] if `capacity < vec.len()`.
+    /// * [`FromVecError::Alloc`] if `capacity * size_of::<T>` overflows
+    ///   [`isize::MAX`], or if the allocator returns an error. `vec` is
+    ///   dropped.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::try_from_vec_with_capacity(vec![1, 2], 8)?;
+    /// assert_eq!(lock, [1, 2]);
+    /// assert_eq!(lock.capacity(), 8);
+    /// assert!(GrowLock::try_from_vec_with_capacity(vec![1, 2], 1).is_err());
+    /// # Ok::<(), growlock::error::FromVecError>(())
+    /// ```
+    #[cfg(feature = "nightly")]
+    pub fn try_from_vec_with_capacity(
+        mut vec: Vec<T, A>,
+        capacity: usize,
+    ) -> Result<Self, FromVecError> {
+        Self::try_reserve_vec(
+            vec.len(),
+            vec.capacity(),
+            capacity,
+            |additional| vec.try_reserve_exact(additional).is_ok(),
+        )?;
+        Ok(vec.into())
+    }
+    /// Checks that `capacity` fits the `len` elements of a [`Vec`], and
+    /// reserves the missing capacity with `try_reserve_exact` (which
+    /// returns whether it succeeded) if its capacity is `vec_capacity`.
+    ///
+    /// Shared by the `try_from_vec_with_capacity` with and without the
+    /// `nightly` feature.
+    fn try_reserve_vec(
+        len: usize,
+        vec_capacity: usize,
+        capacity: usize,
+        try_reserve_exact: impl FnOnce(usize) -> bool,
+    ) -> Result<(), FromVecError> {
+        if capacity < len {
+            return Err(LengthError::new(len, capacity).into());
+        }
+        if vec_capacity < capacity && !try_reserve_exact(capacity - len) {
+            // the kind of the error of `Vec` can't be read on stable.
+            return Err(core::alloc::Layout::array::<T>(capacity)
+                .map_or(
+                    TryReserveError::CapacityOverflow,
+                    TryReserveError::AllocError,
+                )
+                .into());
+        }
+        Ok(())
+    }
+    /// Converts `vec` into a [`GrowLock`] with (at least) the given
+    /// capacity.
+    ///
+    /// The allocation of `vec` is taken over if its capacity is at least
+    /// `capacity`, otherwise it's reallocated to `capacity` elements
+    /// first. Unlike [`From<Vec<T>>`](From), the [`GrowLock`] has room
+    /// for `capacity - vec.len()` more elements, whatever the spare
+    /// capacity of `vec` was.
+    ///
+    /// # Panics
+    /// Panics if `capacity < vec.len()`, or if `capacity *
+    /// size_of::<T>` overflows [`isize::MAX`].
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let mut vec = vec![1, 2, 3];
+    /// vec.shrink_to_fit();
+    /// let lock = GrowLock::from_vec_with_capacity(vec, 10);
+    /// assert_eq!(lock, [1, 2, 3]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[must_use]
+    #[cfg(feature = "nightly")]
+    pub fn from_vec_with_capacity(
+        mut vec: Vec<T, A>,
+        capacity: usize,
+    ) -> Self {
+        assert!(capacity >= vec.len(), "length overflow");
+        if vec.capacity() < capacity {
+            vec.reserve_exact(capacity - vec.len());
+        }
+        vec.into()
+    }
+    /// Decomposes a [`GrowLock<T>`] into its raw components:
+    /// ([`NonNull`] pointer, length, capacity, allocator).
+    ///
+    /// After calling this function, the caller is responsible for cleaning
+    /// up the [`GrowLock<T>`]. Most often, you can do this by calling
+    /// [`from_parts_in`](GrowLock::from_parts_in).
+    pub fn into_parts_with_alloc(self) -> (NonNull<T>, usize, usize, A) {
+        let mut this = ManuallyDrop::new(self);
+        let ptr = this.as_non_null();
+        let len = this.len();
+        let cap = this.capacity();
+        // SAFETY: `this.allocator()` is a reference
+        // so all precondition are satisfied.
+        let alloc = unsafe { ptr::read(this.allocator()) };
+        (ptr, len, cap, alloc)
+    }
+    /// Decomposes a [`GrowLock<T>`] into its raw components:
+    /// (pointer, length, capacity, allocator).
+    ///
+    /// After calling this function, the caller is responsible for cleaning
+    /// up the [`GrowLock<T>`]. Most often, you can do this by calling
+    /// [`from_raw_parts_in`](GrowLock::from_raw_parts_in).
+    #[inline]
+    pub fn into_raw_parts_with_alloc(self) -> (*mut T, usize, usize, A) {
+        let (ptr, len, cap, alloc) = self.into_parts_with_alloc();
+        let ptr = ptr.as_ptr();
+        (ptr, len, cap, alloc)
+    }
+}
+
+impl<T, A: Allocator, const N: usize> GrowLock<[T; N], A> {
+    /// Converts a [`GrowLock<[T; N]>`](GrowLock) into a [`GrowLock<T>`],
+    /// taking over the allocation, like [`Vec::into_flattened`].
+    ///
+    /// The length and the capacity are multiplied by `N`. If `N` is zero,
+    /// the new [`GrowLock`] is empty, and its capacity is zero (unless
+    /// `T` is a ZST): `[T; 0]` is a ZST, so nothing was allocated.
+    ///
+    /// # Panics
+    /// Panics if the new length overflows a `usize`, which can only
+    /// happen if `T` is a ZST.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(4, [[1, 2, 3], [4, 5, 6]]);
+    /// let flat = lock.into_flattened();
+    /// assert_eq!(flat, [1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(flat.capacity(), 12);
+    /// ```
+    #[must_use]
+    pub fn into_flattened(self) -> GrowLock<T, A> {
+        // checked before taking `self` apart, so that the elements are
+        // dropped if it panics.
+        let Some(len) = self.len().checked_mul(N) else {
+            panic!("length overflow");
+        };
+        let (ptr, _, cap, alloc) = self.into_parts_with_alloc();
+        let cap = if size_of::<T>() == 0 {
+            usize::MAX
+        } else {
+            // can't overflow: `[T; N]` is a ZST if `N` is zero (and
+            // `cap * 0 == 0`), otherwise `cap * N` elements of `T` fit in
+            // the allocation, which is at most `isize::MAX` bytes.
+            cap * N
+        };
+        // SAFETY:
+        // * `[T; N]` has the same alignment as `T`, and `cap` arrays take
+        //   the same size as `cap * N` elements (or nothing is allocated
+        //   if the capacity is zero).
+        // * the first `len` arrays are `len * N` initialized elements.
+        unsafe { GrowLock::from_parts_in(ptr.cast(), len, cap, alloc) }
+    }
+}
+
+impl<T> GrowLock<T> {
+    /// Converts the [`GrowLock`] into a [`Box<[T]>`](Box), dropping the
+    /// spare capacity.
+    ///
+    /// This reallocates only if the [`GrowLock`] is not full.
+    #[inline]
+    #[must_use]
+    #[cfg(not(feature = "nightly"))]
+    pub fn into_boxed_slice(self) -> Box<[T]> {
+        Vec::from(self).into_boxed_slice()
+    }
+    /// Converts `vec` into a [`GrowLock`] with (at least) the given
+    /// capacity, returning an error if the reallocation fails.
+    ///
+    /// The allocation of `vec` is taken over if its capacity is at least
+    /// `capacity`, otherwise it's reallocated to `capacity` elements
+    /// first.
+    ///
+    /// # Errors
+    /// * [`FromVecError::Length`] if `capacity < vec.len()`.
+    /// * [`FromVecError::Alloc`] if `capacity * size_of::<T>` overflows
+    ///   [`isize::MAX`], or if the allocator returns an error. `vec` is
+    ///   dropped.
+    #[cfg(not(feature = "nightly"))]
+    pub fn try_from_vec_with_capacity(
+        mut vec: Vec<T>,
+        capacity: usize,
+    ) -> Result<Self, FromVecError> {
+        Self::try_reserve_vec(
+            vec.len(),
+            vec.capacity(),
+            capacity,
+            |additional| vec.try_reserve_exact(additional).is_ok(),
+        )?;
+        Ok(vec.into())
+    }
+    /// Converts `vec` into a [`GrowLock`] with (at least) the given
+    /// capacity.
+    ///
+    /// The allocation of `vec` is taken over if its capacity is at least
+    /// `capacity`, otherwise it's reallocated to `capacity` elements
+    /// first.
+    ///
+    /// # Panics
+    /// Panics if `capacity < vec.len()`, or if `capacity *
+    /// size_of::<T>` overflows [`isize::MAX`].
+    #[must_use]
+    #[cfg(not(feature = "nightly"))]
+    pub fn from_vec_with_capacity(
+        mut vec: Vec<T>,
+        capacity: usize,
+    ) -> Self {
+        assert!(capacity >= vec.len(), "length overflow");
+        if vec.capacity() < capacity {
+            vec.reserve_exact(capacity - vec.len());
+        }
+        vec.into()
+    }
+    /// Creates a new [`GrowLock<T>`],
+    /// returning an error if the allocation fails
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// * `cap * size_of::<T>` overflows `isize::MAX`
+    /// * memory is exhausted
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock: GrowLock<()> = GrowLock::try_with_capacity(10).unwrap();
+    /// ```
+    #[inline]
+    pub fn try_with_capacity(
+        capacity: usize,
+    ) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+
+    /// Creates a new [`GrowLock<T>`].
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock: GrowLock<String> = GrowLock::with_capacity(10);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Creates a new [`GrowLock<T>`] with the given capacity, and fills
+    /// it with the elements of `iter`, returning an error if the
+    /// allocation fails or if the iterator has more than `capacity`
+    /// elements.
+    ///
+    /// # Errors
+    /// * [`TryFillError::Reserve`] if the allocation fails (see
+    ///   [`try_with_capacity`](GrowLock::try_with_capacity)).
+    /// * [`TryFillError::Length`] if `iter` has more than `capacity`
+    ///   elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::try_from_iter_with_capacity(10, 0..5).unwrap();
+    /// assert_eq!(lock, [0, 1, 2, 3, 4]);
+    /// ```
+    #[inline]
+    pub fn try_from_iter_with_capacity<I>(
+        capacity: usize,
+        iter: I,
+    ) -> Result<Self, TryFillError>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        Self::try_from_iter_with_capacity_in(capacity, iter, Global)
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity, and fills
+    /// it with the elements of `iter`.
+    ///
+    /// This is the same as `grow_lock!(capacity, [..])`, but for
+    /// iterators (see also `grow_lock!(capacity; from iter)`).
+    ///
+    /// # Panics
+    /// Panics if `iter` has more than `capacity` elements.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from_iter_with_capacity(10, 0..5);
+    /// assert_eq!(lock, [0, 1, 2, 3, 4]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn from_iter_with_capacity<I>(capacity: usize, iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        Self::from_iter_with_capacity_in(capacity, iter, Global)
+    }
+
+    /// Creates a new [`GrowLock<T>`] with the given capacity, and
+    /// initializes the first `len` elements with `f`, called with each
+    /// index in order.
+    ///
+    /// The length is published once, after all the elements are
+    /// initialized. If `f` panics, the elements already initialized are
+    /// dropped.
+    ///
+    /// # Panics
+    /// Panics if `len > capacity`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from_fn(10, 5, |i| i * 2);
+    /// assert_eq!(lock, [0, 2, 4, 6, 8]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    #[must_use]
+    pub fn from_fn<F>(capacity: usize, len: usize, f: F) -> Self
+    where
+        F: FnMut(usize) -> T,
+    {
+        assert!(
+            len <= capacity,
+            "`len` ({len}) is greater than `capacity` ({capacity})"
+        );
+        Self::from_iter_with_capacity(capacity, (0..len).map(f))
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity, and
+    /// initializes the first `len` elements with the fallible `f`, called
+    /// with each index in order.
+    ///
+    /// If `f` returns an error, the elements already initialized are
+    /// dropped and the error is returned.
+    ///
+    /// # Errors
+    /// Returns the first error returned by `f`.
+    ///
+    /// # Panics
+    /// Panics if `len > capacity`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::try_from_fn(10, 3, |i| u8::try_from(i));
+    /// assert_eq!(lock.unwrap(), [0, 1, 2]);
+    ///
+    /// let lock = GrowLock::try_from_fn(300, 300, |i| u8::try_from(i));
+    /// assert!(lock.is_err());
+    /// ```
+    pub fn try_from_fn<F, E>(
+        capacity: usize,
+        len: usize,
+        mut f: F,
+    ) -> Result<Self, E>
+    where
+        F: FnMut(usize) -> Result<T, E>,
+    {
+        assert!(
+            len <= capacity,
+            "`len` ({len}) is greater than `capacity` ({capacity})"
+        );
+        let lock = Self::with_capacity(capacity);
+        let mut error = None;
+        let mut iter =
+            (0..len).map_while(|i| f(i).map_err(|e| error = Some(e)).ok());
+        // SAFETY: `lock` is not shared yet.
+        unsafe { lock.extend_until_full(&mut iter) };
+        match error {
+            Some(e) => Err(e),
+            None => Ok(lock),
+        }
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity, and clones
+    /// the elements of `src` into it, returning an error instead of
+    /// panicking or aborting.
+    ///
+    /// See [`try_from_slice_in`](GrowLock::try_from_slice_in) for more
+    /// details.
+    ///
+    /// # Errors
+    /// * [`TryReserveError::CapacityOverflow`] if `capacity < src.len()`,
+    ///   or if `capacity * size_of::<T>` overflows [`isize::MAX`].
+    /// * [`TryReserveError::AllocError`] if memory is exhausted.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::try_from_slice(&["a", "b"], 10).unwrap();
+    /// assert_eq!(lock, ["a", "b"]);
+    /// ```
+    #[inline]
+    pub fn try_from_slice(
+        src: &[T],
+        capacity: usize,
+    ) -> Result<Self, TryReserveError>
+    where
+        T: Clone,
+    {
+        Self::try_from_slice_in(src, capacity, Global)
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity, and clones
+    /// the elements of `src` into it.
+    ///
+    /// See [`from_slice_in`](GrowLock::from_slice_in) for more details.
+    ///
+    /// # Panics
+    /// Panics if `capacity < src.len()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::from_slice(&["a", "b"], 10);
+    /// assert_eq!(lock, ["a", "b"]);
+    /// assert_eq!(lock.capacity(), 10);
     /// ```
     #[inline]
     #[must_use]
-    pub fn with_capacity(capacity: usize) -> Self {
-        Self::with_capacity_in(capacity, Global)
+    pub fn from_slice(src: &[T], capacity: usize) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_slice_in(src, capacity, Global)
+    }
+    /// Creates a new [`GrowLock<T>`] with at least the given capacity
+    /// from a [`Cow`].
+    ///
+    /// If `cow` is [`Cow::Owned`], the allocation of the [`Vec`] is taken
+    /// over (growing it if needed) and no element is cloned. If it's
+    /// [`Cow::Borrowed`], the elements are cloned in a new allocation.
+    ///
+    /// # Panics
+    /// Panics if `capacity < cow.len()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::borrow::Cow;
+    ///
+    /// let lock = GrowLock::from_cow_with_capacity(Cow::Borrowed(&[1, 2]), 5);
+    /// assert_eq!(lock, [1, 2]);
+    /// assert_eq!(lock.capacity(), 5);
+    ///
+    /// let owned: Cow<[_]> = Cow::Owned(vec![1, 2]);
+    /// let lock = GrowLock::from_cow_with_capacity(owned, 5);
+    /// assert_eq!(lock, [1, 2]);
+    /// assert!(lock.capacity() >= 5);
+    /// ```
+    #[must_use]
+    pub fn from_cow_with_capacity(
+        cow: Cow<'_, [T]>,
+        capacity: usize,
+    ) -> Self
+    where
+        T: Clone,
+    {
+        match cow {
+            Cow::Borrowed(src) => Self::from_slice(src, capacity),
+            Cow::Owned(mut vec) => {
+                assert!(capacity >= vec.len(), "length overflow");
+                vec.reserve_exact(capacity - vec.len());
+                vec.into()
+            }
+        }
     }
 
     /// Creates a new [`GrowLock<T>`] directly from a [`NonNull`]
@@ -397,23 +2026,14 @@ impl<T> GrowLock<T> {
     /// * at least `len` elements starting from `ptr` need to be properly
     ///   initialized values of type `T`.
     #[inline]
+    #[must_use]
     pub unsafe fn from_parts(
         ptr: NonNull<T>,
-        len: AtomicUsize,
+        len: usize,
         capacity: usize,
     ) -> Self {
-        Self {
-            // SAFETY: the  safety contract must be upheld by the caller
-            buf: unsafe {
-                RawGrowLock::from_nonnull_in(
-                    ptr,
-                    Cap::new_unchecked::<T>(capacity),
-                    Global,
-                )
-            },
-            len,
-            mutex: Mutex::new(()),
-        }
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { Self::from_parts_in(ptr, len, capacity, Global) }
     }
     /// Creates a new [`GrowLock<T>`] directly from a pointer, and
     /// a capacity.
@@ -431,23 +2051,52 @@ impl<T> GrowLock<T> {
     /// * at least `len` elements starting from `ptr` need to be properly
     ///   initialized values of type `T`.
     #[inline]
+    #[must_use]
     pub unsafe fn from_raw_parts(
         ptr: *mut T,
-        len: AtomicUsize,
+        len: usize,
         capacity: usize,
     ) -> Self {
-        Self {
-            // SAFETY: the  safety contract must be upheld by the caller
-            buf: unsafe {
-                RawGrowLock::from_raw_in(
-                    ptr,
-                    Cap::new_unchecked::<T>(capacity),
-                    Global,
-                )
-            },
-            len,
-            mutex: Mutex::new(()),
-        }
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { Self::from_raw_parts_in(ptr, len, capacity, Global) }
+    }
+    /// Same as [`from_parts`](GrowLock::from_parts), but takes the length
+    /// as an [`AtomicUsize`](core::sync::atomic::AtomicUsize).
+    ///
+    /// # Safety
+    /// See [`from_parts`](GrowLock::from_parts).
+    #[inline]
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `from_parts`, which takes the length as `usize`"
+    )]
+    pub unsafe fn from_atomic_parts(
+        ptr: NonNull<T>,
+        len: core::sync::atomic::AtomicUsize,
+        capacity: usize,
+    ) -> Self {
+        let len = len.into_inner();
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { Self::from_parts(ptr, len, capacity) }
+    }
+    /// Same as [`from_raw_parts`](GrowLock::from_raw_parts), but takes the
+    /// length as an [`AtomicUsize`](core::sync::atomic::AtomicUsize).
+    ///
+    /// # Safety
+    /// See [`from_raw_parts`](GrowLock::from_raw_parts).
+    #[inline]
+    #[deprecated(
+        since = "0.2.0",
+        note = "use `from_raw_parts`, which takes the length as `usize`"
+    )]
+    pub unsafe fn from_atomic_raw_parts(
+        ptr: *mut T,
+        len: core::sync::atomic::AtomicUsize,
+        capacity: usize,
+    ) -> Self {
+        let len = len.into_inner();
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { Self::from_raw_parts(ptr, len, capacity) }
     }
     /// Decomposes a [`GrowLock<T>`] into its raw components:
     /// ([`NonNull`] pointer, length, capacity).
@@ -472,20 +2121,72 @@ impl<T> GrowLock<T> {
         (this.as_mut_ptr(), this.len(), this.capacity())
     }
 }
+/// Like [`Vec`], a [`GrowLock`] can hold references which don't outlive
+/// it, as long as the elements don't use them when dropped.
+///
+/// # Examples
+/// ```
+/// use growlock::GrowLock;
+///
+/// let lock: GrowLock<&str>;
+/// let s = String::from("hello");
+/// lock = GrowLock::with_capacity(1);
+/// lock.write().unwrap().push(&s);
+/// // `s` is dropped before `lock`
+/// ```
+/// ```compile_fail,E0597
+/// use growlock::GrowLock;
+///
+/// struct Print<'a>(&'a str);
+/// impl Drop for Print<'_> {
+///     fn drop(&mut self) {
+///         println!("{}", self.0);
+///     }
+/// }
+///
+/// let lock: GrowLock<Print<'_>>;
+/// let s = String::from("hello");
+/// lock = GrowLock::with_capacity(1);
+/// lock.write().unwrap().push(Print(&s));
+/// // `Print` would read `s` after it's dropped
+/// ```
+///
+/// This needs the `nightly` feature: without it, the elements can't
+/// borrow anything dropped before the [`GrowLock`].
+///
+/// # Safety:
+/// The elements are only dropped (never otherwise accessed), and
+/// `RawGrowLock` owns a [`PhantomData<T>`](std::marker::PhantomData),
+/// so the drop checker still checks that `T` itself is safe to drop.
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, A: Allocator> Drop for GrowLock<T, A> {
+    fn drop(&mut self) {
+        // SAFETY: `self` is never used again.
+        unsafe { self.drop_elements() };
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
 impl<T, A: Allocator> Drop for GrowLock<T, A> {
     fn drop(&mut self) {
-        // if `T::IS_ZST` then `capacity()` returns `usize::MAX`
+        // SAFETY: `self` is never used again.
+        unsafe { self.drop_elements() };
+    }
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Drops the elements.
+    ///
+    /// # Safety
+    /// The elements must not be used again.
+    unsafe fn drop_elements(&mut self) {
+        // if `T` is a ZST then `capacity()` returns `usize::MAX`
         if self.capacity() == 0 {
             return;
         }
         // SAFETY: all elements are correctly aligned.
         //  see GrowLock::as_slice for safety.
-        unsafe {
-            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
-                self.as_mut_ptr(),
-                self.len(),
-            ));
-        }
+        unsafe { drop_in_place_rev(self.as_mut_ptr(), self.len()) };
     }
 }
 
@@ -527,6 +2228,58 @@ impl<T, A: Allocator + Default> Default for GrowLock<T, A> {
     }
 }
 
+// ------------------------------ Clone impl ------------------------------
+
+impl<T: Clone, A: Allocator + Clone> Clone for GrowLock<T, A> {
+    /// Clones the elements pushed so far into a new [`GrowLock`] with the
+    /// same capacity.
+    fn clone(&self) -> Self {
+        let this = Self::with_capacity_in(
+            self.capacity(),
+            self.allocator().clone(),
+        );
+        // SAFETY: `this` is not shared yet, and it has the same capacity
+        // as `self`, which is >= `self.len()`.
+        unsafe { this.extend_from_slice_unchecked(self) };
+        this
+    }
+    /// Overwrites `self` with a clone of `source`, reusing the allocation
+    /// of `self` if `self.capacity() >= source.len()`.
+    ///
+    /// The elements of `self` are overwritten in place (see
+    /// [address stability](GrowLock#address-stability)).
+    fn clone_from(&mut self, source: &Self) {
+        self.bump_generation();
+        let src = source.as_slice();
+        if self.capacity() < src.len() {
+            let generation = self.generation();
+            *self = source.clone();
+            self.generation.store(generation, Ordering::Relaxed);
+            return;
+        }
+
+        let len = self.len();
+        if len > src.len() {
+            // the length is updated before dropping the tail, so that if
+            // a `drop` panics, the other elements are not dropped twice.
+            self.len.store(src.len(), Ordering::Release);
+            // SAFETY: elements in `src.len()..len` are initialized and no
+            // longer reachable through `self`.
+            unsafe {
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                    self.as_mut_ptr().add(src.len()),
+                    len - src.len(),
+                ));
+            }
+        }
+
+        let (init, tail) = src.split_at(self.len());
+        self.as_mut_slice().clone_from_slice(init);
+        // SAFETY: we have `&mut self`, and `self.capacity() >= src.len()`.
+        unsafe { self.extend_from_slice_unchecked(tail) };
+    }
+}
+
 // ------------------------------- fmt impl -------------------------------
 
 impl<T: fmt::Debug, A: Allocator> fmt::Debug for GrowLock<T, A> {
@@ -538,15 +2291,29 @@ impl<T: fmt::Debug, A: Allocator> fmt::Debug for GrowLock<T, A> {
 
 // ----------------------------- From impl -----------------------------
 
+#[cfg(feature = "nightly")]
 impl<T, A: Allocator> From<Vec<T, A>> for GrowLock<T, A> {
     #[inline]
     fn from(value: Vec<T, A>) -> Self {
-        let (ptr, len, cap, alloc) = value.into_parts_with_alloc();
+        let (ptr, len, cap, alloc) = value.into_parts_with_allocator();
         // SAFETY: the `GrowLock` is constructed from parts of the given
         // `Vec` so this is safe.
         unsafe { Self::from_parts_in(ptr, len, cap, alloc) }
     }
 }
+#[cfg(not(feature = "nightly"))]
+impl<T> From<Vec<T>> for GrowLock<T> {
+    #[inline]
+    fn from(value: Vec<T>) -> Self {
+        let mut value = ManuallyDrop::new(value);
+        let (ptr, len, cap) =
+            (value.as_mut_ptr(), value.len(), value.capacity());
+        // SAFETY: the `GrowLock` is constructed from parts of the given
+        // `Vec`, which is allocated with the global allocator.
+        unsafe { Self::from_raw_parts(ptr, len, cap) }
+    }
+}
+#[cfg(feature = "nightly")]
 impl<T, A: Allocator> From<GrowLock<T, A>> for Vec<T, A> {
     #[inline]
     fn from(value: GrowLock<T, A>) -> Self {
@@ -556,6 +2323,176 @@ impl<T, A: Allocator> From<GrowLock<T, A>> for Vec<T, A> {
         unsafe { Self::from_parts_in(ptr, len, cap, alloc) }
     }
 }
+#[cfg(not(feature = "nightly"))]
+impl<T> From<GrowLock<T>> for Vec<T> {
+    #[inline]
+    fn from(value: GrowLock<T>) -> Self {
+        let (ptr, len, cap) = value.into_raw_parts();
+        // SAFETY: the `Vec` is constructed from parts of the given
+        // `GrowLock`, which is allocated with the global allocator.
+        unsafe { Self::from_raw_parts(ptr, len, cap) }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for GrowLock<T> {
+    /// Moves the elements of the array in a full [`GrowLock`].
+    #[inline]
+    fn from(value: [T; N]) -> Self {
+        Self::from_array_in(value, 0, Global)
+    }
+}
+impl<T: Clone> From<Cow<'_, [T]>> for GrowLock<T> {
+    /// Takes over the allocation of an owned [`Vec`], without cloning,
+    /// or clones a borrowed slice in a full [`GrowLock`].
+    #[inline]
+    fn from(value: Cow<'_, [T]>) -> Self {
+        match value {
+            Cow::Borrowed(src) => Self::from_slice(src, src.len()),
+            Cow::Owned(vec) => vec.into(),
+        }
+    }
+}
+#[cfg(feature = "nightly")]
+impl<T, A: Allocator> From<VecDeque<T, A>> for GrowLock<T, A> {
+    /// Makes the [`VecDeque`] contiguous, and takes over its allocation
+    /// (preserving the capacity).
+    #[inline]
+    fn from(value: VecDeque<T, A>) -> Self {
+        Vec::from(value).into()
+    }
+}
+#[cfg(not(feature = "nightly"))]
+impl<T> From<VecDeque<T>> for GrowLock<T> {
+    /// Makes the [`VecDeque`] contiguous, and takes over its allocation
+    /// (preserving the capacity).
+    #[inline]
+    fn from(value: VecDeque<T>) -> Self {
+        Vec::from(value).into()
+    }
+}
+impl<T> From<GrowLock<T>> for VecDeque<T> {
+    /// The [`VecDeque`] takes over the allocation of the [`GrowLock`],
+    /// without copying.
+    #[inline]
+    fn from(value: GrowLock<T>) -> Self {
+        Vec::from(value).into()
+    }
+}
+#[cfg(feature = "nightly")]
+impl<T, A: Allocator> From<Box<[T], A>> for GrowLock<T, A> {
+    /// Takes over the allocation of the [`Box`], without copying. The
+    /// resulting [`GrowLock`] is full.
+    #[inline]
+    fn from(value: Box<[T], A>) -> Self {
+        Vec::from(value).into()
+    }
+}
+#[cfg(not(feature = "nightly"))]
+impl<T> From<Box<[T]>> for GrowLock<T> {
+    /// Takes over the allocation of the [`Box`], without copying. The
+    /// resulting [`GrowLock`] is full.
+    #[inline]
+    fn from(value: Box<[T]>) -> Self {
+        Vec::from(value).into()
+    }
+}
+#[cfg(feature = "nightly")]
+impl<T, A: Allocator> From<GrowLock<T, A>> for Box<[T], A> {
+    /// See [`GrowLock::into_boxed_slice`].
+    #[inline]
+    fn from(value: GrowLock<T, A>) -> Self {
+        value.into_boxed_slice()
+    }
+}
+#[cfg(not(feature = "nightly"))]
+impl<T> From<GrowLock<T>> for Box<[T]> {
+    /// See [`GrowLock::into_boxed_slice`].
+    #[inline]
+    fn from(value: GrowLock<T>) -> Self {
+        value.into_boxed_slice()
+    }
+}
+impl<T, A: Allocator, const N: usize> TryFrom<GrowLock<T, A>> for [T; N] {
+    type Error = GrowLock<T, A>;
+    /// Moves the elements of the [`GrowLock`] in an array, deallocating
+    /// the buffer.
+    ///
+    /// # Errors
+    /// If `value.len() != N`, the [`GrowLock`] is returned back.
+    fn try_from(mut value: GrowLock<T, A>) -> Result<Self, Self::Error> {
+        if value.len() != N {
+            return Err(value);
+        }
+        // the length is set to zero first, so that the elements are not
+        // dropped with the `GrowLock`.
+        value.len.store(0, Ordering::Relaxed);
+        // SAFETY:
+        // * the first `N` elements are initialized.
+        // * `[T; N]` has the same alignment of `T`, and the elements are
+        //   contiguous.
+        // * the elements are no longer reachable through `value`.
+        Ok(unsafe { value.as_mut_ptr().cast::<[T; N]>().read() })
+    }
+}
+impl<T> FromIterator<T> for GrowLock<T> {
+    /// Collects the iterator into a [`GrowLock`].
+    ///
+    /// If the iterator reports an exact
+    /// [`size_hint`](Iterator::size_hint), the elements are written
+    /// directly in a [`GrowLock`] with that capacity (so the result is
+    /// full), otherwise they are collected in a [`Vec`] first.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut iter = iter.into_iter();
+        let (lower, upper) = iter.size_hint();
+        if upper != Some(lower) {
+            return iter.collect::<Vec<_>>().into();
+        }
+
+        let lock = Self::with_capacity(lower);
+        // SAFETY: `lock` is not shared yet.
+        unsafe { lock.extend_until_full(&mut iter) };
+        match iter.next() {
+            None => lock,
+            // the iterator lied about its size
+            Some(elem) => {
+                let mut vec = Vec::from(lock);
+                vec.push(elem);
+                vec.extend(iter);
+                vec.into()
+            }
+        }
+    }
+}
+
+/// Appends the elements of an iterator, under a single write lock.
+///
+/// This makes `&GrowLock` usable as a sink by generic code bounded on
+/// [`Extend`].
+///
+/// # Panics
+/// * Panics if the [`GrowLock`] is poisoned (like `write().unwrap()`), so
+///   that the panic of another writer is not silently ignored.
+/// * Panics if the iterator has more elements than the spare capacity (and
+///   the elements pushed until then are kept).
+///
+/// # Examples
+/// ```
+/// use growlock::GrowLock;
+///
+/// fn fill<E: Extend<u32>>(mut sink: E) {
+///     sink.extend(1..=3);
+/// }
+///
+/// let lock = GrowLock::with_capacity(10);
+/// fill(&lock);
+/// fill(&lock);
+/// assert_eq!(lock, [1, 2, 3, 1, 2, 3]);
+/// ```
+impl<T, A: Allocator> Extend<T> for &GrowLock<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.write().unwrap().extend(iter);
+    }
+}
 
 // ----------------------------- PartialEq impl
 // -----------------------------
@@ -647,6 +2584,7 @@ where
         PartialEq::eq(self, &**rhs)
     }
 }
+#[cfg(feature = "nightly")]
 impl<T, U, A, A2> PartialEq<Vec<U, A2>> for GrowLock<T, A>
 where
     T: PartialEq<U>,
@@ -657,6 +2595,149 @@ where
         PartialEq::eq(&**self, &**rhs)
     }
 }
+#[cfg(not(feature = "nightly"))]
+impl<T, U, A> PartialEq<Vec<U>> for GrowLock<T, A>
+where
+    T: PartialEq<U>,
+    A: Allocator,
+{
+    fn eq(&self, rhs: &Vec<U>) -> bool {
+        PartialEq::eq(&**self, &**rhs)
+    }
+}
+#[cfg(feature = "nightly")]
+impl<T, U, A, A2> PartialEq<GrowLock<U, A>> for Vec<T, A2>
+where
+    T: PartialEq<U>,
+    A: Allocator,
+    A2: Allocator,
+{
+    fn eq(&self, rhs: &GrowLock<U, A>) -> bool {
+        PartialEq::eq(&**self, &**rhs)
+    }
+}
+#[cfg(not(feature = "nightly"))]
+impl<T, U, A> PartialEq<GrowLock<U, A>> for Vec<T>
+where
+    T: PartialEq<U>,
+    A: Allocator,
+{
+    fn eq(&self, rhs: &GrowLock<U, A>) -> bool {
+        PartialEq::eq(&**self, &**rhs)
+    }
+}
+impl<T, U, A> PartialEq<Cow<'_, [U]>> for GrowLock<T, A>
+where
+    T: PartialEq<U>,
+    U: Clone,
+    A: Allocator,
+{
+    fn eq(&self, rhs: &Cow<'_, [U]>) -> bool {
+        PartialEq::eq(&**self, &**rhs)
+    }
+}
+impl<T, U, A> PartialEq<GrowLock<U, A>> for Cow<'_, [T]>
+where
+    T: PartialEq<U> + Clone,
+    A: Allocator,
+{
+    fn eq(&self, rhs: &GrowLock<U, A>) -> bool {
+        PartialEq::eq(&**self, &**rhs)
+    }
+}
+
+// ----------------------------- PartialOrd and Ord impl
+// -----------------------------
+
+impl<T, A, A2> PartialOrd<GrowLock<T, A2>> for GrowLock<T, A>
+where
+    T: PartialOrd,
+    A: Allocator,
+    A2: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &GrowLock<T, A2>) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(&**self, &**rhs)
+    }
+}
+impl<T, A> PartialOrd<[T]> for GrowLock<T, A>
+where
+    T: PartialOrd,
+    A: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &[T]) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(&**self, rhs)
+    }
+}
+impl<T, A> PartialOrd<GrowLock<T, A>> for [T]
+where
+    T: PartialOrd,
+    A: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &GrowLock<T, A>) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(self, &**rhs)
+    }
+}
+#[cfg(feature = "nightly")]
+impl<T, A, A2> PartialOrd<Vec<T, A2>> for GrowLock<T, A>
+where
+    T: PartialOrd,
+    A: Allocator,
+    A2: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &Vec<T, A2>) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(&**self, &**rhs)
+    }
+}
+#[cfg(not(feature = "nightly"))]
+impl<T, A> PartialOrd<Vec<T>> for GrowLock<T, A>
+where
+    T: PartialOrd,
+    A: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &Vec<T>) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(&**self, &**rhs)
+    }
+}
+#[cfg(feature = "nightly")]
+impl<T, A, A2> PartialOrd<GrowLock<T, A>> for Vec<T, A2>
+where
+    T: PartialOrd,
+    A: Allocator,
+    A2: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &GrowLock<T, A>) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(&**self, &**rhs)
+    }
+}
+#[cfg(not(feature = "nightly"))]
+impl<T, A> PartialOrd<GrowLock<T, A>> for Vec<T>
+where
+    T: PartialOrd,
+    A: Allocator,
+{
+    #[inline]
+    fn partial_cmp(&self, rhs: &GrowLock<T, A>) -> Option<cmp::Ordering> {
+        PartialOrd::partial_cmp(&**self, &**rhs)
+    }
+}
+/// Lexicographic, like `[T]`, to be consistent with [`Eq`] and
+/// [`Borrow<[T]>`].
+///
+/// Note that pushing to a [`GrowLock`] used as a key (e.g. in a
+/// [`BTreeMap`](std::collections::BTreeMap)) changes its ordering, which
+/// is a logic error for the map.
+impl<T: Ord, A: Allocator> Ord for GrowLock<T, A> {
+    #[inline]
+    fn cmp(&self, rhs: &Self) -> cmp::Ordering {
+        Ord::cmp(&**self, &**rhs)
+    }
+}
 
 // ----------------------------- Eq and Hash impl
 // -----------------------------
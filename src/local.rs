@@ -0,0 +1,390 @@
+//! A single-threaded [`GrowLock`](crate::GrowLock), without atomics nor a
+//! mutex.
+//!
+//! A [`LocalGrowLock`] can't be sent nor shared between threads, even if
+//! its elements can:
+//! ```compile_fail
+//! use growlock::local::LocalGrowLock;
+//!
+//! fn assert_send<T: Send>(_: T) {}
+//! assert_send(LocalGrowLock::<u8>::with_capacity(1));
+//! ```
+//! ```compile_fail
+//! use growlock::local::LocalGrowLock;
+//!
+//! fn assert_sync<T: Sync>(_: &T) {}
+//! assert_sync(&LocalGrowLock::<u8>::with_capacity(1));
+//! ```
+
+use {
+    crate::{
+        cap::Cap,
+        compat::{Allocator, Global},
+        drop_order::drop_in_place_rev,
+        error::{PushError, TryReserveError},
+        guard::WriteGuard,
+        raw::RawGrowLock,
+    },
+    core::{cell::Cell, fmt, marker::PhantomData, mem, ops, ptr, slice},
+};
+
+/// A single-threaded [`GrowLock`](crate::GrowLock): an append-only
+/// buffer, whose elements never move, which can be read while it's
+/// written.
+///
+/// The length is a [`Cell`] instead of an atomic, and the write lock is
+/// a flag, like the one of a [`RefCell`](std::cell::RefCell): only one
+/// [`LocalGrowGuard`] can exist at a time, but the elements can be read
+/// (and borrowed) while it exists. So it's neither [`Send`] nor
+/// [`Sync`], but it can hold the elements which aren't [`Send`] either.
+///
+/// Its guard implements [`WriteGuard`], like the one of a
+/// [`GrowLock`](crate::GrowLock), to write the code which works with
+/// both.
+///
+/// The elements are dropped in reverse order, like the ones of a
+/// [`GrowLock`](crate::GrowLock#drop-order).
+///
+/// # Examples
+/// ```
+/// use growlock::local::LocalGrowLock;
+/// use std::rc::Rc;
+///
+/// let lock = LocalGrowLock::with_capacity(3);
+/// let mut guard = lock.write();
+/// guard.push(Rc::new(1));
+/// let first = &lock[0];
+/// guard.extend([Rc::new(2), Rc::new(3)]);
+/// drop(guard);
+/// assert_eq!(**first, 1);
+/// assert_eq!(lock.len(), 3);
+/// ```
+pub struct LocalGrowLock<T, A: Allocator = Global> {
+    buf: RawGrowLock<T, A>,
+    len: Cell<usize>,
+    writing: Cell<bool>,
+    /// Elements can be pushed through a shared reference (see
+    /// [`GrowLock`](crate::GrowLock)).
+    _invariant: PhantomData<fn(T) -> T>,
+    /// The lock is bound to its thread.
+    _local: PhantomData<*const ()>,
+}
+
+impl<T> LocalGrowLock<T> {
+    /// Creates a new [`LocalGrowLock<T>`] with the given capacity.
+    ///
+    /// # Panics
+    /// Panics if the capacity overflows `isize::MAX` bytes, or if the
+    /// allocation fails.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+    /// Same as [`with_capacity`](LocalGrowLock::with_capacity), but
+    /// returns an error if the allocation fails.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// * `capacity * size_of::<T>` overflows `isize::MAX`
+    /// * memory is exhausted
+    #[inline]
+    pub fn try_with_capacity(
+        capacity: usize,
+    ) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> LocalGrowLock<T, A> {
+    /// Creates a new [`LocalGrowLock<T>`] with the given capacity in the
+    /// provided allocator.
+    ///
+    /// # Panics
+    /// Panics if the capacity overflows `isize::MAX` bytes, or if the
+    /// allocation fails.
+    #[must_use]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let Some(cap) = Cap::new::<T>(capacity) else {
+            panic!("{}", TryReserveError::CapacityOverflow);
+        };
+        Self::from_buf(RawGrowLock::with_capacity_in(cap, alloc))
+    }
+    /// Same as [`with_capacity_in`](LocalGrowLock::with_capacity_in), but
+    /// returns an error if the allocation fails.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// * `capacity * size_of::<T>` overflows `isize::MAX`
+    /// * memory is exhausted
+    pub fn try_with_capacity_in(
+        capacity: usize,
+        alloc: A,
+    ) -> Result<Self, TryReserveError> {
+        let Some(cap) = Cap::new::<T>(capacity) else {
+            return Err(TryReserveError::CapacityOverflow);
+        };
+        Ok(Self::from_buf(RawGrowLock::try_with_capacity_in(
+            cap, alloc,
+        )?))
+    }
+    /// Drops the elements. Only called when the lock is dropped.
+    fn drop_elements(&mut self) {
+        // SAFETY: the first `len` elements are initialized, and they are
+        // never used again.
+        unsafe {
+            drop_in_place_rev(self.buf.as_mut_ptr(), self.len.get());
+        }
+    }
+    const fn from_buf(buf: RawGrowLock<T, A>) -> Self {
+        Self {
+            buf,
+            len: Cell::new(0),
+            writing: Cell::new(false),
+            _invariant: PhantomData,
+            _local: PhantomData,
+        }
+    }
+    /// Equivalent to `&self[..]`
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: the first `len` elements are initialized, and they are
+        // never written again while `self` is shared.
+        unsafe { slice::from_raw_parts(self.buf.as_ptr(), self.len()) }
+    }
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub fn allocator(&self) -> &A {
+        self.buf.allocator()
+    }
+    /// Takes the exclusive write access.
+    ///
+    /// Readers are never blocked by the returned guard.
+    ///
+    /// # Panics
+    /// Panics if a [`LocalGrowGuard`] of this lock already exists.
+    #[inline]
+    pub fn write(&self) -> LocalGrowGuard<'_, T, A> {
+        match self.try_write() {
+            Some(guard) => guard,
+            None => panic!("the `LocalGrowLock` is already written"),
+        }
+    }
+    /// Takes the exclusive write access, or returns [`None`] if a
+    /// [`LocalGrowGuard`] of this lock already exists.
+    #[inline]
+    pub fn try_write(&self) -> Option<LocalGrowGuard<'_, T, A>> {
+        if self.writing.replace(true) {
+            return None;
+        }
+        Some(LocalGrowGuard { lock: self })
+    }
+}
+
+// SAFETY: the elements are only dropped, like in `GrowLock`.
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, A: Allocator> Drop for LocalGrowLock<T, A> {
+    fn drop(&mut self) {
+        self.drop_elements();
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl<T, A: Allocator> Drop for LocalGrowLock<T, A> {
+    fn drop(&mut self) {
+        self.drop_elements();
+    }
+}
+
+impl<T, A: Allocator> ops::Deref for LocalGrowLock<T, A> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, A: Allocator + Default> Default for LocalGrowLock<T, A> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_capacity_in(0, A::default())
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for LocalGrowLock<T, A> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a
+/// [`LocalGrowLock`] when dropped.
+///
+/// This structure is created by the [`write`](LocalGrowLock::write) and
+/// [`try_write`](LocalGrowLock::try_write) methods on [`LocalGrowLock`].
+/// It has the same API as a [`GrowGuard`](crate::guard::GrowGuard).
+pub struct LocalGrowGuard<'lock, T, A: Allocator = Global> {
+    lock: &'lock LocalGrowLock<T, A>,
+}
+
+impl<T, A: Allocator> Drop for LocalGrowGuard<'_, T, A> {
+    #[inline]
+    fn drop(&mut self) {
+        self.lock.writing.set(false);
+    }
+}
+
+impl<T, A: Allocator> LocalGrowGuard<'_, T, A> {
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        self.lock.as_slice()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.lock.capacity()
+    }
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock.len()
+    }
+    /// # Panics
+    /// Panics if `self.is_full()`.
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        assert!(self.try_push(value).is_ok(), "length overflow");
+    }
+    /// # Errors
+    /// Returns an error if `self.is_full()`.
+    #[inline]
+    pub fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        let len = self.len();
+        if len >= self.capacity() {
+            return Err(PushError::new(value, self.capacity()));
+        }
+        // SAFETY: `len < capacity`, and the guard is the only writer.
+        unsafe { self.lock.buf.as_mut_ptr().add(len).write(value) };
+        self.lock.len.set(len + 1);
+        Ok(())
+    }
+}
+
+impl<T, A: Allocator> ops::Deref for LocalGrowGuard<'_, T, A> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for LocalGrowGuard<'_, T, A> {
+    /// Extends the [`LocalGrowLock`] with the contents of an iterator,
+    /// like the one of [`GrowGuard`](crate::guard::GrowGuard): the
+    /// elements are added all at once, or not at all.
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`, without adding any of them (and
+    /// before consuming any element, if its lower size bound already
+    /// doesn't fit).
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        /// The elements written (but not added to the length) by
+        /// `extend`, which are dropped if the batch is abandoned.
+        struct Batch<T> {
+            ptr: *mut T,
+            written: usize,
+        }
+        impl<T> Drop for Batch<T> {
+            fn drop(&mut self) {
+                // SAFETY: the first `written` elements are initialized,
+                // and they were never added to the length.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.ptr,
+                        self.written,
+                    ));
+                }
+            }
+        }
+
+        let iter = iter.into_iter();
+        let len = self.len();
+        let spare = self.capacity() - len;
+        assert!(iter.size_hint().0 <= spare, "length overflow");
+        let mut batch = Batch {
+            // SAFETY: `len <= capacity`, so the ptr is still in the
+            // allocated block.
+            ptr: unsafe { self.lock.buf.as_mut_ptr().add(len) },
+            written: 0,
+        };
+        for elem in iter {
+            // `elem` and the batch are dropped while unwinding.
+            assert!(batch.written < spare, "length overflow");
+            // SAFETY: `len + written < capacity`, and the guard is the
+            // only writer.
+            unsafe { batch.ptr.add(batch.written).write(elem) };
+            batch.written += 1;
+        }
+        self.lock.len.set(len + batch.written);
+        mem::forget(batch);
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T>
+    for LocalGrowGuard<'_, T, A>
+{
+    /// Extends the [`LocalGrowLock`] with copies of the elements of an
+    /// iterator of references, all at once or not at all.
+    ///
+    /// # Panics
+    /// This panics if the iterator has more elements than
+    /// `self.capacity() - self.len()`, without adding any of them.
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, A: Allocator> WriteGuard<T> for LocalGrowGuard<'_, T, A> {
+    #[inline]
+    fn capacity(&self) -> usize {
+        Self::capacity(self)
+    }
+    #[inline]
+    fn push(&mut self, value: T) {
+        Self::push(self, value);
+    }
+    #[inline]
+    fn try_push(&mut self, value: T) -> Result<(), PushError<T>> {
+        Self::try_push(self, value)
+    }
+}
@@ -0,0 +1,479 @@
+//! Writes which don't take the write lock: the lock-free pushes and the
+//! [`Ticket`](crate::ticket::Ticket)s.
+//!
+//! These writers reserve their slots in `reserved`, which is *open* only
+//! while no mutex writer holds the write lock:
+//! * a mutex writer closes it when it takes the write lock, and waits for
+//!   the reserved slots to be *resolved* (published or abandoned);
+//! * it reopens it (from the current length) when it releases the write
+//!   lock;
+//! * a writer which finds it closed takes the write lock instead.
+//!
+//! So the two kinds of writers never write the buffer (nor store the
+//! length) at the same time.
+//!
+//! The reservations are resolved in order. The one which is published
+//! right after the earlier ones (the common case) just stores the length
+//! and `turn`. Otherwise, it's resolved under the `pending` mutex: the
+//! one which is resolved while the earlier ones aren't is kept in
+//! `pending`, and it's resolved by the last of them. The elements of a
+//! reservation are moved down when it's published after an abandoned
+//! one, so that the published prefix never has holes.
+
+use {
+    crate::{
+        GrowLock,
+        compat::Allocator,
+        error::LengthError,
+        sync::{
+            Mutex, PoisonError,
+            atomic::{AtomicUsize, Ordering, fence},
+            hint,
+        },
+    },
+    alloc::collections::BTreeMap,
+    core::{cmp, ptr},
+};
+
+#[cfg(feature = "std")]
+use crate::sync::thread;
+
+/// Set in `reserved` while the slots can be reserved. The other bits are
+/// the index of the next free slot.
+const OPEN: usize = 1;
+/// The maximum number of slots which can be reserved (only reached by
+/// the ZSTs).
+const MAX_SLOTS: usize = usize::MAX >> 1;
+
+pub(crate) struct Reservations {
+    reserved: AtomicUsize,
+    /// The end of the resolved reservations. Only changed by the writer
+    /// whose reservation starts there, or while `reserved` is closed.
+    turn: AtomicUsize,
+    /// The resolved reservations which come after an unresolved one, by
+    /// start: their end, and whether they are published (or abandoned).
+    pending: Mutex<BTreeMap<usize, (usize, bool)>>,
+    /// The number of reservations in `pending`, read without locking it.
+    /// Only changed with `pending` locked.
+    pending_len: AtomicUsize,
+}
+
+impl Reservations {
+    /// Creates closed [`Reservations`]: they are opened by the first
+    /// writer which releases the write lock.
+    pub(crate) fn new() -> Self {
+        Self {
+            reserved: AtomicUsize::new(0),
+            turn: AtomicUsize::new(0),
+            pending: Mutex::new(BTreeMap::new()),
+            pending_len: AtomicUsize::new(0),
+        }
+    }
+    /// Closes the reservations through `&mut`, so they can't be pending.
+    pub(crate) fn close_mut(&mut self) {
+        self.reserved.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Waits until `done` returns `true`, spinning for a while, then yielding
+/// the thread (without `std`, it keeps spinning).
+fn wait_until(mut done: impl FnMut() -> bool) {
+    let mut spins = 0_u32;
+    while !done() {
+        if spins < 64 {
+            spins += 1;
+            hint::spin_loop();
+        } else {
+            #[cfg(feature = "std")]
+            thread::yield_now();
+            #[cfg(not(feature = "std"))]
+            hint::spin_loop();
+        }
+    }
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Appends `value` without taking the write lock (unless a mutex
+    /// writer is holding it), and returns its index.
+    ///
+    /// The slot is reserved with an atomic increment and written without
+    /// any lock. The lengths are still published in order: a push
+    /// becomes visible only after the previous ones, so it waits (by
+    /// spinning) for the pushes and the [`Ticket`](crate::ticket::Ticket)s
+    /// which reserved their slots before it and haven't finished yet.
+    ///
+    /// The mutex writers ([`write`](GrowLock::write) and the like) wait
+    /// for the lock-free pushes in progress when they take the write
+    /// lock, and the lock-free pushes take the write lock themselves
+    /// while a mutex writer holds it: the two kinds of writers are always
+    /// serialized.
+    ///
+    /// Every push wakes up the readers waiting for it, as when the write
+    /// lock is released. Like [`alloc`](GrowLock::alloc), the poisoning
+    /// is ignored.
+    ///
+    /// # Errors
+    /// Returns an error if the [`GrowLock`] is full.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::thread;
+    ///
+    /// let lock = GrowLock::with_capacity(100);
+    /// thread::scope(|s| {
+    ///     for i in 0..4 {
+    ///         let lock = &lock;
+    ///         s.spawn(move || {
+    ///             for j in 0..25 {
+    ///                 let index = lock.push_lockfree(i * 25 + j).unwrap();
+    ///                 assert_eq!(lock[index], i * 25 + j);
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// assert!(lock.is_full());
+    /// assert!(lock.push_lockfree(100).is_err());
+    /// ```
+    pub fn push_lockfree(&self, value: T) -> Result<usize, LengthError>
+    where
+        T: Copy,
+    {
+        let Some(reserved) = self.try_reserve_slots(1) else {
+            return self.push_lockfree_locked(value);
+        };
+        let slot = reserved?;
+        // SAFETY: the slot is reserved by us (and it's inside the
+        // buffer), and no mutex writer can write it until we resolve it.
+        unsafe { self.as_non_null_shared().add(slot).write(value) };
+        // once the previous slots are resolved, ours is published right
+        // away, at its final index.
+        let turn = &self.reservations.turn;
+        wait_until(|| turn.load(Ordering::Acquire) == slot);
+        // SAFETY: the slot is reserved by us, and initialized.
+        match unsafe { self.resolve_slots(slot, slot + 1, true) } {
+            Some(index) => Ok(index),
+            None => unreachable!("the previous slots are resolved"),
+        }
+    }
+    #[cold]
+    fn push_lockfree_locked(
+        &self,
+        value: T,
+    ) -> Result<usize, LengthError> {
+        let guard = self.write().unwrap_or_else(PoisonError::into_inner);
+        let index = guard.len();
+        // SAFETY: the write guard is held.
+        unsafe { self.guarded_try_push(value) }
+            .map_err(|_| LengthError::new(index, self.capacity()))?;
+        // the guard reopens `reserved`.
+        drop(guard);
+        Ok(index)
+    }
+    /// Returns the number of slots which can be reserved (while the
+    /// reservations are open).
+    pub(crate) fn slots_capacity(&self) -> usize {
+        cmp::min(self.capacity(), MAX_SLOTS)
+    }
+    /// Reserves `n` slots (`n > 0`), and returns the index of the first
+    /// one.
+    ///
+    /// Returns [`None`] if `reserved` is closed.
+    pub(crate) fn try_reserve_slots(
+        &self,
+        n: usize,
+    ) -> Option<Result<usize, LengthError>> {
+        let max = self.slots_capacity();
+        let reserved = &self.reservations.reserved;
+        let mut current = reserved.load(Ordering::Acquire);
+        loop {
+            if current & OPEN == 0 {
+                return None;
+            }
+            let start = current >> 1;
+            if n > max - start {
+                return Some(Err(LengthError::new(start, max)));
+            }
+            // `start + n <= MAX_SLOTS`, so this can't overflow.
+            match reserved.compare_exchange_weak(
+                current,
+                current + (n << 1),
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Some(Ok(start)),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+    /// Resolves the slots `start..end`, publishing their elements if
+    /// `publish` is `true`, and abandoning them otherwise.
+    ///
+    /// If the previous slots are resolved, returns the index where the
+    /// elements are published (even when they are abandoned). Otherwise,
+    /// the slots are resolved later, by the last of the previous ones.
+    ///
+    /// # Safety
+    /// * the slots must be reserved by the caller, and never used again.
+    /// * if `publish` is `true`, the slots must be initialized.
+    pub(crate) unsafe fn resolve_slots(
+        &self,
+        start: usize,
+        end: usize,
+        publish: bool,
+    ) -> Option<usize> {
+        let reservations = &self.reservations;
+        if publish
+            && reservations.turn.load(Ordering::Acquire) == start
+            && self.len.load(Ordering::Relaxed) == start
+        {
+            // the previous slots are published, so ours already are at
+            // their final index: nothing has to be moved nor recorded.
+            crate::macros::debug_invariant!(
+                end <= self.capacity(),
+                "published length {end} > capacity {}",
+                self.capacity(),
+            );
+            self.len.store(end, Ordering::Release);
+            reservations.turn.store(end, Ordering::Release);
+            // pairs with the fence in `resolve_slots_locked`: either the
+            // next reservation sees our turn, or we see it pending. It
+            // also pairs with the fence in `listen`, as in
+            // `notify_unlock`, so the listeners which have just started
+            // listening are seen by `notify`.
+            fence(Ordering::SeqCst);
+            if reservations.pending_len.load(Ordering::Relaxed) == 0 {
+                self.notifier.notify(self.len.load(Ordering::Relaxed));
+            } else {
+                self.resolve_pending();
+                // `turn` may have moved after the fence.
+                self.notifier
+                    .notify_unlock(self.len.load(Ordering::Relaxed));
+            }
+            return Some(start);
+        }
+        self.resolve_slots_locked(start, end, publish)
+    }
+    /// Same as [`resolve_slots`](GrowLock::resolve_slots), with `pending`
+    /// locked.
+    #[cold]
+    fn resolve_slots_locked(
+        &self,
+        start: usize,
+        end: usize,
+        publish: bool,
+    ) -> Option<usize> {
+        let reservations = &self.reservations;
+        let mut pending = reservations
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if reservations.turn.load(Ordering::Acquire) != start {
+            pending.insert(start, (end, publish));
+            reservations
+                .pending_len
+                .store(pending.len(), Ordering::Relaxed);
+            // pairs with the fence in `resolve_slots`: either the previous
+            // reservation sees us pending, or we see its turn.
+            fence(Ordering::SeqCst);
+            if reservations.turn.load(Ordering::Acquire) != start {
+                return None;
+            }
+            pending.remove(&start);
+            reservations
+                .pending_len
+                .store(pending.len(), Ordering::Relaxed);
+        }
+        let index = self.len.load(Ordering::Relaxed);
+        let len = self.resolve_in_turn(&mut pending, start, end, publish);
+        drop(pending);
+        // there is no unlock after this, so the listeners which have just
+        // started listening must be seen now.
+        self.notifier.notify_unlock(len);
+        Some(index)
+    }
+    /// Resolves the pending reservation which starts at `turn`, if any
+    /// (with the ones after it).
+    #[cold]
+    fn resolve_pending(&self) {
+        let reservations = &self.reservations;
+        let mut pending = reservations
+            .pending
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // the reservation at `turn` can't be resolved by anyone else
+        // while it's pending and `pending` is locked.
+        let start = reservations.turn.load(Ordering::Acquire);
+        if let Some((end, publish)) = pending.remove(&start) {
+            reservations
+                .pending_len
+                .store(pending.len(), Ordering::Relaxed);
+            self.resolve_in_turn(&mut pending, start, end, publish);
+        }
+    }
+    /// Resolves the slots `start..end` when it's their turn, then the
+    /// pending reservations which follow them, and returns the new
+    /// length.
+    fn resolve_in_turn(
+        &self,
+        pending: &mut BTreeMap<usize, (usize, bool)>,
+        start: usize,
+        end: usize,
+        publish: bool,
+    ) -> usize {
+        let reservations = &self.reservations;
+        let mut len = self.len.load(Ordering::Relaxed);
+        let (mut start, mut end, mut publish) = (start, end, publish);
+        loop {
+            if publish {
+                let count = end - start;
+                if len != start {
+                    // SAFETY: `len < start`, the slots in `len..start` are
+                    // abandoned, and the ones in `start..end` are ours.
+                    unsafe {
+                        let ptr = self.as_non_null_shared();
+                        ptr::copy(
+                            ptr.add(start).as_ptr(),
+                            ptr.add(len).as_ptr(),
+                            count,
+                        );
+                    }
+                }
+                len += count;
+                crate::macros::debug_invariant!(
+                    len <= self.capacity(),
+                    "published length {len} > capacity {}",
+                    self.capacity(),
+                );
+                self.len.store(len, Ordering::Release);
+            }
+            let Some((next_end, next_publish)) = pending.remove(&end)
+            else {
+                reservations.turn.store(end, Ordering::Release);
+                break;
+            };
+            (start, end, publish) = (end, next_end, next_publish);
+        }
+        reservations
+            .pending_len
+            .store(pending.len(), Ordering::Relaxed);
+        // if every reservation is resolved, the abandoned slots at the end
+        // can be reserved again.
+        if len != end {
+            // stored first, so that the writers which reserve the slots
+            // from `len` see it (and not an older `turn == len`). Nobody
+            // has reserved them yet, and the writer which has reserved the
+            // slots from `end` (if any) waits for `pending`, as the length
+            // isn't `end`.
+            reservations.turn.store(len, Ordering::Release);
+            if reservations
+                .reserved
+                .compare_exchange(
+                    end << 1 | OPEN,
+                    len << 1 | OPEN,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                reservations.turn.store(end, Ordering::Release);
+            }
+        }
+        len
+    }
+    /// Closes `reserved`, and waits for the reserved slots to be resolved.
+    ///
+    /// Must be called after the write lock is taken.
+    pub(crate) fn close_lockfree(&self) {
+        let reservations = &self.reservations;
+        let reserved = reservations.reserved.swap(0, Ordering::Acquire);
+        if reserved & OPEN != 0 {
+            let end = reserved >> 1;
+            wait_until(|| {
+                reservations.turn.load(Ordering::Acquire) == end
+            });
+        }
+    }
+    /// Closes `reserved` without waiting, and returns its old value if
+    /// some reserved slots aren't resolved yet (or `0` otherwise).
+    ///
+    /// Must be called after the write lock is taken, and followed by
+    /// [`wait_lockfree_async`](GrowLock::wait_lockfree_async) or
+    /// [`reopen_lockfree`](GrowLock::reopen_lockfree) with the returned
+    /// value, if it's not `0`.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn close_lockfree_nowait(&self) -> usize {
+        let reservations = &self.reservations;
+        let reserved = reservations.reserved.swap(0, Ordering::Acquire);
+        if reserved & OPEN != 0
+            && reservations.turn.load(Ordering::Acquire) != reserved >> 1
+        {
+            reserved
+        } else {
+            0
+        }
+    }
+    /// Waits asynchronously for the slots reserved before
+    /// [`close_lockfree_nowait`](GrowLock::close_lockfree_nowait)
+    /// returned `reserved` to be resolved.
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn wait_lockfree_async(&self, reserved: usize) {
+        let end = reserved >> 1;
+        let turn = &self.reservations.turn;
+        // every resolution is followed by a notification.
+        self.notifier
+            .wait_async(|| turn.load(Ordering::Acquire) == end)
+            .await;
+    }
+    /// Reopens `reserved` as it was before
+    /// [`close_lockfree_nowait`](GrowLock::close_lockfree_nowait)
+    /// returned `reserved`, when the write lock is released without
+    /// waiting for the reserved slots.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn reopen_lockfree(&self, reserved: usize) {
+        // nobody else changes `reserved` while it's closed and the write
+        // lock is held.
+        self.reservations
+            .reserved
+            .store(reserved, Ordering::Release);
+    }
+    /// Same as [`close_lockfree`](GrowLock::close_lockfree), but spins
+    /// at most `spins` times (subtracting them from it) while waiting, and
+    /// then reopens `reserved` and returns `false` if some reserved slots
+    /// still aren't resolved.
+    ///
+    /// Must be called after the write lock is taken.
+    pub(crate) fn try_close_lockfree(&self, spins: &mut usize) -> bool {
+        let reservations = &self.reservations;
+        let reserved = reservations.reserved.swap(0, Ordering::Acquire);
+        if reserved & OPEN == 0 {
+            return true;
+        }
+        let end = reserved >> 1;
+        while reservations.turn.load(Ordering::Acquire) != end {
+            if *spins == 0 {
+                // nobody else changes `reserved` while it's closed and the
+                // write lock is held, so it's reopened as it was.
+                reservations.reserved.store(reserved, Ordering::Release);
+                return false;
+            }
+            *spins -= 1;
+            hint::spin_loop();
+        }
+        true
+    }
+    /// Reopens `reserved` from the current length.
+    ///
+    /// Must be called before the write lock is released.
+    pub(crate) fn open_lockfree(&self) {
+        let len = self.len.load(Ordering::Relaxed);
+        // only the ZSTs can have more elements.
+        if len <= MAX_SLOTS {
+            let reservations = &self.reservations;
+            reservations.turn.store(len, Ordering::Relaxed);
+            reservations
+                .reserved
+                .store(len << 1 | OPEN, Ordering::Release);
+        }
+    }
+}
@@ -6,6 +6,9 @@ macro_rules! grow_lock {
     ($capacity:expr) => {
         $crate::GrowLock::with_capacity($capacity)
     };
+    ($capacity:expr ; from $iter:expr) => {
+        $crate::GrowLock::from_iter_with_capacity($capacity, $iter)
+    };
 
     ($capacity:expr, [$($elem:expr),*$(,)?]) => {{
         let __v__ = $crate::GrowLock::with_capacity($capacity);
@@ -23,7 +26,7 @@ macro_rules! grow_lock {
         {
             let mut __guard__ = __v__.write().unwrap();
             for _ in 0 .. $len {
-                __guard__.push(::std::clone::Clone::clone(&$elem));
+                __guard__.push(::core::clone::Clone::clone(&$elem));
             }
         }
         __v__
@@ -33,7 +36,7 @@ macro_rules! grow_lock {
         {
             let mut __guard__ = __v__.write().unwrap();
             for _ in 0 .. $len {
-                __guard__.push(::std::clone::Clone::clone(&$elem));
+                __guard__.push(::core::clone::Clone::clone(&$elem));
             }
         }
         __v__
@@ -41,6 +44,233 @@ macro_rules! grow_lock {
 
     // this is last because everything can match this
     ($($elem:expr),+$(,)?) => {{
-        $crate::GrowLock::from(::std::vec![$($elem),*])
+        $crate::GrowLock::from($crate::__private::vec![$($elem),*])
+    }};
+}
+
+/// Same as [`grow_lock!`], but the [`GrowLock`](crate::GrowLock) is
+/// allocated with the given allocator.
+///
+/// The allocator expression is evaluated exactly once.
+///
+/// # Examples
+/// ```
+/// use growlock::grow_lock_in;
+/// use std::alloc::System;
+///
+/// let lock = grow_lock_in!(System; 10, [1, 2, 3]);
+/// assert_eq!(lock, [1, 2, 3]);
+/// assert_eq!(lock.capacity(), 10);
+///
+/// let lock = grow_lock_in!(System; "a".to_owned(); 2);
+/// assert_eq!(lock, ["a", "a"]);
+/// assert!(lock.is_full());
+/// ```
+#[macro_export]
+macro_rules! grow_lock_in {
+    ($alloc:expr ; $capacity:expr ; from $iter:expr) => {
+        $crate::GrowLock::from_iter_with_capacity_in($capacity, $iter, $alloc)
+    };
+    ($alloc:expr ; $elem:expr ; $len:expr) => {{
+        let __len__ = $len;
+        $crate::grow_lock_in!($alloc; __len__, [$elem; __len__])
+    }};
+    ($alloc:expr ; $capacity:expr) => {
+        $crate::GrowLock::with_capacity_in($capacity, $alloc)
+    };
+
+    ($alloc:expr ; $capacity:expr, [$($elem:expr),*$(,)?]) => {{
+        let __v__ = $crate::GrowLock::with_capacity_in($capacity, $alloc);
+        {
+            let mut __guard__ = __v__.write().unwrap();
+            $(
+                __guard__.push($elem);
+            )*
+        }
+        __v__
+    }};
+    ($alloc:expr ; $capacity:expr, [$elem:expr ; $len:expr]) => {{
+        let __v__ = $crate::GrowLock::with_capacity_in($capacity, $alloc);
+        {
+            let __len__: usize = $len;
+            let __elem__ = $elem;
+            let mut __guard__ = __v__.write().unwrap();
+            if __len__ > 0 {
+                for _ in 1..__len__ {
+                    __guard__.push(::core::clone::Clone::clone(&__elem__));
+                }
+                // the last element is moved, not cloned
+                __guard__.push(__elem__);
+            }
+        }
+        __v__
+    }};
+}
+
+/// Fallible version of [`grow_lock!`], which returns
+/// [`Result<GrowLock<T>, TryFillError>`](crate::error::TryFillError)
+/// instead of panicking.
+///
+/// The allocation is made with
+/// [`try_with_capacity`](crate::GrowLock::try_with_capacity), and the
+/// elements are pushed with
+/// [`try_push`](crate::guard::GrowGuard::try_push). On the first failure,
+/// the construction is aborted: the remaining element expressions are not
+/// evaluated and the elements already pushed are dropped.
+///
+/// # Examples
+/// ```
+/// use growlock::{error::TryFillError, try_grow_lock};
+///
+/// let lock = try_grow_lock!(10, [1, 2, 3]).unwrap();
+/// assert_eq!(lock, [1, 2, 3]);
+///
+/// let lock = try_grow_lock!("a".to_owned(); 2).unwrap();
+/// assert_eq!(lock, ["a", "a"]);
+///
+/// assert!(matches!(
+///     try_grow_lock!(1, [1, 2]),
+///     Err(TryFillError::Length(_)),
+/// ));
+/// ```
+#[macro_export]
+macro_rules! try_grow_lock {
+    () => {
+        $crate::try_grow_lock!(0)
+    };
+    ($elem:expr ; $len:expr) => {{
+        let __len__ = $len;
+        $crate::try_grow_lock!(__len__, [$elem; __len__])
     }};
+    ($capacity:expr) => {
+        $crate::try_grow_lock_in!(
+            @fill $crate::GrowLock::try_with_capacity($capacity); []
+        )
+    };
+    ($capacity:expr, [$($elem:expr),*$(,)?]) => {
+        $crate::try_grow_lock_in!(
+            @fill $crate::GrowLock::try_with_capacity($capacity);
+            [$($elem),*]
+        )
+    };
+    ($capacity:expr, [$elem:expr ; $len:expr]) => {
+        $crate::try_grow_lock_in!(
+            @repeat $crate::GrowLock::try_with_capacity($capacity);
+            $elem; $len
+        )
+    };
+}
+
+/// Same as [`try_grow_lock!`], but the [`GrowLock`](crate::GrowLock) is
+/// allocated with the given allocator (like [`grow_lock_in!`]).
+///
+/// # Examples
+/// ```
+/// use growlock::try_grow_lock_in;
+/// use std::alloc::System;
+///
+/// let lock = try_grow_lock_in!(System; 10, [1, 2, 3]).unwrap();
+/// assert_eq!(lock, [1, 2, 3]);
+/// ```
+#[macro_export]
+macro_rules! try_grow_lock_in {
+    (@fill $new:expr ; [$($elem:expr),*]) => {
+        '__try_grow_lock: {
+            let __v__ = match $new {
+                ::core::result::Result::Ok(v) => v,
+                ::core::result::Result::Err(e) => {
+                    break '__try_grow_lock ::core::result::Result::Err(
+                        $crate::error::TryFillError::from(e),
+                    );
+                }
+            };
+            {
+                let mut __guard__ = __v__
+                    .write()
+                    .unwrap_or_else($crate::sync::PoisonError::into_inner);
+                $(
+                    if let ::core::result::Result::Err(e) =
+                        __guard__.try_push($elem)
+                    {
+                        break '__try_grow_lock ::core::result::Result::Err(
+                            $crate::error::TryFillError::from(e),
+                        );
+                    }
+                )*
+            }
+            ::core::result::Result::Ok(__v__)
+        }
+    };
+    (@repeat $new:expr ; $elem:expr ; $len:expr) => {
+        '__try_grow_lock: {
+            let __v__ = match $new {
+                ::core::result::Result::Ok(v) => v,
+                ::core::result::Result::Err(e) => {
+                    break '__try_grow_lock ::core::result::Result::Err(
+                        $crate::error::TryFillError::from(e),
+                    );
+                }
+            };
+            {
+                let __len__: usize = $len;
+                if __len__ > __v__.capacity() {
+                    break '__try_grow_lock ::core::result::Result::Err(
+                        $crate::error::TryFillError::Length(
+                            $crate::error::LengthError::new(
+                                0,
+                                __v__.capacity(),
+                            ),
+                        ),
+                    );
+                }
+                let __elem__ = $elem;
+                let mut __guard__ = __v__
+                    .write()
+                    .unwrap_or_else($crate::sync::PoisonError::into_inner);
+                if __len__ > 0 {
+                    for _ in 1..__len__ {
+                        __guard__
+                            .push(::core::clone::Clone::clone(&__elem__));
+                    }
+                    // the last element is moved, not cloned
+                    __guard__.push(__elem__);
+                }
+            }
+            ::core::result::Result::Ok(__v__)
+        }
+    };
+
+    ($alloc:expr ; $elem:expr ; $len:expr) => {{
+        let __len__ = $len;
+        $crate::try_grow_lock_in!($alloc; __len__, [$elem; __len__])
+    }};
+    ($alloc:expr ; $capacity:expr) => {
+        $crate::try_grow_lock_in!(
+            @fill $crate::GrowLock::try_with_capacity_in($capacity, $alloc);
+            []
+        )
+    };
+    ($alloc:expr ; $capacity:expr, [$($elem:expr),*$(,)?]) => {
+        $crate::try_grow_lock_in!(
+            @fill $crate::GrowLock::try_with_capacity_in($capacity, $alloc);
+            [$($elem),*]
+        )
+    };
+    ($alloc:expr ; $capacity:expr, [$elem:expr ; $len:expr]) => {
+        $crate::try_grow_lock_in!(
+            @repeat $crate::GrowLock::try_with_capacity_in($capacity, $alloc);
+            $elem; $len
+        )
+    };
+}
+
+/// Checks an invariant of the crate, with [`debug_assert!`], when the
+/// `debug-invariants` feature is enabled. Otherwise, it expands to
+/// nothing.
+macro_rules! debug_invariant {
+    ($($arg:tt)+) => {
+        #[cfg(feature = "debug-invariants")]
+        debug_assert!($($arg)+);
+    };
 }
+pub(crate) use debug_invariant;
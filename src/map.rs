@@ -0,0 +1,148 @@
+//! Implementation of [`GrowLock::map`].
+
+use {
+    crate::{GrowLock, cap::Cap, compat::Allocator, raw::RawGrowLock},
+    core::{
+        marker::PhantomData,
+        mem,
+        ptr::{self, NonNull},
+    },
+};
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Maps each element of the [`GrowLock`] with `f`, returning a
+    /// [`GrowLock`] with the same capacity and allocator.
+    ///
+    /// If `T` and `U` have the same size and alignment, the allocation is
+    /// reused and each mapped value is written back in the same slot.
+    /// Otherwise a new buffer is allocated and the elements are moved
+    /// into it.
+    ///
+    /// If `f` panics, all the elements (both mapped and not) are dropped
+    /// and the buffer is deallocated.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(10, [1u32, 2, 3]);
+    /// // `u32` and `i32` have the same layout: the allocation is reused.
+    /// let lock = lock.map(|x| -(x as i32));
+    /// assert_eq!(lock, [-1, -2, -3]);
+    /// assert_eq!(lock.capacity(), 10);
+    /// ```
+    pub fn map<U, F>(self, f: F) -> GrowLock<U, A>
+    where
+        F: FnMut(T) -> U,
+    {
+        if size_of::<T>() == size_of::<U>()
+            && align_of::<T>() == align_of::<U>()
+        {
+            self.map_in_place(f)
+        } else {
+            self.map_realloc(f)
+        }
+    }
+
+    /// See [`GrowLock::map`].
+    ///
+    /// `T` and `U` must have the same size and alignment.
+    fn map_in_place<U, F>(self, mut f: F) -> GrowLock<U, A>
+    where
+        F: FnMut(T) -> U,
+    {
+        /// Drops the elements and deallocates the buffer if `f` panics.
+        struct Guard<'a, T, U, A: Allocator> {
+            ptr: NonNull<T>,
+            len: usize,
+            cap: usize,
+            /// Number of elements already mapped (the slot at this index
+            /// was moved in `f`).
+            mapped: usize,
+            alloc: &'a A,
+            _marker: PhantomData<U>,
+        }
+        impl<T, U, A: Allocator> Drop for Guard<'_, T, U, A> {
+            fn drop(&mut self) {
+                // SAFETY:
+                // * the first `mapped` elements are initialized `U`s.
+                // * the elements after `mapped` are initialized `T`s.
+                // * the `RawGrowLock` deallocates the buffer with the same
+                //   layout it was allocated with.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.ptr.cast::<U>().as_ptr(),
+                        self.mapped,
+                    ));
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.ptr.as_ptr().add(self.mapped + 1),
+                        self.len - self.mapped - 1,
+                    ));
+                    drop(RawGrowLock::from_nonnull_in(
+                        self.ptr,
+                        Cap::new_unchecked::<T>(self.cap),
+                        self.alloc,
+                    ));
+                }
+            }
+        }
+
+        let (ptr, len, cap, alloc) = self.into_parts_with_alloc();
+        let mut guard = Guard::<T, U, A> {
+            ptr,
+            len,
+            cap,
+            mapped: 0,
+            alloc: &alloc,
+            _marker: PhantomData,
+        };
+        while guard.mapped < len {
+            // SAFETY:
+            // * the slot is initialized, and it's never read again as a
+            //   `T`.
+            // * `T` and `U` have the same layout, so the slot can hold a
+            //   `U`.
+            unsafe {
+                let slot = ptr.add(guard.mapped);
+                let value = f(slot.read());
+                slot.cast::<U>().write(value);
+            }
+            guard.mapped += 1;
+        }
+        mem::forget(guard);
+
+        // SAFETY:
+        // * the buffer was allocated by `alloc` with the layout of `[T]`,
+        //   which is the same of `[U]`.
+        // * the first `len` elements are initialized `U`s.
+        unsafe { GrowLock::from_parts_in(ptr.cast(), len, cap, alloc) }
+    }
+
+    /// See [`GrowLock::map`].
+    fn map_realloc<U, F>(self, f: F) -> GrowLock<U, A>
+    where
+        F: FnMut(T) -> U,
+    {
+        // if `T` is a ZST, the capacity is `usize::MAX`
+        let cap = if size_of::<T>() == 0 {
+            self.len()
+        } else {
+            self.capacity()
+        };
+        let (ptr, len, old_cap, alloc) = self.into_parts_with_alloc();
+        // SAFETY: these are the parts of a `GrowLock`. Its iterator takes
+        // care of dropping the remaining elements and deallocating the
+        // old buffer (also on panic).
+        let old =
+            unsafe { GrowLock::from_parts_in(ptr, len, old_cap, &alloc) };
+
+        let new = GrowLock::with_capacity_in(cap, &alloc);
+        // SAFETY: `new` is not shared yet.
+        unsafe { new.extend_until_full(&mut old.into_iter().map(f)) };
+
+        let (ptr, len, cap, _) = new.into_parts_with_alloc();
+        // SAFETY: the buffer was allocated by `alloc` (through a
+        // reference), with the layout of `[U]`.
+        unsafe { GrowLock::from_parts_in(ptr, len, cap, alloc) }
+    }
+}
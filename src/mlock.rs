@@ -0,0 +1,209 @@
+//! Page-locked [`GrowLock`]s, whose buffer is never swapped to disk.
+//!
+//! [`PageLocked`] is an [`Allocator`] wrapper which locks the pages of
+//! every block in memory (with `mlock` on unix, and `VirtualLock` on
+//! windows) right after allocating it, and unlocks them right before
+//! deallocating it. On other targets, every allocation fails.
+//!
+//! Combine it with [`Zeroizing`](https://docs.rs/zeroize) (behind the
+//! `zeroize` feature) to also wipe the buffer before it is unlocked.
+
+use {
+    crate::{
+        GrowLock,
+        compat::{AllocError, Allocator, Global},
+        error::TryReserveError,
+    },
+    std::{alloc::Layout, ptr::NonNull},
+};
+
+#[cfg(unix)]
+mod sys {
+    pub(super) fn page_size() -> usize {
+        // SAFETY: `sysconf` has no preconditions.
+        let size = unsafe { ::libc::sysconf(::libc::_SC_PAGESIZE) };
+        // the page size can't be unknown.
+        usize::try_from(size).unwrap_or(1)
+    }
+    /// # Safety
+    /// `ptr..ptr + len` must be allocated.
+    pub(super) unsafe fn lock(ptr: *const u8, len: usize) -> bool {
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { ::libc::mlock(ptr.cast(), len) == 0 }
+    }
+    /// # Safety
+    /// `ptr..ptr + len` must be allocated, and locked by [`lock`].
+    pub(super) unsafe fn unlock(ptr: *const u8, len: usize) {
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { ::libc::munlock(ptr.cast(), len) };
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use ::windows_sys::Win32::System::{
+        Memory::{VirtualLock, VirtualUnlock},
+        SystemInformation::{GetSystemInfo, SYSTEM_INFO},
+    };
+
+    pub(super) fn page_size() -> usize {
+        // SAFETY: `SYSTEM_INFO` is plain data, and `GetSystemInfo`
+        // initializes it.
+        let info = unsafe {
+            let mut info = std::mem::zeroed::<SYSTEM_INFO>();
+            GetSystemInfo(&raw mut info);
+            info
+        };
+        info.dwPageSize as usize
+    }
+    /// # Safety
+    /// `ptr..ptr + len` must be allocated.
+    pub(super) unsafe fn lock(ptr: *const u8, len: usize) -> bool {
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { VirtualLock(ptr.cast(), len) != 0 }
+    }
+    /// # Safety
+    /// `ptr..ptr + len` must be allocated, and locked by [`lock`].
+    pub(super) unsafe fn unlock(ptr: *const u8, len: usize) {
+        // SAFETY: the safety contract must be upheld by the caller
+        unsafe { VirtualUnlock(ptr.cast(), len) };
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    pub(super) const fn page_size() -> usize {
+        1
+    }
+    pub(super) const unsafe fn lock(_: *const u8, _: usize) -> bool {
+        false
+    }
+    pub(super) const unsafe fn unlock(_: *const u8, _: usize) {}
+}
+
+/// [`Allocator`] which locks the pages of its blocks in memory, so they
+/// are never swapped to disk.
+///
+/// The blocks are rounded up to whole pages, so that unlocking a block
+/// never unlocks the pages of another one.
+///
+/// An allocation fails if the pages can't be locked, e.g. if the limit of
+/// locked memory of the process (`RLIMIT_MEMLOCK` on unix) is exceeded,
+/// or if the target is neither unix nor windows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageLocked<A: Allocator = Global>(A);
+
+impl<A: Allocator> PageLocked<A> {
+    /// Wraps `alloc`, locking the blocks it allocates.
+    #[inline]
+    #[must_use]
+    pub const fn new(alloc: A) -> Self {
+        Self(alloc)
+    }
+    /// Returns the wrapped allocator.
+    #[inline]
+    #[must_use]
+    pub const fn inner(&self) -> &A {
+        &self.0
+    }
+}
+
+/// Rounds `layout` up to whole pages.
+fn page_layout(layout: Layout) -> Result<Layout, AllocError> {
+    let page = sys::page_size();
+    layout
+        .align_to(page)
+        .map(|layout| layout.pad_to_align())
+        .map_err(|_| AllocError)
+}
+
+// SAFETY: every block is allocated by the inner allocator, with the same
+// (rounded) layout it is deallocated with.
+unsafe impl<A: Allocator> Allocator for PageLocked<A> {
+    fn allocate(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            return self.0.allocate(layout);
+        }
+        let layout = page_layout(layout)?;
+        let block = self.0.allocate(layout)?;
+        let ptr = block.cast::<u8>();
+        // SAFETY: the block was just allocated with `layout`.
+        if unsafe { sys::lock(ptr.as_ptr(), layout.size()) } {
+            Ok(block)
+        } else {
+            // SAFETY: the block was just allocated with `layout`.
+            unsafe { self.0.deallocate(ptr, layout) };
+            Err(AllocError)
+        }
+    }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() == 0 {
+            // SAFETY: the block was allocated by the inner allocator.
+            unsafe { self.0.deallocate(ptr, layout) };
+            return;
+        }
+        // SAFETY: the same layout was rounded successfully by `allocate`.
+        let layout = unsafe { page_layout(layout).unwrap_unchecked() };
+        // SAFETY: the block was allocated and locked with this layout.
+        unsafe {
+            sys::unlock(ptr.as_ptr(), layout.size());
+            self.0.deallocate(ptr, layout);
+        }
+    }
+}
+
+impl<T> GrowLock<T, PageLocked> {
+    /// Creates a new [`GrowLock<T>`] with the given capacity, whose buffer
+    /// is locked in memory, returning an error if the allocation (or the
+    /// locking) fails.
+    ///
+    /// The buffer takes whole pages, so the capacity is rounded up to the
+    /// elements which fit in them.
+    ///
+    /// # Errors
+    /// * [`TryReserveError::CapacityOverflow`] if `capacity *
+    ///   size_of::<T>` overflows [`isize::MAX`].
+    /// * [`TryReserveError::AllocError`] if memory is exhausted, if the
+    ///   limit of locked memory of the process is exceeded, or if the
+    ///   target doesn't support locking memory.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, mlock::PageLocked};
+    ///
+    /// let key = GrowLock::<u8, PageLocked>::try_with_capacity_locked(32)?;
+    /// key.write().unwrap().extend(*b"hunter2");
+    /// assert_eq!(key, *b"hunter2");
+    /// # Ok::<(), growlock::error::TryReserveError>(())
+    /// ```
+    #[inline]
+    pub fn try_with_capacity_locked(
+        capacity: usize,
+    ) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, PageLocked(Global))
+    }
+    /// Creates a new [`GrowLock<T>`] with the given capacity, whose buffer
+    /// is locked in memory.
+    ///
+    /// See [`try_with_capacity_locked`](GrowLock::try_with_capacity_locked)
+    /// to handle the error if the locking fails (which is more likely than
+    /// an allocation failure).
+    ///
+    /// # Panics
+    /// Panics with the [`TryReserveError`] if `capacity * size_of::<T>`
+    /// overflows [`isize::MAX`], or if the allocation or the locking fails
+    /// (instead of aborting like
+    /// [`with_capacity_in`](GrowLock::with_capacity_in)).
+    #[inline]
+    #[must_use]
+    #[track_caller]
+    pub fn with_capacity_locked(capacity: usize) -> Self {
+        match Self::try_with_capacity_locked(capacity) {
+            Ok(lock) => lock,
+            Err(e) => panic!("{e}"),
+        }
+    }
+}
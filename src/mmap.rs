@@ -0,0 +1,290 @@
+//! Memory-mapped [`GrowLock`]s, backed by a file instead of the heap.
+//!
+//! The file is a [snapshot](GrowLock::write_snapshot) header followed by
+//! the elements (starting at the first offset aligned for `T`, which is
+//! right after the header if `align_of::<T>() <= 8`, so files written by
+//! [`write_snapshot`](GrowLock::write_snapshot) can be mapped too).
+//!
+//! * [`GrowLock::create_mmap`] creates a writable [`GrowLock`], whose
+//!   pushes go to the mapping, and [`GrowLock::flush`] persists them.
+//! * [`GrowLock::from_mmap_readonly`] maps an existing file as a full
+//!   [`GrowLock`], without copying it.
+//!
+//! The mapping is the [allocator](GrowLock::allocator) of the
+//! [`GrowLock`] ([`MmapBacking`]), so it is unmapped when the
+//! [`GrowLock`] is dropped.
+
+use {
+    crate::{
+        GrowLock,
+        bytemuck::{SNAPSHOT_HEADER_LEN, SNAPSHOT_MAGIC},
+        compat::{AllocError, Allocator, Global},
+        sync::atomic::Ordering,
+    },
+    ::bytemuck::Pod,
+    ::memmap2::{MmapMut, MmapOptions},
+    std::{
+        alloc::Layout,
+        fmt,
+        fs::{File, OpenOptions},
+        io,
+        path::Path,
+        ptr::NonNull,
+        sync::PoisonError,
+    },
+};
+
+/// Memory mapping backing a [`GrowLock`], created by
+/// [`GrowLock::create_mmap`] and [`GrowLock::from_mmap_readonly`].
+///
+/// It's used as the [`Allocator`] of the [`GrowLock`]: deallocating the
+/// buffer does nothing, and the file is unmapped when the [`GrowLock`] is
+/// dropped. Any other allocation (e.g. if the buffer is converted into a
+/// [`Vec`] which then grows) is forwarded to [`Global`].
+pub struct MmapBacking {
+    map: MmapMut,
+    /// Offset of the elements from the start of the mapping.
+    offset: usize,
+}
+
+impl MmapBacking {
+    /// Returns the offset of the elements in the file.
+    const fn data_offset<T>() -> usize {
+        SNAPSHOT_HEADER_LEN.next_multiple_of(align_of::<T>())
+    }
+    /// Returns a pointer to the first element.
+    fn data(&self) -> NonNull<u8> {
+        // SAFETY: `offset` is inside the mapping (or one past its end),
+        // which is non-null.
+        unsafe {
+            NonNull::new_unchecked(self.map.as_ptr().cast_mut())
+                .add(self.offset)
+        }
+    }
+}
+
+impl fmt::Debug for MmapBacking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MmapBacking")
+            .field("ptr", &self.map.as_ptr())
+            .field("len", &self.map.len())
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+// SAFETY:
+// * the only block not allocated by `Global` is the mapping, which is
+//   valid until the `MmapBacking` is dropped.
+// * `MmapBacking` is not `Clone`, so no other instance can deallocate its
+//   blocks.
+unsafe impl Allocator for MmapBacking {
+    #[inline]
+    fn allocate(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        Global.allocate(layout)
+    }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // the mapping is unmapped when `self` is dropped.
+        if ptr.addr() != self.data().addr() {
+            // SAFETY: every other block was allocated by `Global`.
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+}
+
+/// Returns an [`InvalidData`](io::ErrorKind::InvalidData) error.
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl<T: Pod> GrowLock<T, MmapBacking> {
+    /// Creates (or truncates) the file at `path`, with room for
+    /// `capacity` elements, and maps it as a new empty [`GrowLock`].
+    ///
+    /// The pushed elements are written to the mapping. Use
+    /// [`flush`](GrowLock::flush) to persist them (and the length).
+    ///
+    /// # Safety
+    /// The file must not be modified (or truncated) by anyone else while
+    /// the [`GrowLock`] is alive.
+    ///
+    /// # Errors
+    /// * [`ErrorKind::InvalidInput`](io::ErrorKind::InvalidInput) if the
+    ///   size of the file would overflow [`isize::MAX`] bytes.
+    /// * Any error creating, resizing or mapping the file.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::{GrowLock, mmap::MmapBacking};
+    ///
+    /// type MmapLock = GrowLock<u32, MmapBacking>;
+    ///
+    /// let path = std::env::temp_dir().join("growlock-create-mmap");
+    /// // SAFETY: the file is not modified by anyone else.
+    /// let lock = unsafe { MmapLock::create_mmap(&path, 4) }?;
+    /// lock.write().unwrap().extend([1, 2, 3]);
+    /// lock.flush()?;
+    /// drop(lock);
+    ///
+    /// // SAFETY: the file is not modified by anyone else.
+    /// let lock = unsafe { MmapLock::from_mmap_readonly(&path) }?;
+    /// assert_eq!(lock, [1, 2, 3]);
+    /// assert!(lock.is_full());
+    /// # drop(lock);
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub unsafe fn create_mmap<P: AsRef<Path>>(
+        path: P,
+        capacity: usize,
+    ) -> io::Result<Self> {
+        let offset = MmapBacking::data_offset::<T>();
+        let file_len = Layout::array::<T>(capacity)
+            .ok()
+            .and_then(|layout| layout.size().checked_add(offset))
+            .filter(|&len| isize::try_from(len).is_ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "capacity overflow",
+                )
+            })?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(file_len as u64)?;
+        // SAFETY: the caller guarantees that the file is not modified.
+        let mut map = unsafe { MmapOptions::new().map_mut(&file) }?;
+        map[..8].copy_from_slice(&SNAPSHOT_MAGIC);
+        map[8..16].copy_from_slice(&(size_of::<T>() as u64).to_le_bytes());
+        map[16..SNAPSHOT_HEADER_LEN].copy_from_slice(&0u64.to_le_bytes());
+
+        let backing = MmapBacking { map, offset };
+        let ptr = backing.data().cast::<T>();
+        if !ptr.is_aligned() {
+            return Err(invalid(
+                "mapping not aligned for the element type",
+            ));
+        }
+        // SAFETY:
+        // * the mapping is owned by `backing`, and `deallocate` ignores
+        //   `ptr`.
+        // * `ptr` is aligned, and the file has room for `capacity`
+        //   elements (which is at most `isize::MAX` bytes).
+        Ok(unsafe { Self::from_parts_in(ptr, 0, capacity, backing) })
+    }
+    /// Maps the file at `path` (written by [`flush`](GrowLock::flush) or
+    /// [`write_snapshot`](GrowLock::write_snapshot)) as a new full
+    /// [`GrowLock`], without copying it.
+    ///
+    /// The mapping is private: the [`GrowLock`] is full, so nothing can
+    /// be pushed, and nothing is ever written to the file.
+    ///
+    /// # Safety
+    /// The file must not be modified (or truncated) by anyone else while
+    /// the [`GrowLock`] is alive.
+    ///
+    /// # Errors
+    /// * [`ErrorKind::InvalidData`](io::ErrorKind::InvalidData) if the
+    ///   header is invalid, if it was written for a different element
+    ///   size, or if the file is shorter than its header says.
+    /// * Any error opening or mapping the file.
+    pub unsafe fn from_mmap_readonly<P: AsRef<Path>>(
+        path: P,
+    ) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the caller guarantees that the file is not modified.
+        let map = unsafe { MmapOptions::new().map_copy(&file) }?;
+        if map.len() < SNAPSHOT_HEADER_LEN || map[..8] != SNAPSHOT_MAGIC {
+            return Err(invalid("invalid snapshot header"));
+        }
+        let mut size = [0; 8];
+        let mut len = [0; 8];
+        size.copy_from_slice(&map[8..16]);
+        len.copy_from_slice(&map[16..SNAPSHOT_HEADER_LEN]);
+        if u64::from_le_bytes(size) != size_of::<T>() as u64 {
+            return Err(invalid("snapshot of a different element size"));
+        }
+        let offset = MmapBacking::data_offset::<T>();
+        let len = usize::try_from(u64::from_le_bytes(len))
+            .ok()
+            .filter(|&len| {
+                len.checked_mul(size_of::<T>())
+                    .and_then(|bytes| bytes.checked_add(offset))
+                    .is_some_and(|end| end <= map.len())
+            })
+            .ok_or_else(|| invalid("snapshot length overflow"))?;
+
+        let backing = MmapBacking { map, offset };
+        let ptr = backing.data().cast::<T>();
+        if !ptr.is_aligned() {
+            return Err(invalid(
+                "mapping not aligned for the element type",
+            ));
+        }
+        // SAFETY:
+        // * the mapping is owned by `backing`, and `deallocate` ignores
+        //   `ptr`.
+        // * `ptr` is aligned, and the file contains `len` elements, which
+        //   are valid as `T` is valid for any bit pattern.
+        Ok(unsafe { Self::from_parts_in(ptr, len, len, backing) })
+    }
+}
+
+impl<T> GrowLock<T, MmapBacking> {
+    /// Persists the elements pushed so far, and the length, to the file.
+    ///
+    /// This waits for the writer (if any, including an async writer or an
+    /// owned guard) to release the lock, and blocks until the data is
+    /// written to disk. Nothing is persisted
+    /// automatically: the elements pushed after the last call are lost
+    /// when the [`GrowLock`] is dropped (even if they are written to the
+    /// file, the length is not updated).
+    ///
+    /// For a [`GrowLock`] created by
+    /// [`from_mmap_readonly`](GrowLock::from_mmap_readonly), this does
+    /// nothing.
+    ///
+    /// # Errors
+    /// Returns an error if the data could not be written to disk.
+    ///
+    /// # Panics
+    /// With the `deadlock-detection` feature, panics if the current
+    /// thread holds the write lock (without it, this deadlocks).
+    #[track_caller]
+    pub fn flush(&self) -> io::Result<()> {
+        self.owner.check();
+        // the header is only written with the mutex locked, and a
+        // poisoned lock has a consistent length anyway. The lock-free
+        // pushers can still store the length.
+        let guard =
+            self.mutex.lock().unwrap_or_else(PoisonError::into_inner);
+        // like `write`, waits for the async writer or the owned guard.
+        #[cfg(not(loom))]
+        let guard = self
+            .notifier
+            .wait_detached(guard)
+            .unwrap_or_else(PoisonError::into_inner);
+        let len = self.len.load(Ordering::Acquire) as u64;
+        let backing = self.allocator();
+        // SAFETY:
+        // * the header is inside the mapping, and it is not reachable from
+        //   the elements.
+        // * the mutex is locked, so no one else writes it.
+        unsafe {
+            NonNull::new_unchecked(backing.map.as_ptr().cast_mut())
+                .add(16)
+                .cast::<[u8; 8]>()
+                .write_unaligned(len.to_le_bytes());
+        }
+        // the writers don't wait for the disk.
+        drop(guard);
+        backing.map.flush()
+    }
+}
@@ -0,0 +1,499 @@
+//! Wake-ups for the readers waiting for new elements, and for the
+//! subscribers.
+
+// The condvars of `loom` can't wait on its write lock (see `sync`).
+#[cfg(loom)]
+use loom::sync::Mutex;
+#[cfg(feature = "tokio")]
+use {
+    crate::sync::TryLockError,
+    ::tokio::sync::{Notify, watch},
+    std::pin::pin,
+};
+#[cfg(feature = "std")]
+use {
+    crate::sync::{
+        Condvar,
+        atomic::{AtomicUsize, Ordering, fence},
+    },
+    std::{
+        sync::{PoisonError, mpsc::Sender},
+        time::{Duration, Instant},
+    },
+};
+
+#[cfg(all(feature = "std", not(loom)))]
+use crate::sync::{LockResult, Mutex, MutexGuard, atomic::AtomicBool};
+
+/// Lets readers block until the length of a
+/// [`GrowLock`](crate::GrowLock) changes, and sends the new lengths to
+/// the subscribers.
+///
+/// Writers call [`notify`](Notifier::notify) after every store of the
+/// length, and [`notify_unlock`](Notifier::notify_unlock) when they
+/// release the write lock. When nobody is listening, the first one is
+/// just a relaxed load of the number of listeners, and the second one is
+/// a fence followed by that load.
+///
+/// Without `std`, nobody can listen.
+pub(crate) struct Notifier {
+    /// Number of readers inside `wait_until`, plus the number of
+    /// subscribers (counting the `watch` channel as one).
+    #[cfg(feature = "std")]
+    listeners: AtomicUsize,
+    /// Also protects the check-then-wait of the readers, so that a
+    /// notification can't be lost between the two.
+    #[cfg(feature = "std")]
+    subscribers: Mutex<Subscribers>,
+    #[cfg(feature = "std")]
+    condvar: Condvar,
+    /// The write lock of the async writers and of the owned guards.
+    #[cfg(all(feature = "std", not(loom)))]
+    detached: DetachedLock,
+    /// The guards waiting on a condvar.
+    #[cfg(feature = "tokio")]
+    parked: Parked,
+}
+
+/// Lets a writer hold the write lock without holding the mutex of the
+/// [`GrowLock`](crate::GrowLock): an async writer across `.await`s, or an
+/// [`OwnedGrowGuard`](crate::guard::OwnedGrowGuard) across threads.
+///
+/// The writer locks the mutex just to set `locked`, and the sync writers
+/// wait on `unlocked` while it's set. The async writers which can't lock
+/// the mutex, or find `locked` already set, wait on `notify` instead.
+#[cfg(all(feature = "std", not(loom)))]
+struct DetachedLock {
+    /// Only changed with the mutex locked.
+    locked: AtomicBool,
+    /// Number of async writers waiting on `notify`.
+    #[cfg(feature = "tokio")]
+    waiting: AtomicUsize,
+    unlocked: Condvar,
+    #[cfg(feature = "tokio")]
+    notify: Notify,
+    /// Woken up by every notification, for the async writers which wait
+    /// (as listeners) for the reserved slots to be resolved.
+    #[cfg(feature = "tokio")]
+    resolved: Notify,
+}
+
+/// Lets a guard wait on a condvar with the write lock released like when
+/// it's dropped, so that the async writers are woken up as usual.
+///
+/// The condvar waits on `mutex` instead of the mutex of the
+/// [`GrowLock`](crate::GrowLock), and the writers which lock the latter
+/// while `count` isn't zero lock `mutex` too: they can't change the
+/// elements (and notify the condvar) before the guard waits on it.
+#[cfg(feature = "tokio")]
+struct Parked {
+    /// Number of guards waiting on a condvar. Only incremented with the
+    /// mutex of the [`GrowLock`](crate::GrowLock) locked.
+    count: AtomicUsize,
+    mutex: Mutex<()>,
+}
+
+/// Unregisters an async writer (or listener) even if its future is
+/// dropped.
+#[cfg(feature = "tokio")]
+struct Waiting<'a>(&'a AtomicUsize);
+
+#[cfg(feature = "tokio")]
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(feature = "std")]
+struct Subscribers {
+    senders: Vec<Sender<usize>>,
+    /// The last length sent to every subscriber.
+    sent: usize,
+    /// Number of readers waiting on the condvar.
+    waiting: usize,
+    /// Created by [`watch`](Notifier::watch), and dropped by the first
+    /// notification after all its receivers are dropped.
+    #[cfg(feature = "tokio")]
+    watch: Option<watch::Sender<usize>>,
+}
+
+impl Notifier {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            listeners: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            subscribers: Mutex::new(Subscribers {
+                senders: Vec::new(),
+                sent: 0,
+                waiting: 0,
+                #[cfg(feature = "tokio")]
+                watch: None,
+            }),
+            #[cfg(feature = "std")]
+            condvar: Condvar::new(),
+            #[cfg(all(feature = "std", not(loom)))]
+            detached: DetachedLock {
+                locked: AtomicBool::new(false),
+                #[cfg(feature = "tokio")]
+                waiting: AtomicUsize::new(0),
+                unlocked: Condvar::new(),
+                #[cfg(feature = "tokio")]
+                notify: Notify::new(),
+                #[cfg(feature = "tokio")]
+                resolved: Notify::new(),
+            },
+            #[cfg(feature = "tokio")]
+            parked: Parked {
+                count: AtomicUsize::new(0),
+                mutex: Mutex::new(()),
+            },
+        }
+    }
+    /// Wakes up the readers waiting in `wait_until` and sends `len` to
+    /// the subscribers, if it sees them.
+    ///
+    /// Must be called after `len` is stored. Without a fence, a listener
+    /// which has just started listening can be missed: it's notified by
+    /// the next call, or by [`notify_unlock`](Notifier::notify_unlock).
+    #[inline]
+    #[cfg_attr(not(feature = "std"), expect(clippy::unused_self))]
+    pub(crate) fn notify(&self, len: usize) {
+        #[cfg(feature = "std")]
+        if self.listeners.load(Ordering::Relaxed) != 0 {
+            self.notify_slow(len);
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = len;
+    }
+    /// Wakes up every reader waiting in `wait_until`, and sends `len` to
+    /// every subscriber (unless it was already sent).
+    ///
+    /// Must be called with the current length, before the write lock is
+    /// released.
+    #[inline]
+    #[cfg_attr(not(feature = "std"), expect(clippy::unused_self))]
+    pub(crate) fn notify_unlock(&self, len: usize) {
+        #[cfg(feature = "std")]
+        {
+            // pairs with the fence in `listen`: either the listener sees
+            // the new length, or we see the listener.
+            fence(Ordering::SeqCst);
+            if self.listeners.load(Ordering::Relaxed) != 0 {
+                self.notify_slow(len);
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        let _ = len;
+    }
+    #[cold]
+    #[cfg(feature = "std")]
+    fn notify_slow(&self, len: usize) {
+        // once we hold the mutex, the readers which have seen the old
+        // length are waiting on the condvar.
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if subscribers.sent != len {
+            subscribers.sent = len;
+            let count = subscribers.senders.len();
+            // the senders of the dropped receivers are dropped too.
+            subscribers.senders.retain(|tx| tx.send(len).is_ok());
+            let hung_up = count - subscribers.senders.len();
+            self.listeners.fetch_sub(hung_up, Ordering::Relaxed);
+        }
+        #[cfg(feature = "tokio")]
+        if let Some(tx) = &subscribers.watch {
+            if tx.is_closed() {
+                // every receiver is dropped: the next `watch` creates a
+                // new sender.
+                subscribers.watch = None;
+                self.listeners.fetch_sub(1, Ordering::Relaxed);
+            } else {
+                tx.send_if_modified(|value| {
+                    let modified = *value != len;
+                    *value = len;
+                    modified
+                });
+            }
+        }
+        let waiting = subscribers.waiting != 0;
+        drop(subscribers);
+        if waiting {
+            self.condvar.notify_all();
+        }
+        #[cfg(feature = "tokio")]
+        self.detached.resolved.notify_waiters();
+    }
+    #[inline]
+    #[cfg(all(test, feature = "tokio", not(any(loom, shuttle))))]
+    pub(crate) fn listeners(&self) -> usize {
+        self.listeners.load(Ordering::Relaxed)
+    }
+    /// Registers a listener, which sees every length stored before the
+    /// next [`notify_unlock`](Notifier::notify_unlock) (if the writer
+    /// doesn't see it).
+    #[cfg(feature = "std")]
+    fn listen(&self) {
+        self.listeners.fetch_add(1, Ordering::Relaxed);
+        // pairs with the fences in `notify_unlock` and `resolve_slots`.
+        fence(Ordering::SeqCst);
+    }
+    /// Adds `tx` to the subscribers, and sends it the current length
+    /// (returned by `len`).
+    #[cfg(feature = "std")]
+    pub(crate) fn subscribe(
+        &self,
+        tx: Sender<usize>,
+        len: impl FnOnce() -> usize,
+    ) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.listen();
+        // if the receiver is already dropped, the sender is removed by
+        // the next notification.
+        let _ = tx.send(len());
+        subscribers.senders.push(tx);
+    }
+    /// Returns a new receiver of the [`watch`] channel of the lengths,
+    /// creating it if needed.
+    ///
+    /// `len` must load the current length.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn watch(
+        &self,
+        len: impl Fn() -> usize,
+    ) -> watch::Receiver<usize> {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let tx = subscribers.watch.get_or_insert_with(|| {
+            self.listen();
+            watch::Sender::new(len())
+        });
+        // the length is loaded inside the lock of the channel, so that it
+        // can't overwrite a newer length sent by a writer.
+        tx.send_if_modified(|value| {
+            let len = len();
+            let modified = *value != len;
+            *value = len;
+            modified
+        });
+        tx.subscribe()
+    }
+    /// Blocks until `ready` returns `true`, or until `timeout` elapses.
+    ///
+    /// Returns the last result of `ready`. It must only depend on the
+    /// length (loaded with at least [`Ordering::Acquire`]).
+    #[cfg(feature = "std")]
+    pub(crate) fn wait_until(
+        &self,
+        mut ready: impl FnMut() -> bool,
+        timeout: Option<Duration>,
+    ) -> bool {
+        if ready() {
+            return true;
+        }
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut guard = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        self.listen();
+        guard.waiting += 1;
+        let ready = loop {
+            // this also handles spurious wake-ups.
+            if ready() {
+                break true;
+            }
+            guard = match deadline {
+                None => self
+                    .condvar
+                    .wait(guard)
+                    .unwrap_or_else(PoisonError::into_inner),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break false;
+                    }
+                    self.condvar
+                        .wait_timeout(guard, deadline - now)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+            };
+        };
+        guard.waiting -= 1;
+        self.listeners.fetch_sub(1, Ordering::Relaxed);
+        ready
+    }
+    /// Waits (with the mutex unlocked) until the async writer or the
+    /// owned guard holding the write lock, if any, releases it.
+    ///
+    /// `guard` must be the guard of the mutex of the
+    /// [`GrowLock`](crate::GrowLock).
+    #[cfg(all(feature = "std", not(loom)))]
+    pub(crate) fn wait_detached<'a>(
+        &self,
+        mut guard: MutexGuard<'a, ()>,
+    ) -> LockResult<MutexGuard<'a, ()>> {
+        let mut poisoned = false;
+        while self.detached.locked.load(Ordering::Relaxed) {
+            guard =
+                self.detached.unlocked.wait(guard).unwrap_or_else(|e| {
+                    poisoned = true;
+                    e.into_inner()
+                });
+        }
+        if poisoned {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+    /// Returns whether an async writer or an owned guard holds the write
+    /// lock.
+    ///
+    /// Must be called with the mutex locked.
+    #[cfg(all(feature = "std", not(loom)))]
+    pub(crate) fn is_detached(&self) -> bool {
+        self.detached.locked.load(Ordering::Relaxed)
+    }
+    /// Keeps the write lock held by the writer holding `guard` (the guard
+    /// of the mutex of the [`GrowLock`](crate::GrowLock)) after unlocking
+    /// the mutex, until [`unlock_detached`](Notifier::unlock_detached).
+    #[cfg(all(feature = "std", not(loom)))]
+    pub(crate) fn lock_detached(&self, guard: MutexGuard<'_, ()>) {
+        self.detached.locked.store(true, Ordering::Relaxed);
+        drop(guard);
+    }
+    /// Waits until the write lock is acquired by this async writer.
+    ///
+    /// `mutex` must be the mutex of the [`GrowLock`](crate::GrowLock).
+    /// The lock must be released with
+    /// [`unlock_detached`](Notifier::unlock_detached).
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn async_lock(&self, mutex: &Mutex<()>) {
+        let lock = &self.detached;
+        lock.waiting.fetch_add(1, Ordering::Relaxed);
+        let _waiting = Waiting(&lock.waiting);
+        // pairs with the fence in `wake_async_writers`: either we see the
+        // unlocked mutex, or the writer which unlocked it sees us.
+        fence(Ordering::SeqCst);
+        loop {
+            let mut notified = pin!(lock.notify.notified());
+            // registers the writer before trying to lock, so that the
+            // `notify_waiters` of a writer unlocking after this is seen.
+            notified.as_mut().enable();
+            if self.try_async_lock(mutex) {
+                return;
+            }
+            notified.await;
+        }
+    }
+    /// Waits asynchronously until `ready` returns `true`, as a listener:
+    /// it must become `true` only through a store followed by a
+    /// notification (like the `turn` of the reserved slots).
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn wait_async(&self, ready: impl Fn() -> bool) {
+        if ready() {
+            return;
+        }
+        self.listen();
+        let _listening = Waiting(&self.listeners);
+        loop {
+            let mut notified = pin!(self.detached.resolved.notified());
+            // registers the listener before checking, so that a
+            // notification after this is seen.
+            notified.as_mut().enable();
+            if ready() {
+                return;
+            }
+            notified.await;
+        }
+    }
+    /// Tries to acquire the write lock for an async writer, without
+    /// waiting.
+    #[cfg(feature = "tokio")]
+    fn try_async_lock(&self, mutex: &Mutex<()>) -> bool {
+        // the async writer doesn't observe the poisoning, like
+        // `tokio::sync::Mutex`.
+        let _guard = match mutex.try_lock() {
+            Ok(guard) => guard,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return false,
+        };
+        let locked = &self.detached.locked;
+        if locked.swap(true, Ordering::Relaxed) {
+            return false;
+        }
+        self.wait_parked();
+        true
+    }
+    /// Releases the write lock acquired by
+    /// [`async_lock`](Notifier::async_lock) or
+    /// [`lock_detached`](Notifier::lock_detached).
+    #[cfg(all(feature = "std", not(loom)))]
+    pub(crate) fn unlock_detached(&self, mutex: &Mutex<()>) {
+        // the mutex is only held for a moment by the other writers while
+        // `locked` is set, so this doesn't block the executor.
+        let guard = mutex.lock().unwrap_or_else(PoisonError::into_inner);
+        self.detached.locked.store(false, Ordering::Relaxed);
+        drop(guard);
+        self.detached.unlocked.notify_all();
+        #[cfg(feature = "tokio")]
+        self.wake_async_writers();
+    }
+    /// Registers a guard about to wait on a condvar, returning the guard
+    /// of the mutex to wait with.
+    ///
+    /// Must be called with the mutex of the [`GrowLock`](crate::GrowLock)
+    /// locked, and followed by [`unpark`](Notifier::unpark) once the
+    /// guard is woken up.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn park(&self) -> MutexGuard<'_, ()> {
+        let parked = &self.parked;
+        parked.count.fetch_add(1, Ordering::Relaxed);
+        parked.mutex.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+    #[cfg(feature = "tokio")]
+    pub(crate) fn unpark(&self) {
+        self.parked.count.fetch_sub(1, Ordering::Relaxed);
+    }
+    /// Waits until the guards which have released the write lock to wait
+    /// on a condvar are waiting on it.
+    ///
+    /// Must be called after the mutex of the [`GrowLock`](crate::GrowLock)
+    /// is locked, before the elements are changed.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn wait_parked(&self) {
+        let parked = &self.parked;
+        // the increments made before the mutex was unlocked are seen.
+        if parked.count.load(Ordering::Relaxed) != 0 {
+            drop(
+                parked
+                    .mutex
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner),
+            );
+        }
+    }
+    /// Wakes up the async writers waiting in
+    /// [`async_lock`](Notifier::async_lock).
+    ///
+    /// Must be called after the mutex is unlocked.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn wake_async_writers(&self) {
+        // pairs with the fence in `async_lock`.
+        fence(Ordering::SeqCst);
+        if self.detached.waiting.load(Ordering::Relaxed) != 0 {
+            self.detached.notify.notify_waiters();
+        }
+    }
+}
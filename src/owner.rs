@@ -0,0 +1,97 @@
+//! Detection of the re-entrant write locks, with the
+//! `deadlock-detection` feature.
+//!
+//! [`Owner`] remembers the thread holding the write lock of a
+//! [`GrowLock`](crate::GrowLock), so that
+//! [`GrowLock::write`](crate::GrowLock::write) panics instead of
+//! deadlocking if it's called again by the same thread. Without the
+//! feature (and when the crate is model-checked), it's empty and nothing
+//! is checked.
+
+#[cfg(all(feature = "deadlock-detection", not(any(loom, shuttle))))]
+pub(crate) use detect::Owner;
+#[cfg(not(all(
+    feature = "deadlock-detection",
+    not(any(loom, shuttle))
+)))]
+pub(crate) use noop::Owner;
+
+#[cfg(all(feature = "deadlock-detection", not(any(loom, shuttle))))]
+mod detect {
+    use crate::sync::atomic::{AtomicU64, Ordering};
+
+    /// See the [module documentation](super).
+    pub(crate) struct Owner {
+        /// The id (see [`thread_id`]) of the thread holding the write
+        /// lock, or zero.
+        ///
+        /// It's only stored by the thread holding the write lock, so a
+        /// thread can only load its own id if it stored it (and didn't
+        /// clear it yet): the loads and the stores can be
+        /// [`Relaxed`](Ordering::Relaxed), as the other threads never see
+        /// their own id, whatever they see.
+        thread: AtomicU64,
+    }
+
+    impl Owner {
+        #[inline]
+        pub(crate) const fn new() -> Self {
+            Self {
+                thread: AtomicU64::new(0),
+            }
+        }
+        /// Panics if the current thread holds the write lock.
+        #[inline]
+        #[track_caller]
+        pub(crate) fn check(&self) {
+            assert!(
+                self.thread.load(Ordering::Relaxed) != thread_id(),
+                "re-entrant `GrowLock::write`: the write lock is already \
+                 held by this thread, so it would deadlock",
+            );
+        }
+        /// Records the current thread as the owner. Called after the
+        /// write lock is acquired.
+        #[inline]
+        pub(crate) fn set(&self) {
+            self.thread.store(thread_id(), Ordering::Relaxed);
+        }
+        /// Called before the write lock is released.
+        #[inline]
+        pub(crate) fn clear(&self) {
+            self.thread.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns a non-zero id of the current thread, unique for the
+    /// lifetime of the process.
+    fn thread_id() -> u64 {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        std::thread_local! {
+            static ID: u64 = NEXT.fetch_add(1, Ordering::Relaxed);
+        }
+        ID.with(|&id| id)
+    }
+}
+
+#[cfg(not(all(feature = "deadlock-detection", not(any(loom, shuttle)))))]
+mod noop {
+    /// See the [module documentation](super).
+    pub(crate) struct Owner;
+
+    impl Owner {
+        #[inline]
+        pub(crate) const fn new() -> Self {
+            Self
+        }
+        #[inline]
+        #[expect(clippy::unused_self, reason = "same API as `detect`")]
+        pub(crate) const fn check(&self) {}
+        #[inline]
+        #[expect(clippy::unused_self, reason = "same API as `detect`")]
+        pub(crate) const fn set(&self) {}
+        #[inline]
+        #[expect(clippy::unused_self, reason = "same API as `detect`")]
+        pub(crate) const fn clear(&self) {}
+    }
+}
@@ -0,0 +1,393 @@
+//! Partitions of the spare capacity of a [`GrowLock`] into regions, which
+//! are written concurrently by independent writers.
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        error::PushError,
+        guard::GrowGuard,
+        sync::{
+            LockResult, Mutex, PoisonError,
+            atomic::{AtomicUsize, Ordering},
+        },
+    },
+    alloc::{boxed::Box, vec::Vec},
+    core::{
+        fmt,
+        iter::FusedIterator,
+        marker::PhantomData,
+        mem::{self, ManuallyDrop},
+        ptr, slice,
+    },
+};
+
+/// The spare capacity of a [`GrowLock`], split into contiguous regions
+/// which are written concurrently through [`RegionWriter`]s.
+///
+/// This structure is created by the [`partition`](GrowLock::partition)
+/// method on [`GrowLock`]. It holds the write lock, so the other writers
+/// wait until it's [`seal`](Partition::seal)ed or dropped.
+///
+/// Each region has its own lock and its own length: the writers of
+/// different regions never contend. The elements of the regions aren't
+/// contiguous until the partition is sealed, so they are read through
+/// [`regions`](Partition::regions) (the [`GrowLock`] itself only shows the
+/// elements pushed before the partition).
+///
+/// When the partition is sealed (or dropped), the elements of the regions
+/// are moved down to fill the capacity left free by the previous regions,
+/// and they're published in the [`GrowLock`], in the order of the regions.
+pub struct Partition<'lock, T, A: Allocator = Global> {
+    /// Dropped by hand, after the elements of the regions are published.
+    guard: ManuallyDrop<GrowGuard<'lock, T, A>>,
+    regions: Box<[Region]>,
+}
+
+/// The bookkeeping of a region of a [`Partition`].
+struct Region {
+    /// The index of the first element of the region in the buffer.
+    start: usize,
+    capacity: usize,
+    /// The number of initialized elements, published with `Release`.
+    len: AtomicUsize,
+    /// Serializes the writers of the region.
+    mutex: Mutex<()>,
+}
+
+/// A writer of a region of a [`Partition`].
+///
+/// This structure is created by the [`writers`](Partition::writers)
+/// method on [`Partition`]. The writers of a region are serialized by its
+/// own lock, which is only held while pushing.
+pub struct RegionWriter<'a, T, A: Allocator = Global> {
+    lock: &'a GrowLock<T, A>,
+    region: &'a Region,
+}
+
+/// An iterator over the elements of the regions of a [`Partition`].
+///
+/// This structure is created by the [`regions`](Partition::regions)
+/// method on [`Partition`].
+pub struct Regions<'a, T> {
+    ptr: *const T,
+    iter: slice::Iter<'a, Region>,
+    _elems: PhantomData<&'a [T]>,
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Locks this [`GrowLock`], and splits its spare capacity into
+    /// regions of `sizes` elements, which can be written concurrently.
+    ///
+    /// The regions start after the elements already pushed, in the order
+    /// of `sizes`, and the capacity after them is left unused until the
+    /// [`Partition`] is sealed.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned (another writer panicked
+    /// while holding the lock). The partition is still returned inside the
+    /// error.
+    ///
+    /// # Panics
+    /// Panics if the sum of `sizes` is greater than
+    /// `self.capacity() - self.len()`.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::thread;
+    ///
+    /// let lock = GrowLock::with_capacity(10);
+    /// lock.write().unwrap().push(0);
+    /// let partition = lock.partition(&[3, 3]).unwrap();
+    /// thread::scope(|s| {
+    ///     for (i, writer) in partition.writers().into_iter().enumerate() {
+    ///         s.spawn(move || {
+    ///             for j in 0..2 {
+    ///                 writer.push(10 * (i + 1) + j);
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// assert!(partition.regions().eq([&[10, 11][..], &[20, 21]]));
+    /// assert_eq!(*lock, [0]);
+    ///
+    /// drop(partition.seal());
+    /// assert_eq!(*lock, [0, 10, 11, 20, 21]);
+    /// ```
+    pub fn partition(
+        &self,
+        sizes: &[usize],
+    ) -> LockResult<Partition<'_, T, A>> {
+        match self.write() {
+            Ok(guard) => Ok(Partition::new(guard, sizes)),
+            Err(e) => {
+                let guard = e.into_inner();
+                Err(PoisonError::new(Partition::new(guard, sizes)))
+            }
+        }
+    }
+}
+
+impl<'lock, T, A: Allocator> Partition<'lock, T, A> {
+    /// # Panics
+    /// Panics if the regions don't fit in the spare capacity.
+    fn new(mut guard: GrowGuard<'lock, T, A>, sizes: &[usize]) -> Self {
+        guard.publish();
+        let spare = guard.capacity() - guard.len();
+        let total = sizes
+            .iter()
+            .try_fold(0_usize, |total, &size| total.checked_add(size));
+        assert!(
+            total.is_some_and(|total| total <= spare),
+            "the regions don't fit in the spare capacity",
+        );
+        let mut start = guard.len();
+        let regions = sizes
+            .iter()
+            .map(|&capacity| {
+                let region = Region {
+                    start,
+                    capacity,
+                    len: AtomicUsize::new(0),
+                    mutex: Mutex::new(()),
+                };
+                start += capacity;
+                region
+            })
+            .collect();
+        Self {
+            guard: ManuallyDrop::new(guard),
+            regions,
+        }
+    }
+    /// Returns the number of regions.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+    /// Returns a writer for each region, in order.
+    ///
+    /// This can be called more than once: the writers of the same region
+    /// take turns.
+    #[must_use]
+    pub fn writers(&self) -> Vec<RegionWriter<'_, T, A>> {
+        self.regions
+            .iter()
+            .map(|region| RegionWriter {
+                lock: self.guard.lock,
+                region,
+            })
+            .collect()
+    }
+    /// Returns an iterator over the elements pushed in each region, in
+    /// order.
+    ///
+    /// The length of each region is loaded when the iterator reaches it.
+    #[inline]
+    #[must_use]
+    pub fn regions(&self) -> Regions<'_, T> {
+        Regions {
+            ptr: self.guard.lock.as_ptr(),
+            iter: self.regions.iter(),
+            _elems: PhantomData,
+        }
+    }
+    /// Merges the regions back into the [`GrowLock`], and returns the
+    /// guard of its write lock.
+    ///
+    /// The elements of the regions are moved down to be contiguous, and
+    /// they're published, so the writers can push after them again.
+    #[must_use]
+    pub fn seal(self) -> GrowGuard<'lock, T, A> {
+        let mut this = ManuallyDrop::new(self);
+        this.merge();
+        drop(mem::take(&mut this.regions));
+        // SAFETY: `this` is never used again, and never dropped.
+        unsafe { ManuallyDrop::take(&mut this.guard) }
+    }
+    /// Moves the elements of the regions after the elements of the
+    /// [`GrowLock`], and publishes them.
+    fn merge(&mut self) {
+        let lock = self.guard.lock;
+        let mut len = self.guard.len();
+        for region in &self.regions {
+            let region_len = region.len.load(Ordering::Acquire);
+            if region.start != len {
+                // SAFETY:
+                // * both ranges are inside the buffer, and the elements of
+                //   the region are initialized.
+                // * `len < region.start`: the elements move down into the
+                //   capacity left free by the previous regions, which no
+                //   reference points into, as the regions can't be
+                //   borrowed anymore.
+                unsafe {
+                    let base = lock.as_non_null_shared();
+                    ptr::copy(
+                        base.add(region.start).as_ptr(),
+                        base.add(len).as_ptr(),
+                        region_len,
+                    );
+                }
+            }
+            len += region_len;
+        }
+        // SAFETY: the write lock is held, and the first `len` elements
+        // are initialized.
+        unsafe { lock.publish_len(len) };
+    }
+}
+
+impl<T, A: Allocator> Drop for Partition<'_, T, A> {
+    /// Seals the partition, and releases the write lock.
+    fn drop(&mut self) {
+        self.merge();
+        // SAFETY: the guard is never used again.
+        unsafe { ManuallyDrop::drop(&mut self.guard) };
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for Partition<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.regions()).finish()
+    }
+}
+
+impl<T, A: Allocator> RegionWriter<'_, T, A> {
+    /// Returns the number of elements pushed in the region.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.region.len.load(Ordering::Acquire)
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+    /// Returns the capacity of the region.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.region.capacity
+    }
+    /// Returns the elements pushed in the region.
+    #[inline]
+    #[must_use]
+    pub fn as_slice(&self) -> &[T] {
+        // SAFETY: see `Regions::next`.
+        unsafe { self.region.as_slice(self.lock.as_ptr()) }
+    }
+    /// Appends `value` to the region.
+    ///
+    /// # Panics
+    /// Panics if the region is full.
+    #[inline]
+    pub fn push(&self, value: T) {
+        assert!(
+            self.try_push(value).is_ok(),
+            "tried to push past the capacity of the region",
+        );
+    }
+    /// Appends `value` to the region, or gives it back if the region is
+    /// full.
+    ///
+    /// # Errors
+    /// Returns an error, which gives back `value`, if the region is full.
+    pub fn try_push(&self, value: T) -> Result<(), PushError<T>> {
+        let region = self.region;
+        // no code which could panic runs while it's held, so it's never
+        // poisoned.
+        let _guard =
+            region.mutex.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = region.len.load(Ordering::Relaxed);
+        if len >= region.capacity {
+            return Err(PushError::new(value, region.capacity));
+        }
+        // SAFETY: the slot is inside the region, which is inside the
+        // buffer, and only written while holding the lock of the region.
+        unsafe {
+            self.lock
+                .as_non_null_shared()
+                .add(region.start + len)
+                .write(value);
+        }
+        region.len.store(len + 1, Ordering::Release);
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator> fmt::Debug for RegionWriter<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegionWriter")
+            .field("elems", &self.as_slice())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+impl Region {
+    /// Returns the elements pushed in the region.
+    ///
+    /// # Safety
+    /// `ptr` must be the buffer of the [`GrowLock`] of the region, and the
+    /// [`Partition`] must be alive for `'a`.
+    unsafe fn as_slice<'a, T>(&self, ptr: *const T) -> &'a [T] {
+        let len = self.len.load(Ordering::Acquire);
+        // SAFETY: the first `len` elements of the region are initialized
+        // (they're published with `Release`), and they're neither moved
+        // nor written while the `Partition` is alive.
+        unsafe { slice::from_raw_parts(ptr.add(self.start), len) }
+    }
+}
+
+impl<'a, T> Iterator for Regions<'a, T> {
+    type Item = &'a [T];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let region = self.iter.next()?;
+        // SAFETY: `self` borrows the `Partition`, whose buffer is `ptr`.
+        Some(unsafe { region.as_slice(self.ptr) })
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Regions<'_, T> {}
+
+impl<T> FusedIterator for Regions<'_, T> {}
+
+// SAFETY: the iterator only reads the elements, like a `&[T]`.
+unsafe impl<T: Sync> Send for Regions<'_, T> {}
+// SAFETY: see `Send`.
+unsafe impl<T: Sync> Sync for Regions<'_, T> {}
+
+impl<T> Clone for Regions<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            ptr: self.ptr,
+            iter: self.iter.clone(),
+            _elems: PhantomData,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Regions<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
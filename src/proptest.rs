@@ -0,0 +1,47 @@
+//! [`proptest`](::proptest) strategies.
+
+use {
+    crate::GrowLock,
+    ::proptest::{
+        collection::{SizeRange, vec},
+        strategy::{Just, Strategy},
+    },
+    std::fmt,
+};
+
+/// Creates a [`Strategy`] generating a [`GrowLock`] with a capacity in
+/// `capacity`, and up to `capacity` elements generated by `element`.
+///
+/// Shrinking reduces the capacity, the length and the elements.
+///
+/// # Examples
+/// ```
+/// use growlock::proptest::grow_lock;
+/// use proptest::{prelude::*, test_runner::TestRunner};
+///
+/// let mut runner = TestRunner::default();
+/// runner
+///     .run(&grow_lock(any::<u8>(), 0..10), |lock| {
+///         prop_assert!(lock.len() <= lock.capacity());
+///         prop_assert!(lock.capacity() < 10);
+///         Ok(())
+///     })
+///     .unwrap();
+/// ```
+pub fn grow_lock<S>(
+    element: S,
+    capacity: impl Into<SizeRange>,
+) -> impl Strategy<Value = GrowLock<S::Value>>
+where
+    S: Strategy + Clone,
+    S::Value: fmt::Debug,
+{
+    let capacity = capacity.into();
+    (capacity.start()..=capacity.end_incl())
+        .prop_flat_map(move |cap| {
+            (Just(cap), vec(element.clone(), 0..=cap))
+        })
+        .prop_map(|(cap, elems)| {
+            GrowLock::from_iter_with_capacity(cap, elems)
+        })
+}
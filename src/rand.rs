@@ -0,0 +1,69 @@
+//! [`rand`](::rand) helpers, to fill a [`GrowLock`] with random values
+//! and shuffle it.
+
+use {
+    crate::{GrowLock, compat::Allocator, guard::GrowGuard},
+    ::rand::{
+        Rng,
+        distr::{Distribution, StandardUniform},
+        seq::SliceRandom,
+    },
+};
+
+impl<T, A: Allocator> GrowGuard<'_, T, A> {
+    /// Appends `n` values sampled from `rng` with the
+    /// [`StandardUniform`] distribution, publishing the new length once.
+    ///
+    /// If sampling panics, the values sampled until then are kept.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than the spare capacity (before sampling
+    /// any value).
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use rand::{SeedableRng, rngs::StdRng};
+    ///
+    /// let lock = GrowLock::<u8>::with_capacity(10);
+    /// let mut rng = StdRng::seed_from_u64(42);
+    /// lock.write().unwrap().extend_random(&mut rng, 4);
+    /// assert_eq!(lock.len(), 4);
+    /// ```
+    pub fn extend_random<R>(&mut self, rng: &mut R, n: usize)
+    where
+        R: Rng + ?Sized,
+        StandardUniform: Distribution<T>,
+    {
+        assert!(n <= self.capacity() - self.len(), "length overflow");
+        let mut iter = rng.sample_iter(StandardUniform).take(n);
+        self.publish();
+        // SAFETY: the write guard is held.
+        unsafe { self.lock.extend_until_full(&mut iter) };
+    }
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Shuffles the elements in place, with the Fisher-Yates algorithm
+    /// (like [`SliceRandom::shuffle`]).
+    ///
+    /// This takes `&mut self` because readers can be reading the elements
+    /// while a write guard is held. The elements are moved (see
+    /// [address stability](GrowLock#address-stability)).
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use rand::{SeedableRng, rngs::StdRng};
+    ///
+    /// let mut lock = GrowLock::from_iter_with_capacity(10, 0..5);
+    /// lock.shuffle(&mut StdRng::seed_from_u64(42));
+    /// assert_eq!(lock.len(), 5);
+    /// assert!((0..5).all(|i| lock.contains(&i)));
+    /// ```
+    #[inline]
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.bump_generation();
+        self.as_mut_slice().shuffle(rng);
+    }
+}
@@ -1,12 +1,16 @@
 //! Inner representation of [`GrowLock`](crate::GrowLock).
 
 use {
-    crate::{cap::Cap, error::TryReserveError},
-    std::{
-        alloc::{Allocator, Global, Layout, handle_alloc_error},
+    crate::{
+        cap::Cap,
+        compat::{Allocator, Global},
+        error::TryReserveError,
+    },
+    alloc::alloc::{Layout, handle_alloc_error},
+    core::{
         marker::PhantomData,
-        mem::SizedTypeProperties as _,
-        ptr::NonNull,
+        mem::ManuallyDrop,
+        ptr::{self, NonNull},
     },
 };
 
@@ -14,7 +18,12 @@ use {
 pub(crate) struct RawGrowLock<T, A: Allocator = Global> {
     ptr: NonNull<T>,
     cap: Cap,
+    /// The capacity the block was allocated with, whose layout is used to
+    /// deallocate it. `cap` is larger if the allocator returned a larger
+    /// block.
+    alloc_cap: Cap,
     alloc: A,
+    /// Tells the drop checker that we own values of type `T`.
     _marker: PhantomData<T>,
 }
 
@@ -30,11 +39,13 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
         cap: Cap,
         alloc: A,
     ) -> Result<Self, TryReserveError> {
-        // `cap` for ZST is zero.
+        // `cap` for ZST is zero. Nothing is allocated, and the pointer is
+        // aligned for `T`, so the empty slices read from it are valid.
         if cap == Cap::ZERO {
             return Ok(Self {
                 ptr: NonNull::dangling(),
                 cap,
+                alloc_cap: cap,
                 alloc,
                 _marker: PhantomData,
             });
@@ -48,10 +59,18 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
             return Err(TryReserveError::AllocError(layout));
         };
         let ptr = block.cast::<T>();
+        // the allocator may return a larger block (e.g. rounded up to a
+        // size class): the slack is used as extra capacity, but the block
+        // is still deallocated with the requested layout.
+        let alloc_cap = cap;
+        let cap = Cap::new::<T>(block.len() / size_of::<T>())
+            .filter(|&rounded| rounded.get() > cap.get())
+            .unwrap_or(cap);
 
         Ok(Self {
             ptr,
             cap,
+            alloc_cap,
             alloc,
             _marker: PhantomData,
         })
@@ -87,9 +106,22 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
         cap: Cap,
         alloc: A,
     ) -> Self {
+        crate::macros::debug_invariant!(
+            ptr.is_aligned(),
+            "the pointer is not aligned to `align_of::<T>()` ({})",
+            align_of::<T>(),
+        );
+        crate::macros::debug_invariant!(
+            cap.get()
+                .checked_mul(size_of::<T>())
+                .is_some_and(|bytes| isize::try_from(bytes).is_ok()),
+            "the capacity {} overflows `isize::MAX` bytes",
+            cap.get(),
+        );
         Self {
-            ptr: ptr.cast(),
+            ptr,
             cap,
+            alloc_cap: cap,
             alloc,
             _marker: PhantomData,
         }
@@ -115,18 +147,17 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
         cap: Cap,
         alloc: A,
     ) -> Self {
-        Self {
-            // SAFETY: the safety contract is transferred to the caller.
-            ptr: unsafe { NonNull::new_unchecked(ptr) },
-            cap,
-            alloc,
-            _marker: PhantomData,
+        // SAFETY: the safety contract is transferred to the caller.
+        unsafe {
+            Self::from_nonnull_in(NonNull::new_unchecked(ptr), cap, alloc)
         }
     }
-    // FIXME should these be taking &mut self?
+    // These take `&self`: the buffer is shared by the readers of the
+    // `GrowLock` and its writer, so they don't grant any access by
+    // themselves. The `GrowLock` decides who can write through them (see
+    // `GrowLock::as_mut_ptr_shared`).
 
     #[inline]
-    #[doc = include_str!("../docs/as_ptr/as_non_null.md")]
     pub(crate) const fn as_non_null(&self) -> NonNull<T> {
         self.ptr
     }
@@ -140,14 +171,14 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
     }
     #[inline]
     pub(crate) const fn capacity(&self) -> usize {
-        if T::IS_ZST {
+        if size_of::<T>() == 0 {
             usize::MAX
         } else {
             self.cap.get()
         }
     }
     #[inline]
-    #[cfg(all(test, not(loom)))]
+    #[cfg(all(test, feature = "std", not(any(loom, shuttle))))]
     pub(crate) const fn raw_cap(&self) -> Cap {
         self.cap
     }
@@ -156,8 +187,36 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
         &self.alloc
     }
 
+    /// Reinterprets the buffer as a buffer of `U`, without reallocating.
+    ///
+    /// # Safety
+    /// `T` and `U` must have the same size and alignment.
+    #[inline]
+    pub(crate) unsafe fn cast<U>(self) -> RawGrowLock<U, A> {
+        let this = ManuallyDrop::new(self);
+        RawGrowLock {
+            ptr: this.ptr.cast(),
+            cap: this.cap,
+            alloc_cap: this.alloc_cap,
+            // SAFETY: `this` is never used (nor dropped) again.
+            alloc: unsafe { ptr::read(&raw const this.alloc) },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Deallocates the buffer. Only called when it's dropped.
+    fn deallocate(&mut self) {
+        if let Some((ptr, layout)) = self.memory_layout() {
+            // SAFETY: we allocated this block of memory with this ptr and
+            // this layout
+            unsafe {
+                self.alloc.deallocate(ptr, layout);
+            }
+        }
+    }
+
     fn memory_layout(&self) -> Option<(NonNull<u8>, Layout)> {
-        if self.cap == Cap::ZERO {
+        if self.alloc_cap == Cap::ZERO {
             None
         } else {
             // SAFETY:
@@ -167,7 +226,8 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
             // * `align` is obtained through align_of so it is a power of
             //   two.
             unsafe {
-                let size = size_of::<T>().unchecked_mul(self.cap.get());
+                let size =
+                    size_of::<T>().unchecked_mul(self.alloc_cap.get());
                 let layout = Layout::from_size_align_unchecked(
                     size,
                     align_of::<T>(),
@@ -178,14 +238,17 @@ impl<T, A: Allocator> RawGrowLock<T, A> {
     }
 }
 
+// SAFETY: the elements are not accessed, only the buffer is deallocated.
+#[cfg(feature = "nightly")]
+unsafe impl<#[may_dangle] T, A: Allocator> Drop for RawGrowLock<T, A> {
+    fn drop(&mut self) {
+        self.deallocate();
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
 impl<T, A: Allocator> Drop for RawGrowLock<T, A> {
     fn drop(&mut self) {
-        if let Some((ptr, layout)) = self.memory_layout() {
-            // SAFETY: we allocated this block of memory with this ptr and
-            // this layout
-            unsafe {
-                self.alloc.deallocate(ptr, layout);
-            }
-        }
+        self.deallocate();
     }
 }
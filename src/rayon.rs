@@ -0,0 +1,268 @@
+//! [`rayon`](::rayon) support.
+
+use {
+    crate::{GrowLock, compat::Allocator, guard::GrowGuard},
+    ::rayon::{
+        iter::{
+            IntoParallelIterator, ParallelExtend, ParallelIterator,
+            plumbing::{Consumer, Folder, Reducer, UnindexedConsumer},
+        },
+        slice, vec,
+    },
+    std::{
+        marker::PhantomData,
+        ptr::{self, NonNull},
+    },
+};
+
+/// Iterates in parallel over a snapshot of the [`GrowLock`].
+///
+/// The length is loaded once, when the parallel iterator is created:
+/// elements pushed after that are not visited.
+///
+/// # Examples
+/// ```
+/// use growlock::grow_lock;
+/// use rayon::prelude::*;
+///
+/// let lock = grow_lock!(100, [1, 2, 3]);
+/// let sum: i32 = lock.par_iter().sum();
+/// assert_eq!(sum, 6);
+/// ```
+impl<'a, T: Sync, A: Allocator> IntoParallelIterator
+    for &'a GrowLock<T, A>
+{
+    type Item = &'a T;
+    type Iter = slice::Iter<'a, T>;
+    #[inline]
+    fn into_par_iter(self) -> Self::Iter {
+        self.as_slice().into_par_iter()
+    }
+}
+/// Moves the elements out of the [`GrowLock`] in parallel.
+impl<T: Send> IntoParallelIterator for GrowLock<T> {
+    type Item = T;
+    type Iter = vec::IntoIter<T>;
+    #[inline]
+    fn into_par_iter(self) -> Self::Iter {
+        Vec::from(self).into_par_iter()
+    }
+}
+
+/// Extends the [`GrowLock`] from a parallel iterator.
+///
+/// If the length of the parallel iterator is known up front (e.g. it's an
+/// [`IndexedParallelIterator`](::rayon::iter::IndexedParallelIterator)),
+/// the elements are written in parallel directly into the spare capacity,
+/// each worker in its own region. Otherwise, they are collected in a
+/// [`Vec`] first, and then moved into the spare capacity.
+///
+/// In both cases the length is published once, at the end: if a worker
+/// panics, the elements already written are dropped and the [`GrowLock`]
+/// is left unchanged.
+///
+/// # Panics
+/// Panics if the iterator has more elements than the spare capacity
+/// (before publishing anything).
+///
+/// # Examples
+/// ```
+/// use growlock::GrowLock;
+/// use rayon::prelude::*;
+///
+/// let lock = GrowLock::with_capacity(1000);
+/// lock.write().unwrap().par_extend((0..1000).into_par_iter().map(|x| x * 2));
+/// assert!(lock.iter().copied().eq((0..1000).map(|x| x * 2)));
+/// ```
+impl<T: Send, A: Allocator> ParallelExtend<T> for GrowGuard<'_, T, A> {
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let par_iter = par_iter.into_par_iter();
+        let len = self.len();
+        let spare = self.capacity() - len;
+        // SAFETY: `len <= capacity`.
+        let dst = unsafe { self.lock.as_non_null_shared().add(len) };
+
+        let count = if let Some(count) = par_iter.opt_len() {
+            assert!(count <= spare, "length overflow");
+            // SAFETY: the first `count` slots of the spare capacity are
+            // not reachable by readers, and the write guard is held.
+            let consumer = unsafe { CollectConsumer::new(dst, count) };
+            let result = par_iter.drive_unindexed(consumer);
+            let written = result.len();
+            assert!(
+                written == count,
+                "expected {count} total writes, but got {written}"
+            );
+            result.release_ownership()
+        } else {
+            let mut vec: Vec<T> = par_iter.collect();
+            let count = vec.len();
+            assert!(count <= spare, "length overflow");
+            // SAFETY:
+            // * `len + count <= capacity`.
+            // * the elements are moved out of `vec`, which forgets them.
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    vec.as_ptr(),
+                    dst.as_ptr(),
+                    count,
+                );
+                vec.set_len(0);
+            }
+            count
+        };
+        // SAFETY: the new elements are initialized, and the write guard is
+        // held.
+        unsafe { self.lock.publish_len(len + count) };
+    }
+}
+
+/// [`NonNull`] which can be sent to the worker threads.
+struct SendPtr<T>(NonNull<T>);
+// SAFETY: it's only used to move `T`s to other threads.
+unsafe impl<T: Send> Send for SendPtr<T> {}
+// SAFETY: each thread writes to its own region.
+unsafe impl<T: Send> Sync for SendPtr<T> {}
+impl<T> Clone for SendPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for SendPtr<T> {}
+
+/// Writes the elements in an uninitialized region, splitting it between
+/// the workers.
+struct CollectConsumer<'c, T> {
+    start: SendPtr<T>,
+    len: usize,
+    _marker: PhantomData<&'c mut T>,
+}
+impl<T> CollectConsumer<'_, T> {
+    /// # Safety
+    /// `start..start + len` must be valid for writes, and not reachable
+    /// by anyone else.
+    const unsafe fn new(start: NonNull<T>, len: usize) -> Self {
+        Self {
+            start: SendPtr(start),
+            len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// The initialized part of the region of a [`CollectConsumer`], which is
+/// dropped unless its ownership is released.
+#[must_use]
+struct CollectResult<'c, T> {
+    start: SendPtr<T>,
+    total_len: usize,
+    initialized_len: usize,
+    _marker: PhantomData<&'c mut [T]>,
+}
+impl<T> CollectResult<'_, T> {
+    const fn len(&self) -> usize {
+        self.initialized_len
+    }
+    /// Releases the ownership of the elements, returning their number.
+    fn release_ownership(mut self) -> usize {
+        let len = self.initialized_len;
+        self.initialized_len = 0;
+        len
+    }
+}
+impl<T> Drop for CollectResult<'_, T> {
+    fn drop(&mut self) {
+        // SAFETY: the first `initialized_len` elements are initialized
+        // and owned by this result.
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.start.0.as_ptr(),
+                self.initialized_len,
+            ));
+        }
+    }
+}
+
+impl<'c, T: Send + 'c> Consumer<T> for CollectConsumer<'c, T> {
+    type Folder = CollectResult<'c, T>;
+    type Reducer = CollectReducer;
+    type Result = CollectResult<'c, T>;
+
+    fn split_at(self, index: usize) -> (Self, Self, CollectReducer) {
+        assert!(index <= self.len);
+        // SAFETY: the two regions are disjoint and inside the region of
+        // `self`.
+        unsafe {
+            (
+                Self::new(self.start.0, index),
+                Self::new(self.start.0.add(index), self.len - index),
+                CollectReducer,
+            )
+        }
+    }
+    fn into_folder(self) -> Self::Folder {
+        CollectResult {
+            start: self.start,
+            total_len: self.len,
+            initialized_len: 0,
+            _marker: PhantomData,
+        }
+    }
+    fn full(&self) -> bool {
+        false
+    }
+}
+impl<'c, T: Send + 'c> UnindexedConsumer<T> for CollectConsumer<'c, T> {
+    /// An iterator with a known length is always split by index.
+    fn split_off_left(&self) -> Self {
+        unreachable!("`CollectConsumer` must be indexed")
+    }
+    fn to_reducer(&self) -> Self::Reducer {
+        CollectReducer
+    }
+}
+
+impl<'c, T: Send + 'c> Folder<T> for CollectResult<'c, T> {
+    type Result = Self;
+
+    fn consume(mut self, item: T) -> Self {
+        assert!(
+            self.initialized_len < self.total_len,
+            "too many values pushed to consumer"
+        );
+        // SAFETY: the slot is inside the region, and uninitialized.
+        unsafe { self.start.0.add(self.initialized_len).write(item) };
+        self.initialized_len += 1;
+        self
+    }
+    fn complete(self) -> Self::Result {
+        self
+    }
+    fn full(&self) -> bool {
+        false
+    }
+}
+
+/// Merges adjacent [`CollectResult`]s.
+struct CollectReducer;
+impl<'c, T> Reducer<CollectResult<'c, T>> for CollectReducer {
+    fn reduce(
+        self,
+        mut left: CollectResult<'c, T>,
+        right: CollectResult<'c, T>,
+    ) -> CollectResult<'c, T> {
+        // If they are not adjacent, `right` is dropped, and the final
+        // length is checked to be short.
+        // SAFETY: `initialized_len <= total_len`, so the pointer is in
+        // the region (or one past its end).
+        let left_end = unsafe { left.start.0.add(left.initialized_len) };
+        if left_end.addr() == right.start.0.addr() {
+            left.total_len += right.total_len;
+            left.initialized_len += right.release_ownership();
+        }
+        left
+    }
+}
@@ -0,0 +1,110 @@
+//! [`io::Read`] cursor over a [`GrowLock<u8>`].
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+    },
+    std::{
+        fmt,
+        io::{self, BufRead, Read},
+    },
+};
+
+/// A cursor over the bytes of a [`GrowLock<u8>`], implementing [`Read`]
+/// and [`BufRead`].
+///
+/// This structure is created by the [`reader`](GrowLock::reader) method
+/// on [`GrowLock`]. It only needs a shared reference, so many readers can
+/// coexist with each other and with a writer.
+///
+/// Each read sees the bytes published until then. When the cursor reaches
+/// the current end, reads return `Ok(0)`, but later reads can return the
+/// bytes pushed in the meantime.
+pub struct GrowLockReader<'a, A: Allocator = Global> {
+    lock: &'a GrowLock<u8, A>,
+    pos: usize,
+}
+
+impl<A: Allocator> GrowLock<u8, A> {
+    /// Returns a [`GrowLockReader`] starting from the first byte.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::io::Read;
+    ///
+    /// let lock = GrowLock::from_str_with_capacity("hello", 11);
+    /// let mut reader = lock.reader();
+    /// let mut buf = [0; 3];
+    /// assert_eq!(reader.read(&mut buf).unwrap(), 3);
+    /// assert_eq!(&buf, b"hel");
+    ///
+    /// lock.write().unwrap().extend(*b" world");
+    /// let mut rest = String::new();
+    /// reader.read_to_string(&mut rest).unwrap();
+    /// assert_eq!(rest, "lo world");
+    /// ```
+    #[inline]
+    #[must_use]
+    pub const fn reader(&self) -> GrowLockReader<'_, A> {
+        GrowLockReader { lock: self, pos: 0 }
+    }
+}
+
+impl<'a, A: Allocator> GrowLockReader<'a, A> {
+    /// Returns the number of bytes read so far.
+    #[inline]
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+    /// Returns the [`GrowLock`] this cursor reads from.
+    #[inline]
+    #[must_use]
+    pub const fn get_ref(&self) -> &'a GrowLock<u8, A> {
+        self.lock
+    }
+    /// Returns the bytes published but not read yet.
+    #[inline]
+    #[must_use]
+    pub fn remaining_slice(&self) -> &'a [u8] {
+        &self.lock.as_slice()[self.pos..]
+    }
+}
+
+impl<A: Allocator> Read for GrowLockReader<'_, A> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = Read::read(&mut self.remaining_slice(), buf)?;
+        self.pos += count;
+        Ok(count)
+    }
+}
+impl<A: Allocator> BufRead for GrowLockReader<'_, A> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining_slice())
+    }
+    #[inline]
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.lock.len());
+    }
+}
+
+impl<A: Allocator> Clone for GrowLockReader<'_, A> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            lock: self.lock,
+            pos: self.pos,
+        }
+    }
+}
+impl<A: Allocator> fmt::Debug for GrowLockReader<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrowLockReader")
+            .field("pos", &self.pos)
+            .field("len", &self.lock.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,184 @@
+//! [`rkyv`](::rkyv) support.
+//!
+//! A [`GrowLock<T>`] is archived as an [`ArchivedVec`] of the elements
+//! pushed when it is serialized, and it's deserialized as a full
+//! [`GrowLock`]. To leave some spare capacity, archive the field with
+//! [`Slack`].
+
+use {
+    crate::{GrowLock, compat::Allocator, error::TryReserveError},
+    ::rkyv::{
+        Archive, Deserialize, Place, Serialize,
+        rancor::{Fallible, Source},
+        ser::{Allocator as SerAllocator, Writer},
+        vec::{ArchivedVec, VecResolver},
+        with::{ArchiveWith, DeserializeWith, SerializeWith},
+    },
+};
+
+/// The resolver of an archived [`GrowLock`].
+///
+/// It keeps the length of the serialized elements, as more elements
+/// could be pushed before the [`GrowLock`] is resolved.
+pub struct GrowLockResolver {
+    vec: VecResolver,
+    len: usize,
+}
+
+impl<T: Archive, A: Allocator> Archive for GrowLock<T, A> {
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = GrowLockResolver;
+    #[inline]
+    fn resolve(
+        &self,
+        resolver: Self::Resolver,
+        out: Place<Self::Archived>,
+    ) {
+        ArchivedVec::resolve_from_len(resolver.len, resolver.vec, out);
+    }
+}
+
+impl<T, A, S> Serialize<S> for GrowLock<T, A>
+where
+    T: Serialize<S>,
+    A: Allocator,
+    S: Fallible + SerAllocator + Writer + ?Sized,
+{
+    /// Serializes the elements pushed until now.
+    fn serialize(
+        &self,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        // the length is loaded once, by `as_slice`.
+        let elems = self.as_slice();
+        Ok(GrowLockResolver {
+            vec: ArchivedVec::serialize_from_slice(elems, serializer)?,
+            len: elems.len(),
+        })
+    }
+}
+
+/// Deserializes the elements of `archived` into a new [`GrowLock`], with
+/// `slack` spare capacity.
+fn deserialize_with_slack<T, D>(
+    archived: &ArchivedVec<T::Archived>,
+    slack: usize,
+    deserializer: &mut D,
+) -> Result<GrowLock<T>, D::Error>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    let capacity = archived
+        .len()
+        .checked_add(slack)
+        .ok_or(TryReserveError::CapacityOverflow)
+        .map_err(D::Error::new)?;
+    let lock =
+        GrowLock::try_with_capacity(capacity).map_err(D::Error::new)?;
+    let mut error = None;
+    let mut iter = archived.iter().map_while(|elem| {
+        elem.deserialize(deserializer)
+            .map_err(|e| error = Some(e))
+            .ok()
+    });
+    // SAFETY: `lock` is not shared yet.
+    unsafe { lock.extend_until_full(&mut iter) };
+    match error {
+        Some(e) => Err(e),
+        None => Ok(lock),
+    }
+}
+
+impl<T, D> Deserialize<GrowLock<T>, D> for ArchivedVec<T::Archived>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    /// Deserializes a full [`GrowLock`].
+    ///
+    /// See [`Slack`] to leave some spare capacity.
+    #[inline]
+    fn deserialize(
+        &self,
+        deserializer: &mut D,
+    ) -> Result<GrowLock<T>, D::Error> {
+        deserialize_with_slack(self, 0, deserializer)
+    }
+}
+
+/// Wrapper which archives a [`GrowLock`] like the default implementation,
+/// but deserializes it with `N` spare capacity.
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, grow_lock, rkyv::Slack};
+/// use rkyv::{Archive, Deserialize, Serialize, rancor::Error};
+///
+/// #[derive(Archive, Serialize, Deserialize)]
+/// struct Log {
+///     #[rkyv(with = Slack<8>)]
+///     entries: GrowLock<u32>,
+/// }
+///
+/// let log = Log { entries: grow_lock!(4, [1, 2]) };
+/// let bytes = rkyv::to_bytes::<Error>(&log).unwrap();
+/// let log = rkyv::from_bytes::<Log, Error>(&bytes).unwrap();
+/// assert_eq!(log.entries, [1, 2]);
+/// assert_eq!(log.entries.capacity(), 10);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Slack<const N: usize>;
+
+impl<T, A, const N: usize> ArchiveWith<GrowLock<T, A>> for Slack<N>
+where
+    T: Archive,
+    A: Allocator,
+{
+    type Archived = ArchivedVec<T::Archived>;
+    type Resolver = GrowLockResolver;
+    #[inline]
+    fn resolve_with(
+        field: &GrowLock<T, A>,
+        resolver: Self::Resolver,
+        out: Place<Self::Archived>,
+    ) {
+        field.resolve(resolver, out);
+    }
+}
+
+impl<T, A, S, const N: usize> SerializeWith<GrowLock<T, A>, S> for Slack<N>
+where
+    T: Serialize<S>,
+    A: Allocator,
+    S: Fallible + SerAllocator + Writer + ?Sized,
+{
+    #[inline]
+    fn serialize_with(
+        field: &GrowLock<T, A>,
+        serializer: &mut S,
+    ) -> Result<Self::Resolver, S::Error> {
+        field.serialize(serializer)
+    }
+}
+
+impl<T, D, const N: usize>
+    DeserializeWith<ArchivedVec<T::Archived>, GrowLock<T>, D> for Slack<N>
+where
+    T: Archive,
+    T::Archived: Deserialize<T, D>,
+    D: Fallible + ?Sized,
+    D::Error: Source,
+{
+    #[inline]
+    fn deserialize_with(
+        field: &ArchivedVec<T::Archived>,
+        deserializer: &mut D,
+    ) -> Result<GrowLock<T>, D::Error> {
+        deserialize_with_slack(field, N, deserializer)
+    }
+}
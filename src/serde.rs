@@ -0,0 +1,223 @@
+//! [`serde`](::serde) support.
+//!
+//! [`GrowLock`] implements [`Serialize`] (as a sequence of the elements
+//! pushed so far) and [`Deserialize`] (with the capacity equal to the
+//! length of the input).
+//!
+//! To keep spare capacity, or to bound the size of untrusted input, use
+//! the [`DeserializeSeed`]s of this module:
+//! * [`AppendTo`] appends the elements to an existing [`GrowLock`].
+//! * [`WithCapacity`] creates a new [`GrowLock`] with a fixed capacity.
+//!
+//! Both of them fail (instead of panicking) if the input has more elements
+//! than the capacity allows.
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+    },
+    ::serde::{
+        Deserialize, Deserializer, Serialize, Serializer,
+        de::{self, DeserializeSeed, IgnoredAny, SeqAccess, Visitor},
+    },
+    alloc::boxed::Box,
+    core::{
+        fmt,
+        marker::PhantomData,
+        ptr::{self, NonNull},
+    },
+};
+
+impl<T: Serialize, A: Allocator> Serialize for GrowLock<T, A> {
+    /// Serializes the elements pushed until now.
+    #[inline]
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.as_slice())
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for GrowLock<T> {
+    /// Deserializes a full [`GrowLock`].
+    ///
+    /// See [`WithCapacity`] to leave some spare capacity.
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        Box::<[T]>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// [`DeserializeSeed`] which appends a sequence to an existing
+/// [`GrowLock`], under a single write guard.
+///
+/// The length is published once, after the whole sequence is deserialized.
+/// If the sequence has more elements than the spare capacity (or if any
+/// other error happens), an error is returned and the [`GrowLock`] is left
+/// unchanged.
+///
+/// # Examples
+/// ```
+/// use growlock::{grow_lock, serde::AppendTo};
+/// use serde::de::DeserializeSeed;
+///
+/// let lock = grow_lock!(4, [1, 2]);
+/// let mut de = serde_json::Deserializer::from_str("[3, 4]");
+/// AppendTo(&lock).deserialize(&mut de).unwrap();
+/// assert_eq!(lock, [1, 2, 3, 4]);
+///
+/// let mut de = serde_json::Deserializer::from_str("[5]");
+/// assert!(AppendTo(&lock).deserialize(&mut de).is_err());
+/// assert_eq!(lock, [1, 2, 3, 4]);
+/// ```
+#[derive(Debug)]
+pub struct AppendTo<'a, T, A: Allocator = Global>(pub &'a GrowLock<T, A>);
+
+impl<'de, T, A> DeserializeSeed<'de> for AppendTo<'_, T, A>
+where
+    T: Deserialize<'de>,
+    A: Allocator,
+{
+    type Value = ();
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T, A> Visitor<'de> for AppendTo<'_, T, A>
+where
+    T: Deserialize<'de>,
+    A: Allocator,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let spare = self.0.capacity() - self.0.len();
+        write!(f, "a sequence of at most {spare} elements")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(
+        self,
+        mut seq: S,
+    ) -> Result<Self::Value, S::Error> {
+        /// Drops the elements written after the length if the
+        /// deserialization fails.
+        struct Pending<T> {
+            dst: NonNull<T>,
+            written: usize,
+        }
+        impl<T> Drop for Pending<T> {
+            fn drop(&mut self) {
+                // SAFETY: the first `written` elements are initialized,
+                // and they are not reachable from the `GrowLock`.
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                        self.dst.as_ptr(),
+                        self.written,
+                    ));
+                }
+            }
+        }
+
+        let lock = self.0;
+        let _guard = lock.write().map_err(|_| {
+            de::Error::custom("the `GrowLock` is poisoned")
+        })?;
+        let len = lock.len();
+        let spare = lock.capacity() - len;
+        if let Some(hint) = seq.size_hint()
+            && hint > spare
+        {
+            return Err(de::Error::invalid_length(hint, &self));
+        }
+
+        let mut pending = Pending {
+            // SAFETY: `len <= capacity`.
+            dst: unsafe { lock.as_non_null_shared().add(len) },
+            written: 0,
+        };
+        while pending.written < spare {
+            let Some(value) = seq.next_element()? else {
+                break;
+            };
+            // SAFETY:
+            // * `len + written < capacity`.
+            // * the write guard is held, so no one else writes here.
+            unsafe { pending.dst.add(pending.written).write(value) };
+            pending.written += 1;
+        }
+        if pending.written == spare
+            && seq.next_element::<IgnoredAny>()?.is_some()
+        {
+            return Err(de::Error::invalid_length(spare + 1, &self));
+        }
+
+        // SAFETY: the new elements are initialized, and the write guard is
+        // held.
+        unsafe { lock.publish_len(len + pending.written) };
+        // the elements are now owned by the `GrowLock`.
+        pending.written = 0;
+        Ok(())
+    }
+}
+
+/// [`DeserializeSeed`] which creates a new [`GrowLock<T>`] with the given
+/// capacity, failing if the input has more elements than the capacity.
+///
+/// The capacity is allocated before reading the input.
+///
+/// # Examples
+/// ```
+/// use growlock::serde::WithCapacity;
+/// use serde::de::DeserializeSeed;
+///
+/// let mut de = serde_json::Deserializer::from_str("[1, 2]");
+/// let lock = WithCapacity::<u32>::new(10).deserialize(&mut de).unwrap();
+/// assert_eq!(lock, [1, 2]);
+/// assert_eq!(lock.capacity(), 10);
+///
+/// let mut de = serde_json::Deserializer::from_str("[1, 2, 3]");
+/// assert!(WithCapacity::<u32>::new(2).deserialize(&mut de).is_err());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct WithCapacity<T> {
+    capacity: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> WithCapacity<T> {
+    #[inline]
+    #[must_use]
+    pub const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            _marker: PhantomData,
+        }
+    }
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for WithCapacity<T> {
+    type Value = GrowLock<T>;
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        let lock = GrowLock::try_with_capacity(self.capacity)
+            .map_err(de::Error::custom)?;
+        AppendTo(&lock).deserialize(deserializer)?;
+        Ok(lock)
+    }
+}
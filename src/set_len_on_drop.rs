@@ -0,0 +1,51 @@
+//! Helper to publish the length of a [`GrowLock`](crate::GrowLock) once
+//! after a bulk write.
+
+use crate::{
+    notify::Notifier,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Keeps a local copy of the length while elements are being written, and
+/// stores it back (with [`Ordering::Release`]) when dropped, waking up the
+/// waiting readers.
+///
+/// If a panic happens mid-write (e.g. in [`Clone::clone`]), the length of
+/// the elements written until then is still published, so they are
+/// correctly dropped by the [`GrowLock`](crate::GrowLock).
+pub(crate) struct SetLenOnDrop<'a> {
+    len: &'a AtomicUsize,
+    notifier: &'a Notifier,
+    local_len: usize,
+}
+
+impl<'a> SetLenOnDrop<'a> {
+    /// The caller must have exclusive write access to `len`.
+    #[inline]
+    pub(crate) fn new(
+        len: &'a AtomicUsize,
+        notifier: &'a Notifier,
+    ) -> Self {
+        Self {
+            local_len: len.load(Ordering::Relaxed),
+            len,
+            notifier,
+        }
+    }
+    #[inline]
+    pub(crate) const fn current_len(&self) -> usize {
+        self.local_len
+    }
+    #[inline]
+    pub(crate) const fn increment_len(&mut self, increment: usize) {
+        self.local_len += increment;
+    }
+}
+
+impl Drop for SetLenOnDrop<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        self.len.store(self.local_len, Ordering::Release);
+        self.notifier.notify(self.local_len);
+    }
+}
@@ -0,0 +1,60 @@
+//! Interoperability with [`SmallVec`].
+
+use {
+    crate::{GrowLock, compat::Allocator, guard::GrowGuard},
+    ::smallvec::{Array, SmallVec},
+    core::ptr,
+};
+
+impl<T, B: Array<Item = T>> From<SmallVec<B>> for GrowLock<T> {
+    /// Takes over the heap allocation of the [`SmallVec`] if it has
+    /// spilled, otherwise the elements are copied in a new allocation.
+    #[inline]
+    fn from(value: SmallVec<B>) -> Self {
+        value.into_vec().into()
+    }
+}
+
+impl<T, A: Allocator> GrowGuard<'_, T, A> {
+    /// Moves all the elements of `other` to the end of the [`GrowLock`],
+    /// leaving `other` empty.
+    ///
+    /// The elements are moved with a single copy, and the length is
+    /// published once.
+    ///
+    /// # Panics
+    /// Panics if `other.len()` is greater than the spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    /// use smallvec::{SmallVec, smallvec};
+    ///
+    /// let lock = grow_lock!(10, [1, 2]);
+    /// let mut batch: SmallVec<[i32; 4]> = smallvec![3, 4, 5];
+    /// lock.write().unwrap().append_smallvec(&mut batch);
+    /// assert_eq!(lock, [1, 2, 3, 4, 5]);
+    /// assert!(batch.is_empty());
+    /// ```
+    pub fn append_smallvec<B>(&mut self, other: &mut SmallVec<B>)
+    where
+        B: Array<Item = T>,
+    {
+        let len = self.len();
+        let count = other.len();
+        assert!(count <= self.capacity() - len, "length overflow");
+
+        // SAFETY:
+        // * there is enough spare capacity for `count` elements.
+        // * the elements are moved out of `other`, which forgets them with
+        //   `set_len(0)`.
+        unsafe {
+            let dst = self.lock.as_non_null_shared().add(len);
+            ptr::copy_nonoverlapping(other.as_ptr(), dst.as_ptr(), count);
+            other.set_len(0);
+        }
+        // SAFETY: the new elements are initialized, and the write guard is
+        // held.
+        unsafe { self.lock.publish_len(len + count) };
+    }
+}
@@ -0,0 +1,102 @@
+//! Consistent views over the elements of a [`GrowLock`].
+
+use {
+    crate::{GrowLock, compat::Allocator},
+    core::{fmt, ops, slice},
+};
+
+/// A view over the elements pushed into a [`GrowLock`] when the view was
+/// taken.
+///
+/// This structure is created by the [`snapshot`](GrowLock::snapshot)
+/// method on [`GrowLock`]. The length is loaded only once, so every read
+/// through the snapshot sees the same elements, even if a writer is
+/// pushing in the meantime. It doesn't lock anything.
+///
+/// Since it borrows the [`GrowLock`], the elements can't be removed or
+/// moved while it's alive (see [`generation`](GrowLock::generation)).
+#[derive(Clone, Copy)]
+pub struct Snapshot<'a, T> {
+    slice: &'a [T],
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Returns a [`Snapshot`] of the elements pushed so far.
+    ///
+    /// Unlike reading through [`Deref`](ops::Deref), which loads the
+    /// length on every access, all the reads through the snapshot are
+    /// consistent with each other.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::grow_lock;
+    ///
+    /// let lock = grow_lock!(5, [1, 2, 3]);
+    /// let snapshot = lock.snapshot();
+    /// lock.write().unwrap().push(4);
+    ///
+    /// // the element pushed after the snapshot is not visible
+    /// assert_eq!(snapshot.len(), 3);
+    /// assert_eq!(snapshot[snapshot.len() - 1], 3);
+    /// assert_eq!(lock.len(), 4);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot<'_, T> {
+        Snapshot {
+            slice: self.as_slice(),
+        }
+    }
+}
+
+impl<'a, T> Snapshot<'a, T> {
+    /// Returns the number of elements in the snapshot.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.slice.len()
+    }
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.slice.is_empty()
+    }
+    /// Returns the elements in the snapshot, with the lifetime of the
+    /// [`GrowLock`].
+    #[inline]
+    #[must_use]
+    pub const fn as_slice(&self) -> &'a [T] {
+        self.slice
+    }
+}
+
+impl<T> ops::Deref for Snapshot<'_, T> {
+    type Target = [T];
+    #[inline]
+    fn deref(&self) -> &[T] {
+        self.slice
+    }
+}
+
+impl<'a, T> IntoIterator for Snapshot<'a, T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.slice.iter()
+    }
+}
+impl<'a, T> IntoIterator for &Snapshot<'a, T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.slice.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Snapshot<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.slice, f)
+    }
+}
@@ -0,0 +1,345 @@
+//! An append-only UTF-8 string, shared like a [`GrowLock`].
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        error::{FromUtf8Error, LengthError},
+        guard::GrowGuard,
+        sync::{LockResult, PoisonError, TryLockError, TryLockResult},
+    },
+    alloc::{string::String, vec::Vec},
+    core::{fmt, ops, str},
+};
+
+/// A [`GrowLock<u8>`](GrowLock) which always holds valid UTF-8: an
+/// append-only [`String`] that can have simultaneously any number of
+/// readers **and** one writer.
+///
+/// The strings are pushed through the [`GrowStringGuard`] returned by
+/// [`write`](GrowString::write), and the readers see them (through
+/// [`Deref<Target = str>`](ops::Deref)) once the new length is published.
+///
+/// # UTF-8
+/// A string (or a character) is pushed only if all of its bytes fit in
+/// the spare capacity, and its bytes are published at once: the readers
+/// never see a part of a multi-byte character, not even when the
+/// [`GrowString`] is almost full.
+///
+/// # Examples
+/// ```
+/// use growlock::string::GrowString;
+/// use std::{fmt::Write, thread};
+///
+/// let log = GrowString::with_capacity(64);
+/// thread::scope(|s| {
+///     s.spawn(|| {
+///         let mut guard = log.write().unwrap();
+///         writeln!(guard, "{} + {} = {}", 1, 2, 1 + 2).unwrap();
+///         guard.push('✓').unwrap();
+///     });
+///     // `&*log` is always a whole number of characters.
+///     let _: &str = &log;
+/// });
+/// assert_eq!(&*log, "1 + 2 = 3\n✓");
+/// assert_eq!(log.len(), 13);
+/// ```
+pub struct GrowString<A: Allocator = Global> {
+    /// Only whole UTF-8 sequences are published.
+    bytes: GrowLock<u8, A>,
+}
+
+impl GrowString {
+    /// Creates a new empty [`GrowString`] with room for `capacity` bytes.
+    ///
+    /// # Panics
+    /// Panics if `capacity` overflows [`isize::MAX`].
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::string::GrowString;
+    ///
+    /// let s = GrowString::with_capacity(16);
+    /// assert!(s.is_empty());
+    /// assert_eq!(s.capacity(), 16);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<A: Allocator> GrowString<A> {
+    /// Creates a new empty [`GrowString`] with room for `capacity` bytes
+    /// in the provided allocator.
+    ///
+    /// # Panics
+    /// Panics if `capacity` overflows [`isize::MAX`].
+    #[inline]
+    #[must_use]
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        Self {
+            bytes: GrowLock::with_capacity_in(capacity, alloc),
+        }
+    }
+    /// Returns the string pushed so far.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only whole UTF-8 sequences are published.
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+    /// Returns the length of the string, in bytes.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+    /// Returns the capacity, in bytes.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+    /// Locks this [`GrowString`] with exclusive write access, blocking the
+    /// current thread until it can be acquired.
+    ///
+    /// Readers are never blocked by the returned guard.
+    ///
+    /// # Errors
+    /// Returns an error if the lock is poisoned (another writer panicked
+    /// while holding the lock). The guard is still returned inside the
+    /// error, and the string is still valid UTF-8.
+    #[inline]
+    pub fn write(&self) -> LockResult<GrowStringGuard<'_, A>> {
+        self.bytes.write().map(GrowStringGuard::new).map_err(|e| {
+            PoisonError::new(GrowStringGuard::new(e.into_inner()))
+        })
+    }
+    /// Attempts to lock this [`GrowString`] with exclusive write access.
+    ///
+    /// This function does not block.
+    ///
+    /// # Errors
+    /// * [`TryLockError::WouldBlock`] if the lock is already held.
+    /// * [`TryLockError::Poisoned`] if the lock is poisoned. The guard is
+    ///   still returned inside the error.
+    #[inline]
+    pub fn try_write(&self) -> TryLockResult<GrowStringGuard<'_, A>> {
+        match self.bytes.try_write() {
+            Ok(guard) => Ok(GrowStringGuard::new(guard)),
+            Err(TryLockError::Poisoned(e)) => Err(TryLockError::Poisoned(
+                PoisonError::new(GrowStringGuard::new(e.into_inner())),
+            )),
+            Err(TryLockError::WouldBlock) => Err(TryLockError::WouldBlock),
+        }
+    }
+    /// Converts the [`GrowString`] into its bytes, without copying.
+    #[inline]
+    #[must_use]
+    pub fn into_bytes(self) -> GrowLock<u8, A> {
+        self.bytes
+    }
+}
+
+impl<A: Allocator> ops::Deref for GrowString<A> {
+    type Target = str;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+impl<A: Allocator> AsRef<str> for GrowString<A> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+impl<A: Allocator> fmt::Debug for GrowString<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+impl<A: Allocator> fmt::Display for GrowString<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+impl<A: Allocator> PartialEq<str> for GrowString<A> {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+impl<A: Allocator> PartialEq<&str> for GrowString<A> {
+    #[inline]
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl From<String> for GrowString {
+    /// Takes over the allocation of the [`String`], without copying.
+    #[inline]
+    fn from(value: String) -> Self {
+        Self {
+            bytes: value.into(),
+        }
+    }
+}
+impl From<&str> for GrowString {
+    /// Copies the string in a full [`GrowString`].
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self {
+            bytes: value.into(),
+        }
+    }
+}
+impl From<GrowString> for String {
+    /// Converts the [`GrowString`] to a [`String`], reusing its
+    /// allocation.
+    #[inline]
+    fn from(value: GrowString) -> Self {
+        let vec = Vec::from(value.bytes);
+        // SAFETY: only whole UTF-8 sequences are published.
+        unsafe { Self::from_utf8_unchecked(vec) }
+    }
+}
+impl<A: Allocator> From<GrowString<A>> for GrowLock<u8, A> {
+    /// See [`GrowString::into_bytes`].
+    #[inline]
+    fn from(value: GrowString<A>) -> Self {
+        value.into_bytes()
+    }
+}
+impl TryFrom<GrowLock<u8>> for GrowString {
+    type Error = FromUtf8Error;
+    /// Converts the [`GrowLock`] to a [`GrowString`], reusing its
+    /// allocation (and its spare capacity).
+    ///
+    /// # Errors
+    /// Returns an error (containing the [`GrowLock`]) if the bytes are
+    /// not valid UTF-8.
+    fn try_from(value: GrowLock<u8>) -> Result<Self, Self::Error> {
+        match str::from_utf8(&value) {
+            Ok(_) => Ok(Self { bytes: value }),
+            Err(error) => Err(FromUtf8Error { lock: value, error }),
+        }
+    }
+}
+
+/// RAII structure used to release the exclusive write access of a
+/// [`GrowString`] when dropped.
+///
+/// This structure is created by the [`write`](GrowString::write) and
+/// [`try_write`](GrowString::try_write) methods on [`GrowString`].
+///
+/// Its [`fmt::Write`] implementation pushes each piece of the formatted
+/// string with [`push_str`](GrowStringGuard::push_str): if the
+/// [`GrowString`] gets full, the pieces pushed until then are kept.
+pub struct GrowStringGuard<'lock, A: Allocator = Global> {
+    guard: GrowGuard<'lock, u8, A>,
+}
+
+impl<'lock, A: Allocator> GrowStringGuard<'lock, A> {
+    #[inline]
+    const fn new(guard: GrowGuard<'lock, u8, A>) -> Self {
+        Self { guard }
+    }
+    /// Returns the string, including what this guard pushed.
+    #[inline]
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // SAFETY: only whole UTF-8 sequences are written.
+        unsafe { str::from_utf8_unchecked(&self.guard) }
+    }
+    /// Returns the length of the string, in bytes.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.guard.len()
+    }
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.guard.is_empty()
+    }
+    /// Returns the capacity, in bytes.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.guard.capacity()
+    }
+    /// Appends `s`, only if all of its bytes fit in the spare capacity.
+    ///
+    /// # Errors
+    /// Returns an error, without pushing anything, if `s.len()` is
+    /// greater than the spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::string::GrowString;
+    ///
+    /// let s = GrowString::with_capacity(4);
+    /// let mut guard = s.write().unwrap();
+    /// guard.push_str("ab").unwrap();
+    /// assert!(guard.push_str("cde").is_err());
+    /// assert_eq!(guard.as_str(), "ab");
+    /// ```
+    #[inline]
+    pub fn push_str(&mut self, s: &str) -> Result<(), LengthError> {
+        self.guard.push_str(s)
+    }
+    /// Appends `ch`, only if all of its bytes fit in the spare capacity.
+    ///
+    /// # Errors
+    /// Returns an error, without pushing anything, if
+    /// [`ch.len_utf8()`](char::len_utf8) is greater than the spare
+    /// capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::string::GrowString;
+    ///
+    /// let s = GrowString::with_capacity(4);
+    /// let mut guard = s.write().unwrap();
+    /// guard.push('a').unwrap();
+    /// assert!(guard.push('🦀').is_err());
+    /// guard.push('é').unwrap();
+    /// assert_eq!(guard.as_str(), "aé");
+    /// ```
+    #[inline]
+    pub fn push(&mut self, ch: char) -> Result<(), LengthError> {
+        self.push_str(ch.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<A: Allocator> ops::Deref for GrowStringGuard<'_, A> {
+    type Target = str;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+impl<A: Allocator> fmt::Write for GrowStringGuard<'_, A> {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s).map_err(|_| fmt::Error)
+    }
+    #[inline]
+    fn write_char(&mut self, c: char) -> fmt::Result {
+        self.push(c).map_err(|_| fmt::Error)
+    }
+}
+impl<A: Allocator> fmt::Debug for GrowStringGuard<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
@@ -0,0 +1,394 @@
+//! The poisoning types of the write locks.
+//!
+//! With the `std` feature (enabled by default), these are the ones of
+//! [`std::sync`]. Without it, the write locks are spinlocks, and these
+//! are equivalent types: as the panics can't be detected without `std`,
+//! the write locks are never poisoned.
+
+#[cfg(not(any(loom, shuttle)))]
+pub(crate) use core::hint;
+#[cfg(not(any(feature = "portable-atomic", loom, shuttle)))]
+pub(crate) use core::sync::atomic;
+#[cfg(feature = "std")]
+pub use std::sync::{
+    LockResult, PoisonError, TryLockError, TryLockResult,
+};
+#[cfg(all(feature = "std", not(any(loom, shuttle))))]
+pub(crate) use std::{
+    sync::{Condvar, Mutex, MutexGuard},
+    thread,
+};
+
+// On the targets without atomic read-modify-write operations, they
+// are emulated by `portable-atomic` (see the "Features" of the
+// crate).
+#[cfg(all(feature = "portable-atomic", not(any(loom, shuttle))))]
+pub(crate) use portable_atomic as atomic;
+#[cfg(not(feature = "std"))]
+pub use spin::{LockResult, PoisonError, TryLockError, TryLockResult};
+#[cfg(all(not(feature = "std"), not(any(loom, shuttle))))]
+pub(crate) use spin::{Mutex, MutexGuard};
+// When the crate is model-checked, the primitives are the ones of
+// `loom` or `shuttle` (see `tests_loom` and `tests_shuttle`). The
+// mutex of `loom` panics instead of being poisoned, so its write
+// locks are `model::Mutex`es. The ones of `shuttle` are
+// `poisonable::Mutex`es, which can be poisoned without a panic.
+#[cfg(loom)]
+pub(crate) use {
+    loom::{
+        hint,
+        sync::{Condvar, atomic},
+        thread,
+    },
+    model::{Mutex, MutexGuard},
+};
+#[cfg(shuttle)]
+pub(crate) use {
+    poisonable::Mutex,
+    shuttle::{
+        hint,
+        sync::{Condvar, MutexGuard, atomic},
+        thread,
+    },
+};
+
+/// A spinlock, with the API of the `Mutex` of `std`.
+#[cfg(not(feature = "std"))]
+mod spin {
+    use {
+        super::atomic::{AtomicBool, Ordering},
+        core::{
+            cell::UnsafeCell,
+            error::Error,
+            fmt, hint,
+            ops::{Deref, DerefMut},
+        },
+    };
+
+    /// See [`std::sync::PoisonError`].
+    pub struct PoisonError<T> {
+        guard: T,
+    }
+
+    impl<T> PoisonError<T> {
+        #[inline]
+        pub const fn new(guard: T) -> Self {
+            Self { guard }
+        }
+        #[inline]
+        pub fn into_inner(self) -> T {
+            self.guard
+        }
+        #[inline]
+        pub const fn get_ref(&self) -> &T {
+            &self.guard
+        }
+        #[inline]
+        pub const fn get_mut(&mut self) -> &mut T {
+            &mut self.guard
+        }
+    }
+
+    impl<T> fmt::Debug for PoisonError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("PoisonError").finish_non_exhaustive()
+        }
+    }
+
+    impl<T> fmt::Display for PoisonError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("poisoned lock: another task failed inside")
+        }
+    }
+
+    impl<T> Error for PoisonError<T> {}
+
+    /// See [`std::sync::TryLockError`].
+    pub enum TryLockError<T> {
+        Poisoned(PoisonError<T>),
+        WouldBlock,
+    }
+
+    impl<T> From<PoisonError<T>> for TryLockError<T> {
+        #[inline]
+        fn from(err: PoisonError<T>) -> Self {
+            Self::Poisoned(err)
+        }
+    }
+
+    impl<T> fmt::Debug for TryLockError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Poisoned(..) => "Poisoned(..)".fmt(f),
+                Self::WouldBlock => "WouldBlock".fmt(f),
+            }
+        }
+    }
+
+    impl<T> fmt::Display for TryLockError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Self::Poisoned(..) => {
+                    "poisoned lock: another task failed inside"
+                }
+                Self::WouldBlock => {
+                    "try_lock failed because the operation would block"
+                }
+            }
+            .fmt(f)
+        }
+    }
+
+    impl<T> Error for TryLockError<T> {}
+
+    /// See [`std::sync::LockResult`].
+    pub type LockResult<T> = Result<T, PoisonError<T>>;
+    /// See [`std::sync::TryLockResult`].
+    pub type TryLockResult<T> = Result<T, TryLockError<T>>;
+
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    // SAFETY: the value is only accessed by the owner of the lock.
+    unsafe impl<T: Send> Send for Mutex<T> {}
+    // SAFETY: the value is only accessed by the owner of the lock.
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(value),
+            }
+        }
+        /// Spins until the lock is acquired. It's never poisoned.
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            loop {
+                if let Ok(guard) = self.try_lock() {
+                    return Ok(guard);
+                }
+                while self.locked.load(Ordering::Relaxed) {
+                    hint::spin_loop();
+                }
+            }
+        }
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            if self
+                .locked
+                .compare_exchange(
+                    false,
+                    true,
+                    Ordering::Acquire,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+            {
+                Ok(MutexGuard { mutex: self })
+            } else {
+                Err(TryLockError::WouldBlock)
+            }
+        }
+    }
+
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+        #[inline]
+        fn deref(&self) -> &T {
+            // SAFETY: the lock is held.
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        #[inline]
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: the lock is held.
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        #[inline]
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+}
+
+/// A mutex poisoned like the one of `std`, for the models of `loom`: the
+/// mutex of `loom` panics instead of returning a [`PoisonError`].
+#[cfg(loom)]
+mod model {
+    use {
+        core::{
+            cell::UnsafeCell,
+            ops::{Deref, DerefMut},
+        },
+        loom::sync::{
+            Condvar, Mutex as StateMutex, MutexGuard as StateGuard,
+        },
+        std::{
+            sync::{LockResult, PoisonError, TryLockError, TryLockResult},
+            thread,
+        },
+    };
+
+    struct State {
+        locked: bool,
+        poisoned: bool,
+        waiting: usize,
+    }
+
+    pub(crate) struct Mutex<T> {
+        /// Never held while the value is used, so it's never poisoned.
+        state: StateMutex<State>,
+        unlocked: Condvar,
+        value: UnsafeCell<T>,
+    }
+
+    // SAFETY: the value is only accessed by the owner of the lock.
+    unsafe impl<T: Send> Send for Mutex<T> {}
+    // SAFETY: the value is only accessed by the owner of the lock.
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self {
+                state: StateMutex::new(State {
+                    locked: false,
+                    poisoned: false,
+                    waiting: 0,
+                }),
+                unlocked: Condvar::new(),
+                value: UnsafeCell::new(value),
+            }
+        }
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            let mut state = self.state.lock().unwrap();
+            while state.locked {
+                state.waiting += 1;
+                state = self.unlocked.wait(state).unwrap();
+                state.waiting -= 1;
+            }
+            self.acquire(state)
+        }
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            let state = self.state.lock().unwrap();
+            if state.locked {
+                return Err(TryLockError::WouldBlock);
+            }
+            Ok(self.acquire(state)?)
+        }
+        fn acquire(
+            &self,
+            mut state: StateGuard<'_, State>,
+        ) -> LockResult<MutexGuard<'_, T>> {
+            state.locked = true;
+            let guard = MutexGuard {
+                mutex: self,
+                panicking: thread::panicking(),
+            };
+            if state.poisoned {
+                Err(PoisonError::new(guard))
+            } else {
+                Ok(guard)
+            }
+        }
+    }
+
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+        /// Whether the thread was already panicking when the lock was
+        /// acquired, like in `std`.
+        panicking: bool,
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+        #[inline]
+        fn deref(&self) -> &T {
+            // SAFETY: the lock is held.
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        #[inline]
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: the lock is held.
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            let mut state = self.mutex.state.lock().unwrap();
+            if !self.panicking && thread::panicking() {
+                state.poisoned = true;
+            }
+            state.locked = false;
+            let waiting = state.waiting != 0;
+            drop(state);
+            if waiting {
+                self.mutex.unlocked.notify_one();
+            }
+        }
+    }
+}
+
+/// The mutex of `shuttle`, which can also be poisoned by a flag: `shuttle`
+/// stops an execution at the first panic of a task, even a caught one, so
+/// the tests poison it with [`poison`](Mutex::poison) instead.
+#[cfg(shuttle)]
+mod poisonable {
+    use {
+        super::atomic::{AtomicBool, Ordering},
+        shuttle::sync::{Mutex as Inner, MutexGuard},
+        std::sync::{LockResult, PoisonError, TryLockResult},
+    };
+
+    pub(crate) struct Mutex<T> {
+        inner: Inner<T>,
+        /// Only stored while the lock is held, so the loads and the
+        /// stores are ordered by the lock.
+        poisoned: AtomicBool,
+    }
+
+    impl<T> Mutex<T> {
+        pub(crate) fn new(value: T) -> Self {
+            Self {
+                inner: Inner::new(value),
+                poisoned: AtomicBool::new(false),
+            }
+        }
+        pub(crate) fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            let guard = self.inner.lock()?;
+            self.check(guard)
+        }
+        pub(crate) fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            let guard = self.inner.try_lock()?;
+            Ok(self.check(guard)?)
+        }
+        /// Poisons the lock, as if the thread holding it panicked.
+        #[cfg(test)]
+        pub(crate) fn poison(&self) {
+            self.poisoned.store(true, Ordering::Relaxed);
+        }
+        fn check<'a>(
+            &self,
+            guard: MutexGuard<'a, T>,
+        ) -> LockResult<MutexGuard<'a, T>> {
+            if self.poisoned.load(Ordering::Relaxed) {
+                Err(PoisonError::new(guard))
+            } else {
+                Ok(guard)
+            }
+        }
+    }
+}
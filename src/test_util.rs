@@ -0,0 +1,165 @@
+//! Utilities to test the code which uses a [`GrowLock`](crate::GrowLock).
+//!
+//! This module is enabled by the `test-util` feature.
+
+use {
+    crate::{
+        compat::{AllocError, Allocator, Global},
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+    core::{alloc::Layout, fmt, ptr::NonNull},
+};
+
+/// An allocator which fails on demand, to test the handling of the
+/// allocation failures (like the errors of
+/// [`try_with_capacity_in`](crate::GrowLock::try_with_capacity_in)).
+///
+/// The successful allocations are forwarded to the inner allocator, and
+/// the allocations, deallocations and failures are counted. The knobs
+/// and the counters take `&self`, so they can be used after the allocator
+/// is moved into a [`GrowLock`](crate::GrowLock) (through
+/// [`allocator`](crate::GrowLock::allocator)), or the allocator can be
+/// lent by reference, as `&A` is an allocator too.
+///
+/// # Examples
+/// ```
+/// use growlock::{GrowLock, test_util::FailingAllocator};
+///
+/// let alloc = FailingAllocator::default();
+/// alloc.fail_after(1);
+/// let lock = GrowLock::<u32, _>::try_with_capacity_in(4, &alloc).unwrap();
+/// assert!(lock.try_clone_in(&alloc).is_err());
+/// drop(lock);
+///
+/// assert_eq!(alloc.allocations(), 1);
+/// assert_eq!(alloc.deallocations(), 1);
+/// assert_eq!(alloc.failures(), 1);
+/// ```
+pub struct FailingAllocator<A = Global> {
+    inner: A,
+    /// The number of allocations after which every allocation fails.
+    fail_after: AtomicUsize,
+    /// The largest size in bytes which is allocated.
+    max_size: AtomicUsize,
+    allocations: AtomicUsize,
+    deallocations: AtomicUsize,
+    failures: AtomicUsize,
+}
+
+impl<A> FailingAllocator<A> {
+    /// Creates a [`FailingAllocator`] which forwards the allocations to
+    /// `inner`, and never fails until told to.
+    #[inline]
+    #[must_use]
+    pub fn new(inner: A) -> Self {
+        Self {
+            inner,
+            fail_after: AtomicUsize::new(usize::MAX),
+            max_size: AtomicUsize::new(usize::MAX),
+            allocations: AtomicUsize::new(0),
+            deallocations: AtomicUsize::new(0),
+            failures: AtomicUsize::new(0),
+        }
+    }
+    /// Returns the inner allocator.
+    #[inline]
+    #[must_use]
+    pub const fn inner(&self) -> &A {
+        &self.inner
+    }
+    /// Lets the next `n` allocations succeed, and fails every allocation
+    /// after them.
+    ///
+    /// `fail_after(0)` fails every allocation from now on.
+    #[inline]
+    pub fn fail_after(&self, n: usize) {
+        let allocations = self.allocations();
+        self.fail_after
+            .store(allocations.saturating_add(n), Ordering::Relaxed);
+    }
+    /// Fails every allocation of more than `bytes` bytes.
+    #[inline]
+    pub fn fail_layouts_larger_than(&self, bytes: usize) {
+        self.max_size.store(bytes, Ordering::Relaxed);
+    }
+    /// Stops failing the allocations.
+    #[inline]
+    pub fn reset(&self) {
+        self.fail_after.store(usize::MAX, Ordering::Relaxed);
+        self.max_size.store(usize::MAX, Ordering::Relaxed);
+    }
+    /// Returns the number of successful allocations.
+    #[inline]
+    #[must_use]
+    pub fn allocations(&self) -> usize {
+        self.allocations.load(Ordering::Relaxed)
+    }
+    /// Returns the number of deallocations.
+    #[inline]
+    #[must_use]
+    pub fn deallocations(&self) -> usize {
+        self.deallocations.load(Ordering::Relaxed)
+    }
+    /// Returns the number of failed allocations (either injected, or
+    /// returned by the inner allocator).
+    #[inline]
+    #[must_use]
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::Relaxed)
+    }
+    /// Returns the number of blocks allocated and not deallocated yet.
+    #[inline]
+    #[must_use]
+    pub fn live(&self) -> usize {
+        self.allocations() - self.deallocations()
+    }
+}
+
+impl Default for FailingAllocator {
+    /// Creates a [`FailingAllocator`] which forwards the allocations to
+    /// the global allocator.
+    #[inline]
+    fn default() -> Self {
+        Self::new(Global)
+    }
+}
+
+impl<A> fmt::Debug for FailingAllocator<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailingAllocator")
+            .field("allocations", &self.allocations())
+            .field("deallocations", &self.deallocations())
+            .field("failures", &self.failures())
+            .finish_non_exhaustive()
+    }
+}
+
+// SAFETY: the blocks are allocated and deallocated by `inner`.
+unsafe impl<A: Allocator> Allocator for FailingAllocator<A> {
+    fn allocate(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let injected = layout.size()
+            > self.max_size.load(Ordering::Relaxed)
+            || self.allocations()
+                >= self.fail_after.load(Ordering::Relaxed);
+        let block = if injected {
+            Err(AllocError)
+        } else {
+            self.inner.allocate(layout)
+        };
+        let counter = match block {
+            Ok(_) => &self.allocations,
+            Err(_) => &self.failures,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        block
+    }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.deallocations.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: the block was allocated by `inner`, and the safety
+        // contract must be upheld by the caller.
+        unsafe { self.inner.deallocate(ptr, layout) }
+    }
+}
@@ -3,9 +3,24 @@
 // > instantly dropped.
 
 use {
-    crate::{GrowLock, cap::Cap, grow_lock},
+    crate::{
+        GrowLock,
+        array::ArrayGrowLock,
+        cache_padded::CachePadded,
+        cap::Cap,
+        compat::{AllocError, Allocator},
+        error::{LengthError, PushError, TryFillError},
+        grow_lock, grow_lock_in,
+        guard::{OwnedGrowGuard, WriteGuard},
+        inline::InlineGrowLock,
+        local::LocalGrowLock,
+        test_util::FailingAllocator,
+        try_grow_lock,
+    },
     std::{
-        alloc::System,
+        alloc::{Layout, System},
+        mem,
+        ptr::{self, NonNull},
         sync::{
             Arc,
             atomic::{AtomicUsize, Ordering},
@@ -15,13 +30,50 @@ use {
     },
 };
 
+#[cfg(feature = "nightly")]
+use crate::{error::TryReserveError, try_grow_lock_in};
+
 /// Helper struct
+#[derive(Clone, Debug)]
 struct AddOnDrop<'a>(&'a AtomicUsize);
 impl Drop for AddOnDrop<'_> {
     fn drop(&mut self) {
         self.0.fetch_add(1, Ordering::Relaxed);
     }
 }
+/// Helper allocator that doubles the size of every block, and checks
+/// that it's deallocated with the requested layout
+#[derive(Default)]
+struct DoublingAlloc(std::sync::Mutex<Vec<(usize, Layout, Layout)>>);
+// SAFETY: every block is allocated and deallocated by `System`, with
+// the same (doubled) layout.
+unsafe impl Allocator for DoublingAlloc {
+    fn allocate(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let size = layout.size().checked_mul(2).ok_or(AllocError)?;
+        let doubled = Layout::from_size_align(size, layout.align())
+            .map_err(|_| AllocError)?;
+        let block = System.allocate(doubled)?;
+        self.0
+            .lock()
+            .unwrap()
+            .push((block.addr().get(), layout, doubled));
+        Ok(block)
+    }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let mut blocks = self.0.lock().unwrap();
+        let i = blocks
+            .iter()
+            .position(|&(addr, ..)| addr == ptr.addr().get())
+            .unwrap();
+        let (_, requested, block) = blocks.swap_remove(i);
+        assert_eq!(layout, requested);
+        // SAFETY: the block was allocated with this layout.
+        unsafe { System.deallocate(ptr, block) }
+    }
+}
 
 // ------------------- constructors -------------------
 
@@ -71,220 +123,2973 @@ fn from_vec() {
     assert_eq!(&lock[..], &[1, 2, 3, 4, 5]);
 }
 
-// ------------------- macro init -------------------
+/// `from_vec_with_capacity` should give room for more elements, even to
+/// a shrunk [`Vec`], and reject a capacity smaller than its length.
+#[test]
+fn from_vec_with_capacity() {
+    use crate::error::{FromVecError, TryReserveError};
+
+    let mut vec = vec![1u32, 2, 3];
+    vec.shrink_to_fit();
+    let lock = GrowLock::from_vec_with_capacity(vec, 5);
+    assert_eq!(lock, [1, 2, 3]);
+    assert_eq!(lock.capacity(), 5);
 
+    let err = GrowLock::try_from_vec_with_capacity(vec![1u32, 2, 3], 2);
+    assert_eq!(
+        err.unwrap_err(),
+        FromVecError::Length(LengthError::new(3, 2))
+    );
+    let err = GrowLock::try_from_vec_with_capacity(vec![1u32], usize::MAX);
+    assert_eq!(
+        err.unwrap_err(),
+        FromVecError::Alloc(TryReserveError::CapacityOverflow)
+    );
+}
+
+/// `into_flattened` should reinterpret the arrays in place, and drop
+/// every element once.
 #[test]
-fn empty_macro() {
-    let lock: GrowLock<String> = grow_lock![];
+fn into_flattened() {
+    let lock = grow_lock!(3, [[1u16, 2], [3, 4]]);
+    let ptr = lock.as_ptr().cast::<u16>();
+    let flat = lock.into_flattened();
+    assert_eq!(flat, [1, 2, 3, 4]);
+    assert_eq!((flat.as_ptr(), flat.capacity()), (ptr, 6));
+    flat.write().unwrap().extend([5, 6]);
+    assert!(flat.is_full());
 
-    assert_eq!(lock.as_slice(), &[] as &[String]);
-    assert!(lock.is_empty());
-    assert_eq!(lock.capacity(), 0);
-    let mut guard = lock.write().unwrap();
-    assert!(guard.try_push("hello world".to_owned()).is_err());
+    let drops = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(4);
+    lock.write()
+        .unwrap()
+        .extend((0..3).map(|_| [AddOnDrop(&drops), AddOnDrop(&drops)]));
+    let flat = lock.into_flattened();
+    assert_eq!((flat.len(), flat.capacity()), (6, 8));
+    assert_eq!(drops.load(Ordering::Relaxed), 0);
+    drop(flat);
+    assert_eq!(drops.load(Ordering::Relaxed), 6);
+}
 
-    assert_eq!(lock, GrowLock::<String>::with_capacity(0));
+/// `into_flattened` with `N == 0` (the arrays are ZSTs) or with ZST
+/// elements.
+#[test]
+fn into_flattened_zst() {
+    let lock = grow_lock!(4, [[], [], []]);
+    let flat: GrowLock<String> = lock.into_flattened();
+    assert!(flat.is_empty());
+    assert_eq!(flat.capacity(), 0);
+    assert!(flat.try_write().unwrap().try_push(String::new()).is_err());
+
+    let lock = grow_lock!(4, [[(); 3], [(); 3]]);
+    let flat = lock.into_flattened();
+    assert_eq!(flat.len(), 6);
+    assert_eq!(flat.capacity(), usize::MAX);
 }
+
+/// `into_flattened` should panic if the length of the ZSTs overflows.
 #[test]
-fn array_macro() {
-    let lock: GrowLock<char> = grow_lock!(10, ['a', 'b', 'c']);
+#[should_panic = "length overflow"]
+fn into_flattened_overflow() {
+    // SAFETY: the elements are ZSTs, so any length is valid.
+    let lock = unsafe {
+        GrowLock::<[(); 2]>::from_parts(
+            ptr::NonNull::dangling(),
+            usize::MAX,
+            0,
+        )
+    };
+    let _ = lock.into_flattened();
+}
 
-    assert_eq!(&lock, &['a', 'b', 'c']);
+/// `from_vec_with_capacity` should take over a large enough allocation,
+/// and reallocate (once) a small one.
+#[cfg(feature = "nightly")]
+#[test]
+fn from_vec_with_capacity_allocations() {
+    use crate::error::FromVecError;
 
-    let mut guard = lock.write().unwrap();
-    for _ in 0..7 {
-        guard.push('_');
+    let alloc = FailingAllocator::default();
+    let mut vec = Vec::with_capacity_in(8, &alloc);
+    vec.extend([1u32, 2]);
+    let lock = GrowLock::from_vec_with_capacity(vec, 4);
+    assert_eq!(lock.capacity(), 8);
+    assert_eq!((alloc.allocations(), alloc.deallocations()), (1, 0));
+    drop(lock);
+
+    let mut vec = Vec::with_capacity_in(2, &alloc);
+    vec.extend([1u32, 2]);
+    let lock = GrowLock::try_from_vec_with_capacity(vec, 6).unwrap();
+    assert_eq!(lock, [1, 2]);
+    assert_eq!(lock.capacity(), 6);
+    assert_eq!((alloc.allocations(), alloc.deallocations()), (3, 2));
+    drop(lock);
+    assert_eq!(alloc.live(), 0);
+
+    let mut vec = Vec::with_capacity_in(2, &alloc);
+    vec.extend([1u32, 2]);
+    alloc.fail_after(0);
+    let err = GrowLock::try_from_vec_with_capacity(vec, 6).unwrap_err();
+    assert!(matches!(
+        err,
+        FromVecError::Alloc(TryReserveError::AllocError(_))
+    ));
+    assert_eq!(alloc.live(), 0);
+}
+
+// ------------------- clone -------------------
+
+/// Tests that a clone keeps the capacity and the elements
+#[test]
+fn clone() {
+    let lock = grow_lock!(10, ["a".to_owned(), "b".to_owned()]);
+    let cloned = lock.clone();
+    assert_eq!(cloned, ["a", "b"]);
+    assert_eq!(cloned.capacity(), 10);
+}
+
+/// `clone_from` should not allocate if the capacity is enough
+#[test]
+fn clone_from_reuses_allocation() {
+    let alloc = FailingAllocator::default();
+    let source = GrowLock::with_capacity_in(10, &alloc);
+    source.write().unwrap().extend([1, 2, 3]);
+    let mut standby = GrowLock::with_capacity_in(5, &alloc);
+    standby.write().unwrap().extend([7, 8, 9, 10, 11]);
+    assert_eq!(alloc.allocations(), 2);
+
+    // shorter source
+    standby.clone_from(&source);
+    assert_eq!(standby, [1, 2, 3]);
+    // longer source
+    source.write().unwrap().extend([4, 5]);
+    standby.clone_from(&source);
+    assert_eq!(standby, [1, 2, 3, 4, 5]);
+    assert_eq!(standby.capacity(), 5);
+    assert_eq!(alloc.allocations(), 2);
+}
+
+/// `clone_from` should reallocate if the capacity is not enough, and drop
+/// the old elements exactly once
+#[test]
+fn clone_from_reallocates() {
+    let counter = AtomicUsize::new(0);
+    let source = GrowLock::with_capacity(4);
+    source
+        .write()
+        .unwrap()
+        .extend((0..4).map(|_| AddOnDrop(&counter)));
+    let mut standby = GrowLock::with_capacity(2);
+    standby.write().unwrap().push(AddOnDrop(&counter));
+
+    standby.clone_from(&source);
+    assert_eq!(counter.load(Ordering::Relaxed), 1);
+    assert_eq!(standby.len(), 4);
+    assert_eq!(standby.capacity(), 4);
+    drop(standby);
+    assert_eq!(counter.load(Ordering::Relaxed), 5);
+}
+
+/// `clone_in` should allocate in the given allocator only
+#[test]
+#[cfg(feature = "nightly")]
+fn clone_in_other_allocator() {
+    let alloc = FailingAllocator::default();
+    let lock = grow_lock!(8, ["x".to_owned(), "y".to_owned()]);
+
+    let in_system: GrowLock<_, System> = lock.clone_in(System);
+    assert_eq!(in_system, lock);
+    assert_eq!(in_system.capacity(), 8);
+
+    let counted = in_system.try_clone_in(&alloc).unwrap();
+    assert_eq!(counted, ["x", "y"]);
+    assert_eq!(alloc.allocations(), 1);
+
+    let back = counted.clone_in(std::alloc::Global);
+    assert_eq!(back, lock);
+    assert_eq!(alloc.allocations(), 1);
+    drop(counted);
+    assert_eq!(alloc.live(), 0);
+}
+
+/// Tests `collect` with exact, lying and empty iterators
+#[test]
+fn from_iter() {
+    /// Iterator that reports a smaller size than the real one
+    struct Lying(std::ops::Range<u32>);
+    impl Iterator for Lying {
+        type Item = u32;
+        fn next(&mut self) -> Option<u32> {
+            self.0.next()
+        }
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            (2, Some(2))
+        }
     }
+
+    let lock: GrowLock<_> = (0..10).collect();
+    assert_eq!(lock, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
     assert!(lock.is_full());
+
+    let lock: GrowLock<_> = Lying(0..5).collect();
+    assert_eq!(lock, [0, 1, 2, 3, 4]);
+
+    let lock: GrowLock<_> = (0..10).filter(|i| i % 2 == 0).collect();
+    assert_eq!(lock, [0, 2, 4, 6, 8]);
+
+    let lock: GrowLock<String> = std::iter::empty().collect();
+    assert!(lock.is_empty());
+    assert_eq!(lock.capacity(), 0);
 }
+
+/// Tests `from_iter_with_capacity` with exact fit, underfill and overflow
 #[test]
-fn repeat_macro() {
-    let lock: GrowLock<String> = grow_lock!(15, ["hello".to_owned(); 4]);
-    for str in &lock[..4] {
-        assert_eq!(str, "hello");
-    }
-    let mut guard = lock.write().unwrap();
-    for _ in 0..11 {
-        guard.push("world".to_owned());
-    }
+fn from_iter_with_capacity() {
+    use crate::error::{LengthError, TryFillError};
+
+    let lock = GrowLock::from_iter_with_capacity(5, 0..5);
+    assert_eq!(lock, [0, 1, 2, 3, 4]);
     assert!(lock.is_full());
+
+    let lock = GrowLock::from_iter_with_capacity(10, vec!['a', 'b']);
+    assert_eq!(lock, ['a', 'b']);
+    assert_eq!(lock.capacity(), 10);
+
+    let counter = AtomicUsize::new(0);
+    let res = GrowLock::try_from_iter_with_capacity(
+        3,
+        (0..5).map(|_| AddOnDrop(&counter)),
+    );
+    assert_eq!(
+        res.err(),
+        Some(TryFillError::Length(LengthError::new(3, 3)))
+    );
+    // the 3 elements in the lock, and the fourth one
+    assert_eq!(counter.load(Ordering::Relaxed), 4);
+}
+/// `from_iter_with_capacity` should panic on length overflow
+#[test]
+#[should_panic(expected = "length overflow")]
+fn from_iter_with_capacity_overflow() {
+    let _ = GrowLock::from_iter_with_capacity(3, 0..4);
 }
 
+/// Tests that arrays are moved (not cloned) in the [`GrowLock`]
 #[test]
-fn array_full_macro() {
-    let lock: GrowLock<char> = grow_lock!['a', 'b', 'c'];
-    assert_eq!(&lock, &['a', 'b', 'c']);
+fn from_array() {
+    let lock = GrowLock::from(["a", "b", "c"]);
+    assert_eq!(lock, ["a", "b", "c"]);
     assert!(lock.is_full());
+
+    let counter = AtomicUsize::new(0);
+    let array = [AddOnDrop(&counter), AddOnDrop(&counter)];
+    let lock = GrowLock::from_array_in(array, 3, System);
+    assert_eq!(lock.len(), 2);
+    assert_eq!(lock.capacity(), 5);
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+    drop(lock);
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
 }
 
+/// Tests that boxed slices round-trip, also when empty
 #[test]
-fn repeat_full_macro() {
-    let lock: GrowLock<String> = grow_lock!["hello".to_owned(); 4];
-    for str in &lock[..4] {
-        assert_eq!(str, "hello");
-    }
+fn from_boxed_slice() {
+    let boxed: Box<[String]> = Box::new(["a".to_owned(), "b".to_owned()]);
+    let ptr = boxed.as_ptr();
+    let lock = GrowLock::from(boxed);
+    assert_eq!(lock.as_ptr(), ptr);
+    assert_eq!(lock, ["a", "b"]);
     assert!(lock.is_full());
+    let boxed = Box::<[String]>::from(lock);
+    assert_eq!(boxed.as_ptr(), ptr);
+
+    let lock = grow_lock!(10, [1, 2, 3]);
+    assert_eq!(&*lock.into_boxed_slice(), &[1, 2, 3]);
+
+    let lock = GrowLock::from(Box::<[u64]>::default());
+    assert_eq!(lock.capacity(), 0);
+    assert!(Box::<[u64]>::from(lock).is_empty());
 }
 
-// ------------------- representation -------------------
-/// Tests if [`GrowLock`] is correctly aligned, also with dangling pointers
-/// and ZSTs
+/// Tests that a panicking `clone` in `from_slice` doesn't leak
 #[test]
-fn alignment() {
-    #[repr(align(64))]
-    #[allow(
-        dead_code,
-        reason = "We need a field to make `Aligned` non-ZST"
-    )]
-    struct Aligned(u64);
-    #[repr(align(128))]
-    struct AlignedZST;
+fn from_slice_clone_panic() {
+    use std::panic;
 
-    let lock = GrowLock::with_capacity(10);
-    let mut guard = lock.write().unwrap();
-    for i in 0..10 {
-        guard.push(Aligned(i));
+    struct PanicOnClone<'a>(AddOnDrop<'a>, bool);
+    impl Clone for PanicOnClone<'_> {
+        fn clone(&self) -> Self {
+            assert!(!self.1, "clone panicked");
+            Self(self.0.clone(), self.1)
+        }
     }
-    let addr = lock.as_ptr().addr();
-    assert_eq!(addr % 64, 0);
 
-    let lock: GrowLock<Aligned> = grow_lock![];
-    let addr = lock.as_ptr().addr();
-    assert_eq!(addr % 64, 0);
+    let counter = AtomicUsize::new(0);
+    let src = [
+        PanicOnClone(AddOnDrop(&counter), false),
+        PanicOnClone(AddOnDrop(&counter), false),
+        PanicOnClone(AddOnDrop(&counter), true),
+    ];
+    let lock = GrowLock::from_slice(&src[..2], 5);
+    assert_eq!(lock.len(), 2);
+    assert_eq!(lock.capacity(), 5);
+    drop(lock);
+    assert_eq!(counter.load(Ordering::Relaxed), 2);
 
-    let lock: GrowLock<AlignedZST> = GrowLock::with_capacity(1);
-    let addr = lock.as_ptr().addr();
-    assert_eq!(addr % 128, 0);
+    let result =
+        panic::catch_unwind(|| GrowLock::from_slice(&src, src.len()));
+    assert!(result.is_err());
+    // the 2 clones are dropped
+    assert_eq!(counter.load(Ordering::Relaxed), 4);
 }
-
-// ------------------- push panics -------------------
-/// `push` should panic on length overflow
+/// `From<Cow>` should only clone in the `Borrowed` case
 #[test]
-#[should_panic(expected = "length overflow")]
-fn push_overflow() {
-    let lock = GrowLock::with_capacity(5);
-    let mut guard = lock.write().unwrap();
-    for i in 0..6 {
-        guard.push(i);
+fn from_cow() {
+    use std::borrow::Cow;
+
+    struct CountClone<'a>(&'a AtomicUsize);
+    impl Clone for CountClone<'_> {
+        fn clone(&self) -> Self {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Self(self.0)
+        }
     }
+
+    let clones = AtomicUsize::new(0);
+    let vec = vec![CountClone(&clones), CountClone(&clones)];
+    let ptr = vec.as_ptr();
+    let lock = GrowLock::from(Cow::Owned(vec));
+    assert_eq!(lock.as_ptr(), ptr);
+    assert_eq!(clones.load(Ordering::Relaxed), 0);
+
+    let lock =
+        GrowLock::from_cow_with_capacity(Cow::Owned(Vec::from(lock)), 10);
+    assert_eq!(lock.len(), 2);
+    assert!(lock.capacity() >= 10);
+    assert_eq!(clones.load(Ordering::Relaxed), 0);
+
+    let lock = GrowLock::from(Cow::Borrowed(lock.as_slice()));
+    assert_eq!(lock.len(), 2);
+    assert_eq!(lock.capacity(), 2);
+    assert_eq!(clones.load(Ordering::Relaxed), 2);
 }
-/// `try_push` should return an error on length overflow
+/// The knobs of [`FailingAllocator`] should fail the fallible
+/// constructors, and its counters should match the allocations.
 #[test]
-fn try_push_overflow() {
-    let lock = GrowLock::with_capacity(5);
-    let mut guard = lock.write().unwrap();
-    for i in 0..5 {
-        assert!(guard.try_push(i).is_ok());
-    }
-    assert!(guard.try_push(6).is_err());
+fn failing_allocator() {
+    let alloc = FailingAllocator::new(System);
+    alloc.fail_after(2);
+    let a = GrowLock::<u8, _>::try_with_capacity_in(1, &alloc).unwrap();
+    let b = a.try_clone_in(&alloc).unwrap();
+    assert!(GrowLock::<u8, _>::try_with_capacity_in(1, &alloc).is_err());
+    // nothing is allocated for an empty capacity.
+    assert!(GrowLock::<u8, _>::try_with_capacity_in(0, &alloc).is_ok());
+    assert_eq!((alloc.allocations(), alloc.failures()), (2, 1));
+
+    alloc.reset();
+    alloc.fail_layouts_larger_than(4);
+    assert!(GrowLock::<u32, _>::try_with_capacity_in(1, &alloc).is_ok());
+    assert!(GrowLock::<u32, _>::try_with_capacity_in(2, &alloc).is_err());
+    drop((a, b));
+    assert_eq!(alloc.live(), 0);
+    assert_eq!(alloc.deallocations(), 3);
+    assert_eq!(alloc.failures(), 2);
 }
+/// The slack of an over-allocated block should be used as capacity, and
+/// the block should still be deallocated.
+#[test]
+fn rounded_capacity() {
+    let drops = AtomicUsize::new(0);
+    let alloc = DoublingAlloc::default();
+    let lock = GrowLock::with_capacity_in(3, &alloc);
+    assert_eq!(lock.capacity(), 6);
+    lock.write()
+        .unwrap()
+        .extend(std::iter::repeat_with(|| AddOnDrop(&drops)).take(6));
+    assert!(lock.is_full());
+    drop(lock);
+    assert_eq!(drops.load(Ordering::Relaxed), 6);
 
-/// Tests if elements are correctly dropped even if the thread panics
+    let lock = GrowLock::<(), _>::with_capacity_in(3, &alloc);
+    assert_eq!(lock.capacity(), usize::MAX);
+    drop(lock);
+    assert!(alloc.0.lock().unwrap().is_empty());
+}
+/// `try_from_slice` should report errors instead of panicking
 #[test]
-fn init_drop_on_panic() {
-    use std::panic;
+fn try_from_slice() {
+    use crate::error::TryReserveError;
 
-    let counter = AtomicUsize::new(0);
-    let result = panic::catch_unwind(|| {
-        let lock = GrowLock::with_capacity(10);
-        let mut guard = lock.write().unwrap();
-        for _ in 0..15 {
-            guard.push(AddOnDrop(&counter));
-        }
-    });
+    let alloc = FailingAllocator::default();
+    alloc.fail_layouts_larger_than(64);
+    let src = [1u64, 2, 3];
+    let lock = GrowLock::try_from_slice_in(&src, 8, &alloc).unwrap();
+    assert_eq!(lock, src);
 
-    assert!(result.is_err());
-    // 10 elements are pushed in the lock, the last is dropped when trying
-    // to push it.
-    assert_eq!(counter.load(Ordering::Relaxed), 11);
+    let err = GrowLock::try_from_slice_in(&src, 9, &alloc).unwrap_err();
+    assert!(matches!(err, TryReserveError::AllocError(_)));
+    assert_eq!(alloc.failures(), 1);
+    let err = GrowLock::try_from_slice_in(&src, 2, &alloc).unwrap_err();
+    assert_eq!(err, TryReserveError::CapacityOverflow);
+    let err = GrowLock::try_from_slice(&src, usize::MAX).unwrap_err();
+    assert_eq!(err, TryReserveError::CapacityOverflow);
 }
+/// The errors of the allocations should convert to the ones of `std`,
+/// and back.
+#[test]
+#[cfg(feature = "nightly")]
+fn try_reserve_error_round_trip() {
+    use {
+        crate::error::TryReserveError,
+        std::collections::TryReserveError as StdError,
+    };
 
-// ------------------- test drop -------------------
+    let err = GrowLock::<u64>::try_with_capacity(usize::MAX).unwrap_err();
+    assert_eq!(err.layout(), None);
+    let std_err = Vec::<u64>::new().try_reserve(usize::MAX).unwrap_err();
+    assert_eq!(StdError::from(err), std_err);
+    assert_eq!(TryReserveError::from(std_err), err);
 
-/// Tests if elements are correctly dropped when the [`GrowLock`] is
-/// dropped
+    let alloc = FailingAllocator::default();
+    alloc.fail_layouts_larger_than(8);
+    let err =
+        GrowLock::<u64, _>::try_with_capacity_in(2, &alloc).unwrap_err();
+    let layout = Layout::array::<u64>(2).unwrap();
+    assert_eq!(err.layout(), Some(layout));
+    let std_err = Vec::<u64, _>::new_in(&alloc)
+        .try_reserve_exact(2)
+        .unwrap_err();
+    assert_eq!(StdError::from(err), std_err);
+    assert_eq!(TryReserveError::from(std_err), err);
+}
+/// The errors of the allocations should be `OutOfMemory` I/O errors.
 #[test]
-fn initialized_drop() {
-    let counter = AtomicUsize::new(0);
-    {
-        let lock = GrowLock::with_capacity(200);
-        let mut guard = lock.write().unwrap();
-        for _ in 0..100 {
-            guard.push(AddOnDrop(&counter));
-        }
-        // here `lock` is dropped
+fn try_reserve_error_io() {
+    fn read_lock() -> std::io::Result<GrowLock<u64>> {
+        Ok(GrowLock::try_with_capacity(usize::MAX)?)
     }
-    assert_eq!(counter.load(Ordering::Relaxed), 100);
+
+    let err = read_lock().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    assert!(
+        err.into_inner()
+            .unwrap()
+            .is::<crate::error::TryReserveError>()
+    );
+}
+/// `from_slice` should panic if the capacity is too small
+#[test]
+#[should_panic(expected = "length overflow")]
+fn from_slice_overflow() {
+    let _ = GrowLock::from_slice(&[1, 2, 3], 2);
 }
 
-/// Tests if ZST elements are correctly dropped when the [`GrowLock`] is
-/// dropped
+/// Tests that strings are converted without lossy passes
 #[test]
-fn zst_drop() {
-    static ZST_COUNTER: AtomicUsize = AtomicUsize::new(0);
-    struct AddZST;
-    impl Drop for AddZST {
-        fn drop(&mut self) {
-            ZST_COUNTER.fetch_add(1, Ordering::Relaxed);
-        }
-    }
-    {
-        let lock = GrowLock::with_capacity(200);
-        let mut guard = lock.write().unwrap();
-        for _ in 0..150 {
-            guard.push(AddZST);
-        }
-        // here `lock` is dropped
-    }
-    assert_eq!(ZST_COUNTER.load(Ordering::Relaxed), 150);
+fn from_str() {
+    let string = "héllo, wörld 🦀".to_owned();
+    let owned = string.clone();
+    let ptr = owned.as_ptr();
+    let lock = GrowLock::from(owned);
+    assert_eq!(lock.as_ptr(), ptr);
+    assert_eq!(lock, string.as_bytes());
+
+    let lock = GrowLock::from(string.as_str());
+    assert_eq!(lock, string.as_bytes());
+    assert!(lock.is_full());
+
+    let lock = GrowLock::from_str_with_capacity(&string, 64);
+    assert_eq!(lock, string.as_bytes());
+    assert_eq!(lock.capacity(), 64);
 }
 
-// ------------------- write -------------------
+/// Tests conversions from bytes to strings
+#[test]
+fn into_string() {
+    let lock = GrowLock::from_str_with_capacity("añb🦀", 20);
+    let ptr = lock.as_ptr();
+    assert_eq!(lock.as_str(), Ok("añb🦀"));
+    let string = String::try_from(lock).unwrap();
+    assert_eq!(string, "añb🦀");
+    assert_eq!(string.as_ptr(), ptr);
+    assert_eq!(string.capacity(), 20);
 
-/// Tests that each writer waits its turn before writing
-/// (this looks at the length)
+    let lock = GrowLock::from(*b"ab\xffc");
+    assert!(lock.as_str().is_err());
+    let err = String::try_from(lock).unwrap_err();
+    assert_eq!(err.utf8_error().valid_up_to(), 2);
+    assert_eq!(err.into_lock(), *b"ab\xffc");
+
+    // truncated multi-byte character
+    let lock = GrowLock::from_slice(&"🦀".as_bytes()[..3], 3);
+    let err = lock.as_str().unwrap_err();
+    assert_eq!(err.valid_up_to(), 0);
+    assert_eq!(err.error_len(), None);
+    assert!(String::try_from(lock).is_err());
+}
+
+/// Tests the writes and the conversions of a [`GrowString`]
 #[test]
-fn write_contention() {
-    const THREADS: usize = 10;
-    const CAP: usize = 1000;
+fn grow_string() {
+    use {crate::string::GrowString, std::fmt::Write};
 
-    let lock = Arc::new(GrowLock::with_capacity(CAP));
-    let mut handles = Vec::with_capacity(THREADS);
-    for t in 0..THREADS {
-        handles.push(thread::spawn({
-            let lock_clone = Arc::clone(&lock);
-            move || {
-                for i in 0..(CAP / THREADS) {
-                    let mut guard = lock_clone.write().unwrap();
-                    guard.push(t * (CAP / THREADS) + i);
-                }
-            }
-        }));
-    }
-    for handle in handles {
-        handle.join().unwrap();
-    }
+    let s = GrowString::with_capacity(16);
+    let mut guard = s.write().unwrap();
+    guard.push_str("añ").unwrap();
+    guard.push('🦀').unwrap();
+    write!(guard, "{}", 42).unwrap();
+    assert!(s.try_write().is_err());
+    drop(guard);
+    assert_eq!(s, "añ🦀42");
+    assert_eq!((s.len(), s.capacity()), (9, 16));
 
-    assert_eq!(lock.len(), CAP);
+    let ptr = s.as_ptr();
+    let string = String::from(s);
+    assert_eq!((string.as_ptr(), string.capacity()), (ptr, 16));
+    let s = GrowString::from(string);
+    assert_eq!((s.as_ptr(), s.capacity()), (ptr, 16));
+    let lock = s.into_bytes();
+    assert_eq!(lock, "añ🦀42".as_bytes());
+    let s = GrowString::try_from(lock).unwrap();
+    assert_eq!(s, "añ🦀42");
+
+    let err =
+        GrowString::try_from(GrowLock::from(*b"ab\xff")).unwrap_err();
+    assert_eq!(err.utf8_error().valid_up_to(), 2);
 }
 
-// ------------------- read -------------------
+/// The readers of a [`GrowString`] should never see a part of a
+/// character, also when the characters stop fitting at the capacity
+/// boundary.
+#[test]
+fn grow_string_utf8_boundary() {
+    use {crate::string::GrowString, std::fmt::Write};
 
-/// tests that we can still read while writing
+    let s = GrowString::with_capacity(5);
+    let mut guard = s.write().unwrap();
+    guard.push_str("abc").unwrap();
+    assert_eq!(guard.push('€'), Err(LengthError::new(3, 5)));
+    let euro = '€';
+    assert!(write!(guard, "é{euro}").is_err());
+    // the pieces which fit are kept.
+    assert_eq!(s, "abcé");
+    assert!(guard.push('a').is_err());
+    drop(guard);
+
+    // 3-byte characters, in a capacity which is not a multiple of 3.
+    let s = GrowString::with_capacity(1000);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let mut guard = s.write().unwrap();
+            while guard.push('€').is_ok() {}
+            assert_eq!(guard.len(), 999);
+        });
+        while s.len() < 999 {
+            let bytes = s.as_bytes();
+            assert!(std::str::from_utf8(bytes).is_ok());
+            assert_eq!(bytes.len() % 3, 0);
+        }
+    });
+    assert_eq!(s.chars().count(), 333);
+}
+
+/// Tests that elements are moved in the array exactly once, and that the
+/// lock is returned on failure
 #[test]
-fn read_while_locked() {
+fn into_array() {
+    let counter = AtomicUsize::new(0);
     let lock = GrowLock::with_capacity(5);
-    {
-        let mut guard = lock.write().unwrap();
-        guard.push("hi");
-        guard.push("there");
-        assert_eq!(&lock[0..2], ["hi", "there"]);
-        guard.push("still locked");
-    }
+    lock.write()
+        .unwrap()
+        .extend((0..3).map(|_| AddOnDrop(&counter)));
+
+    let lock = <[AddOnDrop; 2]>::try_from(lock).unwrap_err();
     assert_eq!(lock.len(), 3);
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+
+    let array = <[AddOnDrop; 3]>::try_from(lock).unwrap();
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+    drop(array);
+    assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+    let array: [u8; 0] = GrowLock::with_capacity(0).try_into().unwrap();
+    assert_eq!(array, []);
 }
 
-/// tests that we can still read while writing, from another thread
+/// Tests that `from_fn` drops the initialized prefix if `f` panics
+#[test]
+fn from_fn_panic() {
+    use std::panic;
+
+    let counter = AtomicUsize::new(0);
+    let result = panic::catch_unwind(|| {
+        GrowLock::from_fn(10, 8, |i| {
+            assert!(i < 5, "oops!");
+            AddOnDrop(&counter)
+        })
+    });
+    assert!(result.is_err());
+    assert_eq!(counter.load(Ordering::Relaxed), 5);
+
+    let result = GrowLock::try_from_fn(10, 8, |i| {
+        if i < 3 {
+            Ok(AddOnDrop(&counter))
+        } else {
+            Err(i)
+        }
+    });
+    assert_eq!(result.unwrap_err(), 3);
+    assert_eq!(counter.load(Ordering::Relaxed), 8);
+}
+/// `from_fn` should panic if `len > capacity`
+#[test]
+#[should_panic(expected = "`len` (3) is greater than `capacity` (2)")]
+fn from_fn_overflow() {
+    let _ = GrowLock::from_fn(2, 3, |i| i);
+}
+
+// ------------------- map -------------------
+
+/// Tests that `map` reuses the allocation when the layouts match
+#[test]
+fn map_in_place() {
+    let lock = grow_lock!(10, [1u32, 2, 3]);
+    let ptr = lock.as_ptr().addr();
+    let lock = lock.map(|x| f32::from(u16::try_from(x).unwrap()) / 2.0);
+    assert_eq!(lock, [0.5, 1.0, 1.5]);
+    assert_eq!(lock.as_ptr().addr(), ptr);
+    assert_eq!(lock.capacity(), 10);
+
+    let lock = grow_lock![(); 3].map(|()| [0u8; 0]);
+    assert_eq!(lock.len(), 3);
+}
+/// Tests `map` when the layouts don't match
+#[test]
+fn map_realloc() {
+    let lock = GrowLock::from_slice_in(&[1u8, 2, 3], 10, System);
+    let lock = lock.map(|x| x.to_string());
+    assert_eq!(lock, ["1", "2", "3"]);
+    assert_eq!(lock.capacity(), 10);
+
+    let lock = grow_lock![(); 3].map(|()| 7u64);
+    assert_eq!(lock, [7, 7, 7]);
+}
+/// Tests that a panic in `map` doesn't double-drop nor leak
+#[test]
+fn map_panic() {
+    use std::panic;
+
+    /// maps 6 elements, panicking on the fourth one
+    fn check<'a, U>(
+        new: &'a AtomicUsize,
+        f: impl Fn(&'a AtomicUsize) -> U,
+    ) {
+        let old = AtomicUsize::new(0);
+        new.store(0, Ordering::Relaxed);
+        let lock = GrowLock::with_capacity(10);
+        lock.write()
+            .unwrap()
+            .extend((0..6).map(|_| AddOnDrop(&old)));
+
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            let mut i = 0;
+            lock.map(|_| {
+                i += 1;
+                assert!(i <= 3, "oops!");
+                f(new)
+            })
+        }));
+        assert!(result.is_err());
+        // each old element is dropped exactly once, and so are the 3 new
+        // elements.
+        assert_eq!(old.load(Ordering::Relaxed), 6);
+        assert_eq!(new.load(Ordering::Relaxed), 3);
+    }
+
+    let new = AtomicUsize::new(0);
+    // same layout
+    check(&new, AddOnDrop);
+    // different layout
+    check(&new, |new| (AddOnDrop(new), 0u64));
+}
+
+// ------------------- iterators -------------------
+
+/// Tests the owned iterator in both directions
+#[test]
+fn into_iter() {
+    let lock = grow_lock!(10, ["a", "b", "c", "d"]);
+    let mut iter = lock.into_iter();
+    assert_eq!(iter.len(), 4);
+    assert_eq!(iter.next(), Some("a"));
+    assert_eq!(iter.next_back(), Some("d"));
+    assert_eq!(iter.as_slice(), ["b", "c"]);
+    assert_eq!(iter.collect::<Vec<_>>(), ["b", "c"]);
+
+    let mut iter = GrowLock::<String>::with_capacity(0).into_iter();
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next(), None);
+}
+/// Tests the owned iterator of ZSTs
+#[test]
+fn into_iter_zst() {
+    let lock = grow_lock![(); 5];
+    let mut iter = lock.into_iter();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.next_back(), Some(()));
+    assert_eq!(iter.count(), 4);
+}
+/// Tests that the remaining elements are dropped with the iterator
+#[test]
+fn into_iter_drop_halfway() {
+    let counter = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(20);
+    lock.write()
+        .unwrap()
+        .extend((0..10).map(|_| AddOnDrop(&counter)));
+
+    let mut iter = lock.into_iter();
+    for _ in 0..4 {
+        drop(iter.next());
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 4);
+    drop(iter);
+    assert_eq!(counter.load(Ordering::Relaxed), 10);
+}
+
+/// Tests that iterating by reference doesn't see elements pushed after
+/// the start of the iteration
+#[test]
+fn iter_snapshot() {
+    let lock = Arc::new(GrowLock::with_capacity(1000));
+    lock.write().unwrap().extend(0..10);
+
+    let handle = thread::spawn({
+        let lock = Arc::clone(&lock);
+        move || lock.write().unwrap().extend(10..1000)
+    });
+    let mut visited = 0;
+    for (i, &elem) in lock.iter().enumerate() {
+        assert_eq!(elem, i);
+        visited += 1;
+    }
+    let iter = (&*lock).into_iter();
+    let len = iter.len();
+    handle.join().unwrap();
+    assert!(visited >= 10);
+    assert_eq!(iter.count(), len);
+
+    let mut sum = 0;
+    for elem in &*lock {
+        sum += elem;
+    }
+    assert_eq!(sum, (0..1000).sum::<usize>());
+}
+
+/// Tests conversions from and to a wrapped-around [`VecDeque`]
+#[test]
+fn from_vec_deque() {
+    use std::collections::VecDeque;
+
+    let mut deque = VecDeque::with_capacity(8);
+    deque.extend([3, 4, 5]);
+    deque.push_front(2);
+    deque.push_front(1);
+    // the deque is wrapped around
+    assert_eq!(deque.as_slices(), (&[1, 2][..], &[3, 4, 5][..]));
+    let cap = deque.capacity();
+
+    let lock = GrowLock::from(deque);
+    assert_eq!(lock, [1, 2, 3, 4, 5]);
+    assert_eq!(lock.capacity(), cap);
+
+    let ptr = lock.as_ptr();
+    let deque = VecDeque::from(lock);
+    assert_eq!(deque.as_slices(), (&[1, 2, 3, 4, 5][..], &[][..]));
+    assert_eq!(deque.as_slices().0.as_ptr(), ptr);
+    assert_eq!(deque.capacity(), cap);
+}
+
+/// Tests that every `into_*parts*` function round-trips with its `from_*`
+/// counterpart
+#[test]
+fn parts_round_trip() {
+    let lock = grow_lock!(10, ["a".to_owned(), "b".to_owned()]);
+
+    let (ptr, len, cap) = lock.into_parts();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock = unsafe { GrowLock::from_parts(ptr, len, cap) };
+    assert_eq!(lock, ["a", "b"]);
+
+    let (ptr, len, cap) = lock.into_raw_parts();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock = unsafe { GrowLock::from_raw_parts(ptr, len, cap) };
+    assert_eq!(lock, ["a", "b"]);
+    assert_eq!(lock.capacity(), 10);
+
+    let lock = lock.clone_in(System);
+    let (ptr, len, cap, alloc) = lock.into_parts_with_alloc();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock = unsafe { GrowLock::from_parts_in(ptr, len, cap, alloc) };
+    assert_eq!(lock, ["a", "b"]);
+
+    let (ptr, len, cap, alloc) = lock.into_raw_parts_with_alloc();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock =
+        unsafe { GrowLock::from_raw_parts_in(ptr, len, cap, alloc) };
+    assert_eq!(lock, ["a", "b"]);
+    assert_eq!(lock.capacity(), 10);
+}
+/// Tests that the deprecated `AtomicUsize` constructors still work
+#[test]
+#[allow(deprecated)]
+fn atomic_parts_round_trip() {
+    let lock = grow_lock!(4, [1, 2, 3]);
+    let (ptr, len, cap) = lock.into_parts();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock = unsafe {
+        GrowLock::from_atomic_parts(ptr, AtomicUsize::new(len), cap)
+    };
+    assert_eq!(lock, [1, 2, 3]);
+
+    let (ptr, len, cap) = lock.into_raw_parts();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock = unsafe {
+        GrowLock::from_atomic_raw_parts(ptr, AtomicUsize::new(len), cap)
+    };
+    assert_eq!(lock, [1, 2, 3]);
+
+    let (ptr, len, cap, alloc) = lock.into_raw_parts_with_alloc();
+    // SAFETY: these are the parts of a `GrowLock`
+    let lock = unsafe {
+        GrowLock::from_atomic_raw_parts_in(
+            ptr,
+            AtomicUsize::new(len),
+            cap,
+            alloc,
+        )
+    };
+    assert_eq!(lock, [1, 2, 3]);
+}
+
+/// Tests the two-phase initialization, with several threads initializing
+/// disjoint slots
+#[test]
+fn uninit_round_trip() {
+    use std::slice;
+
+    let counter = AtomicUsize::new(0);
+    let mut lock = GrowLock::<AddOnDrop>::new_uninit(8);
+    assert!(lock.is_full());
+    // SAFETY: `MaybeUninit` needs no initialization, and the capacity is 8
+    let slots = unsafe { slice::from_raw_parts_mut(lock.as_mut_ptr(), 8) };
+    thread::scope(|s| {
+        for chunk in slots.chunks_mut(3) {
+            s.spawn(|| {
+                for slot in chunk {
+                    slot.write(AddOnDrop(&counter));
+                }
+            });
+        }
+    });
+    // SAFETY: every element is initialized
+    let lock = unsafe { lock.assume_init() };
+    assert_eq!(lock.len(), 8);
+    assert_eq!(lock.capacity(), 8);
+    drop(lock);
+    assert_eq!(counter.load(Ordering::Relaxed), 8);
+}
+/// Tests that `assume_init` keeps the lock poisoned
+#[test]
+fn uninit_keeps_poison() {
+    use std::{mem::MaybeUninit, panic};
+
+    let lock = GrowLock::<u8, _>::new_uninit_in(0, System);
+    let _ = panic::catch_unwind(|| {
+        let _guard = lock.write().unwrap();
+        panic!("oops!");
+    });
+    // SAFETY: the lock is empty
+    let lock = unsafe { lock.assume_init() };
+    assert!(lock.write().is_err());
+
+    let lock = GrowLock::<MaybeUninit<()>>::with_capacity(3);
+    // SAFETY: the lock is empty
+    let lock = unsafe { lock.assume_init() };
+    assert!(lock.write().is_ok());
+}
+/// With an over-allocating allocator, only the requested elements should
+/// need to be initialized, and the slack should stay free for pushes
+#[test]
+fn uninit_rounded_capacity() {
+    let drops = AtomicUsize::new(0);
+    let alloc = DoublingAlloc::default();
+    let mut lock = GrowLock::<AddOnDrop, _>::new_uninit_in(3, &alloc);
+    assert_eq!(lock.len(), 3);
+    assert_eq!(lock.capacity(), 6);
+    let ptr = lock.as_mut_ptr();
+    for i in 0..3 {
+        // SAFETY: `i < len`
+        unsafe { (*ptr.add(i)).write(AddOnDrop(&drops)) };
+    }
+    // SAFETY: the first `len` elements are initialized
+    let lock = unsafe { lock.assume_init() };
+    assert_eq!(lock.len(), 3);
+    assert_eq!(lock.capacity(), 6);
+    lock.write()
+        .unwrap()
+        .extend(std::iter::repeat_with(|| AddOnDrop(&drops)).take(3));
+    assert!(lock.is_full());
+    drop(lock);
+    assert_eq!(drops.load(Ordering::Relaxed), 6);
+
+    let lock = GrowLock::<(), _>::new_uninit_in(3, &alloc);
+    assert_eq!(lock.len(), 3);
+    assert_eq!(lock.capacity(), usize::MAX);
+    // SAFETY: `()` needs no initialization
+    let lock = unsafe { lock.assume_init() };
+    assert_eq!(lock.len(), 3);
+    drop(lock);
+    assert!(alloc.0.lock().unwrap().is_empty());
+}
+
+/// Tests conversions from inline and spilled `SmallVec`s
+#[cfg(feature = "smallvec")]
+#[test]
+fn from_smallvec() {
+    use smallvec::{SmallVec, smallvec};
+
+    let inline: SmallVec<[String; 4]> = smallvec!["a".to_owned(); 4];
+    assert!(!inline.spilled());
+    let lock = GrowLock::from(inline);
+    assert_eq!(lock, ["a"; 4]);
+
+    let spilled: SmallVec<[String; 4]> = smallvec!["b".to_owned(); 5];
+    assert!(spilled.spilled());
+    let ptr = spilled.as_ptr();
+    let lock = GrowLock::from(spilled);
+    assert_eq!(lock, ["b"; 5]);
+    assert_eq!(lock.as_ptr(), ptr);
+}
+/// Tests that `append_smallvec` moves elements exactly once, from inline
+/// and spilled `SmallVec`s
+#[cfg(feature = "smallvec")]
+#[test]
+fn append_smallvec() {
+    use smallvec::SmallVec;
+
+    let counter = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(10);
+    let mut guard = lock.write().unwrap();
+
+    let mut inline: SmallVec<[AddOnDrop; 4]> =
+        (0..4).map(|_| AddOnDrop(&counter)).collect();
+    guard.append_smallvec(&mut inline);
+    assert!(inline.is_empty());
+
+    let mut spilled: SmallVec<[AddOnDrop; 4]> =
+        (0..5).map(|_| AddOnDrop(&counter)).collect();
+    assert!(spilled.spilled());
+    guard.append_smallvec(&mut spilled);
+    assert!(spilled.is_empty());
+    drop((inline, spilled));
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+    assert_eq!(guard.len(), 9);
+
+    let mut too_many: SmallVec<[AddOnDrop; 4]> =
+        (0..2).map(|_| AddOnDrop(&counter)).collect();
+    let result =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            guard.append_smallvec(&mut too_many);
+        }));
+    assert!(result.is_err());
+    assert_eq!(too_many.len(), 2);
+    drop(guard);
+    drop(lock);
+    assert_eq!(counter.load(Ordering::Relaxed), 9);
+}
+
+/// Tests a `GrowLock` allocated in a `bumpalo` arena, which is dropped
+/// after the lock
+#[cfg(feature = "allocator-api2")]
+#[test]
+fn bumpalo_arena() {
+    use bumpalo::Bump;
+
+    let bump = Bump::new();
+    let counter = AtomicUsize::new(0);
+    let lock = GrowLock::<AddOnDrop, _>::with_capacity_in(4, &bump);
+    let ptr = lock.as_ptr().cast::<u8>();
+    lock.write()
+        .unwrap()
+        .extend((0..3).map(|_| AddOnDrop(&counter)));
+    assert_eq!(lock.len(), 3);
+    assert!(
+        lock.iter()
+            .all(|elem| std::ptr::eq(elem.0, &raw const counter))
+    );
+    drop(lock);
+    assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+    // The buffer was the last allocation of the arena, so it was given
+    // back (only once) and it's reused by the next one, which doesn't
+    // overlap with the following allocations.
+    let lock = GrowLock::<u64, _>::with_capacity_in(4, &bump);
+    assert_eq!(lock.as_ptr().cast::<u8>(), ptr);
+    lock.write().unwrap().extend([1, 2, 3, 4]);
+    let after = bump.alloc([u8::MAX; 64]);
+    assert_eq!(lock, [1, 2, 3, 4]);
+    assert_eq!(*after, [u8::MAX; 64]);
+}
+
+/// Comparisons with `Vec` and `Cow` should work in both directions, also
+/// between different element types and allocators
+#[test]
+fn eq_vec_cow() {
+    use std::borrow::Cow;
+
+    let lock = grow_lock_in!(System; 10, ["a".to_owned(), "b".to_owned()]);
+    let vec = vec!["a", "b"];
+    assert_eq!(vec, lock);
+    assert_eq!(lock, vec);
+    assert_ne!(vec![String::new()], lock);
+
+    let borrowed: Cow<[&str]> = Cow::Borrowed(&["a", "b"]);
+    let owned: Cow<[&str]> = Cow::Owned(vec!["a"]);
+    assert_eq!(lock, borrowed);
+    assert_eq!(borrowed, lock);
+    assert_ne!(lock, owned);
+    assert_ne!(owned, lock);
+}
+
+/// The ordering should match the one of `Vec`
+#[test]
+// the keys are never pushed to
+#[expect(clippy::mutable_key_type)]
+fn ordering() {
+    use std::collections::BTreeSet;
+
+    let vecs: [Vec<u8>; 7] = [
+        vec![],
+        vec![0],
+        vec![0, 0],
+        vec![0, 1],
+        vec![1],
+        vec![1, 0, 0],
+        vec![2],
+    ];
+    for a in &vecs {
+        let lock_a = GrowLock::from_slice(a, 5);
+        for b in &vecs {
+            let lock_b = GrowLock::from(b.clone());
+            assert_eq!(lock_a.cmp(&lock_b), a.cmp(b));
+            assert_eq!(lock_a.partial_cmp(&lock_b), a.partial_cmp(b));
+            assert_eq!(lock_a.partial_cmp(b), a.partial_cmp(b));
+            assert_eq!(a.partial_cmp(&lock_b), a.partial_cmp(b));
+            assert_eq!(lock_a.partial_cmp(b.as_slice()), a.partial_cmp(b));
+        }
+    }
+
+    let set: BTreeSet<_> =
+        vecs.iter().rev().cloned().map(GrowLock::from).collect();
+    assert!(set.iter().eq(vecs.iter()));
+    assert!(set.contains([0, 1].as_slice()));
+
+    let nan = GrowLock::from([f64::NAN]);
+    assert_eq!(nan.partial_cmp(&nan), None);
+}
+
+// ------------------- io -------------------
+
+#[test]
+fn io_write() {
+    use std::io::{self, IoSlice, Write};
+
+    let lock = GrowLock::<u8>::with_capacity(16);
+    let mut guard = lock.write().unwrap();
+    let (key, value) = ("a", 1);
+    write!(guard, "{key}={value};").unwrap();
+    assert_eq!(lock, *b"a=1;");
+
+    let mut src = io::Cursor::new(b"0123456789".to_vec());
+    assert_eq!(io::copy(&mut src, &mut guard).unwrap(), 10);
+    assert_eq!(lock, *b"a=1;0123456789");
+
+    let bufs =
+        [IoSlice::new(b"x"), IoSlice::new(b"yz"), IoSlice::new(b"w")];
+    assert_eq!(guard.write_vectored(&bufs).unwrap(), 2);
+    assert!(guard.is_full());
+    assert_eq!(guard.write(b"!").unwrap(), 0);
+    let err = guard.write_all(b"!").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    assert_eq!(lock, *b"a=1;0123456789xy");
+}
+
+/// Reads at most `chunk` bytes at a time, then fails after `fail_after`
+/// bytes
+#[cfg(feature = "nightly")]
+struct ChunkedReader<'a> {
+    data: &'a [u8],
+    chunk: usize,
+    fail_after: usize,
+}
+#[cfg(feature = "nightly")]
+impl std::io::Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.fail_after == 0 {
+            return Err(std::io::Error::other("reader failed"));
+        }
+        let count = buf
+            .len()
+            .min(self.chunk)
+            .min(self.fail_after)
+            .min(self.data.len());
+        buf[..count].copy_from_slice(&self.data[..count]);
+        self.data = &self.data[count..];
+        self.fail_after -= count;
+        Ok(count)
+    }
+}
+#[test]
+#[cfg(feature = "nightly")]
+fn extend_from_reader() {
+    let lock = GrowLock::<u8>::with_capacity(8);
+    let mut guard = lock.write().unwrap();
+    let mut reader = ChunkedReader {
+        data: b"0123456789",
+        chunk: 3,
+        fail_after: 5,
+    };
+    assert_eq!(guard.extend_from_reader(&mut reader).unwrap(), 3);
+    assert_eq!(guard.extend_from_reader(&mut reader).unwrap(), 2);
+    assert_eq!(lock, *b"01234");
+    assert!(guard.extend_from_reader(&mut reader).is_err());
+    assert_eq!(lock, *b"01234");
+
+    reader.fail_after = usize::MAX;
+    assert_eq!(guard.extend_from_reader(&mut reader).unwrap(), 3);
+    assert!(guard.is_full());
+    // full buffer
+    assert_eq!(guard.extend_from_reader(&mut reader).unwrap(), 0);
+    assert_eq!(lock, *b"01234567");
+
+    // EOF
+    let lock = GrowLock::<u8>::with_capacity(8);
+    let mut guard = lock.write().unwrap();
+    assert_eq!(
+        guard.extend_from_reader(&mut std::io::empty()).unwrap(),
+        0
+    );
+    assert!(guard.is_empty());
+}
+#[test]
+#[cfg(feature = "nightly")]
+fn extend_from_reader_exact() {
+    let lock = GrowLock::<u8>::with_capacity(8);
+    let mut guard = lock.write().unwrap();
+    let mut reader = ChunkedReader {
+        data: b"012345678",
+        chunk: 3,
+        fail_after: 6,
+    };
+    guard.extend_from_reader_exact(&mut reader, 4).unwrap();
+    assert_eq!(lock, *b"0123");
+    // fails midway: nothing is published
+    assert!(guard.extend_from_reader_exact(&mut reader, 3).is_err());
+    assert_eq!(lock, *b"0123");
+    // over the capacity
+    reader.fail_after = usize::MAX;
+    assert!(guard.extend_from_reader_exact(&mut reader, 5).is_err());
+    assert_eq!(lock, *b"0123");
+    // EOF
+    let err = guard.extend_from_reader_exact(&mut reader, 4).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    assert_eq!(lock, *b"0123");
+}
+
+#[test]
+fn reader_chunks() {
+    use std::io::{BufRead, Read};
+
+    let data: Vec<u8> = (0..=255).collect();
+    let lock = GrowLock::from_slice(&data, 300);
+    let mut reader = lock.reader();
+    let mut other = lock.reader();
+    let mut read = Vec::new();
+    let mut buf = [0; 7];
+    loop {
+        let n = reader.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        read.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(read, data);
+    assert_eq!(reader.position(), 256);
+
+    // independent position
+    assert_eq!(other.fill_buf().unwrap(), &data[..]);
+    other.consume(250);
+    assert_eq!(other.fill_buf().unwrap(), &data[250..]);
+    other.consume(100);
+    assert_eq!(other.position(), 256);
+    assert_eq!(other.fill_buf().unwrap(), &[0u8; 0]);
+}
+/// A reader should see the bytes pushed by a concurrent writer
+#[test]
+fn reader_concurrent_writer() {
+    use std::io::Read;
+
+    let data: Vec<u8> = (0..=250).cycle().take(1000).collect();
+    let lock = GrowLock::<u8>::with_capacity(data.len());
+    thread::scope(|s| {
+        s.spawn(|| {
+            for chunk in data.chunks(13) {
+                lock.write().unwrap().extend(chunk.iter().copied());
+                thread::yield_now();
+            }
+        });
+        let mut reader = lock.reader();
+        let mut read = Vec::new();
+        let mut buf = [0; 5];
+        while read.len() < data.len() {
+            let n = reader.read(&mut buf).unwrap();
+            read.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(read, data);
+    });
+}
+
+/// A multi-byte character at the capacity edge must not be split
+#[test]
+fn fmt_write_utf8_edge() {
+    use std::fmt::Write;
+
+    let lock = GrowLock::<u8>::with_capacity(5);
+    let mut guard = lock.write().unwrap();
+    guard.push_str("abc").unwrap();
+    // 'é' is 2 bytes and fits, '€' is 3 bytes and doesn't
+    assert_eq!(guard.push_str("€"), Err(LengthError::new(3, 5)));
+    assert_eq!(lock.as_str(), Ok("abc"));
+    let c = '€';
+    assert!(write!(guard, "{c}").is_err());
+    assert_eq!(lock.as_str(), Ok("abc"));
+    guard.write_char('é').unwrap();
+    assert!(guard.is_full());
+    assert_eq!(lock.as_str(), Ok("abcé"));
+    assert_eq!(guard.push_str(""), Ok(()));
+}
+
+#[test]
+fn write_owned() {
+    let lock = Arc::new(GrowLock::with_capacity(4));
+    let mut guard = lock.write_owned().unwrap();
+    let weak = Arc::downgrade(&lock);
+    drop(lock);
+    // the guard keeps the `GrowLock` alive
+    guard.push("a".to_owned());
+    guard.extend(["b".to_owned()]);
+    assert_eq!(*guard, ["a", "b"]);
+    let lock = Arc::clone(OwnedGrowGuard::lock(&guard));
+    assert!(lock.try_write().is_err());
+    drop(guard);
+    assert!(lock.try_write().is_ok());
+    drop(lock);
+    assert!(weak.upgrade().is_none());
+}
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_write_owned() {
+    use tokio::io::{self, AsyncWriteExt};
+
+    let lock = Arc::new(GrowLock::<u8>::with_capacity(1024));
+    let (mut client, mut server) = io::duplex(64);
+    let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let writer = async {
+        client.write_all(&data).await.unwrap();
+        drop(client);
+    };
+    let copy = async {
+        let mut guard = lock.write_owned().unwrap();
+        io::copy(&mut server, &mut guard).await.unwrap()
+    };
+    let ((), copied) = tokio::join!(writer, copy);
+    assert_eq!(copied, 1000);
+    assert_eq!(*lock, data);
+
+    // a full buffer is an error, not pending forever
+    let mut guard = lock.write_owned().unwrap();
+    guard.write_all(&[0; 24]).await.unwrap();
+    let err = guard.write_all(&[0]).await.unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+}
+
+/// Tests that an owned guard can be moved into a spawned task and to
+/// another thread, and keeps the other writers out until it's dropped.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn write_owned_send() {
+    let lock = Arc::new(GrowLock::with_capacity(4));
+    let mut guard = lock.write_owned().unwrap();
+    guard.push(1);
+    let mut guard = tokio::spawn(async move {
+        tokio::task::yield_now().await;
+        guard.push(2);
+        guard
+    })
+    .await
+    .unwrap();
+    assert!(lock.try_write().is_err());
+    let writer = thread::spawn({
+        let lock = Arc::clone(&lock);
+        move || lock.write().unwrap().push(4)
+    });
+    thread::spawn(move || guard.push(3)).join().unwrap();
+    writer.join().unwrap();
+    assert_eq!(*lock, [1, 2, 3, 4]);
+}
+
+/// Tests that many tasks waiting for the length are woken up by a writer
+/// on another thread.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_wait_for_len() {
+    let lock = Arc::new(GrowLock::with_capacity(100));
+    let mut rx = lock.watch_len();
+    let writer = thread::spawn({
+        let lock = Arc::clone(&lock);
+        move || {
+            for i in 0..100 {
+                thread::sleep(Duration::from_micros(100));
+                lock.write().unwrap().push(i);
+            }
+        }
+    });
+    let (a, b, c, last) = tokio::join!(
+        lock.async_wait_for_len(10),
+        lock.async_wait_for_len(50),
+        lock.async_wait_for_len(100),
+        async {
+            rx.wait_for(|&len| len == 100).await.unwrap();
+            *rx.borrow()
+        },
+    );
+    assert!(a && b && c);
+    assert_eq!(last, 100);
+    assert_eq!(lock.len(), 100);
+    writer.join().unwrap();
+
+    assert!(lock.async_wait_for_len(100).await);
+    assert!(!lock.async_wait_for_len(101).await);
+    assert_eq!(*lock.watch_len().borrow(), 100);
+}
+
+/// Tests that the channel of `watch_len` is dropped by a write once its
+/// receivers are dropped, and created again by the next call.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn watch_len_dropped() {
+    let lock = GrowLock::with_capacity(4);
+    drop(lock.watch_len());
+    assert_eq!(lock.notifier.listeners(), 1);
+    lock.write().unwrap().push(1);
+    assert_eq!(lock.notifier.listeners(), 0);
+    let mut rx = lock.watch_len();
+    assert_eq!(*rx.borrow_and_update(), 1);
+    lock.write().unwrap().push(2);
+    rx.changed().await.unwrap();
+    assert_eq!(*rx.borrow_and_update(), 2);
+
+    // a receiver left keeps the channel
+    let rx2 = rx.clone();
+    drop(rx);
+    lock.write().unwrap().push(3);
+    assert_eq!(*rx2.borrow(), 3);
+    assert_eq!(lock.notifier.listeners(), 1);
+}
+
+/// Tests that the async writers (which await while holding the lock) and
+/// a sync writer are serialized.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn write_async_contending() {
+    let lock = Arc::new(GrowLock::with_capacity(50));
+    let sync_writer = thread::spawn({
+        let lock = Arc::clone(&lock);
+        move || lock.write().unwrap().extend(0..10)
+    });
+    // `tokio::spawn` also checks that the future is `Send`.
+    let tasks: Vec<_> = (1..5)
+        .map(|i| {
+            let lock = Arc::clone(&lock);
+            tokio::spawn(async move {
+                let mut guard = lock.write_async().await;
+                for x in i * 10..(i + 1) * 10 {
+                    guard.push(x);
+                    tokio::task::yield_now().await;
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+    sync_writer.join().unwrap();
+
+    assert!(lock.is_full());
+    let mut starts: Vec<_> = lock
+        .as_chunks::<10>()
+        .0
+        .iter()
+        .map(|chunk| {
+            assert!(chunk.iter().copied().eq(chunk[0]..chunk[0] + 10));
+            chunk[0]
+        })
+        .collect();
+    starts.sort_unstable();
+    assert_eq!(starts, [0, 10, 20, 30, 40]);
+}
+
+/// Tests that `write_async` waits for the tickets held across `.await`s
+/// on the same (single-threaded) executor without blocking it, and that
+/// it releases the lock if it's cancelled while waiting for them.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn write_async_waits_for_tickets() {
+    use futures_util::FutureExt;
+
+    let lock = GrowLock::with_capacity(10);
+    drop(lock.write().unwrap());
+    let first = lock.reserve_ticket(2).unwrap();
+    assert!(lock.write_async().now_or_never().is_none());
+    // the slots can still be reserved without the write lock.
+    let second = lock.reserve_ticket(1).unwrap();
+    let writer = async {
+        lock.write_async().await.push(4);
+    };
+    let committer = async {
+        tokio::task::yield_now().await;
+        second.commit_with(|_| 3);
+        tokio::task::yield_now().await;
+        first.commit_with(|i| i);
+    };
+    tokio::join!(writer, committer);
+    assert_eq!(*lock, [0, 1, 3, 4]);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn write_async_blocks_sync_writers() {
+    use std::{panic, sync::TryLockError};
+
+    let lock = GrowLock::with_capacity(10);
+    // the async writers ignore the poisoning.
+    let _ = panic::catch_unwind(|| {
+        let _guard = lock.write();
+        panic!("poison");
+    });
+    let mut guard = lock.write_async().await;
+    guard.push(1);
+    assert!(matches!(lock.try_write(), Err(TryLockError::WouldBlock)));
+    thread::scope(|s| {
+        let writer = s.spawn(|| {
+            let Err(e) = lock.write() else {
+                panic!("the lock should be poisoned");
+            };
+            e.into_inner().push(3);
+        });
+        thread::sleep(Duration::from_millis(10));
+        guard.push(2);
+        drop(guard);
+        writer.join().unwrap();
+    });
+    assert_eq!(*lock, [1, 2, 3]);
+}
+
+/// Tests that an async writer can lock the [`GrowLock`] while a guard
+/// waits on a [`Condvar`](std::sync::Condvar), even if it started waiting
+/// for it before.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn write_async_during_guard_wait() {
+    use std::sync::{Barrier, Condvar};
+
+    let lock = Arc::new(GrowLock::with_capacity(10));
+    let cv = Arc::new(Condvar::new());
+    let locked = Arc::new(Barrier::new(2));
+    let waiter = thread::spawn({
+        let (lock, cv, locked) =
+            (Arc::clone(&lock), Arc::clone(&cv), Arc::clone(&locked));
+        move || {
+            let guard = lock.write().unwrap();
+            locked.wait();
+            // lets the async writer find the lock taken.
+            thread::sleep(Duration::from_millis(10));
+            let mut guard =
+                guard.wait_while(&cv, <[_]>::is_empty).unwrap();
+            guard.push(2);
+        }
+    });
+    locked.wait();
+    lock.write_async().await.push(1);
+    cv.notify_all();
+    waiter.join().unwrap();
+    assert_eq!(*lock, [1, 2]);
+}
+
+/// Tests that the notifications of the writers which lock the
+/// [`GrowLock`] while a guard waits on a
+/// [`Condvar`](std::sync::Condvar) are never lost, with the async writers
+/// enabled.
+#[cfg(feature = "tokio")]
+#[test]
+fn guard_wait_notified_by_writers() {
+    use std::sync::Condvar;
+
+    let lock = GrowLock::with_capacity(200);
+    let cv = Condvar::new();
+    // each thread pushes the lengths with its own parity.
+    let push_turns = |parity| {
+        for _ in 0..100 {
+            let guard = lock.write().unwrap();
+            let mut guard =
+                guard.wait_while(&cv, |e| e.len() % 2 != parity).unwrap();
+            guard.push(guard.len());
+            drop(guard);
+            cv.notify_all();
+        }
+    };
+    thread::scope(|s| {
+        s.spawn(|| push_turns(1));
+        push_turns(0);
+    });
+    assert!(lock.iter().copied().eq(0..200));
+}
+
+/// Tests that a stream racing a producer yields every element in order.
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn stream_from_producer() {
+    use futures_util::StreamExt;
+
+    let lock = Arc::new(GrowLock::with_capacity(1_000));
+    let head = Arc::clone(&lock).stream_from(0).take(500);
+    let tail = Arc::clone(&lock).stream_from(900);
+    let producer = thread::spawn({
+        let lock = Arc::clone(&lock);
+        move || {
+            for i in 0..1_000 {
+                if i % 100 == 0 {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                lock.write().unwrap().push(i);
+            }
+        }
+    });
+    let (head, tail) =
+        tokio::join!(head.collect::<Vec<_>>(), tail.collect::<Vec<_>>());
+    producer.join().unwrap();
+    assert!(head.into_iter().eq(0..500));
+    assert!(tail.into_iter().eq(900..1_000));
+    assert_eq!(lock.stream_from(1_001).count().await, 0);
+}
+
+/// The parallel iterator should only visit the elements pushed before
+/// its creation
+#[cfg(feature = "rayon")]
+#[test]
+fn par_iter_snapshot() {
+    use rayon::prelude::*;
+
+    let lock = GrowLock::from_iter_with_capacity(20_000, 0..10_000u64);
+    thread::scope(|s| {
+        let iter = lock.par_iter();
+        s.spawn(|| lock.write().unwrap().extend(10_000..20_000));
+        let sum: u64 = iter.map(|&x| x).sum();
+        assert_eq!(sum, (0..10_000u64).sum::<u64>());
+    });
+    assert!(lock.is_full());
+
+    let doubled: Vec<u64> = lock.into_par_iter().map(|x| x * 2).collect();
+    assert!(doubled.iter().copied().eq((0..20_000).map(|x| x * 2)));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_extend() {
+    use rayon::prelude::*;
+
+    let lock = GrowLock::with_capacity(10_000);
+    let mut guard = lock.write().unwrap();
+    guard.extend([0, 1]);
+    // indexed
+    guard.par_extend((2..5_000).into_par_iter());
+    // unindexed
+    guard.par_extend((5_000..10_000).into_par_iter().filter(|_| true));
+    assert!(guard.is_full());
+    assert!(lock.iter().copied().eq(0..10_000));
+}
+/// A panicking worker must not publish anything, and must drop the
+/// elements written by the other workers exactly once
+#[cfg(feature = "rayon")]
+#[test]
+fn par_extend_panic() {
+    use {
+        crate::sync::PoisonError,
+        rayon::prelude::*,
+        std::{iter, panic},
+    };
+
+    let created = AtomicUsize::new(0);
+    let dropped = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(1_000);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        let mut guard = lock.write().unwrap();
+        guard.extend(iter::repeat_with(|| AddOnDrop(&dropped)).take(10));
+        guard.par_extend((0..990).into_par_iter().map(|i| {
+            assert!(i != 500, "worker panicked");
+            created.fetch_add(1, Ordering::Relaxed);
+            AddOnDrop(&dropped)
+        }));
+    }));
+    assert!(result.is_err());
+    // only the elements pushed before `par_extend` are published
+    assert_eq!(lock.len(), 10);
+    let written = created.load(Ordering::Relaxed);
+    assert!(written < 990);
+    assert_eq!(dropped.load(Ordering::Relaxed), written);
+
+    // the lock is poisoned, but still usable
+    let guard = lock.write();
+    assert!(guard.is_err());
+    let mut guard = guard.unwrap_or_else(PoisonError::into_inner);
+    assert_eq!(guard.len(), 10);
+    guard.push(AddOnDrop(&dropped));
+    drop(guard);
+    assert_eq!(lock.len(), 11);
+    assert!(lock.write().is_err());
+    drop(lock);
+    assert_eq!(dropped.load(Ordering::Relaxed), written + 11);
+
+    let lock = GrowLock::with_capacity(1_000);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        lock.write()
+            .unwrap()
+            .par_extend((0..1_001).into_par_iter().map(|_| 0u8));
+    }));
+    assert!(result.is_err());
+    assert!(lock.is_empty());
+}
+
+#[cfg(feature = "arbitrary")]
+#[test]
+fn arbitrary() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    let bytes: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let mut u = Unstructured::new(&bytes);
+    while !u.is_empty() {
+        let lock = GrowLock::<u16>::arbitrary(&mut u).unwrap();
+        assert!(lock.len() <= lock.capacity());
+        assert!(lock.capacity() - lock.len() <= 64);
+    }
+    let lock =
+        GrowLock::<u8>::arbitrary_take_rest(Unstructured::new(&bytes))
+            .unwrap();
+    assert!(lock.len() <= lock.capacity());
+}
+/// A `GrowLock` should behave like a `Vec` with a fixed capacity
+#[cfg(feature = "proptest")]
+mod proptest_vec {
+    use {
+        crate::proptest::grow_lock,
+        proptest::{collection::vec, prelude::*},
+    };
+
+    proptest! {
+        #[test]
+        fn push_like_vec(
+            lock in grow_lock(any::<i32>(), 0..32),
+            pushed in vec(any::<i32>(), 0..32),
+        ) {
+            let mut expected = lock.to_vec();
+            let mut guard = lock.write().unwrap();
+            for &x in &pushed {
+                let fits = expected.len() < lock.capacity();
+                prop_assert_eq!(guard.try_push(x).is_ok(), fits);
+                if fits {
+                    expected.push(x);
+                }
+            }
+            drop(guard);
+            prop_assert_eq!(&lock, &expected);
+            prop_assert_eq!(Vec::from(lock), expected);
+        }
+    }
+}
+
+/// Helper allocator that returns blocks starting `offset` bytes past an
+/// address aligned to 8, to choose the alignment of the buffers.
+#[cfg(feature = "bytemuck")]
+struct OffsetAlloc {
+    offset: usize,
+}
+#[cfg(feature = "bytemuck")]
+impl OffsetAlloc {
+    /// The layout of the block allocated by `System` for `layout`.
+    fn outer(&self, layout: Layout) -> Result<Layout, AllocError> {
+        if !self.offset.is_multiple_of(layout.align()) {
+            return Err(AllocError);
+        }
+        let size =
+            layout.size().checked_add(self.offset).ok_or(AllocError)?;
+        Layout::from_size_align(size, layout.align().max(8))
+            .map_err(|_| AllocError)
+    }
+}
+// SAFETY: every block is a part of a block allocated by `System`, which is
+// deallocated with the same layout.
+#[cfg(feature = "bytemuck")]
+unsafe impl Allocator for OffsetAlloc {
+    fn allocate(
+        &self,
+        layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let block = System.allocate(self.outer(layout)?)?;
+        // SAFETY: the outer block has `offset` more bytes.
+        let ptr = unsafe { block.cast::<u8>().add(self.offset) };
+        Ok(NonNull::slice_from_raw_parts(ptr, layout.size()))
+    }
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let outer = self.outer(layout).unwrap();
+        // SAFETY: `ptr` is `offset` bytes into a block allocated with
+        // `outer`.
+        unsafe { System.deallocate(ptr.sub(self.offset), outer) }
+    }
+}
+
+/// Tests that `cast_slice` fails when the buffer is misaligned for the
+/// target type, whatever the alignment given by the global allocator.
+#[cfg(feature = "bytemuck")]
+#[test]
+fn bytemuck_cast_misaligned() {
+    use bytemuck::PodCastError;
+
+    let lock =
+        GrowLock::from_slice_in(&[0u8; 8], 16, OffsetAlloc { offset: 1 });
+    assert_eq!(
+        lock.cast_slice::<u16>(),
+        Err(PodCastError::TargetAlignmentGreaterAndInputNotAligned)
+    );
+    assert_eq!(lock.cast_slice::<[u8; 2]>(), Ok(&[[0; 2]; 4][..]));
+
+    // aligned for `u16`, but not for `u32`
+    let lock =
+        GrowLock::from_slice_in(&[0u8; 8], 16, OffsetAlloc { offset: 2 });
+    assert_eq!(lock.cast_slice::<u16>(), Ok(&[0; 4][..]));
+    assert_eq!(
+        lock.cast_slice::<u32>(),
+        Err(PodCastError::TargetAlignmentGreaterAndInputNotAligned)
+    );
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn bytemuck_cast() {
+    use {crate::error::ExtendFromBytesError, bytemuck::PodCastError};
+
+    let words = [0x0102_0304u32, 0x0506_0708, 0x090a_0b0c];
+    let bytes: Vec<u8> =
+        words.iter().flat_map(|w| w.to_ne_bytes()).collect();
+
+    // the buffer of a `GrowLock<u8>` is only aligned for `u32` if the
+    // allocator aligns it.
+    let lock =
+        GrowLock::from_slice_in(&bytes, 16, OffsetAlloc { offset: 0 });
+    assert_eq!(lock.as_bytes(), bytes);
+    assert_eq!(lock.cast_slice::<u32>().unwrap(), words);
+    lock.write().unwrap().push(0);
+    assert_eq!(
+        lock.cast_slice::<u32>(),
+        Err(PodCastError::OutputSliceWouldHaveSlop)
+    );
+    // misaligned offsets in the source are copied, not cast
+    let words_lock = GrowLock::<u32>::with_capacity(3);
+    let mut guard = words_lock.write().unwrap();
+    let mut shifted = vec![0u8];
+    shifted.extend_from_slice(&bytes);
+    guard.extend_from_bytes(&shifted[1..5]).unwrap();
+    guard.extend_from_bytes(&shifted[5..]).unwrap();
+    assert_eq!(words_lock, words);
+    assert_eq!(words_lock.as_bytes(), bytes);
+
+    assert_eq!(
+        guard.extend_from_bytes(&shifted[1..4]),
+        Err(ExtendFromBytesError::Cast(
+            PodCastError::OutputSliceWouldHaveSlop
+        )),
+    );
+    assert_eq!(
+        guard.extend_from_bytes(&shifted[1..5]),
+        Err(ExtendFromBytesError::Length(LengthError::new(3, 3))),
+    );
+    assert_eq!(words_lock, words);
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn snapshot_round_trip() {
+    use std::io::{Cursor, ErrorKind};
+
+    let lock =
+        GrowLock::from_iter_with_capacity(100, (0..50u64).map(|x| x * 3));
+    let mut file = Cursor::new(Vec::new());
+    lock.write_snapshot(&mut file).unwrap();
+    let bytes = file.into_inner();
+    assert_eq!(bytes.len(), 24 + 50 * 8);
+
+    let read = GrowLock::<u64>::read_snapshot(&bytes[..], 10).unwrap();
+    assert_eq!(read, lock);
+    assert_eq!(read.capacity(), 60);
+
+    // truncated
+    for end in [0, 10, 24, 25, bytes.len() - 1] {
+        let err =
+            GrowLock::<u64>::read_snapshot(&bytes[..end], 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+    // different element size
+    let err = GrowLock::<u32>::read_snapshot(&bytes[..], 0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    // corrupted
+    let mut corrupted = bytes.clone();
+    corrupted[0] ^= 1;
+    let err =
+        GrowLock::<u64>::read_snapshot(&corrupted[..], 0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    // over-stated length
+    let mut corrupted = bytes;
+    corrupted[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert!(GrowLock::<u64>::read_snapshot(&corrupted[..], 0).is_err());
+    corrupted[16..24].copy_from_slice(&51u64.to_le_bytes());
+    let err =
+        GrowLock::<u64>::read_snapshot(&corrupted[..], 0).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+// `miri` doesn't support `mmap`.
+#[cfg(all(feature = "mmap", not(miri)))]
+#[test]
+fn mmap_reopen() {
+    use {crate::mmap::MmapBacking, std::io::ErrorKind};
+    type MmapLock<T> = GrowLock<T, MmapBacking>;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lock");
+    // SAFETY: the file is only used by this test.
+    let lock = unsafe { MmapLock::<u64>::create_mmap(&path, 8) }.unwrap();
+    assert!(lock.is_empty());
+    assert_eq!(lock.capacity(), 8);
+    lock.write().unwrap().extend([1, 2, 3, 4, 5]);
+    lock.flush().unwrap();
+    // not flushed
+    lock.write().unwrap().push(6);
+    drop(lock);
+
+    // SAFETY: the file is only used by this test.
+    let lock =
+        unsafe { MmapLock::<u64>::from_mmap_readonly(&path) }.unwrap();
+    assert_eq!(lock, [1, 2, 3, 4, 5]);
+    assert!(lock.is_full());
+    assert_eq!(
+        lock.write().unwrap().try_push(7),
+        Err(PushError::new(7, 5))
+    );
+    // the buffer moves to the heap when it grows (a `Vec` with a custom
+    // allocator needs the `nightly` feature).
+    #[cfg(feature = "nightly")]
+    {
+        let mut vec = Vec::from(lock);
+        vec.push(7);
+        assert_eq!(vec, [1, 2, 3, 4, 5, 7]);
+    }
+
+    // a snapshot can be mapped too.
+    let snapshot = dir.path().join("snapshot");
+    let lock = GrowLock::from_iter_with_capacity(10, 0..4u32);
+    lock.write_snapshot(std::fs::File::create(&snapshot).unwrap())
+        .unwrap();
+    // SAFETY: the file is only used by this test.
+    let mapped =
+        unsafe { MmapLock::<u32>::from_mmap_readonly(&snapshot) }.unwrap();
+    assert_eq!(mapped, lock);
+    drop(mapped);
+
+    // different element size
+    // SAFETY: the file is only used by this test.
+    let err = unsafe { MmapLock::<u16>::from_mmap_readonly(&snapshot) }
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    // truncated
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&snapshot)
+        .unwrap();
+    file.set_len(24 + 3 * 4).unwrap();
+    // SAFETY: the file is only used by this test.
+    let err = unsafe { MmapLock::<u32>::from_mmap_readonly(&snapshot) }
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+}
+
+/// `flush` waits for the owned guards (and the async writers) like
+/// `write`, instead of writing the header under them.
+#[cfg(all(feature = "mmap", not(miri)))]
+#[test]
+fn mmap_flush_waits_for_owned_guard() {
+    use {crate::mmap::MmapBacking, std::sync::mpsc};
+    type MmapLock<T> = GrowLock<T, MmapBacking>;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lock");
+    // SAFETY: the file is only used by this test.
+    let lock = Arc::new(
+        unsafe { MmapLock::<u64>::create_mmap(&path, 8) }.unwrap(),
+    );
+    let mut guard = lock.write_owned().unwrap();
+    guard.push(1);
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn({
+        let lock = Arc::clone(&lock);
+        move || {
+            lock.flush().unwrap();
+            tx.send(()).unwrap();
+        }
+    });
+    assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+    guard.push(2);
+    drop(guard);
+    rx.recv_timeout(Duration::from_secs(10))
+        .expect("`flush` didn't wake up");
+    handle.join().unwrap();
+    drop(lock);
+
+    // SAFETY: the file is only used by this test.
+    let lock =
+        unsafe { MmapLock::<u64>::from_mmap_readonly(&path) }.unwrap();
+    assert_eq!(lock, [1, 2]);
+}
+
+#[cfg(all(feature = "zeroize", feature = "nightly"))]
+#[test]
+fn zeroize() {
+    use {
+        std::{slice, sync::atomic::AtomicBool},
+        zeroize::{Zeroize, Zeroizing},
+    };
+
+    /// Helper allocator that checks if the buffer is wiped before
+    /// deallocating it
+    struct WipedAlloc<'a>(&'a AtomicBool);
+    // SAFETY: every call is forwarded to `System`.
+    unsafe impl Allocator for WipedAlloc<'_> {
+        fn allocate(
+            &self,
+            layout: Layout,
+        ) -> Result<NonNull<[u8]>, AllocError> {
+            System.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            // SAFETY: the buffer is allocated for `layout.size()` bytes,
+            // which must be initialized for this test.
+            let bytes = unsafe {
+                slice::from_raw_parts(ptr.as_ptr(), layout.size())
+            };
+            self.0
+                .store(bytes.iter().all(|&b| b == 0), Ordering::Relaxed);
+            // SAFETY: the safety contract must be upheld by the caller
+            unsafe { System.deallocate(ptr, layout) }
+        }
+    }
+
+    let mut lock = GrowLock::from_str_with_capacity("secret", 16);
+    lock.zeroize();
+    assert!(lock.is_empty());
+    assert_eq!(lock.capacity(), 16);
+    let (ptr, len, cap) = lock.into_parts();
+    // SAFETY: the whole buffer was zeroed.
+    let bytes = unsafe { slice::from_raw_parts(ptr.as_ptr(), cap) };
+    assert_eq!(bytes, [0; 16]);
+    // SAFETY: the parts come from `into_parts`.
+    drop(unsafe { GrowLock::from_parts(ptr, len, cap) });
+
+    let mut lock = grow_lock!(4, [vec![1u8; 8], vec![2; 8]]);
+    lock.zeroize();
+    assert!(lock.is_empty());
+
+    let wiped = AtomicBool::new(false);
+    let lock =
+        Zeroizing::new(GrowLock::with_capacity_in(32, WipedAlloc(&wiped)));
+    // only a part of the buffer is initialized.
+    lock.write().unwrap().extend(*b"hunter2");
+    drop(lock);
+    assert!(wiped.load(Ordering::Relaxed));
+}
+
+// `miri` doesn't support `mlock`.
+#[cfg(all(feature = "mlock", unix, not(miri)))]
+#[test]
+fn mlock() {
+    use crate::mlock::PageLocked;
+
+    /// Returns the amount of locked memory of the process, in kB
+    #[cfg(target_os = "linux")]
+    fn locked_kb() -> usize {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let line =
+            status.lines().find(|l| l.starts_with("VmLck:")).unwrap();
+        line.split_whitespace().nth(1).unwrap().parse().unwrap()
+    }
+
+    // SAFETY: `sysconf` has no preconditions.
+    let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    let page = usize::try_from(page).unwrap();
+    #[cfg(target_os = "linux")]
+    let before = locked_kb();
+
+    let lock = GrowLock::<u64, PageLocked>::try_with_capacity_locked(100)
+        .unwrap();
+    lock.write().unwrap().extend(0..100);
+    // the buffer takes whole pages, which are used as capacity.
+    assert!(lock.as_ptr().addr().is_multiple_of(page));
+    assert_eq!(lock.capacity(), page / size_of::<u64>());
+    #[cfg(target_os = "linux")]
+    assert_eq!(locked_kb(), before + page / 1024);
+
+    drop(lock);
+    #[cfg(target_os = "linux")]
+    assert_eq!(locked_kb(), before);
+}
+
+/// `with_capacity_locked` panics (instead of aborting) when the
+/// allocation fails, here because the block is too large to be mapped.
+#[cfg(all(feature = "mlock", unix, not(miri)))]
+#[test]
+#[should_panic(expected = "allocator returned an error")]
+fn mlock_alloc_error() {
+    use crate::mlock::PageLocked;
+
+    let _ = GrowLock::<u8, PageLocked>::with_capacity_locked(
+        isize::MAX as usize / 2,
+    );
+}
+
+#[cfg(feature = "rkyv")]
+#[test]
+fn rkyv_round_trip() {
+    use {
+        crate::rkyv::Slack,
+        rkyv::{Archive, Deserialize, Serialize, rancor::Error},
+    };
+
+    #[derive(Archive, Serialize, Deserialize)]
+    struct Level {
+        name: String,
+        ids: GrowLock<u32>,
+        #[rkyv(with = Slack<4>)]
+        tags: GrowLock<String>,
+        markers: GrowLock<()>,
+    }
+
+    let level = Level {
+        name: "intro".to_owned(),
+        ids: grow_lock!(10, [1, 2, 3]),
+        tags: grow_lock!(2, ["a".to_owned()]),
+        markers: GrowLock::with_capacity(0),
+    };
+    level.markers.write().unwrap().extend([(), ()]);
+    let bytes = rkyv::to_bytes::<Error>(&level).unwrap();
+
+    // zero-copy access
+    let archived = rkyv::access::<ArchivedLevel, Error>(&bytes).unwrap();
+    assert_eq!(archived.name, "intro");
+    assert_eq!(archived.ids.as_slice(), [1, 2, 3]);
+    assert_eq!(archived.tags.len(), 1);
+    assert_eq!(archived.tags[0], "a");
+    assert_eq!(archived.markers.len(), 2);
+
+    let read = rkyv::deserialize::<Level, Error>(archived).unwrap();
+    assert_eq!(read.name, "intro");
+    assert_eq!(read.ids, [1, 2, 3]);
+    assert!(read.ids.is_full());
+    assert_eq!(read.tags, ["a"]);
+    assert_eq!(read.tags.capacity(), 5);
+    assert_eq!(read.markers, [(), ()]);
+}
+
+/// Checks that the types can be logged (nothing is logged on the host,
+/// which has no global logger)
+#[cfg(feature = "defmt")]
+#[test]
+fn defmt_format() {
+    use crate::error::{FromUtf8Error, TryReserveError};
+
+    fn assert_format<T: defmt::Format>() {}
+
+    assert_format::<TryReserveError>();
+    assert_format::<LengthError>();
+    assert_format::<TryFillError>();
+    assert_format::<FromUtf8Error>();
+    assert_format::<GrowLock<u8>>();
+    assert_format::<GrowLock<u8, System>>();
+}
+
+/// Random values and shuffles should match the same operations on a
+/// [`Vec`], with the same seed
+#[cfg(feature = "rand")]
+#[test]
+fn rand_like_vec() {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    let lock = GrowLock::<u32>::with_capacity(100);
+    let mut rng = StdRng::seed_from_u64(7);
+    lock.write().unwrap().extend_random(&mut rng, 60);
+    let mut expected = Vec::<u32>::with_capacity(100);
+    let mut rng = StdRng::seed_from_u64(7);
+    expected.extend((0..60).map(|_| rng.random::<u32>()));
+    assert_eq!(lock, expected);
+
+    let mut lock = lock;
+    let mut rng = StdRng::seed_from_u64(8);
+    lock.shuffle(&mut rng);
+    let mut rng = StdRng::seed_from_u64(8);
+    rand::seq::SliceRandom::shuffle(&mut expected[..], &mut rng);
+    assert_eq!(lock, expected);
+    assert_eq!(lock.capacity(), 100);
+}
+
+/// `extend_random` should not sample anything if `n` exceeds the spare
+/// capacity
+#[cfg(feature = "rand")]
+#[test]
+fn rand_extend_overflow() {
+    use {
+        rand::{SeedableRng, rngs::StdRng},
+        std::panic::{AssertUnwindSafe, catch_unwind},
+    };
+
+    let lock = grow_lock!(4, [1u8, 2]);
+    let mut rng = StdRng::seed_from_u64(0);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        lock.write().unwrap().extend_random(&mut rng, 3);
+    }));
+    assert!(result.is_err());
+    assert_eq!(lock, [1, 2]);
+}
+
+// ------------------- macro init -------------------
+
+#[test]
+fn empty_macro() {
+    let lock: GrowLock<String> = grow_lock![];
+
+    assert_eq!(lock.as_slice(), &[] as &[String]);
+    assert!(lock.is_empty());
+    assert_eq!(lock.capacity(), 0);
+    let mut guard = lock.write().unwrap();
+    assert!(guard.try_push("hello world".to_owned()).is_err());
+
+    assert_eq!(lock, GrowLock::<String>::with_capacity(0));
+}
+#[test]
+fn array_macro() {
+    let lock: GrowLock<char> = grow_lock!(10, ['a', 'b', 'c']);
+
+    assert_eq!(&lock, &['a', 'b', 'c']);
+
+    let mut guard = lock.write().unwrap();
+    for _ in 0..7 {
+        guard.push('_');
+    }
+    assert!(lock.is_full());
+}
+#[test]
+fn repeat_macro() {
+    let lock: GrowLock<String> = grow_lock!(15, ["hello".to_owned(); 4]);
+    for str in &lock[..4] {
+        assert_eq!(str, "hello");
+    }
+    let mut guard = lock.write().unwrap();
+    for _ in 0..11 {
+        guard.push("world".to_owned());
+    }
+    assert!(lock.is_full());
+}
+
+#[test]
+fn array_full_macro() {
+    let lock: GrowLock<char> = grow_lock!['a', 'b', 'c'];
+    assert_eq!(&lock, &['a', 'b', 'c']);
+    assert!(lock.is_full());
+}
+
+#[test]
+fn repeat_full_macro() {
+    let lock: GrowLock<String> = grow_lock!["hello".to_owned(); 4];
+    for str in &lock[..4] {
+        assert_eq!(str, "hello");
+    }
+    assert!(lock.is_full());
+}
+
+#[test]
+fn from_iter_macro() {
+    let lock = grow_lock!(10; from 0..5);
+    assert_eq!(lock, [0, 1, 2, 3, 4]);
+    assert_eq!(lock.capacity(), 10);
+
+    let vec = vec!["a".to_owned(), "b".to_owned()];
+    let lock = grow_lock!(2; from vec.into_iter());
+    assert_eq!(lock, ["a", "b"]);
+    assert!(lock.is_full());
+
+    let mut evals = 0;
+    let lock = grow_lock_in!(System; 3; from {
+        evals += 1;
+        [1, 2]
+    });
+    assert_eq!(evals, 1);
+    assert_eq!(lock, [1, 2]);
+}
+#[test]
+#[should_panic = "length overflow"]
+fn from_iter_macro_overflow() {
+    let _ = grow_lock!(3; from 0..4);
+}
+#[test]
+fn empty_in_macro() {
+    let lock: GrowLock<String, _> = grow_lock_in!(System; 0);
+    assert!(lock.is_empty());
+    assert_eq!(lock.capacity(), 0);
+    let mut guard = lock.write().unwrap();
+    assert!(guard.try_push("hello world".to_owned()).is_err());
+}
+#[test]
+fn array_in_macro() {
+    let alloc = FailingAllocator::default();
+    let mut evals = 0;
+    let lock = grow_lock_in!(
+        {
+            evals += 1;
+            &alloc
+        };
+        10,
+        ['a', 'b', 'c']
+    );
+    assert_eq!(evals, 1);
+    assert_eq!(alloc.allocations(), 1);
+    assert_eq!(&lock, &['a', 'b', 'c']);
+    assert_eq!(lock.capacity(), 10);
+}
+#[test]
+fn repeat_in_macro() {
+    let counter = AtomicUsize::new(0);
+    let mut evals = 0;
+    let lock = grow_lock_in!(System; {
+        evals += 1;
+        AddOnDrop(&counter)
+    }; 4);
+    assert_eq!(evals, 1);
+    // the template element is moved in the `GrowLock`
+    assert_eq!(counter.load(Ordering::Relaxed), 0);
+    assert_eq!(lock.len(), 4);
+    assert!(lock.is_full());
+    drop(lock);
+    assert_eq!(counter.load(Ordering::Relaxed), 4);
+
+    let lock: GrowLock<String, _> =
+        grow_lock_in!(System; 15, ["hello".to_owned(); 4]);
+    assert_eq!(lock, ["hello"; 4]);
+    assert_eq!(lock.capacity(), 15);
+    let lock: GrowLock<String, _> =
+        grow_lock_in!(System; "a".to_owned(); 0);
+    assert!(lock.is_empty());
+}
+
+#[test]
+fn try_macro() {
+    let lock: GrowLock<String> = try_grow_lock!().unwrap();
+    assert_eq!(lock.capacity(), 0);
+    let lock = try_grow_lock!(10, ['a', 'b', 'c']).unwrap();
+    assert_eq!(&lock, &['a', 'b', 'c']);
+    assert_eq!(lock.capacity(), 10);
+    let lock = try_grow_lock!(15, ["hello".to_owned(); 4]).unwrap();
+    assert_eq!(lock, ["hello"; 4]);
+    let lock = try_grow_lock!["hello".to_owned(); 4].unwrap();
+    assert!(lock.is_full());
+
+    // must error, not panic
+    assert_eq!(
+        try_grow_lock!(0, ['a']),
+        Err(TryFillError::Length(LengthError::new(0, 0))),
+    );
+    assert_eq!(
+        try_grow_lock!(3, ['a'; 4]),
+        Err(TryFillError::Length(LengthError::new(0, 3))),
+    );
+}
+/// On failure, the elements already pushed are dropped and the remaining
+/// ones are not evaluated
+#[test]
+#[cfg(feature = "nightly")]
+fn try_macro_abort() {
+    let counter = AtomicUsize::new(0);
+    let mut evals = 0;
+    let result = try_grow_lock!(
+        2,
+        [
+            AddOnDrop(&counter),
+            AddOnDrop(&counter),
+            AddOnDrop(&counter),
+            {
+                evals += 1;
+                AddOnDrop(&counter)
+            }
+        ]
+    );
+    assert!(matches!(result, Err(TryFillError::Length(_))));
+    assert_eq!(evals, 0);
+    // the 2 pushed elements, and the one that didn't fit
+    assert_eq!(counter.load(Ordering::Relaxed), 3);
+
+    let alloc = FailingAllocator::default();
+    alloc.fail_layouts_larger_than(16);
+    let result = try_grow_lock_in!(&alloc; 100, [AddOnDrop(&counter)]);
+    assert!(matches!(
+        result,
+        Err(TryFillError::Reserve(TryReserveError::AllocError(_)))
+    ));
+    alloc.fail_layouts_larger_than(64);
+    let lock = try_grow_lock_in!(&alloc; 'a'; 4).unwrap();
+    assert_eq!(lock, ['a'; 4]);
+}
+
+// ------------------- representation -------------------
+/// Tests if [`GrowLock`] is correctly aligned, also with dangling pointers
+/// and ZSTs
+#[test]
+fn alignment() {
+    #[repr(align(64))]
+    #[allow(
+        dead_code,
+        reason = "We need a field to make `Aligned` non-ZST"
+    )]
+    struct Aligned(u64);
+    #[repr(align(128))]
+    struct AlignedZST;
+
+    let lock = GrowLock::with_capacity(10);
+    let mut guard = lock.write().unwrap();
+    for i in 0..10 {
+        guard.push(Aligned(i));
+    }
+    let addr = lock.as_ptr().addr();
+    assert_eq!(addr % 64, 0);
+
+    let lock: GrowLock<Aligned> = grow_lock![];
+    let addr = lock.as_ptr().addr();
+    assert_eq!(addr % 64, 0);
+
+    let lock: GrowLock<AlignedZST> = GrowLock::with_capacity(1);
+    let addr = lock.as_ptr().addr();
+    assert_eq!(addr % 128, 0);
+}
+
+// ------------------- push panics -------------------
+/// `push` should panic on length overflow
+#[test]
+#[should_panic(expected = "length overflow")]
+fn push_overflow() {
+    let lock = GrowLock::with_capacity(5);
+    let mut guard = lock.write().unwrap();
+    for i in 0..6 {
+        guard.push(i);
+    }
+}
+/// `try_push` should return an error on length overflow
+#[test]
+fn try_push_overflow() {
+    let lock = GrowLock::with_capacity(5);
+    let mut guard = lock.write().unwrap();
+    for i in 0..5 {
+        assert!(guard.try_push(i).is_ok());
+    }
+    assert!(guard.try_push(6).is_err());
+}
+
+/// The value rejected by `try_push` should be given back in the error,
+/// without being dropped.
+#[test]
+fn push_error_round_trip() {
+    let dropped = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(1);
+    let mut guard = lock.write().unwrap();
+    guard.push(AddOnDrop(&dropped));
+    let PushError { value, .. } =
+        guard.try_push(AddOnDrop(&dropped)).unwrap_err();
+    let value = guard.push_deferred(value).unwrap_err().into_inner();
+    let local = LocalGrowLock::with_capacity(0);
+    let value = local.write().try_push(value).unwrap_err().into_inner();
+    assert_eq!(dropped.load(Ordering::Relaxed), 0);
+    assert!(ptr::eq(value.0, &raw const dropped));
+
+    let err = crate::error::GrowLockError::from(PushError::new(value, 1));
+    assert_eq!(dropped.load(Ordering::Relaxed), 0);
+    let value = err.into_value().unwrap();
+    assert_eq!(
+        LengthError::from(PushError::new(value, 1)),
+        LengthError::new(1, 1)
+    );
+    assert_eq!(dropped.load(Ordering::Relaxed), 1);
+}
+
+/// The errors of the full locks should tell their length and capacity.
+#[test]
+fn length_error_display() {
+    let lock = GrowLock::<u8>::with_capacity(7);
+    let mut guard = lock.write().unwrap();
+    guard.push_str("abcde").unwrap();
+    let msg = guard.push_str("fgh").unwrap_err().to_string();
+    assert!(msg.contains("length: 5") && msg.contains("capacity: 7"));
+    guard.push_str("fg").unwrap();
+    let msg = guard.try_push(b'h').unwrap_err().to_string();
+    assert!(msg.contains("capacity: 7"));
+}
+
+/// The errors of the other fallible methods should convert to a
+/// `GrowLockError`, with the messages of its variants.
+#[test]
+fn grow_lock_error_variants() {
+    use crate::error::{GrowLockError, TryReserveError};
+
+    fn describe<T>(err: &GrowLockError<T>) -> &'static str {
+        match err {
+            GrowLockError::Full { .. } => "full",
+            GrowLockError::WouldBlock(_) => "would block",
+            GrowLockError::TimedOut(_) => "timed out",
+            GrowLockError::Poisoned => "poisoned",
+            GrowLockError::Alloc(_) => "alloc",
+        }
+    }
+
+    let lock = GrowLock::with_capacity(1);
+    let mut guard = lock.write().unwrap();
+    guard.push(String::from("a"));
+    let full = GrowLockError::from(
+        guard.try_push(String::from("b")).unwrap_err(),
+    );
+    assert_eq!(describe(&full), "full");
+    assert_eq!(
+        full.to_string(),
+        "the elements don't fit in the `GrowLock` (length: 1, capacity: 1)"
+    );
+    assert_eq!(full.into_value().as_deref(), Some("b"));
+
+    let would_block =
+        GrowLockError::<()>::from(lock.try_write().err().unwrap());
+    assert_eq!(describe(&would_block), "would block");
+    assert_eq!(
+        would_block.to_string(),
+        "the write lock of the `GrowLock` is held"
+    );
+    drop(guard);
+
+    let timed_out = GrowLockError::TimedOut(String::from("c"));
+    assert_eq!(describe(&timed_out), "timed out");
+    assert_eq!(
+        timed_out.to_string(),
+        "timed out waiting for the write lock of the `GrowLock`"
+    );
+    assert_eq!(timed_out.into_value().as_deref(), Some("c"));
+
+    let _ = thread::scope(|s| {
+        s.spawn(|| {
+            let _guard = lock.write().unwrap();
+            panic!("poisoning the lock");
+        })
+        .join()
+    });
+    let poisoned = GrowLockError::<()>::from(lock.write().err().unwrap());
+    assert_eq!(describe(&poisoned), "poisoned");
+    assert_eq!(
+        poisoned.to_string(),
+        "the write lock of the `GrowLock` is poisoned"
+    );
+    assert_eq!(poisoned.into_value(), None);
+
+    let alloc = GrowLockError::from(
+        GrowLock::<u64>::try_from_iter_with_capacity(usize::MAX, [])
+            .unwrap_err(),
+    );
+    assert_eq!(describe(&alloc), "alloc");
+    assert_eq!(
+        alloc,
+        GrowLockError::Alloc(TryReserveError::CapacityOverflow)
+    );
+    assert_eq!(
+        alloc.to_string(),
+        TryReserveError::CapacityOverflow.to_string()
+    );
+}
+
+/// Tests if elements are correctly dropped even if the thread panics
+#[test]
+fn init_drop_on_panic() {
+    use std::panic;
+
+    let counter = AtomicUsize::new(0);
+    let result = panic::catch_unwind(|| {
+        let lock = GrowLock::with_capacity(10);
+        let mut guard = lock.write().unwrap();
+        for _ in 0..15 {
+            guard.push(AddOnDrop(&counter));
+        }
+    });
+
+    assert!(result.is_err());
+    // 10 elements are pushed in the lock, the last is dropped when trying
+    // to push it.
+    assert_eq!(counter.load(Ordering::Relaxed), 11);
+}
+
+// ------------------- test drop -------------------
+
+/// Tests if elements are correctly dropped when the [`GrowLock`] is
+/// dropped
+#[test]
+fn initialized_drop() {
+    let counter = AtomicUsize::new(0);
+    {
+        let lock = GrowLock::with_capacity(200);
+        let mut guard = lock.write().unwrap();
+        for _ in 0..100 {
+            guard.push(AddOnDrop(&counter));
+        }
+        // here `lock` is dropped
+    }
+    assert_eq!(counter.load(Ordering::Relaxed), 100);
+}
+
+/// Tests if ZST elements are correctly dropped when the [`GrowLock`] is
+/// dropped
+#[test]
+fn zst_drop() {
+    static ZST_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    struct AddZST;
+    impl Drop for AddZST {
+        fn drop(&mut self) {
+            ZST_COUNTER.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    {
+        let lock = GrowLock::with_capacity(200);
+        let mut guard = lock.write().unwrap();
+        for _ in 0..150 {
+            guard.push(AddZST);
+        }
+        // here `lock` is dropped
+    }
+    assert_eq!(ZST_COUNTER.load(Ordering::Relaxed), 150);
+}
+
+/// Helper struct that records its id when dropped, and panics if it's
+/// `panic`.
+struct RecordOnDrop<'a> {
+    id: u32,
+    panic: bool,
+    order: &'a std::sync::Mutex<Vec<u32>>,
+}
+impl Drop for RecordOnDrop<'_> {
+    fn drop(&mut self) {
+        self.order.lock().unwrap().push(self.id);
+        assert!(!self.panic, "panic in drop");
+    }
+}
+
+/// Tests that the elements of every lock are dropped from the last one
+/// to the first one.
+#[test]
+fn drop_order() {
+    let order = std::sync::Mutex::new(Vec::new());
+    let elems = || {
+        (0..4).map(|id| RecordOnDrop {
+            id,
+            panic: false,
+            order: &order,
+        })
+    };
+    let take = || mem::take(&mut *order.lock().unwrap());
+
+    let lock = GrowLock::with_capacity(8);
+    lock.write().unwrap().extend(elems());
+    drop(lock);
+    assert_eq!(take(), [3, 2, 1, 0]);
+
+    let lock = LocalGrowLock::with_capacity(8);
+    lock.write().extend(elems());
+    drop(lock);
+    assert_eq!(take(), [3, 2, 1, 0]);
+
+    let lock = ArrayGrowLock::<_, 8>::new();
+    lock.write().unwrap().extend(elems());
+    drop(lock);
+    assert_eq!(take(), [3, 2, 1, 0]);
+
+    for capacity in [4, 8] {
+        let lock = InlineGrowLock::<_, 4>::with_capacity(capacity);
+        lock.write().unwrap().extend(elems());
+        drop(lock);
+        assert_eq!(take(), [3, 2, 1, 0]);
+    }
+}
+
+/// Tests that the elements before a panicking `drop` are still dropped,
+/// in reverse order.
+#[test]
+fn drop_order_panic() {
+    let order = std::sync::Mutex::new(Vec::new());
+    let lock = GrowLock::with_capacity(4);
+    lock.write().unwrap().extend((0..4).map(|id| RecordOnDrop {
+        id,
+        panic: id == 2,
+        order: &order,
+    }));
+    let result = std::panic::catch_unwind(move || drop(lock));
+    assert!(result.is_err());
+    assert_eq!(*order.lock().unwrap(), [3, 2, 1, 0]);
+}
+
+/// Tests that the elements can reference each other across
+/// [`GrowLock`]s dropped in the same scope (like `test_vec_cycle` in the
+/// standard library).
+#[test]
+#[cfg(feature = "nightly")]
+fn dropck_cycle() {
+    use std::cell::Cell;
+
+    struct C<'a> {
+        v: GrowLock<Cell<Option<&'a C<'a>>>>,
+    }
+    impl C<'_> {
+        fn new() -> Self {
+            let v = GrowLock::with_capacity(2);
+            v.write()
+                .unwrap()
+                .extend([Cell::new(None), Cell::new(None)]);
+            Self { v }
+        }
+    }
+
+    let (c1, c2, c3) = (C::new(), C::new(), C::new());
+    c1.v[0].set(Some(&c2));
+    c1.v[1].set(Some(&c3));
+    c2.v[0].set(Some(&c2));
+    c2.v[1].set(Some(&c3));
+    c3.v[0].set(Some(&c1));
+    c3.v[1].set(Some(&c2));
+}
+
+// ------------------- write -------------------
+
+/// Tests that each writer waits its turn before writing
+/// (this looks at the length)
+#[test]
+fn write_contention() {
+    const THREADS: usize = 10;
+    const CAP: usize = 1000;
+
+    let lock = Arc::new(GrowLock::with_capacity(CAP));
+    let mut handles = Vec::with_capacity(THREADS);
+    for t in 0..THREADS {
+        handles.push(thread::spawn({
+            let lock_clone = Arc::clone(&lock);
+            move || {
+                for i in 0..(CAP / THREADS) {
+                    let mut guard = lock_clone.write().unwrap();
+                    guard.push(t * (CAP / THREADS) + i);
+                }
+            }
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(lock.len(), CAP);
+}
+
+/// `try_write_spin` should get the lock released by a writer within the
+/// budget, and give up on a writer which holds it longer.
+#[test]
+fn try_write_spin() {
+    use std::{
+        sync::{Barrier, TryLockError},
+        time::Instant,
+    };
+
+    let lock = GrowLock::with_capacity(2);
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut guard = lock.write().unwrap();
+            barrier.wait();
+            // busy, like a real-time writer.
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(1) {}
+            guard.push(1);
+        });
+        barrier.wait();
+        lock.try_write_spin(usize::MAX).unwrap().push(2);
+    });
+    assert_eq!(lock, [1, 2]);
+
+    let guard = lock.write().unwrap();
+    thread::scope(|s| {
+        s.spawn(|| {
+            let result = lock.try_write_spin(1000);
+            assert!(matches!(result, Err(TryLockError::WouldBlock)));
+        });
+    });
+    drop(guard);
+    assert!(lock.try_write_spin(0).is_ok());
+}
+
+/// The spins of `try_write_spin` should also bound the wait for the
+/// tickets in progress.
+#[test]
+fn try_write_spin_ticket() {
+    use std::sync::{Barrier, TryLockError};
+
+    let lock = GrowLock::with_capacity(4);
+    let ticket = lock.reserve_ticket(1).unwrap();
+    assert!(matches!(
+        lock.try_write_spin(1000),
+        Err(TryLockError::WouldBlock),
+    ));
+    let barrier = Barrier::new(2);
+    thread::scope(|s| {
+        s.spawn(|| {
+            barrier.wait();
+            ticket.commit_with(|_| 1);
+        });
+        barrier.wait();
+        lock.try_write_spin(usize::MAX).unwrap().push(2);
+    });
+    assert_eq!(lock, [1, 2]);
+
+    mem::forget(lock.reserve_ticket(1).unwrap());
+    assert!(matches!(
+        lock.try_write_spin(1000),
+        Err(TryLockError::WouldBlock),
+    ));
+}
+
+/// `try_write_spin` should return a poisoned lock at once, like
+/// `try_write`.
+#[test]
+fn try_write_spin_poisoned() {
+    use std::sync::TryLockError;
+
+    let lock = GrowLock::<u32>::with_capacity(2);
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = lock.write().unwrap();
+        panic!("poison");
+    });
+    let Err(TryLockError::Poisoned(e)) = lock.try_write_spin(usize::MAX)
+    else {
+        panic!("the lock should be poisoned");
+    };
+    e.into_inner().push(1);
+    assert_eq!(lock, [1]);
+}
+
+/// Tests `Extend<&T>` from slices, slice iterators and filtered
+/// iterators
+#[test]
+fn extend_from_refs() {
+    let lock = GrowLock::<u8>::with_capacity(16);
+    let mut guard = lock.write().unwrap();
+    guard.extend(b"abc");
+    guard.extend(&b"de"[..]);
+    let src = [1, 2, 3, 4, 5, 6];
+    guard.extend(src.iter());
+    guard.extend(src.iter().filter(|&&x| x % 2 == 0));
+    drop(guard);
+    assert_eq!(lock, *b"abcde\x01\x02\x03\x04\x05\x06\x02\x04\x06");
+
+    let lock = GrowLock::<u8>::with_capacity(2);
+    let result = thread::scope(|s| {
+        s.spawn(|| lock.write().unwrap().extend(&[1, 2, 3])).join()
+    });
+    assert!(result.is_err());
+}
+
+/// `Extend` on `&GrowLock` should hold the lock for the whole iteration
+#[test]
+fn extend_shared_ref() {
+    use std::cell::Cell;
+
+    let lock = GrowLock::with_capacity(10);
+    let unlocked = Cell::new(0);
+    let iter = (0..5).inspect(|_| {
+        if lock.try_write().is_ok() {
+            unlocked.set(unlocked.get() + 1);
+        }
+    });
+    (&lock).extend(iter);
+    assert_eq!(lock, [0, 1, 2, 3, 4]);
+    assert_eq!(unlocked.get(), 0);
+    // the lock is released at the end
+    assert!(lock.try_write().is_ok());
+
+    let mut sink = &lock;
+    sink.extend([5, 6]);
+    assert_eq!(lock, [0, 1, 2, 3, 4, 5, 6]);
+}
+
+/// Tests `extend` with exact iterators, inexact ones and iterators that
+/// lie about their size: the elements are published all at once, or not
+/// at all.
+#[test]
+fn extend_size_hint() {
+    use std::{
+        cell::Cell,
+        panic::{AssertUnwindSafe, catch_unwind},
+    };
+
+    /// Iterator that reports a fixed size hint, and counts the elements
+    /// it yields
+    struct Lying<'a>(
+        std::ops::Range<u32>,
+        (usize, Option<usize>),
+        &'a Cell<usize>,
+    );
+    impl Iterator for Lying<'_> {
+        type Item = u32;
+        fn next(&mut self) -> Option<u32> {
+            self.2.set(self.2.get() + 1);
+            self.0.next()
+        }
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            self.1
+        }
+    }
+    let extend = |lock: &GrowLock<_>, iter| {
+        catch_unwind(AssertUnwindSafe(|| {
+            lock.write().unwrap().extend(iter);
+        }))
+    };
+
+    let consumed = Cell::new(0);
+    let lock = GrowLock::with_capacity(10);
+    lock.write().unwrap().extend(vec![0, 1, 2]);
+    // too short hint
+    lock.write()
+        .unwrap()
+        .extend(Lying(3..8, (2, Some(2)), &consumed));
+    assert_eq!(lock, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // too short hint, and the elements don't fit: nothing is published
+    assert!(extend(&lock, Lying(8..12, (1, Some(1)), &consumed)).is_err());
+    assert_eq!(lock, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    // exact or inexact lower bound which doesn't fit: nothing is consumed
+    consumed.set(0);
+    assert!(extend(&lock, Lying(8..11, (3, Some(3)), &consumed)).is_err());
+    assert!(extend(&lock, Lying(8..11, (3, None), &consumed)).is_err());
+    assert_eq!(consumed.get(), 0);
+    assert_eq!(lock.len(), 8);
+
+    // inexact size which doesn't fit: the written elements are dropped
+    let dropped = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(3);
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        lock.write()
+            .unwrap()
+            .extend((0..5).filter(|_| true).map(|_| AddOnDrop(&dropped)));
+    }));
+    assert!(result.is_err());
+    assert!(lock.is_empty());
+    assert_eq!(dropped.load(Ordering::Relaxed), 4);
+}
+
+/// Tests that the deferred elements are published in batches, and that
+/// the other writes of the guard publish them first.
+#[test]
+fn push_deferred_publish() {
+    let lock = GrowLock::with_capacity(5);
+    let mut guard = lock.write().unwrap();
+    guard.push_deferred(0).unwrap();
+    guard.push_deferred(1).unwrap();
+    assert_eq!(guard.len(), 2);
+    assert!(lock.is_empty());
+    guard.publish();
+    assert_eq!(lock, [0, 1]);
+    guard.push_deferred(2).unwrap();
+    guard.push(3);
+    assert_eq!(lock, [0, 1, 2, 3]);
+    guard.push_deferred(4).unwrap();
+    assert!(guard.push_deferred(5).is_err());
+    assert_eq!(*guard, [0, 1, 2, 3, 4]);
+    drop(guard);
+    assert_eq!(lock, [0, 1, 2, 3, 4]);
+}
+
+/// Tests that a panic between deferred pushes publishes only the pushed
+/// elements, while a reader checks every visible one.
+#[test]
+fn push_deferred_panic() {
+    let dropped = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(8);
+    thread::scope(|s| {
+        let writer = s.spawn(|| {
+            let mut guard = lock.write().unwrap();
+            for i in 0..8 {
+                assert!(i < 5, "panic between pushes");
+                guard.push_deferred((i, AddOnDrop(&dropped))).unwrap();
+            }
+        });
+        while !writer.is_finished() {
+            for (i, (value, _)) in lock.iter().enumerate() {
+                assert_eq!(*value, i);
+            }
+        }
+        assert!(writer.join().is_err());
+    });
+    assert!(lock.write().is_err());
+    assert_eq!(lock.len(), 5);
+    assert!(lock.iter().map(|(i, _)| *i).eq(0..5));
+    drop(lock);
+    assert_eq!(dropped.load(Ordering::Relaxed), 5);
+}
+
+// ------------------- read -------------------
+
+/// tests that we can still read while writing
+#[test]
+fn read_while_locked() {
+    let lock = GrowLock::with_capacity(5);
+    {
+        let mut guard = lock.write().unwrap();
+        guard.push("hi");
+        guard.push("there");
+        assert_eq!(&lock[0..2], ["hi", "there"]);
+        guard.push("still locked");
+    }
+    assert_eq!(lock.len(), 3);
+}
+
+/// tests that we can still read while writing, from another thread
 #[test]
 fn slow_write() {
     let lock = Arc::new(GrowLock::with_capacity(10));
@@ -292,33 +3097,1194 @@ fn slow_write() {
         let mut guard = lock.write().unwrap();
         guard.extend(["hi", "hello", "world"]);
     }
-    let handle = thread::spawn({
-        let lock_clone = Arc::clone(&lock);
-        move || {
-            let mut guard = lock_clone.write().unwrap();
-            guard.push("foo");
-            thread::sleep(Duration::from_millis(300));
-            guard.push("bar");
+    let handle = thread::spawn({
+        let lock_clone = Arc::clone(&lock);
+        move || {
+            let mut guard = lock_clone.write().unwrap();
+            guard.push("foo");
+            thread::sleep(Duration::from_millis(300));
+            guard.push("bar");
+        }
+    });
+
+    // we wait for the writer to take the lock
+    // (20millis is overkill, but we never know)
+    thread::sleep(Duration::from_millis(20));
+
+    assert!(lock.len() >= 3);
+    // while `handle` is writing, we still can read initialized elements.
+    assert_eq!(&lock[..3], &["hi", "hello", "world"]);
+    // here, 4th element could be (and probably is) already initialized
+    if let Some(&fourth) = lock.get(3) {
+        dbg!(fourth);
+        assert_eq!(fourth, "foo");
+    }
+
+    handle.join().unwrap();
+    // at this point all the elements are already pushed
+    assert_eq!(lock.len(), 5);
+    assert_eq!(&lock[3..], &["foo", "bar"]);
+}
+
+/// Tests that a snapshot keeps the same length while a writer pushes.
+#[test]
+fn snapshot_consistent() {
+    let lock = GrowLock::with_capacity(10_000);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut guard = lock.write().unwrap();
+            for i in 0..10_000 {
+                guard.push(i);
+            }
+        });
+        for _ in 0..100 {
+            let snapshot = lock.snapshot();
+            let len = snapshot.len();
+            if let Some(&last) = snapshot.last() {
+                assert_eq!(snapshot[len - 1], last);
+                assert_eq!(last, len - 1);
+            }
+            assert_eq!(snapshot.iter().count(), len);
+            let sum: usize = snapshot.into_iter().sum();
+            assert_eq!(sum, len * len.saturating_sub(1) / 2);
+            assert_eq!(snapshot.len(), len);
+        }
+    });
+    assert_eq!(lock.snapshot().len(), 10_000);
+    assert_eq!(
+        format!("{:?}", grow_lock!(2, [1, 2]).snapshot()),
+        "[1, 2]"
+    );
+}
+
+/// Tests that the elements never move while pushing, with an intrusive
+/// list which links every node to the address of the previous one.
+#[test]
+fn address_stability() {
+    use std::ptr;
+
+    struct Node {
+        value: usize,
+        prev: *const Node,
+    }
+
+    const N: usize = 10_000;
+    let lock = GrowLock::with_capacity(N);
+    let mut addresses = Vec::with_capacity(N);
+    let mut prev = ptr::null();
+    for value in 0..N {
+        lock.write().unwrap().push(Node { value, prev });
+        prev = &raw const lock[value];
+        addresses.push(prev);
+    }
+    assert!(lock.iter().map(ptr::from_ref).eq(addresses));
+
+    let mut node = prev;
+    for value in (0..N).rev() {
+        // SAFETY: the nodes are never moved nor dropped while `lock` is
+        // borrowed.
+        let current = unsafe { &*node };
+        assert_eq!(current.value, value);
+        node = current.prev;
+    }
+    assert!(node.is_null());
+}
+
+#[test]
+fn get_pinned() {
+    use std::{marker::PhantomPinned, pin::pin, ptr};
+
+    let lock = pin!(GrowLock::with_capacity(100));
+    let lock = lock.as_ref();
+    lock.write().unwrap().push((0, PhantomPinned));
+    let first = lock.get_pinned(0).unwrap();
+    assert!(lock.get_pinned(1).is_none());
+
+    lock.write()
+        .unwrap()
+        .extend((1..100).map(|i| (i, PhantomPinned)));
+    assert!(ptr::eq(first.get_ref(), &raw const lock[0]));
+    assert_eq!(lock.get_pinned(99).unwrap().0, 99);
+    assert!(lock.get_pinned(100).is_none());
+}
+
+#[test]
+fn index_of_ref() {
+    let lock = GrowLock::with_capacity(100);
+    let mut refs = Vec::new();
+    for i in 0..100 {
+        lock.write().unwrap().push(i);
+        refs.push(lock.last().unwrap());
+    }
+    for (i, elem) in refs.into_iter().enumerate() {
+        assert_eq!(lock.index_of_ref(elem), Some(i));
+    }
+    let range = lock.as_ptr_range();
+    assert_eq!(range.start, lock.as_ptr());
+    assert_eq!(range.end, lock.as_ptr().wrapping_add(100));
+
+    // a reference to an equal value on the stack.
+    let outside = 5;
+    assert_eq!(lock[5], outside);
+    assert_eq!(lock.index_of_ref(&outside), None);
+
+    // a reference which straddles two elements.
+    let pairs = GrowLock::from_iter_with_capacity(4, [[0u8, 1], [2, 3]]);
+    let straddling: &[u8; 2] =
+        pairs.as_flattened()[1..3].try_into().unwrap();
+    assert_eq!(pairs.index_of_ref(straddling), None);
+    assert_eq!(pairs.index_of_ref(&pairs[1]), Some(1));
+
+    let zsts = GrowLock::from_iter_with_capacity(4, [(); 4]);
+    assert_eq!(zsts.index_of_ref(&zsts[0]), None);
+}
+
+/// Tests that the read handles see the elements pushed by the writer.
+#[test]
+fn read_handle() {
+    const N: usize = 1_000;
+    let lock = Arc::new(GrowLock::with_capacity(N));
+    let handle = lock.read_handle();
+    let consumers: Vec<_> = (0..4)
+        .map(|_| {
+            let handle = handle.clone();
+            thread::spawn(move || {
+                let mut tail = handle.tail();
+                let mut sum = 0;
+                while !tail.next_batch().is_empty() {}
+                for (i, &x) in handle.iter_from(N / 2).enumerate() {
+                    assert_eq!(x, N / 2 + i);
+                    sum += x;
+                }
+                sum
+            })
+        })
+        .collect();
+    for i in 0..N {
+        lock.write().unwrap().push(i);
+    }
+    for consumer in consumers {
+        assert_eq!(consumer.join().unwrap(), (N / 2..N).sum::<usize>());
+    }
+    assert_eq!(handle.len(), N);
+    assert_eq!(handle[N - 1], N - 1);
+    assert_eq!(handle.snapshot().len(), N);
+    drop(lock);
+    // the handle keeps the lock alive.
+    assert_eq!(handle.get(1), Some(&1));
+}
+
+/// Tests that a [`Cursor`](crate::cursor::Cursor) sees every element
+/// exactly once while a writer pushes.
+#[test]
+fn cursor_drain() {
+    use crate::cursor::Cursor;
+
+    const N: usize = 10_000;
+    let lock = GrowLock::with_capacity(N);
+    let mut seen = Vec::with_capacity(N);
+    thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..N {
+                lock.write().unwrap().push(i);
+            }
+        });
+        let mut cursor = Cursor::new();
+        while seen.len() < N {
+            seen.extend_from_slice(cursor.poll(&lock));
+        }
+        assert_eq!(cursor.position(), N);
+        assert_eq!(cursor.poll(&lock), [0; 0]);
+    });
+    assert!(seen.into_iter().eq(0..N));
+
+    // out-of-range starts are clamped.
+    assert_eq!(Cursor::at(N + 1).poll(&lock), [0; 0]);
+    assert_eq!(lock.iter_from(N + 1).len(), 0);
+    assert!(lock.iter_from(N - 2).eq(&[N - 2, N - 1]));
+}
+
+/// Tests that a [`Cursor`](crate::cursor::Cursor) refuses to read the
+/// elements once they're overwritten.
+#[test]
+fn cursor_stale() {
+    use crate::{cursor::Cursor, error::StaleCursor};
+
+    let mut lock = GrowLock::from_iter_with_capacity(10, 0..5);
+    let mut cursor = Cursor::at(2);
+    assert_eq!(cursor.try_poll(&lock), Ok(&[2, 3, 4][..]));
+    let generation = lock.generation();
+    lock.write().unwrap().extend([5, 6]);
+    assert_eq!(lock.generation(), generation);
+    assert_eq!(cursor.try_poll(&lock), Ok(&[5, 6][..]));
+
+    lock.clone_from(&GrowLock::from_iter_with_capacity(3, [7, 8, 9]));
+    assert!(lock.generation() > generation);
+    assert_eq!(cursor.try_poll(&lock), Err(StaleCursor));
+    assert_eq!(cursor.position(), 7);
+    // a new cursor adopts the new generation.
+    assert_eq!(Cursor::new().poll(&lock), [7, 8, 9]);
+
+    // the generation is kept when the allocation is replaced.
+    let generation = lock.generation();
+    lock.clone_from(&GrowLock::from_iter_with_capacity(20, 0..20));
+    assert_eq!(lock.generation(), generation + 1);
+}
+
+/// Tests 8 writers pushing concurrently into their own region of a
+/// [`Partition`](crate::partition::Partition) while a reader reads them,
+/// and the merge of the partially filled regions when it's sealed.
+#[test]
+fn partition_concurrent() {
+    const REGIONS: usize = 8;
+    const SIZE: usize = 1_000;
+    // the regions are partially filled, so the merge moves them down.
+    let count = |region: usize| SIZE - region * 100;
+
+    let lock = GrowLock::with_capacity(2 + REGIONS * SIZE + 10);
+    lock.write().unwrap().extend([usize::MAX; 2]);
+    let partition = lock.partition(&[SIZE; REGIONS]).unwrap();
+    assert_eq!(partition.len(), REGIONS);
+    thread::scope(|s| {
+        for (region, writer) in partition.writers().into_iter().enumerate()
+        {
+            s.spawn(move || {
+                for i in 0..count(region) {
+                    writer.push(region * SIZE + i);
+                }
+                assert_eq!(writer.len(), count(region));
+            });
+        }
+        s.spawn(|| {
+            while partition.regions().map(<[_]>::len).sum::<usize>()
+                < (0..REGIONS).map(count).sum()
+            {
+                for (region, elems) in partition.regions().enumerate() {
+                    let start = region * SIZE;
+                    assert!(
+                        elems
+                            .iter()
+                            .copied()
+                            .eq(start..start + elems.len())
+                    );
+                }
+            }
+        });
+        // the other writers wait for the partition.
+        assert!(lock.try_write().is_err());
+    });
+    assert_eq!(*lock, [usize::MAX; 2]);
+
+    let mut guard = partition.seal();
+    guard.push(0);
+    drop(guard);
+    let expected = (0..REGIONS)
+        .flat_map(|region| region * SIZE..region * SIZE + count(region));
+    assert!(lock[2..lock.len() - 1].iter().copied().eq(expected));
+    assert_eq!(lock.len(), 2 + (0..REGIONS).map(count).sum::<usize>() + 1);
+}
+
+/// Tests that the elements of a [`Partition`](crate::partition::Partition)
+/// are dropped exactly once, also when it's dropped without being sealed
+/// while a writer panics.
+#[test]
+fn partition_drop() {
+    use std::panic::{self, AssertUnwindSafe};
+
+    let drops = &AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(20);
+    let partition = lock.partition(&[5, 5, 5]).unwrap();
+    let writers = partition.writers();
+    writers[0].push(AddOnDrop(drops));
+    writers[2].push(AddOnDrop(drops));
+    let full = (0..5).map(|_| AddOnDrop(drops));
+    full.for_each(|elem| writers[1].push(elem));
+    let value = writers[1].try_push(AddOnDrop(drops)).err().unwrap();
+    drop(value.into_inner());
+    assert_eq!(drops.load(Ordering::Relaxed), 1);
+    drop(writers);
+    drop(partition);
+    assert_eq!(lock.len(), 7);
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let partition = lock.partition(&[2, 2]).unwrap();
+        thread::scope(|s| {
+            for writer in partition.writers() {
+                s.spawn(move || {
+                    writer.push(AddOnDrop(drops));
+                    writer.push(AddOnDrop(drops));
+                    writer.push(AddOnDrop(drops));
+                });
+            }
+        });
+    }));
+    assert!(result.is_err());
+    // the partition was merged while unwinding, which poisoned the lock.
+    assert_eq!(lock.len(), 11);
+    assert!(lock.write().is_err());
+    // the values which didn't fit were dropped by the panicking writers.
+    assert_eq!(drops.load(Ordering::Relaxed), 3);
+    drop(lock);
+    assert_eq!(drops.load(Ordering::Relaxed), 14);
+}
+
+/// Tests that the regions of a [`Partition`](crate::partition::Partition)
+/// must fit in the spare capacity.
+#[test]
+#[should_panic = "the regions don't fit in the spare capacity"]
+fn partition_overflow() {
+    let lock = GrowLock::<u8>::with_capacity(10);
+    lock.write().unwrap().push(0);
+    let _ = lock.partition(&[5, usize::MAX]);
+}
+
+/// Tests a writer which waits with [`GrowGuard::wait_while`] for a
+/// consumer on another thread to drain what it pushed.
+///
+/// [`GrowGuard::wait_while`]: crate::guard::GrowGuard::wait_while
+#[test]
+fn guard_wait_drained() {
+    use {crate::cursor::Cursor, std::sync::Condvar};
+
+    const N: usize = 100;
+    const BATCH: usize = 10;
+    let lock = GrowLock::with_capacity(N);
+    let cv = Condvar::new();
+    let drained = AtomicUsize::new(0);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut cursor = Cursor::new();
+            while cursor.position() < N {
+                if cursor.poll(&lock).is_empty() {
+                    thread::yield_now();
+                    continue;
+                }
+                drained.store(cursor.position(), Ordering::Relaxed);
+                // the condition is checked with the write lock held:
+                // taking it orders the store before the
+                // check, or the check before the
+                // notification.
+                drop(lock.write());
+                cv.notify_all();
+            }
+        });
+        let mut guard = lock.write().unwrap();
+        for batch in (0..N).collect::<Vec<_>>().chunks(BATCH) {
+            for &i in batch {
+                // published by `wait_while`, or the consumer would never
+                // see them.
+                guard.push_deferred(i).unwrap();
+            }
+            guard = guard
+                .wait_while(&cv, |elems| {
+                    drained.load(Ordering::Relaxed) < elems.len()
+                })
+                .unwrap();
+            assert_eq!(drained.load(Ordering::Relaxed), guard.len());
+        }
+    });
+    assert!(lock.iter().copied().eq(0..N));
+}
+
+/// Tests the timeouts and the poisoning of the waits of a
+/// [`GrowGuard`](crate::guard::GrowGuard).
+#[test]
+fn guard_wait_timeout() {
+    use std::sync::Condvar;
+
+    let lock = GrowLock::with_capacity(4);
+    let cv = Condvar::new();
+    let mut guard = lock.write().unwrap();
+    guard.push_deferred(1).unwrap();
+    let (guard, result) =
+        guard.wait_timeout(&cv, Duration::from_millis(10)).unwrap();
+    assert!(result.timed_out());
+    assert_eq!(*lock, [1]);
+    let (guard, result) = guard
+        .wait_timeout_while(
+            &cv,
+            Duration::from_millis(10),
+            <[_]>::is_empty,
+        )
+        .unwrap();
+    assert!(!result.timed_out());
+    let (guard, result) = guard
+        .wait_timeout_while(&cv, Duration::from_millis(10), |elems| {
+            elems.len() < 2
+        })
+        .unwrap();
+    assert!(result.timed_out());
+
+    // the lock is poisoned while the guard waits.
+    thread::scope(|s| {
+        let poisoner = s.spawn(|| {
+            let mut guard = lock.write().unwrap();
+            guard.push(2);
+            cv.notify_all();
+            panic!("poison");
+        });
+        let Err(e) = guard.wait_while(&cv, |elems| elems.len() < 2) else {
+            panic!("the lock should be poisoned");
+        };
+        assert_eq!(*e.into_inner(), [1, 2]);
+        assert!(poisoner.join().is_err());
+    });
+}
+
+/// Tests the clamping of [`GrowLock::chunk_from`], and that a
+/// [`ChunkCursor`](crate::cursor::ChunkCursor) ships every element exactly
+/// once, in order, while a writer pushes.
+#[test]
+fn chunk_cursor_drain() {
+    use crate::cursor::ChunkCursor;
+
+    const N: usize = 10_000;
+    const MAX: usize = 64;
+    let lock = GrowLock::with_capacity(N);
+    let mut seen = Vec::with_capacity(N);
+    thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..N {
+                lock.write().unwrap().push(i);
+            }
+        });
+        let mut cursor = ChunkCursor::new(MAX);
+        while seen.len() < N {
+            if let Some(chunk) = cursor.next_chunk(&lock) {
+                assert!(!chunk.is_empty() && chunk.len() <= MAX);
+                seen.extend_from_slice(chunk);
+            }
+        }
+        assert_eq!(cursor.position(), N);
+        assert_eq!(cursor.next_chunk(&lock), None);
+    });
+    assert!(seen.into_iter().eq(0..N));
+
+    assert_eq!(lock.chunk_from(N - 2, MAX), [N - 2, N - 1]);
+    assert_eq!(lock.chunk_from(2, 3), [2, 3, 4]);
+    assert_eq!(lock.chunk_from(2, usize::MAX), &lock[2..]);
+    // out-of-range starts are clamped, and `max == 0` is empty.
+    assert_eq!(lock.chunk_from(N + 1, MAX), [0; 0]);
+    assert_eq!(lock.chunk_from(usize::MAX, usize::MAX), [0; 0]);
+    assert_eq!(lock.chunk_from(0, 0), [0; 0]);
+    let mut cursor = ChunkCursor::at(N + 1, MAX);
+    assert_eq!(cursor.next_chunk(&lock), None);
+    assert_eq!(cursor.position(), N + 1);
+}
+
+/// Tests that a [`ChunkCursor`](crate::cursor::ChunkCursor) can't return
+/// empty chunks.
+#[test]
+#[should_panic = "the chunks of a `ChunkCursor` can't be empty"]
+fn chunk_cursor_zero() {
+    let _ = crate::cursor::ChunkCursor::new(0);
+}
+
+#[test]
+#[should_panic = "the elements of the `GrowLock` changed"]
+fn cursor_stale_poll() {
+    use crate::cursor::Cursor;
+
+    let mut lock = GrowLock::from_iter_with_capacity(10, 0..5);
+    let mut cursor = Cursor::new();
+    cursor.poll(&lock);
+    lock.clone_from(&GrowLock::with_capacity(0));
+    cursor.poll(&lock);
+}
+
+/// Tests that a [`TailCursor`](crate::cursor::TailCursor) blocks until
+/// every element is pushed, and sees each of them exactly once.
+#[test]
+fn tail_cursor_producer_consumer() {
+    const N: usize = 1_000;
+    let lock = GrowLock::with_capacity(N);
+    let seen = thread::scope(|s| {
+        let consumer = s.spawn(|| {
+            let mut tail = lock.tail();
+            let mut seen = Vec::with_capacity(N);
+            loop {
+                let batch = tail.next_batch();
+                if batch.is_empty() {
+                    break seen;
+                }
+                seen.extend_from_slice(batch);
+            }
+        });
+        for i in 0..N {
+            let mut guard = lock.write().unwrap();
+            guard.push(i);
+            if i % 100 == 0 {
+                // keeps the guard while the consumer waits.
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        consumer.join().unwrap()
+    });
+    assert!(seen.into_iter().eq(0..N));
+}
+
+/// Tests that [`TailCursor::next_batch_timeout`] gives up, and then sees
+/// the elements pushed later.
+///
+/// [`TailCursor::next_batch_timeout`]: crate::cursor::TailCursor::next_batch_timeout
+#[test]
+fn tail_cursor_timeout() {
+    let lock = GrowLock::with_capacity(2);
+    let mut tail = lock.tail();
+    assert_eq!(tail.next_batch_timeout(Duration::from_millis(20)), [0; 0]);
+    thread::scope(|s| {
+        s.spawn(|| {
+            thread::sleep(Duration::from_millis(20));
+            lock.write().unwrap().push(1);
+        });
+        assert_eq!(tail.next_batch_timeout(Duration::from_mins(1)), [1]);
+    });
+    assert_eq!(tail.position(), 1);
+}
+
+/// Tests that [`GrowLock::wait_for_len`] waits for every worker.
+#[test]
+fn wait_for_len_workers() {
+    const WORKERS: usize = 4;
+    let lock = GrowLock::with_capacity(WORKERS);
+    thread::scope(|s| {
+        for (i, delay) in [30, 0, 20, 10].into_iter().enumerate() {
+            let lock = &lock;
+            s.spawn(move || {
+                thread::sleep(Duration::from_millis(delay));
+                lock.write().unwrap().push(i);
+            });
+        }
+        assert!(lock.wait_for_len(WORKERS, None));
+        assert_eq!(lock.len(), WORKERS);
+    });
+    // already met, or never met.
+    assert!(lock.wait_for_len(1, None));
+    assert!(!lock.wait_for_len(WORKERS + 1, None));
+}
+
+/// Tests that [`GrowLock::wait_for_len`] times out.
+#[test]
+fn wait_for_len_timeout() {
+    let lock = grow_lock!(3, [1]);
+    let _guard = lock.write().unwrap();
+    let timeout = Duration::from_millis(20);
+    let start = std::time::Instant::now();
+    assert!(!lock.wait_for_len(2, Some(timeout)));
+    assert!(start.elapsed() >= timeout);
+    assert!(lock.wait_for_len(1, Some(Duration::ZERO)));
+}
+
+/// Tests that the subscribers receive the lengths in order, and that a
+/// dropped subscriber doesn't stop the others.
+#[test]
+fn subscribe_broadcast() {
+    const N: usize = 300;
+    let lock = GrowLock::with_capacity(N);
+    let rx1 = lock.subscribe();
+    let rx2 = lock.subscribe();
+    thread::scope(|s| {
+        let observer =
+            s.spawn(move || rx1.iter().take_while(|&len| len < N).count());
+        let dropped = s.spawn(move || {
+            // hangs up mid-stream.
+            rx2.iter().take_while(|&len| len < N / 2).count()
+        });
+        for i in 0..N {
+            lock.write().unwrap().push(i);
+        }
+        // every length is sent once, starting from 0.
+        assert_eq!(observer.join().unwrap(), N);
+        assert!(dropped.join().unwrap() <= N / 2);
+    });
+
+    let rx = lock.subscribe();
+    assert_eq!(rx.try_iter().collect::<Vec<_>>(), [N]);
+}
+
+// ------------------- arena -------------------
+
+/// Tests that the references returned by [`GrowLock::alloc`] stay valid
+/// while the other threads keep allocating.
+#[test]
+fn alloc_concurrent() {
+    const THREADS: usize = 4;
+    const N: usize = 1_000;
+    let arena = GrowLock::with_capacity(THREADS * N);
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let arena = &arena;
+            s.spawn(move || {
+                let mut refs = Vec::with_capacity(N);
+                for i in 0..N {
+                    let value = arena.alloc((t, i.to_string())).unwrap();
+                    refs.push(value);
+                    // the previous references are still readable.
+                    let (t0, i0) = refs[i / 2];
+                    assert_eq!((*t0, i0.parse()), (t, Ok(i / 2)));
+                }
+                for (i, &(t0, i0)) in refs.iter().enumerate() {
+                    assert_eq!((*t0, i0.parse()), (t, Ok(i)));
+                }
+            });
+        }
+    });
+    assert!(arena.is_full());
+    assert_eq!(
+        arena.alloc((0, String::new())),
+        Err(crate::error::GrowLockError::Full {
+            value: (0, String::new()),
+            len: THREADS * N,
+            capacity: THREADS * N,
+        })
+    );
+}
+
+/// Tests that concurrent calls of [`GrowLock::alloc_slice`] get disjoint,
+/// contiguous slices.
+#[test]
+fn alloc_slice_concurrent() {
+    const THREADS: usize = 8;
+    const N: usize = 200;
+    let arena = GrowLock::with_capacity(THREADS * N * 3);
+    let interned: Vec<Vec<&[usize]>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let arena = &arena;
+                s.spawn(move || {
+                    (0..N)
+                        .map(|i| {
+                            let src = [t, i, t * N + i];
+                            if i % 2 == 0 {
+                                arena.alloc_slice(&src).unwrap()
+                            } else {
+                                arena.alloc_slice_copy(&src).unwrap()
+                            }
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert!(arena.is_full());
+    assert!(arena.alloc_slice_copy(&[0]).is_err());
+    // an empty slice always fits.
+    assert_eq!(arena.alloc_slice(&[]).unwrap(), [0; 0]);
+
+    let mut starts = Vec::new();
+    for (t, slices) in interned.into_iter().enumerate() {
+        for (i, slice) in slices.into_iter().enumerate() {
+            assert_eq!(slice, [t, i, t * N + i]);
+            starts.push(arena.index_of_ref(&slice[0]).unwrap());
+        }
+    }
+    starts.sort_unstable();
+    assert!(starts.into_iter().eq((0..THREADS * N).map(|i| i * 3)));
+}
+
+/// Tests that the strings interned concurrently don't overlap.
+#[test]
+fn alloc_str_concurrent() {
+    const THREADS: usize = 4;
+    const N: usize = 500;
+    let interner = GrowLock::with_capacity(THREADS * N * 16);
+    let strings: Vec<Vec<&str>> = thread::scope(|s| {
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let interner = &interner;
+                s.spawn(move || {
+                    (0..N)
+                        .map(|i| {
+                            let ident = format!("t{t}_é{i}");
+                            interner.alloc_str(&ident).unwrap()
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut ranges = Vec::new();
+    for (t, thread_strings) in strings.into_iter().enumerate() {
+        for (i, s) in thread_strings.into_iter().enumerate() {
+            assert_eq!(s, format!("t{t}_é{i}"));
+            ranges.push(s.as_bytes().as_ptr_range());
+        }
+    }
+    ranges.sort_unstable_by_key(|range| range.start);
+    for pair in ranges.windows(2) {
+        assert!(pair[0].end <= pair[1].start);
+    }
+    assert_eq!(ranges[0].start, interner.as_ptr());
+    assert_eq!(ranges[ranges.len() - 1].end, interner.as_ptr_range().end);
+
+    let full = GrowLock::with_capacity(2);
+    assert_eq!(full.alloc_str("é").unwrap(), "é");
+    assert_eq!(full.alloc_str("a"), Err(LengthError::new(2, 2)));
+}
+
+/// Tests the lock-free pushes racing with the mutex writers.
+#[test]
+fn push_lockfree_concurrent() {
+    const THREADS: usize = 4;
+    const N: usize = 1000;
+    let lock = GrowLock::with_capacity(THREADS * N * 2);
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let lock = &lock;
+            s.spawn(move || {
+                for i in 0..N {
+                    let value = t * N + i;
+                    let index = lock.push_lockfree(value).unwrap();
+                    assert_eq!(lock[index], value);
+                }
+            });
+            s.spawn(move || {
+                for i in 0..N {
+                    if i % 2 == 0 {
+                        lock.write().unwrap().push(usize::MAX);
+                    } else {
+                        lock.alloc(usize::MAX).unwrap();
+                    }
+                }
+            });
         }
     });
+    assert!(lock.is_full());
+    let mut pushed: Vec<_> =
+        lock.iter().copied().filter(|&x| x != usize::MAX).collect();
+    pushed.sort_unstable();
+    assert!(pushed.into_iter().eq(0..THREADS * N));
+    assert_eq!(
+        lock.push_lockfree(0),
+        Err(LengthError::new(THREADS * N * 2, THREADS * N * 2))
+    );
 
-    // we wait for the writer to take the lock
-    // (20millis is overkill, but we never know)
-    thread::sleep(Duration::from_millis(20));
+    // the slots of the ZSTs are reserved too.
+    let zst = GrowLock::with_capacity(0);
+    assert_eq!(zst.push_lockfree(()), Ok(0));
+    assert_eq!(zst.push_lockfree(()), Ok(1));
+    assert_eq!(zst.len(), 2);
+}
 
-    assert!(lock.len() >= 3);
-    // while `handle` is writing, we still can read initialized elements.
-    assert_eq!(&lock[..3], &["hi", "hello", "world"]);
-    // here, 4th element could be (and probably is) already initialized
-    if let Some(&fourth) = lock.get(3) {
-        dbg!(fourth);
-        assert_eq!(fourth, "foo");
+/// Tests that the tickets are published in order, and that the abandoned
+/// ones leave no holes.
+#[test]
+fn ticket_out_of_order() {
+    let lock = GrowLock::with_capacity(6);
+    let a = lock.reserve_ticket(2).unwrap();
+    let b = lock.reserve_ticket(2).unwrap();
+    let c = lock.reserve_ticket(1).unwrap();
+    assert_eq!(lock.reserve_ticket(2).err(), Some(LengthError::new(5, 6)));
+    c.commit_with(|_| String::from("c"));
+    assert!(lock.is_empty());
+    drop(a);
+    assert!(lock.is_empty());
+    b.commit_with(|i| format!("b{i}"));
+    assert_eq!(*lock, ["b0", "b1", "c"]);
+
+    // the abandoned slots are available again to the next writers.
+    lock.write().unwrap().push(String::from("d"));
+    let ticket = lock.reserve_ticket(2).unwrap();
+    ticket.commit_with(|i| format!("e{i}"));
+    assert_eq!(*lock, ["b0", "b1", "c", "d", "e0", "e1"]);
+    assert!(lock.reserve_ticket(0).unwrap().is_empty());
+}
+
+/// The non-blocking writers should give up while a ticket is reserved (or
+/// leaked), and leave the slots reservable.
+#[test]
+fn ticket_try_write() {
+    use {crate::error::GrowLockError, std::sync::TryLockError};
+
+    let lock = GrowLock::with_capacity(4);
+    let ticket = lock.reserve_ticket(1).unwrap();
+    assert!(matches!(lock.try_write(), Err(TryLockError::WouldBlock)));
+    assert_eq!(
+        lock.push_nonblocking(1),
+        Err(GrowLockError::WouldBlock(1)),
+    );
+    // `reserve_ticket` would take the write lock if the slots were still
+    // closed, waiting for `ticket` forever.
+    let next = lock.reserve_ticket(1).unwrap();
+    ticket.commit_with(|_| 2);
+    next.commit_with(|_| 3);
+    lock.push_nonblocking(4).unwrap();
+    assert_eq!(*lock, [2, 3, 4]);
+
+    mem::forget(lock.reserve_ticket(1).unwrap());
+    assert!(matches!(lock.try_write(), Err(TryLockError::WouldBlock)));
+    assert_eq!(
+        lock.push_nonblocking(5),
+        Err(GrowLockError::WouldBlock(5)),
+    );
+    assert_eq!(*lock, [2, 3, 4]);
+}
+
+/// Tests that the elements of a ticket are dropped if its constructor
+/// panics.
+#[test]
+fn ticket_commit_with_panic() {
+    let dropped = AtomicUsize::new(0);
+    let lock = GrowLock::with_capacity(4);
+    let ticket = lock.reserve_ticket(3).unwrap();
+    let result =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            ticket.commit_with(|i| {
+                assert!(i < 2);
+                AddOnDrop(&dropped)
+            });
+        }));
+    assert!(result.is_err());
+    assert_eq!(dropped.load(Ordering::Relaxed), 2);
+    assert!(lock.is_empty());
+    lock.reserve_ticket(4)
+        .unwrap()
+        .commit_with(|_| AddOnDrop(&dropped));
+    assert_eq!(lock.len(), 4);
+}
+
+/// Tests the tickets racing with the lock-free pushes and the mutex
+/// writers.
+#[test]
+fn ticket_concurrent() {
+    const THREADS: usize = 4;
+    const N: usize = 500;
+    let lock = GrowLock::with_capacity(THREADS * N * 5);
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let lock = &lock;
+            s.spawn(move || {
+                for i in 0..N {
+                    let ticket = lock.reserve_ticket(3).unwrap();
+                    if i % 5 == 0 {
+                        drop(ticket);
+                    } else {
+                        ticket.commit_with(|j| (t * N + i) * 3 + j);
+                    }
+                }
+            });
+            s.spawn(move || {
+                for _ in 0..N {
+                    lock.push_lockfree(usize::MAX).unwrap();
+                    lock.write().unwrap().push(usize::MAX);
+                }
+            });
+        }
+    });
+    let published = THREADS * N * 2 + THREADS * (N - N / 5) * 3;
+    assert_eq!(lock.len(), published);
+    let mut values: Vec<_> =
+        lock.iter().copied().filter(|&x| x != usize::MAX).collect();
+    values.sort_unstable();
+    let expected = (0..THREADS * N)
+        .filter(|k| !(k % N).is_multiple_of(5))
+        .flat_map(|k| k * 3..k * 3 + 3);
+    assert!(values.into_iter().eq(expected));
+}
+
+/// Documents the layout of [`GrowLock`]: with the `cache-padded` feature,
+/// the length has a cache line of its own.
+#[test]
+fn cache_padded_layout() {
+    let line = align_of::<CachePadded<u8>>();
+    if cfg!(feature = "cache-padded") {
+        assert!(line == 64 || line == 128);
+        assert_eq!(size_of::<CachePadded<AtomicUsize>>(), line);
+        assert_eq!(mem::offset_of!(GrowLock<u8>, len) % line, 0);
+        assert_eq!(align_of::<GrowLock<u8>>(), line);
+        // the length, the mutex and the reservations, plus a line for
+        // the other fields.
+        assert!(size_of::<GrowLock<u8>>() >= 4 * line);
+    } else {
+        assert_eq!(line, 1);
+        assert_eq!(
+            size_of::<CachePadded<AtomicUsize>>(),
+            size_of::<usize>()
+        );
     }
+}
 
-    handle.join().unwrap();
-    // at this point all the elements are already pushed
-    assert_eq!(lock.len(), 5);
-    assert_eq!(&lock[3..], &["foo", "bar"]);
+// ------------------- inline -------------------
+
+/// Tests that the elements of an [`InlineGrowLock`] are dropped, both
+/// inline and on the heap, at the boundary between the two.
+#[test]
+fn inline_drop() {
+    let dropped = AtomicUsize::new(0);
+    for capacity in [0, 3, 4, 5] {
+        let lock = InlineGrowLock::<_, 4>::with_capacity(capacity);
+        assert_eq!(lock.is_inline(), capacity <= 4);
+        assert_eq!(lock.capacity(), capacity.max(4));
+        let mut guard = lock.write().unwrap();
+        guard.extend((0..capacity.max(4)).map(|_| AddOnDrop(&dropped)));
+        assert!(guard.is_full());
+        assert!(guard.try_push(AddOnDrop(&dropped)).is_err());
+        drop(guard);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+        drop(lock);
+        assert_eq!(dropped.load(Ordering::Relaxed), capacity.max(4) + 1);
+        dropped.store(0, Ordering::Relaxed);
+    }
+
+    let zst = InlineGrowLock::<(), 0>::with_capacity(10);
+    assert!(zst.is_inline());
+    zst.write().unwrap().extend([(); 10]);
+    assert_eq!(zst.len(), 10);
+}
+
+/// Tests the readers of an [`InlineGrowLock`] while it's written.
+#[test]
+fn inline_concurrent() {
+    let lock = InlineGrowLock::<usize, 8>::new();
+    thread::scope(|s| {
+        s.spawn(|| {
+            for i in 0..8 {
+                lock.write().unwrap().push(i);
+            }
+        });
+        s.spawn(|| {
+            while lock.len() < 8 {
+                let len = lock.len();
+                assert!(lock[..len].iter().copied().eq(0..len));
+            }
+        });
+    });
+    assert!(lock.try_write().unwrap().is_full());
+    assert_eq!(format!("{lock:?}"), "[0, 1, 2, 3, 4, 5, 6, 7]");
+}
+
+// ------------------- array -------------------
+
+/// Tests pushing into an [`ArrayGrowLock`] up to its capacity, and the
+/// overflow.
+#[test]
+fn array_push_overflow() {
+    static LOCK: ArrayGrowLock<u32, 3> = ArrayGrowLock::new();
+    assert!(LOCK.is_empty());
+    let mut guard = LOCK.write().unwrap();
+    guard.push(1);
+    assert!(guard.try_push(2).is_ok());
+    guard.extend([3]);
+    assert!(guard.is_full());
+    assert!(guard.try_push(4).is_err());
+    drop(guard);
+    assert_eq!(*LOCK, [1, 2, 3]);
+    assert_eq!(LOCK.capacity(), 3);
+}
+
+/// Tests that the elements of an [`ArrayGrowLock`] are dropped exactly
+/// once, including the one dropped by a failed `try_push`.
+#[test]
+fn array_drop() {
+    let dropped = AtomicUsize::new(0);
+    let lock = ArrayGrowLock::<_, 4>::new();
+    let mut guard = lock.write().unwrap();
+    guard.extend((0..4).map(|_| AddOnDrop(&dropped)));
+    assert!(guard.try_push(AddOnDrop(&dropped)).is_err());
+    drop(guard);
+    assert_eq!(dropped.load(Ordering::Relaxed), 1);
+    drop(lock);
+    assert_eq!(dropped.load(Ordering::Relaxed), 5);
+}
+
+/// Tests that `extend` on an [`ArrayGrowLock`] publishes the elements all
+/// at once, or not at all, like on a [`GrowLock`].
+#[test]
+fn array_extend_overflow() {
+    use std::{
+        panic::{AssertUnwindSafe, catch_unwind},
+        sync::PoisonError,
+    };
+
+    let dropped = AtomicUsize::new(0);
+    let lock = ArrayGrowLock::<_, 3>::new();
+    // the lower size bound doesn't fit: nothing is consumed
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        lock.write()
+            .unwrap()
+            .extend((0..4).map(|_| AddOnDrop(&dropped)));
+    }));
+    assert!(result.is_err());
+    assert!(lock.is_empty());
+    assert_eq!(dropped.load(Ordering::Relaxed), 0);
+    // inexact size which doesn't fit: the written elements are dropped
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        lock.write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend((0..5).filter(|_| true).map(|_| AddOnDrop(&dropped)));
+    }));
+    assert!(result.is_err());
+    assert!(lock.is_empty());
+    assert_eq!(dropped.load(Ordering::Relaxed), 4);
+}
+
+/// Tests an [`ArrayGrowLock`] of zero-sized elements, which has no
+/// storage but an unbounded capacity.
+#[test]
+fn array_zst() {
+    let lock = ArrayGrowLock::<(), 0>::new();
+    assert_eq!(size_of_val(&lock), size_of::<ArrayGrowLock<u8, 0>>());
+    assert_eq!(lock.capacity(), usize::MAX);
+    lock.write().unwrap().extend([(); 100]);
+    assert_eq!(lock.len(), 100);
+}
+
+// ------------------- local -------------------
+
+/// Tests pushing into a [`LocalGrowLock`] up to its capacity, and the
+/// single write guard.
+#[test]
+fn local_push_overflow() {
+    let lock = LocalGrowLock::with_capacity(3);
+    let mut guard = lock.write();
+    assert!(lock.try_write().is_none());
+    guard.push('a');
+    let first = &lock[0];
+    guard.extend(&['b']);
+    assert!(guard.try_push('c').is_ok());
+    assert!(guard.is_full());
+    assert!(guard.try_push('d').is_err());
+    drop(guard);
+    assert_eq!(*first, 'a');
+    assert_eq!(*lock, ['a', 'b', 'c']);
+    assert!(lock.try_write().is_some());
+}
+
+/// Tests that the elements of a [`LocalGrowLock`] are dropped exactly
+/// once, including ZSTs.
+#[test]
+fn local_drop() {
+    let dropped = AtomicUsize::new(0);
+    let lock = LocalGrowLock::with_capacity(4);
+    lock.write().extend((0..3).map(|_| AddOnDrop(&dropped)));
+    assert_eq!(dropped.load(Ordering::Relaxed), 0);
+    drop(lock);
+    assert_eq!(dropped.load(Ordering::Relaxed), 3);
+
+    let zst = LocalGrowLock::with_capacity(0);
+    assert_eq!(zst.capacity(), usize::MAX);
+    zst.write().extend([(); 10]);
+    assert_eq!(zst.len(), 10);
+}
+
+/// Tests that `extend` on a [`LocalGrowLock`] adds the elements all at
+/// once, or not at all, like on a [`GrowLock`].
+#[test]
+fn local_extend_overflow() {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    let dropped = AtomicUsize::new(0);
+    let lock = LocalGrowLock::with_capacity(3);
+    lock.write().push(AddOnDrop(&dropped));
+    // the lower size bound doesn't fit: nothing is consumed
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        lock.write().extend((0..3).map(|_| AddOnDrop(&dropped)));
+    }));
+    assert!(result.is_err());
+    assert_eq!(lock.len(), 1);
+    assert_eq!(dropped.load(Ordering::Relaxed), 0);
+    // inexact size which doesn't fit: the written elements are dropped
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        lock.write()
+            .extend((0..3).filter(|_| true).map(|_| AddOnDrop(&dropped)));
+    }));
+    assert!(result.is_err());
+    assert_eq!(lock.len(), 1);
+    assert_eq!(dropped.load(Ordering::Relaxed), 3);
+    lock.write().extend((0..2).map(|_| AddOnDrop(&dropped)));
+    assert_eq!(lock.len(), 3);
+}
+
+/// Tests the code generic over the guards, with every lock of the crate.
+#[test]
+fn write_guard_generic() {
+    fn fill(guard: &mut impl WriteGuard<usize>) {
+        while !guard.is_full() {
+            guard.push(guard.len());
+        }
+        assert!(guard.try_push(0).is_err());
+    }
+
+    let lock = GrowLock::with_capacity(4);
+    fill(&mut lock.write().unwrap());
+    let local = LocalGrowLock::with_capacity(4);
+    fill(&mut local.write());
+    let inline = InlineGrowLock::<_, 4>::new();
+    fill(&mut inline.write().unwrap());
+    let array = ArrayGrowLock::<_, 4>::new();
+    fill(&mut array.write().unwrap());
+    for slice in [&*lock, &*local, &*inline, &*array] {
+        assert_eq!(slice, [0, 1, 2, 3]);
+    }
+}
+
+// ------------------- atomic -------------------
+
+/// Tests many threads updating disjoint and shared atomic buckets, and
+/// the bucket which isn't published.
+#[test]
+fn atomic_buckets() {
+    use std::sync::atomic::AtomicU64;
+
+    const THREADS: usize = 8;
+    const ROUNDS: u64 = 1000;
+
+    let buckets = GrowLock::<u64>::with_capacity(THREADS + 2)
+        .into_atomic::<AtomicU64>();
+    buckets
+        .write()
+        .unwrap()
+        .extend((0..=THREADS).map(|_| 0.into()));
+    thread::scope(|s| {
+        for t in 0..THREADS {
+            let buckets = &buckets;
+            s.spawn(move || {
+                for _ in 0..ROUNDS {
+                    // disjoint
+                    buckets[t].fetch_add(1, Ordering::Relaxed);
+                    // shared
+                    let result = buckets.fetch_update_at(
+                        THREADS,
+                        Ordering::AcqRel,
+                        Ordering::Acquire,
+                        |x| Some(x + 2),
+                    );
+                    assert!(result.unwrap().is_ok());
+                    // not published yet
+                    let last = buckets.fetch_update_at(
+                        THREADS + 1,
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                        Some,
+                    );
+                    assert!(last.is_none());
+                }
+            });
+        }
+    });
+    let values = buckets.into_values();
+    assert_eq!(values[..THREADS], [ROUNDS; THREADS]);
+    assert_eq!(values[THREADS], 2 * ROUNDS * THREADS as u64);
+}
+
+/// Tests that the conversions to and from the atomics reuse the
+/// allocation.
+#[test]
+fn atomic_in_place() {
+    use std::sync::atomic::AtomicU32;
+
+    let lock = grow_lock!(4, [1_u32, 2, 3]);
+    let ptr = lock.as_ptr().addr();
+    let lock = lock.into_atomic::<AtomicU32>();
+    assert_eq!(lock.as_ptr().addr(), ptr);
+    lock[1].store(5, Ordering::Relaxed);
+    let lock = lock.into_values();
+    assert_eq!(lock.as_ptr().addr(), ptr);
+    assert_eq!(lock, [1, 5, 3]);
+    assert_eq!(lock.capacity(), 4);
 }
 
 // ------------------- poisoning -------------------
@@ -339,3 +4305,393 @@ fn poisoning() {
 
     assert!(lock.write().is_err());
 }
+
+/// Tests that a [`GrowLock`] can be used in
+/// [`catch_unwind`](std::panic::catch_unwind), and gets poisoned.
+#[test]
+fn catch_unwind_write() {
+    let lock = GrowLock::<u8>::with_capacity(5);
+    let result = std::panic::catch_unwind(|| {
+        let mut guard = lock.write().unwrap();
+        guard.push(1);
+        panic!("oops!");
+    });
+    assert!(result.is_err());
+    assert!(lock.write().is_err());
+    assert_eq!(lock, [1]);
+}
+
+/// Checks statically which [`GrowLock`]s are unwind safe.
+#[test]
+fn unwind_safe() {
+    use std::{
+        cell::Cell,
+        panic::{RefUnwindSafe, UnwindSafe},
+    };
+
+    const fn assert_unwind_safe<T: UnwindSafe>() {}
+    const fn assert_ref_unwind_safe<T: RefUnwindSafe>() {}
+
+    assert_unwind_safe::<GrowLock<u8>>();
+    assert_ref_unwind_safe::<GrowLock<u8>>();
+    assert_unwind_safe::<&GrowLock<String>>();
+    // owned, like `Vec<Cell<u8>>`.
+    assert_unwind_safe::<GrowLock<Cell<u8>>>();
+}
+
+#[test]
+fn self_locking_push() {
+    use crate::error::GrowLockError;
+
+    let lock = GrowLock::with_capacity(4);
+    lock.push(1).unwrap();
+    assert_eq!(lock.try_push(2), Ok(()));
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = lock.write();
+        panic!("poison");
+    });
+    // both push anyway, and report the poisoning.
+    assert!(lock.push(3).is_err());
+    assert_eq!(lock.try_push(4), Err(GrowLockError::Poisoned));
+    assert_eq!(*lock, [1, 2, 3, 4]);
+    // a full lock gives the value back, also when poisoned.
+    assert_eq!(lock.try_push(5).unwrap_err().into_value(), Some(5));
+    assert_eq!(*lock, [1, 2, 3, 4]);
+
+    let lock = GrowLock::with_capacity(1);
+    lock.push(String::from("a")).unwrap();
+    let err = lock.try_push(String::from("b")).unwrap_err();
+    assert_eq!(
+        err,
+        GrowLockError::Full {
+            value: String::from("b"),
+            len: 1,
+            capacity: 1,
+        }
+    );
+    assert_eq!(err.into_value().unwrap(), "b");
+}
+
+#[test]
+#[should_panic = "length overflow"]
+fn self_locking_push_full() {
+    let lock = GrowLock::with_capacity(1);
+    lock.push(1).unwrap();
+    let _ = lock.push(2);
+}
+
+#[test]
+fn extend_locked() {
+    let lock = GrowLock::with_capacity(5);
+    let mut iter = 0..4;
+    assert_eq!(lock.extend_locked(iter.by_ref()).unwrap(), 4);
+    assert_eq!(lock.extend_locked(Vec::new()).unwrap(), 0);
+    // only one element fits.
+    let mut iter = 4..8;
+    assert_eq!(lock.extend_locked(iter.by_ref()).unwrap(), 1);
+    assert_eq!(iter, 5..8);
+    assert_eq!(lock.extend_locked(iter).unwrap(), 0);
+    assert!(lock.iter().copied().eq(0..5));
+
+    let lock = GrowLock::with_capacity(5);
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = lock.write();
+        panic!("poison");
+    });
+    let err = lock.extend_locked(0..3).unwrap_err();
+    assert_eq!(err.into_inner(), 3);
+    assert_eq!(*lock, [0, 1, 2]);
+}
+
+/// Tests that [`GrowLock::push_nonblocking`] gives back the value
+/// whenever it's not pushed.
+#[test]
+fn push_nonblocking() {
+    use crate::error::GrowLockError;
+
+    let lock = GrowLock::with_capacity(2);
+    thread::scope(|s| {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let lock = &lock;
+        let holder = s.spawn(move || {
+            let _guard = lock.write().unwrap();
+            tx.send(()).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        });
+        rx.recv().unwrap();
+        let err = lock.push_nonblocking(Box::new(1)).unwrap_err();
+        assert_eq!(err, GrowLockError::WouldBlock(Box::new(1)));
+        holder.join().unwrap();
+    });
+    lock.push_nonblocking(Box::new(1)).unwrap();
+    lock.push_nonblocking(Box::new(2)).unwrap();
+    let err = lock.push_nonblocking(Box::new(3)).unwrap_err();
+    assert!(matches!(err, GrowLockError::Full { len: 2, .. }));
+    assert_eq!(*err.into_value().unwrap(), 3);
+
+    // like `try_push`, a poisoned lock gets the value anyway.
+    let lock = GrowLock::with_capacity(1);
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = lock.write();
+        panic!("poison");
+    });
+    let err = lock.push_nonblocking(String::from("sample")).unwrap_err();
+    assert_eq!(err, GrowLockError::Poisoned);
+    assert_eq!(*lock, ["sample"]);
+    let err = lock.push_nonblocking(String::from("full")).unwrap_err();
+    assert_eq!(err.into_value().unwrap(), "full");
+}
+
+// ------------------- debug invariants -------------------
+
+/// `from_parts` should catch a length greater than the capacity.
+#[cfg(all(feature = "debug-invariants", debug_assertions))]
+#[test]
+#[should_panic(expected = "`from_parts`: length 3 > capacity 2")]
+fn debug_invariants_len() {
+    let mut vec = Vec::<u32>::with_capacity(2);
+    let ptr = ptr::NonNull::new(vec.as_mut_ptr()).unwrap();
+    mem::forget(vec);
+    // SAFETY: the invariant is broken on purpose, and it's checked before
+    // the lock is built.
+    let _ = unsafe { GrowLock::from_parts(ptr, 3, 2) };
+}
+/// `from_raw_parts_in` should catch a length greater than the capacity.
+#[cfg(all(feature = "debug-invariants", debug_assertions))]
+#[test]
+#[should_panic(expected = "`from_raw_parts`: length 3 > capacity 2")]
+fn debug_invariants_raw_len() {
+    let mut vec = Vec::<u32, _>::with_capacity_in(2, System);
+    let ptr = vec.as_mut_ptr();
+    mem::forget(vec);
+    // SAFETY: the invariant is broken on purpose, and it's checked before
+    // the lock is built.
+    let _ = unsafe { GrowLock::from_raw_parts_in(ptr, 3, 2, System) };
+}
+/// `from_parts` should catch a pointer which isn't aligned for `T`.
+#[cfg(all(feature = "debug-invariants", debug_assertions))]
+#[test]
+#[should_panic(
+    expected = "the pointer is not aligned to `align_of::<T>()` (8)"
+)]
+fn debug_invariants_alignment() {
+    let ptr = ptr::NonNull::<u64>::dangling().cast::<u8>();
+    // SAFETY: the invariant is broken on purpose, and it's checked before
+    // the lock is built.
+    let ptr = unsafe { ptr.add(1) }.cast::<u64>();
+    // SAFETY: see above.
+    let _ = unsafe { GrowLock::from_parts(ptr, 0, 0) };
+}
+
+// ------------------- deadlock detection -------------------
+
+/// A re-entrant `write` should panic instead of deadlocking (the test
+/// would hang without the `deadlock-detection` feature, so it gives up
+/// after a timeout).
+#[cfg(feature = "deadlock-detection")]
+#[test]
+fn deadlock_detection_reentrant() {
+    use std::{panic, sync::mpsc};
+
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let lock = GrowLock::<u32>::with_capacity(2);
+        let _guard = lock.write().unwrap();
+        let result = panic::catch_unwind(|| drop(lock.write()));
+        tx.send(result.unwrap_err()).unwrap();
+    });
+    let payload = rx
+        .recv_timeout(Duration::from_secs(10))
+        .expect("the re-entrant `write` deadlocked");
+    let msg = payload.downcast_ref::<&str>().unwrap();
+    assert!(msg.starts_with("re-entrant `GrowLock::write`"));
+    handle.join().unwrap();
+}
+/// `flush` takes the write lock too, so it should panic like a
+/// re-entrant `write`.
+#[cfg(all(feature = "deadlock-detection", feature = "mmap", not(miri)))]
+#[test]
+fn deadlock_detection_flush() {
+    use {
+        crate::mmap::MmapBacking,
+        std::{panic, sync::mpsc},
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lock");
+    // SAFETY: the file is only used by this test.
+    let lock =
+        unsafe { GrowLock::<u64, MmapBacking>::create_mmap(&path, 8) }
+            .unwrap();
+    let (tx, rx) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let guard = lock.write().unwrap();
+        let result = panic::catch_unwind(|| drop(lock.flush()));
+        tx.send(result.unwrap_err()).unwrap();
+        drop(guard);
+        lock.flush().unwrap();
+    });
+    let payload = rx
+        .recv_timeout(Duration::from_secs(10))
+        .expect("`flush` deadlocked");
+    let msg = payload.downcast_ref::<&str>().unwrap();
+    assert!(msg.starts_with("re-entrant `GrowLock::write`"));
+    handle.join().unwrap();
+}
+
+/// The owner is cleared when the guard is dropped (or waits on a
+/// condvar), and the writes of the other threads still block.
+#[cfg(feature = "deadlock-detection")]
+#[test]
+fn deadlock_detection_other_threads() {
+    use std::sync::Condvar;
+
+    let lock = GrowLock::<u32>::with_capacity(4);
+    let cv = Condvar::new();
+    drop(lock.write().unwrap());
+    let guard = lock.write().unwrap();
+    thread::scope(|s| {
+        s.spawn(|| {
+            lock.write().unwrap().push(1);
+            cv.notify_all();
+        });
+        thread::sleep(Duration::from_millis(10));
+        let guard = guard.wait_while(&cv, <[u32]>::is_empty).unwrap();
+        assert_eq!(*guard, [1]);
+    });
+    lock.write().unwrap().push(2);
+    assert_eq!(lock, [1, 2]);
+}
+
+// ------------------- provenance -------------------
+// > NOTE: these tests are meant to be run with
+// > `MIRIFLAGS="-Zmiri-strict-provenance" cargo miri test --lib
+// > provenance`
+
+/// Tests the reads of the empty locks of an over-aligned type, whose
+/// buffer is a dangling pointer (which must be aligned for the type).
+#[test]
+fn provenance_empty_reads() {
+    use std::hash::{BuildHasher, RandomState};
+
+    #[repr(align(64))]
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct Aligned(u8);
+
+    let hasher = RandomState::new();
+    let empty: [Aligned; 0] = [];
+    let locks = [
+        GrowLock::<Aligned>::with_capacity(0),
+        GrowLock::default(),
+        GrowLock::from(Vec::new()),
+        GrowLock::from_slice(&empty, 0),
+    ];
+    for lock in &locks {
+        assert!(lock.as_ptr().is_aligned());
+        assert!(lock.is_empty() && lock.iter().next().is_none());
+        assert_eq!(*lock, empty);
+        assert_eq!(lock, &locks[0]);
+        assert_eq!(hasher.hash_one(lock), hasher.hash_one(&empty[..]));
+        assert_eq!(format!("{lock:?}"), "[]");
+    }
+    let local = LocalGrowLock::<Aligned>::with_capacity(0);
+    assert!(local.as_ptr().is_aligned());
+    assert_eq!(*local, empty);
+    assert_eq!(format!("{local:?}"), "[]");
+    let array = ArrayGrowLock::<Aligned, 0>::new();
+    assert_eq!(*array, empty);
+    let inline = InlineGrowLock::<Aligned, 0>::new();
+    assert_eq!(*inline, empty);
+    assert!(inline.as_ptr().is_aligned());
+}
+/// The dangling pointers of the unallocated locks should be aligned for
+/// their elements, also after a `map` to a more aligned type.
+#[test]
+fn provenance_dangling_alignment() {
+    #[repr(align(64))]
+    struct Align64(#[allow(dead_code, reason = "not a ZST")] u8);
+    #[repr(align(4096))]
+    struct Align4096(#[allow(dead_code, reason = "not a ZST")] u8);
+
+    let mut lock = GrowLock::<Align64>::with_capacity(0);
+    assert!(lock.as_ptr().is_aligned());
+    assert!(lock.as_non_null().cast::<Align64>().as_ptr().is_aligned());
+    let lock = lock.map(|Align64(x)| Align4096(x));
+    assert!(lock.as_ptr().is_aligned());
+    assert!(lock.is_empty());
+
+    let lock = GrowLock::<Align4096>::try_with_capacity(0).unwrap();
+    let (ptr, len, cap) = lock.into_parts();
+    assert!(ptr.as_ptr().is_aligned());
+    // SAFETY: these are the parts of a `GrowLock`.
+    let lock = unsafe { GrowLock::from_parts(ptr, len, cap) };
+    assert_eq!(lock.as_ptr().addr() % 4096, 0);
+    assert!(lock.as_slice().is_empty());
+}
+/// Tests the writes to the spare capacity through the pointer of
+/// `as_mut_ptr_shared`, while the write lock is held and the published
+/// elements are borrowed.
+#[test]
+fn provenance_shared_mut_ptr() {
+    let lock = GrowLock::with_capacity(4);
+    lock.write().unwrap().extend([1_u32, 2]);
+    let published = lock.as_slice();
+
+    thread::scope(|s| {
+        let guard = lock.write().unwrap();
+        s.spawn(|| assert_eq!(lock.as_slice()[..2], [1, 2]));
+        let ptr = lock.as_mut_ptr_shared();
+        assert_eq!(ptr.cast_const(), lock.as_ptr());
+        // SAFETY: the slots `2..4` are spare capacity, and the write lock
+        // is held.
+        unsafe {
+            ptr.add(2).write(3);
+            ptr.add(3).write(4);
+            assert_eq!(ptr.add(2).read() + ptr.add(3).read(), 7);
+        }
+        assert_eq!(guard.len(), 2);
+    });
+    assert_eq!(published, [1, 2]);
+    assert_eq!(lock.len(), 2);
+    let mut guard = lock.write().unwrap();
+    guard.push(5);
+    assert_eq!(*guard, [1, 2, 5]);
+}
+/// Tests construction, pushes and reads under strict provenance.
+#[test]
+fn provenance_push_read() {
+    let lock = GrowLock::with_capacity(4);
+    {
+        let mut guard = lock.write().unwrap();
+        guard.push(1_u16);
+        guard.extend([2, 3]);
+    }
+    let slice = lock.as_slice();
+    assert_eq!(slice, [1, 2, 3]);
+    assert!(lock.as_ptr().addr().is_multiple_of(align_of::<u16>()));
+
+    // empty and ZST locks use a dangling (but aligned) pointer.
+    let empty = GrowLock::<u64>::with_capacity(0);
+    assert!(empty.as_ptr().addr().is_multiple_of(align_of::<u64>()));
+    assert!(empty.is_empty());
+    let zst = GrowLock::from_iter_with_capacity(3, [(), (), ()]);
+    assert_eq!(zst.as_slice(), [(), (), ()]);
+}
+
+/// Tests the round-trip of [`GrowLock::into_parts`] and
+/// [`GrowLock::into_raw_parts`] under strict provenance.
+#[test]
+fn provenance_parts_round_trip() {
+    let lock = grow_lock!(5, [String::from("a"), String::from("b")]);
+    let (ptr, len, cap) = lock.into_parts();
+    // SAFETY: the parts come from `into_parts`.
+    let lock = unsafe { GrowLock::from_parts(ptr, len, cap) };
+    lock.write().unwrap().push(String::from("c"));
+    assert_eq!(lock, ["a", "b", "c"]);
+
+    let (ptr, len, cap) = lock.into_raw_parts();
+    // SAFETY: the parts come from `into_raw_parts`.
+    let lock = unsafe { GrowLock::from_raw_parts(ptr, len, cap) };
+    assert_eq!(lock, ["a", "b", "c"]);
+    assert_eq!(lock.capacity(), 5);
+}
@@ -1,8 +1,12 @@
 //! Run these tests with
-//! `RUSTFLAGS="--cfg loom" cargo test tests_loom --release`
+//! `RUSTFLAGS="--cfg loom" cargo test --lib tests_loom --release`
+//!
+//! Under `loom`, the atomics, the mutexes and the condvars of the crate
+//! are the ones of `loom`. The mutex of the write lock is wrapped so
+//! that it's poisoned like the one of `std`.
 
 use {
-    crate::grow_lock,
+    crate::{grow_lock, sync::PoisonError},
     loom::{sync::Arc, thread},
 };
 
@@ -74,3 +78,172 @@ fn length_consistency_panic() {
         assert_eq!(lock.len(), 1);
     });
 }
+
+/// Tests that a writer which panics while it holds the write lock
+/// poisons it, without leaving the [`GrowLock`](crate::GrowLock)
+/// inconsistent for the next writer.
+#[test]
+fn poisoning_recovery() {
+    loom::model(|| {
+        let lock = Arc::new(grow_lock!(2));
+        let panicking = thread::spawn({
+            let lock = Arc::clone(&lock);
+            move || {
+                let _ = std::panic::catch_unwind(|| {
+                    let mut guard = lock.write().unwrap();
+                    guard.push(1);
+                    panic!("the writer panicked");
+                });
+            }
+        });
+
+        let guard = lock.write();
+        let poisoned = guard.is_err();
+        let mut guard = guard.unwrap_or_else(PoisonError::into_inner);
+        // the lock is poisoned iff the panicking writer took it first.
+        assert_eq!(poisoned, guard.len() == 1);
+        guard.push(2);
+        drop(guard);
+        panicking.join().unwrap();
+        let mut elements = lock.to_vec();
+        elements.sort_unstable();
+        assert_eq!(elements, [1, 2]);
+    });
+}
+
+/// Tests that a [`TailCursor`](crate::cursor::TailCursor) waiting for an
+/// element is always woken up.
+#[test]
+fn tail_cursor_wake_up() {
+    loom::model(|| {
+        let lock = Arc::new(grow_lock!(2));
+        thread::spawn({
+            let lock = Arc::clone(&lock);
+            move || {
+                let mut guard = lock.write().unwrap();
+                guard.push(1);
+                guard.push(2);
+            }
+        });
+
+        let mut tail = lock.tail();
+        let mut seen = tail.next_batch().len();
+        while seen < 2 {
+            seen += tail.next_batch().len();
+        }
+        assert_eq!(tail.position(), 2);
+    });
+}
+
+/// Tests that the lock-free pushers publish their elements in order, and
+/// that a reader never sees an uninitialized element.
+#[test]
+fn push_lockfree_contention() {
+    loom::model(|| {
+        let lock = Arc::new(grow_lock!(2));
+        // a new `GrowLock` is opened to the lock-free pushers by the first
+        // writer.
+        drop(lock.write().unwrap());
+        let handles: Vec<_> = (1..=2)
+            .map(|i| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || lock.push_lockfree(i).unwrap())
+            })
+            .collect();
+
+        let len = lock.len();
+        assert!(lock[..len].iter().all(|&x| x != 0));
+        for (i, handle) in (1..=2).zip(handles) {
+            let index = handle.join().unwrap();
+            assert_eq!(lock[index], i);
+        }
+        assert_eq!(lock.len(), 2);
+    });
+}
+
+/// Tests that a lock-free pusher and a mutex writer never write the same
+/// slot.
+#[test]
+fn push_lockfree_write() {
+    loom::model(|| {
+        let lock = Arc::new(grow_lock!(3));
+        drop(lock.write().unwrap());
+        let lockfree = thread::spawn({
+            let lock = Arc::clone(&lock);
+            move || lock.push_lockfree(1).unwrap()
+        });
+        let writer = thread::spawn({
+            let lock = Arc::clone(&lock);
+            move || lock.write().unwrap().push(2)
+        });
+
+        let len = lock.len();
+        assert!(lock[..len].iter().all(|&x| x != 0));
+        let index = lockfree.join().unwrap();
+        writer.join().unwrap();
+        assert_eq!(lock[index], 1);
+        let mut elements = lock.to_vec();
+        elements.sort_unstable();
+        assert_eq!(elements, [1, 2]);
+        // `reserved` is reopened from the length pushed by the writer.
+        assert_eq!(lock.push_lockfree(3), Ok(2));
+    });
+}
+
+/// Tests that a ticket committed before an earlier one is published by
+/// the latter, also when it's published without locking `pending`.
+#[test]
+fn ticket_out_of_order() {
+    loom::model(|| {
+        let lock = Arc::new(grow_lock!(3));
+        drop(lock.write().unwrap());
+        let handles: Vec<_> = (1..=2)
+            .map(|i| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    lock.reserve_ticket(1).unwrap().commit_with(|_| i);
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        let mut values = lock.to_vec();
+        values.sort_unstable();
+        assert_eq!(values, [1, 2]);
+        // every reservation is resolved, so a mutex writer doesn't wait.
+        lock.write().unwrap().push(3);
+        assert_eq!(lock.len(), 3);
+    });
+}
+
+/// Tests that an abandoned ticket doesn't leave a hole, even when a later
+/// ticket is committed before it's dropped.
+#[test]
+fn ticket_abandoned() {
+    loom::model(|| {
+        let lock = Arc::new(grow_lock!(2));
+        drop(lock.write().unwrap());
+        let handles: Vec<_> = (1..=2)
+            .map(|i| {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    let ticket = lock.reserve_ticket(1).unwrap();
+                    if i == 1 {
+                        ticket.commit_with(|_| i);
+                    }
+                })
+            })
+            .collect();
+
+        let len = lock.len();
+        assert!(lock[..len].iter().all(|&x| x == 1));
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(*lock, [1]);
+        // the abandoned slot is reserved again.
+        lock.reserve_ticket(1).unwrap().commit_with(|_| 2);
+        assert_eq!(*lock, [1, 2]);
+    });
+}
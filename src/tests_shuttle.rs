@@ -0,0 +1,153 @@
+//! Run these tests with
+//! `RUSTFLAGS="--cfg shuttle" cargo test --lib tests_shuttle --release`
+//!
+//! Unlike the models of `loom` (see `tests_loom`), which check every
+//! schedule of a few threads, these check random schedules of bigger
+//! scenarios. Under `shuttle`, the atomics, the mutexes and the condvars
+//! of the crate are the ones of `shuttle`.
+//!
+//! `shuttle` stops an execution at the first panic of a task, even a
+//! caught one: the writers poison the write lock with a flag instead (see
+//! `sync`).
+
+use {
+    crate::{GrowLock, sync::PoisonError},
+    shuttle::{
+        sync::Arc,
+        thread::{self, JoinHandle},
+    },
+};
+
+/// The number of random schedules checked by each test.
+const ITERATIONS: usize = 300;
+const WRITERS: usize = 10;
+const READERS: usize = 5;
+/// The number of elements pushed by each writer, in a single `extend`.
+const BATCH: usize = 3;
+
+/// Spawns the writers, each extending the lock with its own batch.
+fn spawn_writers(lock: &Arc<GrowLock<usize>>) -> Vec<JoinHandle<()>> {
+    (0..WRITERS)
+        .map(|i| {
+            let lock = Arc::clone(lock);
+            thread::spawn(move || {
+                let batch = (0..BATCH).map(|j| i * BATCH + j);
+                lock.write().unwrap().extend(batch);
+            })
+        })
+        .collect()
+}
+
+/// Tests that the readers only see whole batches, and that every batch is
+/// contiguous.
+#[test]
+fn write_contention() {
+    shuttle::check_random(
+        || {
+            let lock = Arc::new(GrowLock::with_capacity(WRITERS * BATCH));
+            let writers = spawn_writers(&lock);
+            let readers: Vec<_> = (0..READERS)
+                .map(|_| {
+                    let lock = Arc::clone(&lock);
+                    thread::spawn(move || {
+                        let slice = lock.as_slice();
+                        assert_eq!(slice.len() % BATCH, 0);
+                        for batch in slice.chunks(BATCH) {
+                            assert_eq!(batch[0] % BATCH, 0);
+                            assert!(
+                                batch.windows(2).all(|w| w[0] + 1 == w[1])
+                            );
+                        }
+                    })
+                })
+                .collect();
+
+            for handle in writers.into_iter().chain(readers) {
+                handle.join().unwrap();
+            }
+            let mut elements = lock.to_vec();
+            elements.sort_unstable();
+            assert!(elements.into_iter().eq(0..WRITERS * BATCH));
+        },
+        ITERATIONS,
+    );
+}
+
+/// Tests that a writer which poisons the write lock (as if it panicked)
+/// doesn't leave the [`GrowLock`] inconsistent: the next writers see the
+/// poisoning and its whole batch, and the readers only see whole batches.
+#[test]
+fn poisoning_recovery() {
+    shuttle::check_random(
+        || {
+            let lock = Arc::new(GrowLock::with_capacity(WRITERS * BATCH));
+            let writers: Vec<_> = (0..WRITERS)
+                .map(|i| {
+                    let lock = Arc::clone(&lock);
+                    thread::spawn(move || {
+                        let batch = (0..BATCH).map(|j| i * BATCH + j);
+                        let guard = lock.write();
+                        let poisoned = guard.is_err();
+                        let mut guard =
+                            guard.unwrap_or_else(PoisonError::into_inner);
+                        // the first batch is pushed by the poisoning
+                        // writer.
+                        assert_eq!(poisoned, guard.contains(&0));
+                        guard.extend(batch);
+                        if i == 0 {
+                            lock.mutex.poison();
+                        }
+                    })
+                })
+                .collect();
+            let reader = {
+                let lock = Arc::clone(&lock);
+                thread::spawn(move || {
+                    let slice = lock.as_slice();
+                    assert_eq!(slice.len() % BATCH, 0);
+                    for batch in slice.chunks(BATCH) {
+                        assert!(
+                            batch.windows(2).all(|w| w[0] + 1 == w[1])
+                        );
+                    }
+                })
+            };
+
+            for handle in writers.into_iter().chain([reader]) {
+                handle.join().unwrap();
+            }
+            assert!(lock.write().is_err());
+            let mut elements = lock.to_vec();
+            elements.sort_unstable();
+            assert!(elements.into_iter().eq(0..WRITERS * BATCH));
+        },
+        ITERATIONS,
+    );
+}
+
+/// Tests that a [`TailCursor`](crate::cursor::TailCursor) returns every
+/// element once, in order, while the writers push them.
+#[test]
+fn tail_cursor_consumption() {
+    shuttle::check_random(
+        || {
+            let lock = Arc::new(GrowLock::with_capacity(WRITERS * BATCH));
+            let writers = spawn_writers(&lock);
+
+            let mut tail = lock.tail();
+            let mut consumed = Vec::with_capacity(WRITERS * BATCH);
+            loop {
+                let batch = tail.next_batch();
+                if batch.is_empty() {
+                    break;
+                }
+                consumed.extend_from_slice(batch);
+            }
+            for handle in writers {
+                handle.join().unwrap();
+            }
+            assert_eq!(consumed, *lock);
+        },
+        ITERATIONS,
+    );
+}
@@ -0,0 +1,225 @@
+//! Slots reserved in a [`GrowLock`], which are written without holding
+//! the write lock.
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        error::LengthError,
+        sync::PoisonError,
+    },
+    core::{
+        mem::{self, ManuallyDrop, MaybeUninit},
+        ptr, slice,
+    },
+};
+
+/// `n` contiguous slots reserved at the end of a [`GrowLock`], which are
+/// written without holding the write lock.
+///
+/// This structure is created by the
+/// [`reserve_ticket`](GrowLock::reserve_ticket) method on [`GrowLock`].
+///
+/// The elements are published by [`commit`](Ticket::commit), once every
+/// ticket reserved before this one is committed or dropped, so the
+/// readers only see a fully initialized prefix. `commit` never waits:
+/// the ticket which completes the prefix publishes the later ones.
+///
+/// A ticket dropped without being committed is *abandoned*: its slots
+/// are skipped, and the elements of the tickets committed after it are
+/// moved down to fill them before being published (the elements which
+/// were written in the abandoned ticket are leaked). So the elements may
+/// be published at a lower index than the one of their slots, and
+/// their addresses must not be relied on before the commit. The slots
+/// left free at the end are reserved again once every reserved ticket is
+/// committed or dropped.
+///
+/// The mutex writers ([`write`](GrowLock::write) and the like) wait for
+/// every reserved ticket to be committed or dropped when they take the
+/// write lock: taking it on a thread which holds a ticket deadlocks.
+/// With the `tokio` feature, [`write_async`](GrowLock::write_async)
+/// waits for them asynchronously, so a ticket can be held across
+/// `.await`s by a task of the same executor.
+/// Leaking a ticket (e.g. with [`mem::forget`](std::mem::forget)) blocks
+/// the mutex writers and the lock-free pushers forever.
+/// [`try_write`](GrowLock::try_write) (and the other non-blocking
+/// writers) don't wait for the tickets: they fail while one is reserved.
+pub struct Ticket<'lock, T, A: Allocator = Global> {
+    lock: &'lock GrowLock<T, A>,
+    start: usize,
+    len: usize,
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Reserves `n` contiguous slots at the end of the [`GrowLock`], and
+    /// returns a [`Ticket`] to write them.
+    ///
+    /// The slots are reserved with an atomic increment, like in
+    /// [`push_lockfree`](GrowLock::push_lockfree), so several producers
+    /// can construct their elements in parallel. If a mutex writer holds
+    /// the write lock, this waits for it to be released.
+    ///
+    /// # Errors
+    /// Returns an error if the `n` slots don't fit in the spare capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::thread;
+    ///
+    /// let lock = GrowLock::with_capacity(8);
+    /// thread::scope(|s| {
+    ///     for i in 0..2 {
+    ///         let lock = &lock;
+    ///         s.spawn(move || {
+    ///             let ticket = lock.reserve_ticket(4).unwrap();
+    ///             ticket.commit_with(|j| format!("{i}.{j}"));
+    ///         });
+    ///     }
+    /// });
+    /// assert_eq!(lock.len(), 8);
+    /// assert!(lock.contains(&String::from("1.3")));
+    /// ```
+    pub fn reserve_ticket(
+        &self,
+        n: usize,
+    ) -> Result<Ticket<'_, T, A>, LengthError> {
+        if n == 0 {
+            return Ok(Ticket {
+                lock: self,
+                start: 0,
+                len: 0,
+            });
+        }
+        loop {
+            if let Some(start) = self.try_reserve_slots(n) {
+                return Ok(Ticket {
+                    lock: self,
+                    start: start?,
+                    len: n,
+                });
+            }
+            // the slots are reopened when the write lock is released, but
+            // not from a length which doesn't fit in them.
+            let guard =
+                self.write().unwrap_or_else(PoisonError::into_inner);
+            if n > self.slots_capacity().saturating_sub(guard.len()) {
+                return Err(LengthError::new(
+                    guard.len(),
+                    self.slots_capacity(),
+                ));
+            }
+        }
+    }
+}
+
+impl<T, A: Allocator> Ticket<'_, T, A> {
+    /// Returns the number of reserved slots.
+    #[inline]
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    #[inline]
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the reserved slots.
+    #[inline]
+    #[must_use]
+    pub fn slots_mut(&mut self) -> &mut [MaybeUninit<T>] {
+        // SAFETY: the slots are inside the buffer, and they are reserved
+        // by this ticket, so no one else reads nor writes them.
+        unsafe {
+            let ptr = self.lock.as_non_null_shared().add(self.start);
+            slice::from_raw_parts_mut(ptr.as_ptr().cast(), self.len)
+        }
+    }
+    /// Commits the ticket: its elements are published once every ticket
+    /// reserved before it is committed or dropped.
+    ///
+    /// # Safety
+    /// Every slot must be initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// let lock = GrowLock::with_capacity(2);
+    /// let mut ticket = lock.reserve_ticket(2).unwrap();
+    /// for (slot, value) in ticket.slots_mut().iter_mut().zip([1, 2]) {
+    ///     slot.write(value);
+    /// }
+    /// // SAFETY: both slots are initialized.
+    /// unsafe { ticket.commit() };
+    /// assert_eq!(*lock, [1, 2]);
+    /// ```
+    pub unsafe fn commit(self) {
+        let this = ManuallyDrop::new(self);
+        if !this.is_empty() {
+            // SAFETY: the slots are reserved by this ticket, which is
+            // never used again, and the caller guarantees
+            // they're initialized.
+            unsafe {
+                this.lock.resolve_slots(
+                    this.start,
+                    this.start + this.len,
+                    true,
+                );
+            }
+        }
+    }
+    /// Initializes every slot with `f`, called with the index of the
+    /// slot in the ticket, and [`commit`](Ticket::commit)s the ticket.
+    ///
+    /// If `f` panics, the elements it returned are dropped, and the
+    /// ticket is abandoned.
+    pub fn commit_with(mut self, mut f: impl FnMut(usize) -> T) {
+        /// Drops the initialized slots if `f` panics.
+        struct Initialized<'a, T> {
+            slots: &'a mut [MaybeUninit<T>],
+            len: usize,
+        }
+        impl<T> Drop for Initialized<'_, T> {
+            fn drop(&mut self) {
+                // SAFETY: the first `len` slots are initialized.
+                unsafe {
+                    ptr::drop_in_place(
+                        self.slots[..self.len].assume_init_mut(),
+                    );
+                }
+            }
+        }
+
+        let mut initialized = Initialized {
+            slots: self.slots_mut(),
+            len: 0,
+        };
+        while initialized.len < initialized.slots.len() {
+            let value = f(initialized.len);
+            initialized.slots[initialized.len].write(value);
+            initialized.len += 1;
+        }
+        mem::forget(initialized);
+        // SAFETY: every slot is initialized.
+        unsafe { self.commit() };
+    }
+}
+
+impl<T, A: Allocator> Drop for Ticket<'_, T, A> {
+    /// Abandons the ticket.
+    fn drop(&mut self) {
+        if !self.is_empty() {
+            // SAFETY: the slots are reserved by this ticket, which is
+            // never used again.
+            unsafe {
+                self.lock.resolve_slots(
+                    self.start,
+                    self.start + self.len,
+                    false,
+                );
+            }
+        }
+    }
+}
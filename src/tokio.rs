@@ -0,0 +1,285 @@
+//! [`tokio`](::tokio) support.
+
+use {
+    crate::{
+        GrowLock,
+        compat::Allocator,
+        error::LengthError,
+        guard::{AsyncGrowGuard, OwnedGrowGuard},
+    },
+    ::futures_core::Stream,
+    ::tokio::{io::AsyncWrite, sync::watch},
+    std::{
+        io::{self, IoSlice, Write as _},
+        mem,
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll, ready},
+    },
+};
+
+/// Appends the bytes to the [`GrowLock`], up to its capacity, without
+/// ever returning [`Poll::Pending`].
+///
+/// The length is published after each write. Once the [`GrowLock`] is
+/// full, writing a non-empty buffer fails with
+/// [`ErrorKind::WriteZero`](io::ErrorKind::WriteZero) (wrapping a
+/// [`LengthError`]), instead of waiting forever.
+impl<A: Allocator> AsyncWrite for OwnedGrowGuard<u8, A> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if !buf.is_empty() && this.is_full() {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                LengthError::new(this.len(), this.capacity()),
+            )));
+        }
+        Poll::Ready(this.write(buf))
+    }
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if bufs.iter().any(|buf| !buf.is_empty()) && this.is_full() {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                LengthError::new(this.len(), this.capacity()),
+            )));
+        }
+        Poll::Ready(this.write_vectored(bufs))
+    }
+    #[inline]
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+    #[inline]
+    fn poll_flush(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+    #[inline]
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Locks this [`GrowLock`] with exclusive write access, waiting
+    /// asynchronously until it can be acquired.
+    ///
+    /// The returned guard can be held across `.await`s, and readers are
+    /// never blocked by it.
+    ///
+    /// The async writers and the sync ones (of [`write`](GrowLock::write)
+    /// and [`try_write`](GrowLock::try_write)) share the same lock, so
+    /// they can be mixed freely. The async writer only holds the
+    /// internal mutex for a moment, to mark the lock as taken: while
+    /// it's taken, the sync writers block, and
+    /// [`try_write`](GrowLock::try_write) fails with
+    /// [`WouldBlock`](std::sync::TryLockError::WouldBlock).
+    ///
+    /// Once the lock is acquired, this also waits (asynchronously) for
+    /// the reserved [`Ticket`](crate::ticket::Ticket)s to be committed or
+    /// dropped, which may be held by other tasks of the same executor.
+    ///
+    /// Like [`tokio::sync::Mutex`](https://docs.rs/tokio/latest/tokio/sync/struct.Mutex.html),
+    /// the async guard ignores the poisoning: it's returned even if a sync
+    /// writer panicked, and it doesn't poison the lock if the task panics
+    /// while holding it.
+    ///
+    /// # Cancel safety
+    /// This method is cancel safe: dropping the future before it completes
+    /// doesn't acquire the lock (nor keeps it, if it's dropped while
+    /// waiting for the tickets). There is no fairness between the
+    /// writers.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let lock = GrowLock::with_capacity(10);
+    /// let mut guard = lock.write_async().await;
+    /// guard.push(1);
+    /// tokio::task::yield_now().await;
+    /// guard.extend([2, 3]);
+    /// drop(guard);
+    ///
+    /// assert_eq!(*lock.write().unwrap(), [1, 2, 3]);
+    /// # });
+    /// ```
+    pub async fn write_async(&self) -> AsyncGrowGuard<'_, T, A> {
+        /// Releases the write lock if the future is dropped while
+        /// waiting for the reserved slots.
+        struct Closing<'a, T, A: Allocator> {
+            lock: &'a GrowLock<T, A>,
+            reserved: usize,
+        }
+        impl<T, A: Allocator> Drop for Closing<'_, T, A> {
+            fn drop(&mut self) {
+                self.lock.reopen_lockfree(self.reserved);
+                self.lock.notifier.unlock_detached(&self.lock.mutex);
+            }
+        }
+
+        self.notifier.async_lock(&self.mutex).await;
+        let reserved = self.close_lockfree_nowait();
+        if reserved != 0 {
+            // the tickets may be held by tasks of this executor, so the
+            // thread must not be blocked.
+            let closing = Closing {
+                lock: self,
+                reserved,
+            };
+            self.wait_lockfree_async(reserved).await;
+            mem::forget(closing);
+        }
+        // SAFETY: the async write lock was just acquired, and the
+        // reserved slots are resolved.
+        unsafe { AsyncGrowGuard::new(self) }
+    }
+    /// Returns a [`watch::Receiver`] of the length, which is updated after
+    /// every push (or bulk write).
+    ///
+    /// The channel is created by the first call, so the writers don't pay
+    /// for it until then.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let lock = GrowLock::with_capacity(10);
+    /// let mut rx = lock.watch_len();
+    /// assert_eq!(*rx.borrow(), 0);
+    ///
+    /// lock.write().unwrap().extend([1, 2, 3]);
+    /// rx.changed().await.unwrap();
+    /// assert_eq!(*rx.borrow_and_update(), 3);
+    /// # });
+    /// ```
+    #[must_use]
+    pub fn watch_len(&self) -> watch::Receiver<usize> {
+        self.notifier.watch(|| self.len())
+    }
+    /// Waits until at least `n` elements are pushed, returning whether
+    /// `len() >= n`.
+    ///
+    /// If the condition is already met, this returns immediately. If `n`
+    /// is greater than the capacity, it returns `false` immediately.
+    ///
+    /// This is the async version of
+    /// [`wait_for_len`](GrowLock::wait_for_len): to give up after a
+    /// timeout, use [`tokio::time::timeout`](https://docs.rs/tokio/latest/tokio/time/fn.timeout.html).
+    pub async fn async_wait_for_len(&self, n: usize) -> bool {
+        if self.len() >= n {
+            return true;
+        }
+        if n > self.capacity() {
+            return false;
+        }
+        let mut rx = self.watch_len();
+        // the sender is never dropped while `self` is borrowed.
+        rx.wait_for(|&len| len >= n).await.is_ok()
+    }
+}
+
+impl<T, A> GrowLock<T, A>
+where
+    T: Clone + Send + Sync + 'static,
+    A: Allocator + Send + Sync + 'static,
+{
+    /// Returns a [`Stream`] of clones of the elements, starting from the
+    /// index `start`, which yields the new elements as they're pushed.
+    ///
+    /// The stream waits like
+    /// [`async_wait_for_len`](GrowLock::async_wait_for_len), and it ends
+    /// once the [`GrowLock`] is full and every element is yielded (or
+    /// immediately, if `start` is greater than the capacity).
+    ///
+    /// This is the async version of
+    /// [`TailCursor`](crate::cursor::TailCursor).
+    ///
+    /// # Examples
+    /// ```
+    /// use futures_util::StreamExt;
+    /// use growlock::GrowLock;
+    /// use std::sync::Arc;
+    ///
+    /// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+    /// let lock = Arc::new(GrowLock::with_capacity(4));
+    /// lock.write().unwrap().push(1);
+    /// let stream = Arc::clone(&lock).stream_from(0);
+    /// lock.write().unwrap().extend([2, 3, 4]);
+    /// assert_eq!(stream.collect::<Vec<_>>().await, [1, 2, 3, 4]);
+    /// # });
+    /// ```
+    pub fn stream_from(
+        self: Arc<Self>,
+        start: usize,
+    ) -> impl Stream<Item = T> + Send {
+        StreamFrom {
+            lock: self,
+            pos: start,
+            wait: None,
+        }
+    }
+}
+
+/// The [`Stream`] returned by [`GrowLock::stream_from`].
+struct StreamFrom<T, A: Allocator> {
+    lock: Arc<GrowLock<T, A>>,
+    /// Index of the next element to yield.
+    pos: usize,
+    /// Waits for the element at `pos`.
+    wait: Option<Pin<Box<dyn Future<Output = bool> + Send>>>,
+}
+
+impl<T, A> Stream for StreamFrom<T, A>
+where
+    T: Clone + Send + Sync + 'static,
+    A: Allocator + Send + Sync + 'static,
+{
+    type Item = T;
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        loop {
+            // the index, not the wake-ups, decides what comes next: a
+            // missed wake-up can only delay an element.
+            if let Some(elem) = this.lock.get(this.pos) {
+                this.wait = None;
+                this.pos += 1;
+                return Poll::Ready(Some(elem.clone()));
+            }
+            let wait = this.wait.get_or_insert_with(|| {
+                let lock = Arc::clone(&this.lock);
+                let n = this.pos.saturating_add(1);
+                Box::pin(async move { lock.async_wait_for_len(n).await })
+            });
+            // the element at `pos` can never be pushed.
+            if !ready!(wait.as_mut().poll(cx)) {
+                return Poll::Ready(None);
+            }
+            this.wait = None;
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let lower = self.lock.len().saturating_sub(self.pos);
+        let upper = self.lock.capacity().saturating_sub(self.pos);
+        (lower, Some(upper))
+    }
+}
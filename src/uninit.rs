@@ -0,0 +1,121 @@
+//! Two-phase initialization of a [`GrowLock`], through
+//! [`GrowLock<MaybeUninit<T>>`].
+
+use {
+    crate::{
+        GrowLock,
+        compat::{Allocator, Global},
+        sync::atomic::Ordering,
+    },
+    core::{
+        marker::PhantomData,
+        mem::{ManuallyDrop, MaybeUninit},
+        ptr,
+    },
+};
+
+impl<T, A: Allocator> GrowLock<T, A> {
+    /// Creates a new [`GrowLock`] of `capacity` uninitialized elements in
+    /// the provided allocator.
+    ///
+    /// The length is `capacity`, while [`capacity()`](GrowLock::capacity)
+    /// can be greater (e.g. if the allocator returns a larger block).
+    /// Once the first `capacity` elements are initialized (e.g. through
+    /// [`as_mut_ptr`](GrowLock::as_mut_ptr)), the [`GrowLock`] can be
+    /// converted with [`assume_init`](GrowLock::assume_init).
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::{alloc::System, mem::MaybeUninit};
+    ///
+    /// let mut lock = GrowLock::<u32, _>::new_uninit_in(3, System);
+    /// let ptr = lock.as_mut_ptr();
+    /// for i in 0..3 {
+    ///     // SAFETY: `i < capacity`
+    ///     unsafe { ptr.add(i).write(MaybeUninit::new(i as u32 * 2)) };
+    /// }
+    /// // SAFETY: every element is initialized
+    /// let lock = unsafe { lock.assume_init() };
+    /// assert_eq!(lock, [0, 2, 4]);
+    /// ```
+    #[must_use]
+    pub fn new_uninit_in(
+        capacity: usize,
+        alloc: A,
+    ) -> GrowLock<MaybeUninit<T>, A> {
+        let lock = GrowLock::with_capacity_in(capacity, alloc);
+        // `MaybeUninit<T>` is always initialized.
+        lock.len.store(capacity, Ordering::Release);
+        lock
+    }
+}
+
+impl<T> GrowLock<T> {
+    /// Creates a new [`GrowLock`] of `capacity` uninitialized elements.
+    ///
+    /// See [`new_uninit_in`](GrowLock::new_uninit_in) for more details.
+    ///
+    /// # Examples
+    /// ```
+    /// use growlock::GrowLock;
+    /// use std::mem::MaybeUninit;
+    ///
+    /// let mut lock = GrowLock::<String>::new_uninit(2);
+    /// let ptr = lock.as_mut_ptr();
+    /// // SAFETY: the capacity is 2
+    /// unsafe {
+    ///     ptr.write(MaybeUninit::new("hello".to_owned()));
+    ///     ptr.add(1).write(MaybeUninit::new("world".to_owned()));
+    /// }
+    /// // SAFETY: every element is initialized
+    /// let lock = unsafe { lock.assume_init() };
+    /// assert_eq!(lock, ["hello", "world"]);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn new_uninit(capacity: usize) -> GrowLock<MaybeUninit<T>> {
+        Self::new_uninit_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> GrowLock<MaybeUninit<T>, A> {
+    /// Converts to [`GrowLock<T>`], without copying.
+    ///
+    /// The capacity, the allocator and the lock (including its poisoning)
+    /// are preserved.
+    ///
+    /// # Safety
+    /// The first `self.len()` elements (which, for a [`GrowLock`] created
+    /// with [`new_uninit`](GrowLock::new_uninit), are the `capacity`
+    /// elements it was asked for) must be initialized. The ones past the
+    /// length don't need to be.
+    #[must_use]
+    pub unsafe fn assume_init(self) -> GrowLock<T, A> {
+        let this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never used (nor dropped) again, so every field
+        // is moved out exactly once.
+        let (buf, len, generation, reservations, mutex, owner, notifier) = unsafe {
+            (
+                ptr::read(&raw const this.buf),
+                ptr::read(&raw const this.len),
+                ptr::read(&raw const this.generation),
+                ptr::read(&raw const this.reservations),
+                ptr::read(&raw const this.mutex),
+                ptr::read(&raw const this.owner),
+                ptr::read(&raw const this.notifier),
+            )
+        };
+        GrowLock {
+            // SAFETY: `MaybeUninit<T>` has the same layout as `T`.
+            buf: unsafe { buf.cast() },
+            len,
+            generation,
+            reservations,
+            mutex,
+            owner,
+            notifier,
+            _invariant: PhantomData,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! [`zeroize`](::zeroize) support, to wipe secrets stored in a
+//! [`GrowLock`].
+
+use {
+    crate::{GrowLock, compat::Allocator, sync::atomic::Ordering},
+    ::zeroize::Zeroize,
+    core::{mem::MaybeUninit, ptr, slice},
+};
+
+/// Zeroizes the elements, drops them, and then zeroizes the whole
+/// buffer (including the spare capacity), like [`Vec`] does.
+///
+/// The [`GrowLock`] is left empty, with the same capacity.
+///
+/// To wipe the buffer when the [`GrowLock`] is dropped, wrap it in
+/// [`Zeroizing`](::zeroize::Zeroizing).
+///
+/// # Examples
+/// ```
+/// use growlock::GrowLock;
+/// use zeroize::{Zeroize, Zeroizing};
+///
+/// let mut lock = GrowLock::from_str_with_capacity("secret", 16);
+/// lock.zeroize();
+/// assert!(lock.is_empty());
+/// assert_eq!(lock.capacity(), 16);
+///
+/// let key = Zeroizing::new(GrowLock::<u8>::with_capacity(32));
+/// key.write().unwrap().extend(*b"hunter2");
+/// // the buffer is wiped here
+/// drop(key);
+/// ```
+impl<T: Zeroize, A: Allocator> Zeroize for GrowLock<T, A> {
+    fn zeroize(&mut self) {
+        self.bump_generation();
+        self.as_mut_slice().iter_mut().zeroize();
+
+        // the length is set to zero first, so that if a `drop` panics,
+        // the elements are not dropped twice.
+        let len = self.len();
+        self.len.store(0, Ordering::Relaxed);
+        // SAFETY: the first `len` elements are initialized, and they are
+        // no longer reachable through `self`.
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.as_mut_ptr(),
+                len,
+            ));
+        }
+
+        // for ZSTs, `capacity()` is `usize::MAX` but there is no buffer.
+        let bytes = self.capacity() * size_of::<T>();
+        // SAFETY:
+        // * the buffer is allocated for `capacity` elements (or dangling
+        //   if `bytes` is zero).
+        // * we have `&mut self`, so nobody else can access the buffer.
+        let buf = unsafe {
+            slice::from_raw_parts_mut(
+                self.as_mut_ptr().cast::<MaybeUninit<u8>>(),
+                bytes,
+            )
+        };
+        buf.zeroize();
+    }
+}
@@ -0,0 +1,58 @@
+//! These tests live outside of the crate because `serde_json` implements
+//! `PartialEq<Value>` for the integers, which would make the type of the
+//! empty array literals in the unit tests ambiguous.
+#![cfg(feature = "serde")]
+
+use growlock::{GrowLock, grow_lock};
+
+#[test]
+fn serde_round_trip() {
+    let lock = grow_lock!(10, ["a".to_owned(), "b".to_owned()]);
+    let json = serde_json::to_string(&lock).unwrap();
+    assert_eq!(json, r#"["a","b"]"#);
+    let de: GrowLock<String> = serde_json::from_str(&json).unwrap();
+    assert_eq!(de, lock);
+    assert!(de.is_full());
+}
+/// Over-limit payloads should be rejected, leaving the `GrowLock`
+/// unchanged
+#[test]
+fn serde_append_to_over_limit() {
+    use {
+        growlock::serde::{AppendTo, WithCapacity},
+        serde::de::DeserializeSeed,
+    };
+
+    let lock = grow_lock!(4, ["a".to_owned()]);
+    let mut de = serde_json::Deserializer::from_str(r#"["b", "c"]"#);
+    AppendTo(&lock).deserialize(&mut de).unwrap();
+    assert_eq!(lock, ["a", "b", "c"]);
+
+    for json in [r#"["d", "e"]"#, r#"["d", 1]"#, r#"["d""#] {
+        let mut de = serde_json::Deserializer::from_str(json);
+        assert!(AppendTo(&lock).deserialize(&mut de).is_err());
+        assert_eq!(lock, ["a", "b", "c"]);
+    }
+    let mut de = serde_json::Deserializer::from_str(r#"["d"]"#);
+    AppendTo(&lock).deserialize(&mut de).unwrap();
+    assert!(lock.is_full());
+
+    let mut de = serde_json::Deserializer::from_str("[1, 2, 3]");
+    let result = WithCapacity::<u8>::new(2).deserialize(&mut de);
+    assert!(result.unwrap_err().to_string().contains("at most 2"));
+    let mut de = serde_json::Deserializer::from_str("[]");
+    let lock = WithCapacity::<u8>::new(2).deserialize(&mut de).unwrap();
+    assert!(lock.is_empty());
+    assert_eq!(lock.capacity(), 2);
+}
+/// An empty `GrowLock` should be serialized as an empty sequence, and
+/// deserialized with no capacity
+#[test]
+fn serde_empty() {
+    let lock = GrowLock::<u8>::with_capacity(4);
+    let json = serde_json::to_string(&lock).unwrap();
+    assert_eq!(json, "[]");
+    let de: GrowLock<u8> = serde_json::from_str(&json).unwrap();
+    assert!(de.is_empty());
+    assert_eq!(de.capacity(), 0);
+}